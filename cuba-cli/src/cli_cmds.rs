@@ -1,4 +1,6 @@
-use clap::{ArgAction, Parser, Subcommand};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+
+use crate::console_out::Verbosity;
 
 #[derive(Parser)]
 #[command(
@@ -10,34 +12,169 @@ use clap::{ArgAction, Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: MainCommands,
+
+    /// The verbosity of the console output, -v prints only transferred files and errors, -vv
+    /// prints all messages with file sizes and rates.
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// The format of the console output.
+    // The id differs from the name, since subcommands have an `output` file argument.
+    #[arg(
+        long = "output",
+        id = "output_format",
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Text
+    )]
+    pub output: OutputFormat,
+}
+
+/// Defines an `OutputFormat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Messages and progress bars for humans.
+    #[default]
+    Text,
+
+    /// A JSON object per line for each message, closed by a summary.
+    Json,
+}
+
+/// Defines a `VerifyModeArg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum VerifyModeArg {
+    /// The files, which are not verified yet or failed the last verify.
+    #[default]
+    New,
+
+    /// All files.
+    All,
+
+    /// The files, which are not verified within `--stale-days`.
+    Stale,
+}
+
+/// Defines a `ManifestFormatArg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ManifestFormatArg {
+    /// A line per file like `b3sum`, checked with `b3sum -c`.
+    #[default]
+    Text,
+
+    /// A JSON document with size and flags per file.
+    Json,
+}
+
+/// Methods of `Cli`.
+impl Cli {
+    /// Returns the verbosity of the console output.
+    pub fn verbosity(&self) -> Verbosity {
+        match self.verbose {
+            0 => Verbosity::Default,
+            1 => Verbosity::Quiet,
+            _ => Verbosity::Verbose,
+        }
+    }
 }
 
 #[derive(Subcommand)]
 pub enum MainCommands {
     /// Run a backup
     Backup {
-        /// The name of the backup profile.
-        backup: String,
+        /// The names of the backup profiles, they are run in the given order.
+        #[arg(num_args(1..), required_unless_present = "profile_filter")]
+        backup: Vec<String>,
+
+        /// Run all backup profiles, whose names match the glob pattern.
+        #[arg(long, conflicts_with = "backup")]
+        profile_filter: Option<String>,
+
+        /// Do not run the backup.
+        #[arg(long, action = ArgAction::SetTrue)]
+        dry_run: bool,
+
+        /// Show the estimated bytes to transfer before the backup.
+        #[arg(long, action = ArgAction::SetTrue)]
+        estimate_size: bool,
     },
     /// Run a restore
     Restore {
         /// The name of the restore profile.
         restore: String,
     },
+    /// Restore a single file
+    RestoreFile {
+        /// The name of the restore profile.
+        restore: String,
+
+        /// The path of the file in the source of the backup.
+        file: String,
+
+        /// Save the file to this absolute path instead of the dest dir of the profile.
+        #[arg(long)]
+        save_to: Option<String>,
+    },
     /// Run a verify
     Verify {
-        /// The name of the backup profile.
-        backup: String,
+        /// The names of the backup profiles, they are verified in the given order.
+        #[arg(num_args(1..), required = true)]
+        backup: Vec<String>,
 
-        /// Verify all files.
-        #[arg(long, action = ArgAction::SetTrue)]
+        /// Verify all files, same as `--mode all`.
+        #[arg(long, action = ArgAction::SetTrue, conflicts_with = "mode")]
         all: bool,
+
+        /// The files, which are verified.
+        #[arg(long, value_enum, default_value_t = VerifyModeArg::New)]
+        mode: VerifyModeArg,
+
+        /// The days after which a verified file is stale.
+        #[arg(long, default_value_t = 30)]
+        stale_days: u64,
+
+        /// Compare against freshly computed signatures of the source files.
+        #[arg(long, action = ArgAction::SetTrue)]
+        full: bool,
     },
     /// Run a clean
     Clean {
+        /// The names of the backup profiles, they are cleaned in the given order.
+        #[arg(num_args(1..), required = true)]
+        backup: Vec<String>,
+    },
+    /// Run a prune
+    Prune {
+        /// The names of the backup profiles, they are pruned in the given order.
+        #[arg(num_args(1..), required = true)]
+        backup: Vec<String>,
+    },
+    /// Export the backup index as csv
+    ExportCsv {
         /// The name of the backup profile.
         backup: String,
+
+        /// The csv file.
+        output: String,
     },
+    /// Export a checksum manifest of a backup
+    Manifest {
+        #[command(subcommand)]
+        command: ManifestCommands,
+    },
+    /// Merge two backup indexes (cuba.json.gz) into one
+    MergeIndex {
+        /// The first index.
+        file1: String,
+
+        /// The second index.
+        file2: String,
+
+        /// The merged index.
+        output: String,
+    },
+    /// Show the message counts of the last run
+    Status,
     /// Manage passwords.
     Password {
         #[command(subcommand)]
@@ -64,6 +201,27 @@ pub enum PasswordCommands {
     },
     /// Lists the password ids.
     List,
+    /// Verifies that a password is stored.
+    Verify {
+        /// The password id.
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ManifestCommands {
+    /// Export the signatures of the backup index.
+    Export {
+        /// The name of the backup profile.
+        backup: String,
+
+        /// The manifest file.
+        output: String,
+
+        /// The format of the manifest.
+        #[arg(long, value_enum, default_value_t = ManifestFormatArg::Text)]
+        format: ManifestFormatArg,
+    },
 }
 
 #[derive(Subcommand)]
@@ -73,6 +231,8 @@ pub enum ConfigCommands {
         #[command(subcommand)]
         command: ConfigExampleCommands,
     },
+    /// Validate the config.
+    Validate,
 }
 
 #[derive(Subcommand)]
@@ -2,12 +2,33 @@ use console::Style;
 use cuba_lib::shared::message::Info;
 use cuba_lib::shared::msg_receiver::MsgHandler;
 use cuba_lib::shared::npath::{Rel, UNPath};
+use cuba_lib::shared::rich_error::RichError;
+use indicatif::{HumanBytes, HumanDuration};
+use std::collections::HashMap;
 use std::error::Error;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Defines a `Verbosity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    /// Prints only transferred files and errors.
+    Quiet,
+
+    /// Prints all messages, the progress bars take their place during a run.
+    #[default]
+    Default,
+
+    /// Prints all messages, file sizes and transfer rates.
+    Verbose,
+}
 
 /// Defines a `ConsoleOut`.
 ///
 /// Prints messages to the console.
 pub struct ConsoleOut {
+    verbosity: Verbosity,
+    task_start: Mutex<HashMap<usize, Instant>>,
     green: Style,
     yellow: Style,
     red: Style,
@@ -16,19 +37,32 @@ pub struct ConsoleOut {
 /// Methods of `ConsoleOut`.
 impl ConsoleOut {
     /// Creates a new `ConsoleOut`.
-    pub fn new() -> Self {
+    pub fn new(verbosity: Verbosity) -> Self {
         let green = Style::new().green().bold();
         let yellow = Style::new().yellow().bold();
         let red = Style::new().red().bold();
 
-        Self { green, yellow, red }
+        Self {
+            verbosity,
+            task_start: Mutex::new(HashMap::new()),
+            green,
+            yellow,
+            red,
+        }
+    }
+
+    /// Prints a task info, if not quiet.
+    fn print_task_info(&self, rel_path: &UNPath<Rel>, info: &(dyn Info + Send + Sync)) {
+        if self.verbosity != Verbosity::Quiet {
+            println!("{:?} : {}", rel_path, self.green.apply_to(info));
+        }
     }
 }
 
 /// Impl of `Default` for `ConsoleOut`.
 impl Default for ConsoleOut {
     fn default() -> Self {
-        Self::new()
+        Self::new(Verbosity::default())
     }
 }
 
@@ -37,11 +71,19 @@ impl MsgHandler for ConsoleOut {
     /// Handles a `TaskInfo::Start` message.
     fn task_start(
         &self,
-        _thread_number: usize,
+        thread_number: usize,
         rel_path: &UNPath<Rel>,
         info: &(dyn Info + Send + Sync),
     ) {
-        println!("{:?} : {}", rel_path, self.green.apply_to(info));
+        // Remember the start for the transfer rate.
+        if self.verbosity == Verbosity::Verbose {
+            self.task_start
+                .lock()
+                .unwrap()
+                .insert(thread_number, Instant::now());
+        }
+
+        self.print_task_info(rel_path, info);
     }
 
     /// Handles a `TaskInfo::Transferring` message.
@@ -51,7 +93,7 @@ impl MsgHandler for ConsoleOut {
         rel_path: &UNPath<Rel>,
         info: &(dyn Info + Send + Sync),
     ) {
-        println!("{:?} : {}", rel_path, self.green.apply_to(info));
+        self.print_task_info(rel_path, info);
     }
 
     /// Handles a `TaskInfo::Finished` message.
@@ -61,7 +103,7 @@ impl MsgHandler for ConsoleOut {
         rel_path: &UNPath<Rel>,
         info: &(dyn Info + Send + Sync),
     ) {
-        println!("{:?} : {}", rel_path, self.green.apply_to(info));
+        self.print_task_info(rel_path, info);
     }
 
     /// Handles a `TaskInfo::Transferred` message.
@@ -74,6 +116,36 @@ impl MsgHandler for ConsoleOut {
         println!("{:?} : {}", rel_path, self.green.apply_to(info));
     }
 
+    /// Handles a `TransferredBytes` message.
+    fn task_transferred_bytes(&self, thread_number: usize, rel_path: &UNPath<Rel>, bytes: u64) {
+        if self.verbosity == Verbosity::Verbose {
+            let elapsed = self
+                .task_start
+                .lock()
+                .unwrap()
+                .remove(&thread_number)
+                .map(|start| start.elapsed());
+
+            match elapsed {
+                Some(elapsed) if !elapsed.is_zero() => println!(
+                    "{:?} : {}",
+                    rel_path,
+                    self.green.apply_to(format!(
+                        "{} in {} ({}/s)",
+                        HumanBytes(bytes),
+                        HumanDuration(elapsed),
+                        HumanBytes((bytes as f64 / elapsed.as_secs_f64()) as u64)
+                    ))
+                ),
+                _ => println!(
+                    "{:?} : {}",
+                    rel_path,
+                    self.green.apply_to(HumanBytes(bytes))
+                ),
+            }
+        }
+    }
+
     /// Handles a `TaskInfo::UpToDate` message.
     fn task_up_to_date(
         &self,
@@ -81,7 +153,7 @@ impl MsgHandler for ConsoleOut {
         rel_path: &UNPath<Rel>,
         info: &(dyn Info + Send + Sync),
     ) {
-        println!("{:?} : {}", rel_path, self.green.apply_to(info));
+        self.print_task_info(rel_path, info);
     }
 
     /// Handles a `TaskInfo::Verified` message.
@@ -95,12 +167,7 @@ impl MsgHandler for ConsoleOut {
     }
 
     /// Handles a `TaskMessage` with error.
-    fn task_error(
-        &self,
-        _thread_number: usize,
-        rel_path: &UNPath<Rel>,
-        error: &(dyn Error + Send + Sync),
-    ) {
+    fn task_error(&self, _thread_number: usize, rel_path: &UNPath<Rel>, error: &RichError) {
         println!("{:?} : {}", rel_path, self.red.apply_to(error));
     }
 
@@ -0,0 +1,39 @@
+use cuba_lib::shared::msg_receiver::MsgHandler;
+use cuba_lib::shared::npath::{Rel, UNPath};
+use cuba_lib::shared::rich_error::RichError;
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Defines an `ErrorCounter`.
+///
+/// Counts the error messages of a run.
+#[derive(Default)]
+pub struct ErrorCounter {
+    count: AtomicUsize,
+}
+
+/// Methods of `ErrorCounter`.
+impl ErrorCounter {
+    /// Returns the number of counted errors.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+/// Impl of `MsgHandler` for `ErrorCounter`.
+impl MsgHandler for ErrorCounter {
+    /// Handles a `TaskMessage` with error.
+    fn task_error(&self, _thread_number: usize, _rel_path: &UNPath<Rel>, _error: &RichError) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Handles a `CleanMessage` with error.
+    fn clean_error(&self, _rel_path: &UNPath<Rel>, _error: &(dyn Error + Send + Sync)) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Handles a `ErrorMessage`.
+    fn error(&self, _error: &(dyn Error + Send + Sync)) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
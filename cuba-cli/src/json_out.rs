@@ -0,0 +1,223 @@
+use cuba_lib::shared::message::Info;
+use cuba_lib::shared::msg_receiver::{MsgHandler, trace_error};
+use cuba_lib::shared::npath::{Rel, UNPath};
+use cuba_lib::shared::rich_error::RichError;
+use serde_json::{Value, json};
+use std::error::Error;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Defines a `JsonOut`.
+///
+/// Prints each message as a JSON object per line (NDJSON) to the console.
+/// On stop a summary of the run is printed as the last object.
+#[derive(Default)]
+pub struct JsonOut {
+    transferred: AtomicUsize,
+    up_to_date: AtomicUsize,
+    errors: AtomicUsize,
+}
+
+/// Methods of `JsonOut`.
+impl JsonOut {
+    /// Prints a JSON object as a line.
+    fn print(&self, json: Value) {
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}", json);
+    }
+
+    /// Prints a task info.
+    fn print_task_info(
+        &self,
+        kind: &str,
+        thread_number: usize,
+        rel_path: &UNPath<Rel>,
+        info: &(dyn Info + Send + Sync),
+    ) {
+        self.print(json!({
+            "kind": kind,
+            "thread_number": thread_number,
+            "rel_path": rel_path.to_unicode(),
+            "text": info.to_string(),
+        }));
+    }
+
+    /// Prints a progress info.
+    fn print_progress(&self, kind: &str, ticks: u64, info: &(dyn Info + Send + Sync)) {
+        self.print(json!({
+            "kind": kind,
+            "ticks": ticks,
+            "text": info.to_string(),
+        }));
+    }
+}
+
+/// Impl of `MsgHandler` for `JsonOut`.
+impl MsgHandler for JsonOut {
+    /// Prints the summary.
+    fn stopped(&self) {
+        self.print(json!({
+            "kind": "summary",
+            "transferred": self.transferred.load(Ordering::Relaxed),
+            "up_to_date": self.up_to_date.load(Ordering::Relaxed),
+            "errors": self.errors.load(Ordering::Relaxed),
+        }));
+    }
+
+    /// Handles a `TaskInfo::Start` message.
+    fn task_start(
+        &self,
+        thread_number: usize,
+        rel_path: &UNPath<Rel>,
+        info: &(dyn Info + Send + Sync),
+    ) {
+        self.print_task_info("task_start", thread_number, rel_path, info);
+    }
+
+    /// Handles a `TaskInfo::Transferring` message.
+    fn task_transferring(
+        &self,
+        thread_number: usize,
+        rel_path: &UNPath<Rel>,
+        info: &(dyn Info + Send + Sync),
+    ) {
+        self.print_task_info("task_transferring", thread_number, rel_path, info);
+    }
+
+    /// Handles a `TaskInfo::Finished` message.
+    fn task_finished(
+        &self,
+        thread_number: usize,
+        rel_path: &UNPath<Rel>,
+        info: &(dyn Info + Send + Sync),
+    ) {
+        self.print_task_info("task_finished", thread_number, rel_path, info);
+    }
+
+    /// Handles a `TaskInfo::Transferred` message.
+    fn task_transferred(
+        &self,
+        thread_number: usize,
+        rel_path: &UNPath<Rel>,
+        info: &(dyn Info + Send + Sync),
+    ) {
+        self.transferred.fetch_add(1, Ordering::Relaxed);
+        self.print_task_info("task_transferred", thread_number, rel_path, info);
+    }
+
+    /// Handles a `TransferredBytes` message.
+    fn task_transferred_bytes(&self, thread_number: usize, rel_path: &UNPath<Rel>, bytes: u64) {
+        self.print(json!({
+            "kind": "task_transferred_bytes",
+            "thread_number": thread_number,
+            "rel_path": rel_path.to_unicode(),
+            "bytes": bytes,
+        }));
+    }
+
+    /// Handles a `TaskInfo::UpToDate` message.
+    fn task_up_to_date(
+        &self,
+        thread_number: usize,
+        rel_path: &UNPath<Rel>,
+        info: &(dyn Info + Send + Sync),
+    ) {
+        self.up_to_date.fetch_add(1, Ordering::Relaxed);
+        self.print_task_info("task_up_to_date", thread_number, rel_path, info);
+    }
+
+    /// Handles a `TaskInfo::Verified` message.
+    fn task_verified(
+        &self,
+        thread_number: usize,
+        rel_path: &UNPath<Rel>,
+        info: &(dyn Info + Send + Sync),
+    ) {
+        self.print_task_info("task_verified", thread_number, rel_path, info);
+    }
+
+    /// Handles a `TaskMessage` with error.
+    fn task_error(&self, thread_number: usize, rel_path: &UNPath<Rel>, error: &RichError) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        self.print(json!({
+            "kind": "task_error",
+            "thread_number": thread_number,
+            "rel_path": rel_path.to_unicode(),
+            "text": error.to_string(),
+            "error": error.to_json(),
+        }));
+    }
+
+    /// Handles a `ProgressInfo::Ticks` message.
+    fn progress_ticks(&self, ticks: u64, info: &(dyn Info + Send + Sync)) {
+        self.print_progress("progress_ticks", ticks, info);
+    }
+
+    /// Handles a `ProgressInfo::Duration` message.
+    fn progress_duration(&self, ticks: u64, info: &(dyn Info + Send + Sync)) {
+        self.print_progress("progress_duration", ticks, info);
+    }
+
+    /// Handles a `ProgressInfo::Bytes` message.
+    fn progress_bytes(&self, transferred: u64, total: u64, info: &(dyn Info + Send + Sync)) {
+        self.print(json!({
+            "kind": "progress_bytes",
+            "transferred": transferred,
+            "total": total,
+            "text": info.to_string(),
+        }));
+    }
+
+    /// Handles a `CleanInfo::Ok` message.
+    fn clean_ok(&self, rel_path: &UNPath<Rel>, info: &(dyn Info + Send + Sync)) {
+        self.print(json!({
+            "kind": "clean_ok",
+            "rel_path": rel_path.to_unicode(),
+            "text": info.to_string(),
+        }));
+    }
+
+    /// Handles a `CleanInfo::Removed` message.
+    fn clean_removed(&self, rel_path: &UNPath<Rel>, info: &(dyn Info + Send + Sync)) {
+        self.print(json!({
+            "kind": "clean_removed",
+            "rel_path": rel_path.to_unicode(),
+            "text": info.to_string(),
+        }));
+    }
+
+    /// Handles a `CleanMessage` with error.
+    fn clean_error(&self, rel_path: &UNPath<Rel>, error: &(dyn Error + Send + Sync)) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        self.print(json!({
+            "kind": "clean_error",
+            "rel_path": rel_path.to_unicode(),
+            "text": trace_error(error),
+        }));
+    }
+
+    /// Handles a `InfoMessage`.
+    fn info(&self, info: &(dyn Info + Send + Sync)) {
+        self.print(json!({
+            "kind": "info",
+            "text": info.to_string(),
+        }));
+    }
+
+    /// Handles a `WarnMessage`.
+    fn warn(&self, warning: &(dyn Info + Send + Sync)) {
+        self.print(json!({
+            "kind": "warn",
+            "text": warning.to_string(),
+        }));
+    }
+
+    /// Handles a `ErrorMessage`.
+    fn error(&self, error: &(dyn Error + Send + Sync)) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        self.print(json!({
+            "kind": "error",
+            "text": trace_error(error),
+        }));
+    }
+}
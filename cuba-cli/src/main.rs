@@ -1,29 +1,51 @@
 mod cli_cmds;
 mod console_out;
+mod error_counter;
+mod json_out;
 mod msg_file_logger;
 mod progress_bars;
+mod summary_collector;
 
 use clap::{CommandFactory, Parser};
 use crossbeam_channel::{Sender, unbounded};
-use cuba_lib::{send_error, send_info};
+use cuba_lib::{send_error, send_info, send_warn};
+use indicatif::HumanBytes;
 use inquire::Password;
-use secrecy::SecretString;
+use secrecy::{ExposeSecret, SecretString};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use std::{fs, io};
 
-use cuba_lib::core::cuba::{Cuba, RunHandle};
+use cuba_lib::core::cuba::{Cuba, ManifestFormat, RunHandle, VerifyMode};
 use cuba_lib::shared::config::{EXAMPLE_CONFIG, load_config_from_file};
 use cuba_lib::shared::message::Message;
 use cuba_lib::shared::message::StringError;
-use cuba_lib::shared::msg_dispatcher::MsgDispatcher;
+use cuba_lib::shared::msg_dispatcher::{DispatcherMetrics, MsgDispatcher};
 use cuba_lib::shared::msg_receiver::MsgReceiver;
-
-use crate::cli_cmds::{Cli, ConfigCommands, ConfigExampleCommands, MainCommands, PasswordCommands};
-use crate::console_out::ConsoleOut;
+use cuba_lib::shared::npath::{Abs, File, NPath, PLATFORM_MAX_PATH_LEN, Rel, set_max_path_len};
+
+use crate::cli_cmds::{
+    Cli, ConfigCommands, ConfigExampleCommands, MainCommands, ManifestCommands, ManifestFormatArg,
+    OutputFormat, PasswordCommands, VerifyModeArg,
+};
+use crate::console_out::{ConsoleOut, Verbosity};
+use crate::error_counter::ErrorCounter;
+use crate::json_out::JsonOut;
 use crate::msg_file_logger::MsgFileLoggerBuilder;
 use crate::progress_bars::ProgressBars;
+use crate::summary_collector::SummaryCollector;
+
+/// The config file.
+const CONFIG_FILE: &str = "cuba.toml";
+
+/// The file of the message counts of the last run, next to the config file.
+const STATS_FILE: &str = "cuba-stats.json";
+
+/// The exit code of a run, which was canceled by Ctrl-C.
+const EXIT_CODE_CANCELED: i32 = 130;
 
 /// A macro the subscribes the `MsgFileLogger` to the `MsgDispatcher`.
 macro_rules! use_logger {
@@ -49,6 +71,7 @@ macro_rules! use_logger {
 macro_rules! unuse_logger {
     ($msg_logger:ident, $msg_dispatcher:expr) => {{
         if let Some(mut logger) = $msg_logger.take() {
+            $msg_dispatcher.flush();
             logger.stop();
         }
     }};
@@ -56,10 +79,26 @@ macro_rules! unuse_logger {
 
 /// A macro the subscribes the `ConsoleOut` to the `MsgDispatcher`.
 macro_rules! use_console_out {
+    ($msg_console_out:ident, $msg_dispatcher:expr, $verbosity:expr) => {{
+        let ch_msg_receiver = $msg_dispatcher.subscribe();
+
+        let msg_receiver = MsgReceiver::new(ch_msg_receiver, Arc::new(ConsoleOut::new($verbosity)));
+        $msg_console_out = Some(msg_receiver);
+
+        if let Some(console_out) = $msg_console_out.as_mut() {
+            console_out.start();
+        }
+    }};
+}
+
+/// A macro the subscribes the `JsonOut` to the `MsgDispatcher`.
+///
+/// The `JsonOut` takes the place of the `ConsoleOut`.
+macro_rules! use_json_out {
     ($msg_console_out:ident, $msg_dispatcher:expr) => {{
         let ch_msg_receiver = $msg_dispatcher.subscribe();
 
-        let msg_receiver = MsgReceiver::new(ch_msg_receiver, Arc::new(ConsoleOut::new()));
+        let msg_receiver = MsgReceiver::new(ch_msg_receiver, Arc::new(JsonOut::default()));
         $msg_console_out = Some(msg_receiver);
 
         if let Some(console_out) = $msg_console_out.as_mut() {
@@ -72,6 +111,7 @@ macro_rules! use_console_out {
 macro_rules! unuse_console_out {
     ($msg_console_out:ident, $msg_dispatcher:expr) => {{
         if let Some(mut console_out) = $msg_console_out.take() {
+            $msg_dispatcher.flush();
             console_out.stop();
         }
     }};
@@ -92,15 +132,66 @@ macro_rules! use_progress {
     }};
 }
 
+/// A macro the subscribes the `ProgressBars` with a labeled total bar to the `MsgDispatcher`.
+macro_rules! use_labeled_progress {
+    ($msg_progress_bars:ident, $msg_dispatcher:expr, $threads:expr, $label:expr) => {{
+        let ch_msg_receiver = $msg_dispatcher.subscribe();
+
+        let msg_receiver = MsgReceiver::new(
+            ch_msg_receiver,
+            Arc::new(ProgressBars::new($threads).with_label($label)),
+        );
+
+        $msg_progress_bars = Some(msg_receiver);
+
+        if let Some(progress) = $msg_progress_bars.as_mut() {
+            progress.start();
+        }
+    }};
+}
+
 /// A macro the unsubscribes the `ProgressBars` from the `MsgDispatcher`.
 macro_rules! unuse_progress {
     ($msg_progress_bars:ident, $msg_dispatcher:expr) => {{
         if let Some(mut progress) = $msg_progress_bars.take() {
+            $msg_dispatcher.flush();
             progress.stop();
         }
     }};
 }
 
+/// Runs `run` with an `ErrorCounter` and a `SummaryCollector` subscribed to the `MsgDispatcher`.
+///
+/// Prints the summary for the text output. Returns the result of `run` and the number of errors.
+fn with_summary<R>(
+    msg_dispatcher: &MsgDispatcher<Arc<dyn Message>>,
+    output: OutputFormat,
+    run: impl FnOnce() -> R,
+) -> (R, usize) {
+    // Count the errors of the run.
+    let error_counter = Arc::new(ErrorCounter::default());
+    let mut msg_error_counter = MsgReceiver::new(msg_dispatcher.subscribe(), error_counter.clone());
+    msg_error_counter.start();
+
+    // Collect the summary of the run.
+    let summary_collector = Arc::new(SummaryCollector::default());
+    let mut msg_summary_collector =
+        MsgReceiver::new(msg_dispatcher.subscribe(), summary_collector.clone());
+    msg_summary_collector.start();
+
+    let result = run();
+
+    msg_dispatcher.flush();
+    msg_error_counter.stop();
+    msg_summary_collector.stop();
+
+    if output == OutputFormat::Text {
+        summary_collector.print("Summary");
+    }
+
+    (result, error_counter.count())
+}
+
 /// A prompt for setting the password.
 fn prompt_password(sender: Sender<Arc<dyn Message>>) -> String {
     loop {
@@ -139,7 +230,7 @@ fn prompt_password(sender: Sender<Arc<dyn Message>>) -> String {
 
 /// Writes the example config to the cuba.toml.
 pub fn write_example_config(sender: Sender<Arc<dyn Message>>) {
-    let path = Path::new("cuba.toml");
+    let path = Path::new(CONFIG_FILE);
 
     if path.exists() {
         print!("cuba.toml already exists. Overwrite? [y/N]: ");
@@ -170,7 +261,50 @@ pub fn write_example_config(sender: Sender<Arc<dyn Message>>) {
     }
 }
 
+/// Returns the path of the stats file, which is next to the config file.
+fn stats_file_path() -> PathBuf {
+    std::path::absolute(CONFIG_FILE)
+        .unwrap_or_else(|_| PathBuf::from(CONFIG_FILE))
+        .with_file_name(STATS_FILE)
+}
+
+/// Writes the message counts of a run to the stats file.
+fn write_metrics(sender: Sender<Arc<dyn Message>>, metrics: &DispatcherMetrics) {
+    let result = serde_json::to_string_pretty(metrics)
+        .map_err(io::Error::other)
+        .and_then(|json| fs::write(stats_file_path(), json));
+
+    if let Err(error) = result {
+        send_error!(sender, error);
+    }
+}
+
+/// Reads the message counts of the last run from the stats file.
+fn read_metrics(sender: Sender<Arc<dyn Message>>) -> Option<DispatcherMetrics> {
+    let stats_file_path = stats_file_path();
+    let result = fs::read_to_string(&stats_file_path)
+        .and_then(|json| serde_json::from_str(&json).map_err(io::Error::other));
+
+    match result {
+        Ok(metrics) => Some(metrics),
+        Err(error) => {
+            send_error!(
+                sender,
+                StringError::new(format!(
+                    "Failed to read {}: {}",
+                    stats_file_path.display(),
+                    error
+                ))
+            );
+            None
+        }
+    }
+}
+
 fn main() {
+    // Limit the paths to the length of the platform.
+    set_max_path_len(PLATFORM_MAX_PATH_LEN);
+
     let (sender, receiver) = unbounded::<Arc<dyn Message>>();
 
     let mut msg_dispatcher = MsgDispatcher::new(receiver.clone());
@@ -184,8 +318,45 @@ fn main() {
     #[allow(unused_assignments)]
     let mut msg_progress_bars: Option<MsgReceiver> = None;
 
+    // Parse the args first, the console output depends on them.
+    let cli = Cli::try_parse();
+    let verbosity = cli.as_ref().map(Cli::verbosity).unwrap_or_default();
+    let output = cli.as_ref().map(|cli| cli.output).unwrap_or_default();
+
+    // Only the default text output shows the progress bars, quiet, verbose and JSON print
+    // the messages.
+    let show_progress = verbosity == Verbosity::Default && output == OutputFormat::Text;
+
+    // The exit code of the process.
+    let mut exit_code = 0;
+
+    // The handle of the runs, Ctrl-C cancels the current run and skips the following ones.
+    let run_handle = RunHandle::new();
+    let canceled = Arc::new(AtomicBool::new(false));
+
+    {
+        let run_handle = run_handle.clone();
+        let canceled = canceled.clone();
+
+        if let Err(err) = ctrlc::set_handler(move || {
+            // A second Ctrl-C exits at once.
+            if canceled.swap(true, Ordering::SeqCst) {
+                std::process::exit(EXIT_CODE_CANCELED);
+            }
+
+            run_handle.request_cancel();
+        }) {
+            send_error!(sender, err);
+        }
+    }
+
+    let is_canceled = || canceled.load(Ordering::SeqCst);
+
     use_logger!(msg_logger, msg_dispatcher);
-    use_console_out!(msg_console_out, msg_dispatcher);
+    match output {
+        OutputFormat::Text => use_console_out!(msg_console_out, msg_dispatcher, verbosity),
+        OutputFormat::Json => use_json_out!(msg_console_out, msg_dispatcher),
+    }
 
     // Show help if no arguments are passed.
     if std::env::args().len() == 1 {
@@ -193,75 +364,370 @@ fn main() {
     } else {
         let mut cuba = Cuba::new(sender.clone());
 
-        if let Some(config) = load_config_from_file(sender.clone(), "cuba.toml") {
+        if let Some(config) = load_config_from_file(sender.clone(), CONFIG_FILE) {
             cuba.set_config(config);
         }
 
-        match Cli::try_parse() {
+        // Persist the message counts of runs for the status.
+        let persist_metrics = matches!(
+            &cli,
+            Ok(Cli {
+                command: MainCommands::Backup { .. }
+                    | MainCommands::Restore { .. }
+                    | MainCommands::RestoreFile { .. }
+                    | MainCommands::Verify { .. }
+                    | MainCommands::Clean { .. }
+                    | MainCommands::Prune { .. },
+                ..
+            })
+        );
+
+        match cli {
             Ok(cli) => match &cli.command {
-                MainCommands::Backup { backup } => {
+                MainCommands::Backup {
+                    backup,
+                    profile_filter,
+                    dry_run,
+                    estimate_size,
+                } => {
                     if let Some(config) = cuba.requires_config() {
-                        send_info!(sender, "Start backup of {:?}", backup);
-                        unuse_console_out!(msg_console_out, msg_dispatcher);
-                        use_progress!(msg_progress_bars, msg_dispatcher, config.transfer_threads);
-
-                        cuba.run_backup(RunHandle::default(), backup);
+                        // The profiles to run.
+                        let backups = match profile_filter {
+                            Some(profile_filter) if backup.is_empty() => cuba
+                                .match_backup_profiles(profile_filter)
+                                .unwrap_or_default(),
+                            _ => backup.clone(),
+                        };
+
+                        if backups.is_empty() {
+                            send_error!(
+                                sender,
+                                StringError::new("No backup profile to run".to_string())
+                            );
+                            exit_code = 1;
+                        }
 
-                        unuse_progress!(msg_progress_bars, msg_dispatcher);
-                        use_console_out!(msg_console_out, msg_dispatcher);
-                        send_info!(sender, "Backup finished");
+                        for backup in &backups {
+                            // Skip the following profiles, if canceled.
+                            if is_canceled() {
+                                break;
+                            }
+
+                            // The exit code is the count of the failed profiles.
+                            let mut failed = false;
+
+                            if *estimate_size {
+                                match cuba.get_backup_size_estimate(backup) {
+                                    Some(size) => send_info!(
+                                        sender,
+                                        "Estimated size of backup {:?}: {}",
+                                        backup,
+                                        HumanBytes(size)
+                                    ),
+                                    None => failed = true,
+                                }
+                            }
+
+                            if *dry_run {
+                                if failed {
+                                    exit_code += 1;
+                                }
+                                continue;
+                            }
+
+                            send_info!(sender, "Start backup of {:?}", backup);
+                            let (result, errors) = with_summary(&msg_dispatcher, output, || {
+                                if show_progress {
+                                    unuse_console_out!(msg_console_out, msg_dispatcher);
+                                    use_progress!(
+                                        msg_progress_bars,
+                                        msg_dispatcher,
+                                        config.transfer_threads
+                                    );
+                                }
+
+                                let result = cuba.run_backup(run_handle.clone(), backup);
+
+                                if show_progress {
+                                    unuse_progress!(msg_progress_bars, msg_dispatcher);
+                                    use_console_out!(msg_console_out, msg_dispatcher, verbosity);
+                                }
+
+                                result
+                            });
+                            send_info!(sender, "Backup finished");
+
+                            if failed || result.is_err() || errors > 0 {
+                                exit_code += 1;
+                            }
+                        }
                     }
                 }
                 MainCommands::Restore { restore } => {
                     if let Some(config) = cuba.requires_config() {
                         send_info!(sender, "Start restore of {:?}", restore);
-                        unuse_console_out!(msg_console_out, msg_dispatcher);
-                        use_progress!(msg_progress_bars, msg_dispatcher, config.transfer_threads);
-
-                        cuba.run_restore(RunHandle::default(), restore);
-
-                        unuse_progress!(msg_progress_bars, msg_dispatcher);
-                        use_console_out!(msg_console_out, msg_dispatcher);
+                        let (result, errors) = with_summary(&msg_dispatcher, output, || {
+                            if show_progress {
+                                unuse_console_out!(msg_console_out, msg_dispatcher);
+                                use_progress!(
+                                    msg_progress_bars,
+                                    msg_dispatcher,
+                                    config.transfer_threads
+                                );
+                            }
+
+                            let result = cuba.run_restore(run_handle.clone(), restore, None, None);
+
+                            if show_progress {
+                                unuse_progress!(msg_progress_bars, msg_dispatcher);
+                                use_console_out!(msg_console_out, msg_dispatcher, verbosity);
+                            }
+
+                            result
+                        });
                         send_info!(sender, "Restore finished");
+
+                        if result.is_err() || errors > 0 {
+                            exit_code = 1;
+                        }
                     }
                 }
-                MainCommands::Verify { backup, all } => {
-                    if let Some(config) = cuba.requires_config() {
-                        send_info!(sender, "Start verify of {:?}", backup);
-                        unuse_console_out!(msg_console_out, msg_dispatcher);
-                        use_progress!(msg_progress_bars, msg_dispatcher, config.transfer_threads);
-
-                        cuba.run_verify(RunHandle::default(), backup, all);
+                MainCommands::RestoreFile {
+                    restore,
+                    file,
+                    save_to,
+                } => {
+                    let restore_file = NPath::<Rel, File>::try_from(file.as_str());
+                    let restore_to = save_to
+                        .as_deref()
+                        .map(NPath::<Abs, File>::try_from)
+                        .transpose();
+
+                    match (restore_file, restore_to) {
+                        (Ok(restore_file), Ok(restore_to)) => {
+                            send_info!(sender, "Start restore of {:?} from {:?}", file, restore);
+
+                            let result = cuba.run_restore(
+                                run_handle.clone(),
+                                restore,
+                                Some(&restore_file),
+                                restore_to.as_ref(),
+                            );
+
+                            send_info!(sender, "Restore finished");
+
+                            if result.is_err() {
+                                exit_code = 1;
+                            }
+                        }
+                        (Err(err), _) | (_, Err(err)) => {
+                            send_error!(sender, err);
+                            exit_code = 1;
+                        }
+                    }
+                }
+                MainCommands::Verify {
+                    backup,
+                    all,
+                    mode,
+                    stale_days,
+                    full,
+                } => {
+                    // Select the verified files.
+                    let verify_mode = match mode {
+                        _ if *all => VerifyMode::All,
+                        VerifyModeArg::New => VerifyMode::New,
+                        VerifyModeArg::All => VerifyMode::All,
+                        VerifyModeArg::Stale => {
+                            VerifyMode::Stale(Duration::from_secs(stale_days * 24 * 60 * 60))
+                        }
+                    };
 
-                        unuse_progress!(msg_progress_bars, msg_dispatcher);
-                        use_console_out!(msg_console_out, msg_dispatcher);
-                        send_info!(sender, "Verify finished");
+                    if let Some(config) = cuba.requires_config() {
+                        for backup in backup {
+                            // Skip the following profiles, if canceled.
+                            if is_canceled() {
+                                break;
+                            }
+
+                            send_info!(sender, "Start verify of {:?}", backup);
+                            let (result, errors) = with_summary(&msg_dispatcher, output, || {
+                                if show_progress {
+                                    unuse_console_out!(msg_console_out, msg_dispatcher);
+                                    use_progress!(
+                                        msg_progress_bars,
+                                        msg_dispatcher,
+                                        config.transfer_threads
+                                    );
+                                }
+
+                                let result =
+                                    cuba.run_verify(run_handle.clone(), backup, &verify_mode, full);
+
+                                if show_progress {
+                                    unuse_progress!(msg_progress_bars, msg_dispatcher);
+                                    use_console_out!(msg_console_out, msg_dispatcher, verbosity);
+                                }
+
+                                result
+                            });
+                            send_info!(sender, "Verify finished");
+
+                            // The exit code is the count of the failed profiles.
+                            if result.is_err() || errors > 0 {
+                                exit_code += 1;
+                            }
+                        }
                     }
                 }
                 MainCommands::Clean { backup } => {
                     if let Some(config) = cuba.requires_config() {
-                        send_info!(sender, "Start clean of {:?}", backup);
-                        unuse_console_out!(msg_console_out, msg_dispatcher);
-                        use_progress!(msg_progress_bars, msg_dispatcher, config.transfer_threads);
-
-                        cuba.run_clean(RunHandle::default(), backup);
-
-                        unuse_progress!(msg_progress_bars, msg_dispatcher);
-                        use_console_out!(msg_console_out, msg_dispatcher);
-                        send_info!(sender, "Clean finished");
+                        for backup in backup {
+                            // Skip the following profiles, if canceled.
+                            if is_canceled() {
+                                break;
+                            }
+
+                            send_info!(sender, "Start clean of {:?}", backup);
+                            let (result, errors) = with_summary(&msg_dispatcher, output, || {
+                                if show_progress {
+                                    unuse_console_out!(msg_console_out, msg_dispatcher);
+                                    use_labeled_progress!(
+                                        msg_progress_bars,
+                                        msg_dispatcher,
+                                        config.transfer_threads,
+                                        &format!("Clean {}", backup)
+                                    );
+                                }
+
+                                let result = cuba.run_clean(run_handle.clone(), backup);
+
+                                if show_progress {
+                                    unuse_progress!(msg_progress_bars, msg_dispatcher);
+                                    use_console_out!(msg_console_out, msg_dispatcher, verbosity);
+                                }
+
+                                result
+                            });
+                            send_info!(sender, "Clean of {:?} finished", backup);
+
+                            // The exit code is the count of the failed profiles.
+                            if result.is_err() || errors > 0 {
+                                exit_code += 1;
+                            }
+                        }
+                    }
+                }
+                MainCommands::Prune { backup } => {
+                    if let Some(config) = cuba.requires_config() {
+                        for backup in backup {
+                            // Skip the following profiles, if canceled.
+                            if is_canceled() {
+                                break;
+                            }
+
+                            send_info!(sender, "Start prune of {:?}", backup);
+                            let (result, errors) = with_summary(&msg_dispatcher, output, || {
+                                if show_progress {
+                                    unuse_console_out!(msg_console_out, msg_dispatcher);
+                                    use_labeled_progress!(
+                                        msg_progress_bars,
+                                        msg_dispatcher,
+                                        config.transfer_threads,
+                                        &format!("Prune {}", backup)
+                                    );
+                                }
+
+                                let result = cuba.run_prune(run_handle.clone(), backup);
+
+                                if show_progress {
+                                    unuse_progress!(msg_progress_bars, msg_dispatcher);
+                                    use_console_out!(msg_console_out, msg_dispatcher, verbosity);
+                                }
+
+                                result
+                            });
+                            send_info!(sender, "Prune of {:?} finished", backup);
+
+                            // The exit code is the count of the failed profiles.
+                            if result.is_err() || errors > 0 {
+                                exit_code += 1;
+                            }
+                        }
+                    }
+                }
+                MainCommands::ExportCsv { backup, output } => {
+                    cuba.export_backup_csv(backup, output);
+                }
+                MainCommands::Manifest { command } => match command {
+                    ManifestCommands::Export {
+                        backup,
+                        output,
+                        format,
+                    } => {
+                        let format = match format {
+                            ManifestFormatArg::Text => ManifestFormat::Text,
+                            ManifestFormatArg::Json => ManifestFormat::Json,
+                        };
+
+                        cuba.export_backup_manifest(backup, output, format);
+                    }
+                },
+                MainCommands::MergeIndex {
+                    file1,
+                    file2,
+                    output,
+                } => {
+                    if cuba.merge_index(file1, file2, output).is_err() {
+                        exit_code = 1;
+                    }
+                }
+                MainCommands::Status => {
+                    if let Some(metrics) = read_metrics(sender.clone()) {
+                        println!("Last run:");
+                        println!("  Infos:    {}", metrics.info_count);
+                        println!("  Warnings: {}", metrics.warn_count);
+                        println!("  Errors:   {}", metrics.error_count);
+                    } else {
+                        exit_code = 1;
                     }
                 }
                 MainCommands::Password { command } => match command {
                     PasswordCommands::Set { id } => {
-                        let password = prompt_password(sender);
+                        let password = prompt_password(sender.clone());
                         cuba.set_password(id, &SecretString::from(password));
                     }
                     PasswordCommands::Delete { id } => {
                         cuba.delete_password(id);
                     }
+                    PasswordCommands::Verify { id } => match cuba.get_password(id) {
+                        Some(password) if !password.expose_secret().is_empty() => {
+                            send_info!(sender, "Password for '{}' is set", id);
+                        }
+                        Some(_) => {
+                            send_error!(
+                                sender,
+                                StringError::new(format!("Password for '{}' is empty", id))
+                            );
+                            exit_code = 1;
+                        }
+                        None => {
+                            // The error is sent by get_password.
+                            exit_code = 1;
+                        }
+                    },
                     PasswordCommands::List => {
-                        if let Some(password_ids) = cuba.get_password_ids() {
-                            println!("{:?}", password_ids);
+                        if let Some(password_ids) = cuba.list_passwords() {
+                            for password_id in password_ids {
+                                let usage = match cuba.config() {
+                                    Some(config) if config.has_password_id(&password_id) => {
+                                        "(in use)"
+                                    }
+                                    _ => "(unused)",
+                                };
+
+                                println!("{} {}", password_id, usage);
+                            }
                         }
                     }
                 },
@@ -271,18 +737,48 @@ fn main() {
                             println!("{}", EXAMPLE_CONFIG);
                         }
                         ConfigExampleCommands::Write => {
-                            write_example_config(sender);
+                            write_example_config(sender.clone());
                         }
                     },
+                    ConfigCommands::Validate => {
+                        if let Some(config) = cuba.requires_config() {
+                            let errors = config.validate();
+
+                            for err in &errors {
+                                send_error!(sender, err.clone());
+                            }
+
+                            if errors.is_empty() {
+                                send_info!(sender, "Config is valid");
+                            } else {
+                                exit_code = 1;
+                            }
+                        } else {
+                            exit_code = 1;
+                        }
+                    }
                 },
             },
             Err(err) => {
                 send_error!(sender.clone(), StringError::new(format!("{}", err)));
             }
         }
+
+        // A canceled run has its own exit code.
+        if is_canceled() {
+            send_warn!(sender, "Canceled by Ctrl-C");
+            exit_code = EXIT_CODE_CANCELED;
+        }
+
+        if persist_metrics {
+            msg_dispatcher.flush();
+            write_metrics(sender.clone(), &msg_dispatcher.metrics());
+        }
     }
 
     unuse_logger!(msg_logger, msg_dispatcher);
     unuse_console_out!(msg_console_out, msg_dispatcher);
     msg_dispatcher.stop();
+
+    std::process::exit(exit_code);
 }
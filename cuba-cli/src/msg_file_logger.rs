@@ -2,6 +2,7 @@ use flexi_logger::DeferredNow;
 use flexi_logger::writers::LogWriter;
 use flexi_logger::{Logger, WriteMode};
 use log::{LevelFilter, Record};
+use serde_json::json;
 use std::error::Error;
 use std::io::Write;
 use std::sync::Mutex;
@@ -9,6 +10,7 @@ use std::sync::Mutex;
 use cuba_lib::shared::message::Info;
 use cuba_lib::shared::msg_receiver::{MsgHandler, trace_error};
 use cuba_lib::shared::npath::{Rel, UNPath};
+use cuba_lib::shared::rich_error::RichError;
 
 /// Defines a `MsgLogFile`
 struct MsgLogFile {
@@ -169,13 +171,15 @@ impl MsgHandler for MsgFileLogger {
     }
 
     /// Handles a `TaskMessage` with error.
-    fn task_error(
-        &self,
-        _thread_number: usize,
-        rel_path: &UNPath<Rel>,
-        error: &(dyn Error + Send + Sync),
-    ) {
-        log::error!("{:?} : {}", rel_path, trace_error(error));
+    fn task_error(&self, thread_number: usize, rel_path: &UNPath<Rel>, error: &RichError) {
+        // Structured, so that all fields can be evaluated.
+        let json = json!({
+            "thread_number": thread_number,
+            "rel_path": rel_path.to_unicode(),
+            "error": error.to_json(),
+        });
+
+        log::error!("{}", json);
     }
 
     /// Handles a `CleanInfo::Removed` message.
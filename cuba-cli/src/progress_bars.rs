@@ -1,18 +1,27 @@
 use console::Style;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressState, ProgressStyle};
 use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 use cuba_lib::shared::message::Info;
 use cuba_lib::shared::msg_receiver::MsgHandler;
 use cuba_lib::shared::npath::{Rel, UNPath};
+use cuba_lib::shared::progress_rate::ProgressRate;
+use cuba_lib::shared::rich_error::RichError;
 
 /// Visualizes messages as progress bars.
 pub struct ProgressBars {
     threads: usize,
-    _multi_progress: MultiProgress,
+    multi_progress: MultiProgress,
     progress_bars: Arc<Vec<Mutex<ProgressBar>>>,
+    bytes_bar: Mutex<Option<ProgressBar>>,
+    bytes_style: ProgressStyle,
+    thread_style: ProgressStyle,
+    file_style: ProgressStyle,
+    progress_rate: Arc<Mutex<ProgressRate>>,
     error_occurred: Arc<Vec<Mutex<bool>>>,
+    error_count: Arc<AtomicUsize>,
     progress_bar_index: Mutex<usize>,
     green: Style,
     red: Style,
@@ -25,13 +34,36 @@ impl ProgressBars {
         let mut progress_bars = Vec::new();
         let mut error_occurred = Vec::new();
         let multi_progress = MultiProgress::new();
+        let progress_rate = Arc::new(Mutex::new(ProgressRate::new()));
 
         let thread_style =
             ProgressStyle::with_template("{prefix:.bold.dim} {spinner:.green} {wide_msg}").unwrap();
-        let total_style =
-            ProgressStyle::with_template("{prefix:.bold.dim} [{wide_bar:.green}] {percent}%")
+        let file_style =
+            ProgressStyle::with_template("{prefix:.bold.dim} [{bar:20.green}] {wide_msg}")
                 .unwrap()
                 .progress_chars(". ");
+        let total_style = ProgressStyle::with_template(
+            "{prefix:.bold.dim} [{wide_bar:.green}] {percent}% {elapsed_precise} ETA {eta} {throughput}",
+        )
+        .unwrap()
+        .with_key("throughput", {
+            let progress_rate = progress_rate.clone();
+
+            move |_state: &ProgressState, writer: &mut dyn std::fmt::Write| {
+                let progress_rate = progress_rate.lock().unwrap();
+
+                // Only operations, which transfer bytes, have a throughput.
+                if progress_rate.has_bytes() {
+                    let _ = write!(writer, "{:.1} MiB/s", progress_rate.mib_per_sec());
+                }
+            }
+        })
+        .progress_chars(". ");
+        let bytes_style = ProgressStyle::with_template(
+            "{prefix:.bold.dim} [{wide_bar:.green}] {bytes}/{total_bytes}",
+        )
+        .unwrap()
+        .progress_chars(". ");
 
         for i in 0..threads {
             let bar = multi_progress.add(ProgressBar::new(0));
@@ -49,15 +81,33 @@ impl ProgressBars {
 
         Self {
             threads,
-            _multi_progress: multi_progress,
+            multi_progress,
             progress_bars: Arc::new(progress_bars),
+            bytes_bar: Mutex::new(None),
+            bytes_style,
+            thread_style,
+            file_style,
+            progress_rate,
             error_occurred: Arc::new(error_occurred),
+            error_count: Arc::new(AtomicUsize::new(0)),
             progress_bar_index: Mutex::new(0),
             green: Style::new().green().bold(),
             red: Style::new().red().bold(),
         }
     }
 
+    /// Labels the total progress bar, e.g. with the profile name.
+    pub fn with_label(self, label: &str) -> Self {
+        if let Some(total_bar_mutex) = self.progress_bars.get(self.threads) {
+            total_bar_mutex
+                .lock()
+                .unwrap()
+                .set_prefix(format!("[{}]", label));
+        }
+
+        self
+    }
+
     // Handles a task info.
     fn handle_task_info(
         &self,
@@ -85,6 +135,8 @@ impl ProgressBars {
         rel_path: &UNPath<Rel>,
         error: &(dyn Error + Send + Sync),
     ) {
+        self.error_count.fetch_add(1, Ordering::Relaxed);
+
         if let Some(bar_mutex) = self.progress_bars.get(thread_number) {
             let bar = bar_mutex.lock().unwrap();
 
@@ -117,6 +169,8 @@ impl ProgressBars {
 
     /// Handles a clean error.
     fn handle_clean_error(&self, rel_path: &UNPath<Rel>, error: &(dyn Error + Send + Sync)) {
+        self.error_count.fetch_add(1, Ordering::Relaxed);
+
         let pb_index: usize;
 
         {
@@ -138,6 +192,9 @@ impl MsgHandler for ProgressBars {
     fn started(&self) {
         let mut index = self.progress_bar_index.lock().unwrap();
         *index = 0;
+
+        self.progress_rate.lock().unwrap().restart();
+        self.error_count.store(0, Ordering::Relaxed);
     }
 
     /// Called after the `MsgReceiver` has stopped.
@@ -145,6 +202,24 @@ impl MsgHandler for ProgressBars {
         for bar_mutex in self.progress_bars.iter() {
             bar_mutex.lock().unwrap().finish();
         }
+
+        // The bytes bar is only shown for operations, which transfer bytes.
+        if let Some(bytes_bar) = self.bytes_bar.lock().unwrap().take() {
+            bytes_bar.finish();
+        }
+
+        // Summarize the errors, the progress bars only show the first one per thread.
+        let error_count = self.error_count.load(Ordering::Relaxed);
+
+        if error_count > 0 {
+            println!(
+                "{}",
+                self.red.apply_to(format!(
+                    "{} errors occurred during this operation. Check cuba.error.log for details.",
+                    error_count
+                ))
+            );
+        }
     }
 
     /// Handles a `TaskInfo::Start` message.
@@ -154,6 +229,17 @@ impl MsgHandler for ProgressBars {
         rel_path: &UNPath<Rel>,
         info: &(dyn Info + Send + Sync),
     ) {
+        // Back to the spinner, if the last file had a progress.
+        if let Some(bar_mutex) = self.progress_bars.get(thread_number) {
+            let bar = bar_mutex.lock().unwrap();
+
+            if bar.length() != Some(0) {
+                bar.set_style(self.thread_style.clone());
+                bar.set_length(0);
+                bar.set_position(0);
+            }
+        }
+
         self.handle_task_info(thread_number, rel_path, info);
     }
 
@@ -199,6 +285,21 @@ impl MsgHandler for ProgressBars {
         }
     }
 
+    /// Handles a `TaskInfo::Progress` message.
+    fn task_progress(&self, thread_number: usize, _rel_path: &UNPath<Rel>, done: u64, total: u64) {
+        if let Some(bar_mutex) = self.progress_bars.get(thread_number) {
+            let bar = bar_mutex.lock().unwrap();
+
+            // Switch to the bar on the first progress of the file.
+            if bar.length() != Some(total) {
+                bar.set_style(self.file_style.clone());
+                bar.set_length(total);
+            }
+
+            bar.set_position(done);
+        }
+    }
+
     /// Handles a `TaskInfo::UpToDate` message.
     fn task_up_to_date(
         &self,
@@ -220,12 +321,7 @@ impl MsgHandler for ProgressBars {
     }
 
     /// Handles a `TaskMessage` with error.
-    fn task_error(
-        &self,
-        thread_number: usize,
-        rel_path: &UNPath<Rel>,
-        error: &(dyn Error + Send + Sync),
-    ) {
+    fn task_error(&self, thread_number: usize, rel_path: &UNPath<Rel>, error: &RichError) {
         self.handle_task_error(thread_number, rel_path, error);
     }
 
@@ -243,6 +339,24 @@ impl MsgHandler for ProgressBars {
         }
     }
 
+    /// Handles a `ProgressInfo::ByteProgress` message.
+    fn progress_bytes(&self, transferred: u64, total: u64, _info: &(dyn Info + Send + Sync)) {
+        self.progress_rate.lock().unwrap().update(transferred);
+
+        let mut bytes_bar = self.bytes_bar.lock().unwrap();
+
+        // Add the bytes bar below the total progress bar, on the first byte progress.
+        let bar = bytes_bar.get_or_insert_with(|| {
+            let bar = self.multi_progress.add(ProgressBar::new(total));
+            bar.set_style(self.bytes_style.clone());
+            bar.set_prefix("[Bytes]".to_string());
+            bar
+        });
+
+        bar.set_length(total.max(transferred));
+        bar.set_position(transferred);
+    }
+
     /// Handles a `CleanInfo::Ok` message.
     fn clean_ok(&self, rel_path: &UNPath<Rel>, info: &(dyn Info + Send + Sync)) {
         self.handle_clean_info(rel_path, info);
@@ -265,5 +379,7 @@ impl MsgHandler for ProgressBars {
     fn warn(&self, _warning: &(dyn Info + Send + Sync)) {}
 
     /// Handles a `ErrorMessage`.
-    fn error(&self, _error: &(dyn Error + Send + Sync)) {}
+    fn error(&self, _error: &(dyn Error + Send + Sync)) {
+        self.error_count.fetch_add(1, Ordering::Relaxed);
+    }
 }
@@ -0,0 +1,182 @@
+use cuba_lib::shared::message::Info;
+use cuba_lib::shared::msg_receiver::MsgHandler;
+use cuba_lib::shared::npath::{Rel, UNPath};
+use cuba_lib::shared::rich_error::RichError;
+use indicatif::{HumanBytes, HumanDuration};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Defines a `Summary`.
+#[derive(Default)]
+struct Summary {
+    /// The nodes of the run, as announced by the progress duration.
+    nodes: u64,
+    transferred: usize,
+    up_to_date: usize,
+    verified: usize,
+    skipped: usize,
+    removed: usize,
+    bytes: u64,
+    errors: usize,
+
+    /// The start of the run.
+    start: Option<Instant>,
+
+    /// The elapsed time, set on stop.
+    elapsed: Duration,
+
+    /// The threads, which have a result for their current task.
+    task_results: HashMap<usize, bool>,
+}
+
+/// Methods of `Summary`.
+impl Summary {
+    /// Marks the current task of `thread_number` with a result.
+    fn task_result(&mut self, thread_number: usize) {
+        self.task_results.insert(thread_number, true);
+    }
+}
+
+/// Defines a `SummaryCollector`.
+///
+/// Tallies the messages of a run, so that a summary can be printed at the end.
+#[derive(Default)]
+pub struct SummaryCollector {
+    summary: Mutex<Summary>,
+}
+
+/// Methods of `SummaryCollector`.
+impl SummaryCollector {
+    /// Prints the summary as a table.
+    pub fn print(&self, title: &str) {
+        let summary = self.summary.lock().unwrap();
+
+        println!("{}:", title);
+        println!("  Nodes:       {}", summary.nodes);
+        println!("  Transferred: {}", summary.transferred);
+        println!("  Up to date:  {}", summary.up_to_date);
+        println!("  Verified:    {}", summary.verified);
+        println!("  Skipped:     {}", summary.skipped);
+        println!("  Removed:     {}", summary.removed);
+        println!("  Bytes:       {}", HumanBytes(summary.bytes));
+        println!("  Errors:      {}", summary.errors);
+        println!("  Elapsed:     {}", HumanDuration(summary.elapsed));
+    }
+}
+
+/// Impl of `MsgHandler` for `SummaryCollector`.
+impl MsgHandler for SummaryCollector {
+    /// Starts the clock.
+    fn started(&self) {
+        self.summary.lock().unwrap().start = Some(Instant::now());
+    }
+
+    /// Stops the clock.
+    fn stopped(&self) {
+        let mut summary = self.summary.lock().unwrap();
+
+        if let Some(start) = summary.start {
+            summary.elapsed = start.elapsed();
+        }
+    }
+
+    /// Handles a `TaskInfo::Start` message.
+    fn task_start(
+        &self,
+        thread_number: usize,
+        _rel_path: &UNPath<Rel>,
+        _info: &(dyn Info + Send + Sync),
+    ) {
+        self.summary
+            .lock()
+            .unwrap()
+            .task_results
+            .insert(thread_number, false);
+    }
+
+    /// Handles a `TaskInfo::Finished` message.
+    ///
+    /// A task without a result was skipped.
+    fn task_finished(
+        &self,
+        thread_number: usize,
+        _rel_path: &UNPath<Rel>,
+        _info: &(dyn Info + Send + Sync),
+    ) {
+        let mut summary = self.summary.lock().unwrap();
+
+        if summary.task_results.remove(&thread_number) == Some(false) {
+            summary.skipped += 1;
+        }
+    }
+
+    /// Handles a `TaskInfo::Transferred` message.
+    fn task_transferred(
+        &self,
+        thread_number: usize,
+        _rel_path: &UNPath<Rel>,
+        _info: &(dyn Info + Send + Sync),
+    ) {
+        let mut summary = self.summary.lock().unwrap();
+        summary.transferred += 1;
+        summary.task_result(thread_number);
+    }
+
+    /// Handles a `TransferredBytes` message.
+    fn task_transferred_bytes(&self, _thread_number: usize, _rel_path: &UNPath<Rel>, bytes: u64) {
+        self.summary.lock().unwrap().bytes += bytes;
+    }
+
+    /// Handles a `TaskInfo::UpToDate` message.
+    fn task_up_to_date(
+        &self,
+        thread_number: usize,
+        _rel_path: &UNPath<Rel>,
+        _info: &(dyn Info + Send + Sync),
+    ) {
+        let mut summary = self.summary.lock().unwrap();
+        summary.up_to_date += 1;
+        summary.task_result(thread_number);
+    }
+
+    /// Handles a `TaskInfo::Verified` message.
+    fn task_verified(
+        &self,
+        thread_number: usize,
+        _rel_path: &UNPath<Rel>,
+        _info: &(dyn Info + Send + Sync),
+    ) {
+        let mut summary = self.summary.lock().unwrap();
+        summary.verified += 1;
+        summary.task_result(thread_number);
+    }
+
+    /// Handles a `TaskMessage` with error.
+    fn task_error(&self, thread_number: usize, _rel_path: &UNPath<Rel>, _error: &RichError) {
+        let mut summary = self.summary.lock().unwrap();
+        summary.errors += 1;
+        summary.task_result(thread_number);
+    }
+
+    /// Handles a `ProgressInfo::Duration` message.
+    fn progress_duration(&self, ticks: u64, _info: &(dyn Info + Send + Sync)) {
+        self.summary.lock().unwrap().nodes += ticks;
+    }
+
+    /// Handles a `CleanInfo::Removed` message.
+    fn clean_removed(&self, _rel_path: &UNPath<Rel>, _info: &(dyn Info + Send + Sync)) {
+        self.summary.lock().unwrap().removed += 1;
+    }
+
+    /// Handles a `CleanMessage` with error.
+    fn clean_error(&self, _rel_path: &UNPath<Rel>, _error: &(dyn Error + Send + Sync)) {
+        self.summary.lock().unwrap().errors += 1;
+    }
+
+    /// Handles a `ErrorMessage`.
+    fn error(&self, _error: &(dyn Error + Send + Sync)) {
+        self.summary.lock().unwrap().errors += 1;
+    }
+}
@@ -1,11 +1,12 @@
 use std::{
     collections::HashSet,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use crossbeam_channel::Sender;
 use cuba_lib::{
-    core::cuba::{Cuba, RunHandle},
+    core::cuba::{Cuba, RunHandle, VerifyMode},
     shared::{message::Message, msg_dispatcher::MsgDispatcher},
 };
 use egui::Color32;
@@ -14,17 +15,38 @@ use crate::{
     AppView, UpdateHandler, ViewId,
     egui_widgets::ProgressSpinner,
     task_progress::{TaskMessageType, TaskProgress},
-    util::make_cuba_runner,
+    util::{RunResult, RunThread, is_run_thread_alive, make_cuba_runner, run_result_label},
 };
 
+/// The age after which a verified file is stale.
+const VERIFY_STALE_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// The width of the file progress bar of a task.
+const TASK_PROGRESS_WIDTH: f32 = 48.0;
+
+/// Formats `duration` as hours, minutes and seconds.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Defines a `DraggedProfile`.
+///
+/// The drag payload of a profile, which is pinned when dropped onto the dock area.
+pub struct DraggedProfile(pub String);
+
 /// Defines a `BackupView`.
 pub struct BackupView {
     run_handle: RunHandle,
     sender: Sender<Arc<dyn Message>>,
     cuba: Arc<RwLock<Cuba>>,
     selected_profiles: HashSet<String>,
+    pinned_profile: Option<String>,
     msg_dispatcher: Arc<MsgDispatcher<Arc<dyn Message>>>,
     task_progress: Arc<TaskProgress>,
+    run_result: RunResult,
+    run_thread: RunThread,
 }
 
 /// Methods of `BackupView`.
@@ -43,22 +65,52 @@ impl BackupView {
             sender,
             cuba,
             selected_profiles: HashSet::new(),
+            pinned_profile: None,
             msg_dispatcher,
             task_progress,
+            run_result: RunResult::default(),
+            run_thread: RunThread::default(),
+        }
+    }
+
+    /// Pins the view to `profile`, the profile list is hidden.
+    pub fn pinned(mut self, profile: &str) -> Self {
+        self.selected_profiles = HashSet::from([profile.to_string()]);
+        self.pinned_profile = Some(profile.to_string());
+        self
+    }
+
+    /// Requests to cancel a running operation.
+    pub fn request_cancel(&self) {
+        if self.is_running() {
+            self.run_handle.request_cancel();
         }
     }
+
+    /// Returns true, if the thread of the last run has not finished.
+    pub fn is_running(&self) -> bool {
+        is_run_thread_alive(&self.run_thread)
+    }
 }
 
 /// Impl of `AppView` for `BackupView`.
 impl AppView for BackupView {
     /// Returns the name of the view.
     fn name(&self) -> &str {
-        "Backup"
+        self.pinned_profile.as_deref().unwrap_or("Backup")
     }
 
     /// Returns the view id.
     fn view_id(&self) -> ViewId {
-        ViewId::Backup
+        match &self.pinned_profile {
+            Some(profile) => ViewId::ProfileBackup(profile.clone()),
+            None => ViewId::Backup,
+        }
+    }
+
+    /// Returns the `RunHandle` of the view.
+    fn run_handle(&self) -> Option<&RunHandle> {
+        Some(&self.run_handle)
     }
 
     /// Renders the view for egui.
@@ -68,39 +120,56 @@ impl AppView for BackupView {
 
         // Horizontal layout (profile list, profile content).
         ui.horizontal(|ui| {
-            // Vertical layout (heading, list).
-            ui.vertical(|ui| {
-                ui.set_width(200.0);
-                ui.set_height(height);
+            // A pinned view has no profile list.
+            if self.pinned_profile.is_none() {
+                // Vertical layout (heading, list).
+                ui.vertical(|ui| {
+                    ui.set_width(200.0);
+                    ui.set_height(height);
 
-                // Profile list heading.
-                ui.heading("Profiles");
+                    // Profile list heading.
+                    ui.heading("Profiles");
 
-                // Separator.
-                ui.separator();
+                    // Separator.
+                    ui.separator();
 
-                // Profile list.
-                egui::ScrollArea::vertical()
-                    .auto_shrink([false; 2])
-                    .show(ui, |ui| {
-                        if let Some(config) = self.cuba.read().unwrap().config() {
-                            for profile in config.backup.keys() {
-                                let selected = self.selected_profiles.contains(profile);
-
-                                if ui.selectable_label(selected, profile).clicked() {
-                                    if selected {
-                                        self.selected_profiles.remove(profile);
-                                    } else {
-                                        self.selected_profiles.insert(profile.clone());
+                    // Profile list.
+                    egui::ScrollArea::vertical()
+                        .auto_shrink([false; 2])
+                        .show(ui, |ui| {
+                            if let Some(config) = self.cuba.read().unwrap().config() {
+                                for profile in config.backup.keys() {
+                                    let selected = self.selected_profiles.contains(profile);
+
+                                    // A profile can be dragged onto the dock area.
+                                    let response = ui
+                                        .selectable_label(selected, profile)
+                                        .interact(egui::Sense::click_and_drag());
+
+                                    response.dnd_set_drag_payload(DraggedProfile(profile.clone()));
+
+                                    if response.clicked() {
+                                        if selected {
+                                            self.selected_profiles.remove(profile);
+                                        } else {
+                                            self.selected_profiles.insert(profile.clone());
+                                        }
                                     }
                                 }
                             }
-                        }
-                    });
-            });
+                        });
 
-            // Separator.
-            ui.separator();
+                    // A profile dropped back onto the list is not pinned.
+                    if ui.ui_contains_pointer()
+                        && ui.input(|input| input.pointer.any_released())
+                    {
+                        egui::DragAndDrop::clear_payload(ui.ctx());
+                    }
+                });
+
+                // Separator.
+                ui.separator();
+            }
 
             // Vertical layout (profile content).
             ui.vertical(|ui| {
@@ -171,14 +240,26 @@ impl AppView for BackupView {
                     // The task message table.
                     egui::Grid::new("Tasks").show(ui, |ui| {
                         for thread_number in 0..config.transfer_threads {
-                            ui.add(
-                                ProgressSpinner::new(
-                                    &self.task_progress.get_task_progress(thread_number),
-                                )
-                                .size(16.0)
-                                .spinning_color(Color32::WHITE)
-                                .invalid_color(Color32::DARK_GRAY),
-                            );
+                            // A bar for the file progress, a spinner if the size is unknown.
+                            match self.task_progress.get_task_fraction(thread_number) {
+                                Some(fraction) => {
+                                    ui.add(
+                                        egui::ProgressBar::new(fraction)
+                                            .desired_width(TASK_PROGRESS_WIDTH)
+                                            .desired_height(16.0),
+                                    );
+                                }
+                                None => {
+                                    ui.add(
+                                        ProgressSpinner::new(
+                                            &self.task_progress.get_task_progress(thread_number),
+                                        )
+                                        .size(16.0)
+                                        .spinning_color(Color32::WHITE)
+                                        .invalid_color(Color32::DARK_GRAY),
+                                    );
+                                }
+                            }
 
                             let task_message = self.task_progress.get_task_message(thread_number);
 
@@ -206,28 +287,71 @@ impl AppView for BackupView {
                     // Separator.
                     ui.separator();
 
-                    // The progress bar.
+                    // The progress bar, with the elapsed time, eta and throughput next to it.
                     let progress = self.task_progress.get_total_progress().normalized();
+                    let progress_rate = self.task_progress.get_progress_rate();
+
+                    let mut rate_text = format_duration(progress_rate.elapsed());
+
+                    if let Some(eta) = progress_rate.eta(progress) {
+                        rate_text.push_str(&format!("  ETA {}", format_duration(eta)));
+                    }
+
+                    if progress_rate.has_bytes() {
+                        rate_text.push_str(&format!("  {:.1} MiB/s", progress_rate.mib_per_sec()));
+                    }
 
-                    ui.add(
-                        egui::ProgressBar::new(progress).text(
-                            egui::RichText::new(format!("{:.1} %", progress * 100.0))
-                                .monospace()
-                                .color(Color32::LIGHT_GRAY),
-                        ),
+                    ui.with_layout(
+                        egui::Layout::right_to_left(egui::Align::Center),
+                        |ui| {
+                            if self.run_handle.is_running() {
+                                ui.label(
+                                    egui::RichText::new(rate_text)
+                                        .monospace()
+                                        .color(Color32::LIGHT_GRAY),
+                                );
+                            }
+
+                            ui.add(
+                                egui::ProgressBar::new(progress).text(
+                                    egui::RichText::new(format!("{:.1} %", progress * 100.0))
+                                        .monospace()
+                                        .color(Color32::LIGHT_GRAY),
+                                ),
+                            );
+                        },
                     );
 
+                    // The error summary of the finished run.
+                    let error_count = self.task_progress.error_count();
+
+                    if self.task_progress.is_finished() && error_count > 0 {
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "{} errors occurred during this operation. Check the Errors log for details.",
+                                error_count
+                            ))
+                            .color(Color32::LIGHT_RED),
+                        );
+                    }
+
+                    // The result banner of the finished run.
+                    run_result_label(ui, &self.run_handle, &self.run_result);
+
                     // Separator.
                     ui.separator();
 
                     // Prepare a runner.
                     let run = make_cuba_runner(
+                        ui.ctx().clone(),
                         self.run_handle.clone(),
                         self.sender.clone(),
                         self.cuba.clone(),
                         self.selected_profiles.clone(),
                         self.msg_dispatcher.clone(),
                         self.task_progress.clone(),
+                        self.run_result.clone(),
+                        self.run_thread.clone(),
                     );
 
                     // Horizontal layout (run buttons).
@@ -247,7 +371,10 @@ impl AppView for BackupView {
                                 run(
                                     "Backup".to_string(),
                                     Box::new(|cuba, run_handle, profile| {
-                                        cuba.read().unwrap().run_backup(run_handle, &profile)
+                                        cuba.read()
+                                            .unwrap()
+                                            .run_backup(run_handle, &profile)
+                                            .map(|_| ())
                                     }),
                                 );
                             }
@@ -259,7 +386,7 @@ impl AppView for BackupView {
                                     Box::new(|cuba, run_handle, profile| {
                                         cuba.read()
                                             .unwrap()
-                                            .run_verify(run_handle, &profile, &false)
+                                            .run_verify(run_handle, &profile, &VerifyMode::New, &false)
                                     }),
                                 );
                             }
@@ -269,7 +396,27 @@ impl AppView for BackupView {
                                 run(
                                     "Verify".to_string(),
                                     Box::new(|cuba, run_handle, profile| {
-                                        cuba.read().unwrap().run_verify(run_handle, &profile, &true)
+                                        cuba.read().unwrap().run_verify(
+                                            run_handle,
+                                            &profile,
+                                            &VerifyMode::All,
+                                            &false,
+                                        )
+                                    }),
+                                );
+                            }
+
+                            // The verify stale button.
+                            if ui.button("Start Verify stale").clicked() {
+                                run(
+                                    "Verify".to_string(),
+                                    Box::new(|cuba, run_handle, profile| {
+                                        cuba.read().unwrap().run_verify(
+                                            run_handle,
+                                            &profile,
+                                            &VerifyMode::Stale(VERIFY_STALE_AGE),
+                                            &false,
+                                        )
                                     }),
                                 );
                             }
@@ -283,6 +430,16 @@ impl AppView for BackupView {
                                     }),
                                 );
                             }
+
+                            // The prune button.
+                            if ui.button("Start Prune").clicked() {
+                                run(
+                                    "Prune".to_string(),
+                                    Box::new(|cuba, run_handle, profile| {
+                                        cuba.read().unwrap().run_prune(run_handle, &profile)
+                                    }),
+                                );
+                            }
                         }
                     });
                 }
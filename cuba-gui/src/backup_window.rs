@@ -0,0 +1,150 @@
+use std::sync::{Arc, RwLock};
+
+use crossbeam_channel::unbounded;
+use cuba_lib::{
+    core::cuba::Cuba,
+    shared::{config::load_config_from_file, message::Message, msg_dispatcher::MsgDispatcher},
+};
+
+use crate::{
+    AppView,
+    backup_view::BackupView,
+    msg_log_views::{DEFAULT_MAX_MESSAGES, MsgLogLevel, MsgLogView},
+};
+
+/// Defines a `BackupWindow`.
+///
+/// A secondary window with its own `Cuba` instance and `MsgDispatcher`, so that a backup can
+/// run independently of the main window.
+pub struct BackupWindow {
+    viewport_id: egui::ViewportId,
+    title: String,
+    _msg_dispatcher: Arc<MsgDispatcher<Arc<dyn Message>>>,
+    backup_view: BackupView,
+    infos_view: MsgLogView,
+    errors_view: MsgLogView,
+    open: bool,
+}
+
+/// Methods of `BackupWindow`.
+impl BackupWindow {
+    /// Creates a new `BackupWindow`, which loads the config from `config_file`.
+    pub fn new(egui_context: egui::Context, number: usize, config_file: &str) -> Self {
+        // Sender and receiver for messages between the window and its Cuba instance.
+        let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+
+        // The message dispatcher.
+        let mut msg_dispatcher = MsgDispatcher::new(receiver.clone());
+        msg_dispatcher.start();
+        let arc_msg_dispatcher = Arc::new(msg_dispatcher);
+
+        // The Cuba instance.
+        let cuba = Arc::new(RwLock::new(Cuba::new(sender.clone())));
+
+        // The backup view.
+        let backup_view = BackupView::new(
+            egui_context.clone(),
+            sender.clone(),
+            cuba.clone(),
+            arc_msg_dispatcher.clone(),
+        );
+
+        // The infos view.
+        let infos_view = MsgLogView::new(
+            egui_context.clone(),
+            MsgLogLevel::Info,
+            arc_msg_dispatcher.clone(),
+            DEFAULT_MAX_MESSAGES,
+        );
+
+        // The errors view.
+        let errors_view = MsgLogView::new(
+            egui_context,
+            MsgLogLevel::Error,
+            arc_msg_dispatcher.clone(),
+            DEFAULT_MAX_MESSAGES,
+        );
+
+        // Load the config, after the views are subscribed.
+        if let Some(config) = load_config_from_file(sender, config_file) {
+            cuba.write().unwrap().set_config(config);
+        }
+
+        Self {
+            viewport_id: egui::ViewportId::from_hash_of(("BackupWindow", number)),
+            title: format!("Cuba GUI - Backup {}", number),
+            _msg_dispatcher: arc_msg_dispatcher,
+            backup_view,
+            infos_view,
+            errors_view,
+            open: true,
+        }
+    }
+
+    /// Returns true, if the window is open.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Returns true, if the run of the window has not finished.
+    ///
+    /// A closed window is kept until its canceled run has finished.
+    pub fn is_running(&self) -> bool {
+        self.backup_view.is_running()
+    }
+
+    /// Shows the window.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+
+        let viewport_builder = egui::ViewportBuilder::default()
+            .with_title(&self.title)
+            .with_inner_size(egui::vec2(1000.0, 700.0));
+
+        ctx.show_viewport_immediate(self.viewport_id, viewport_builder, |ctx, class| {
+            if class == egui::ViewportClass::Embedded {
+                // Multiple viewports are not supported, show it as egui window.
+                let mut open = true;
+
+                egui::Window::new(&self.title)
+                    .open(&mut open)
+                    .default_size(egui::vec2(1000.0, 700.0))
+                    .show(ctx, |ui| self.ui(ui));
+
+                self.open &= open;
+            } else {
+                egui::CentralPanel::default().show(ctx, |ui| self.ui(ui));
+
+                if ctx.input(|input| input.viewport().close_requested()) {
+                    self.open = false;
+                }
+            }
+        });
+
+        // Cancel a running operation on close.
+        if !self.open {
+            self.backup_view.request_cancel();
+        }
+    }
+
+    /// Renders the window content.
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        // The message logs.
+        egui::TopBottomPanel::bottom("Logs")
+            .resizable(true)
+            .default_height(200.0)
+            .show_inside(ui, |ui| {
+                ui.columns(2, |columns| {
+                    self.infos_view.ui(&mut columns[0]);
+                    self.errors_view.ui(&mut columns[1]);
+                });
+            });
+
+        // The backup view.
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            self.backup_view.ui(ui);
+        });
+    }
+}
@@ -1,15 +1,20 @@
 #![allow(dead_code)]
 
 use std::{
-    path::Path,
+    collections::VecDeque,
+    path::PathBuf,
     sync::{Arc, RwLock},
 };
 
 use crossbeam_channel::Sender;
 use cuba_lib::{
     core::cuba::Cuba,
+    send_error,
     shared::{
-        config::{ConfigEntryKey, ConfigEntryMut, ConfigEntryType},
+        config::{
+            AuthType, Config, ConfigEntryKey, ConfigEntryMut, ConfigEntryType,
+            DEFAULT_COMPRESSION_LEVEL, MAX_COMPRESSION_LEVEL, SignatureAlgo,
+        },
         config_writer::ConfigWriter,
         message::Message,
         npath::{Abs, Dir, Rel},
@@ -17,11 +22,103 @@ use cuba_lib::{
 };
 
 use crate::{
-    AppView, ViewId,
-    egui_widgets::{GlobListWidget, NPathEditor, NPathEditorBuffer, build_row, label_value_table},
+    AppView, CONFIG_FILE, ViewId,
+    egui_widgets::{
+        GlobListWidget, NPathEditor, NPathEditorBuffer, PathBrowserWidget, build_row,
+        label_value_table,
+    },
     password_ids::PasswordIDs,
 };
 
+/// The maximum depth of the config history.
+const MAX_HISTORY_DEPTH: usize = 50;
+
+/// Defines a `ConfigHistory`.
+///
+/// The undo and redo stacks of the config. An edit is compared with the snapshot of the last
+/// step when it ends, so the config is only cloned, if it has changed.
+struct ConfigHistory {
+    undo_stack: VecDeque<Config>,
+    redo_stack: VecDeque<Config>,
+    snapshot: Option<Config>,
+    edit_focus: Option<egui::Id>,
+}
+
+/// Methods of `ConfigHistory`.
+impl ConfigHistory {
+    /// Creates a new `ConfigHistory`.
+    fn new() -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: VecDeque::new(),
+            snapshot: None,
+            edit_focus: None,
+        }
+    }
+
+    /// Commits the changes since the last step as a new step.
+    fn commit(&mut self, config: &Config) {
+        if self
+            .snapshot
+            .as_ref()
+            .is_some_and(|snapshot| snapshot == config)
+        {
+            return;
+        }
+
+        if let Some(snapshot) = self.snapshot.replace(config.clone()) {
+            self.undo_stack.push_back(snapshot);
+
+            if self.undo_stack.len() > MAX_HISTORY_DEPTH {
+                self.undo_stack.pop_front();
+            }
+
+            self.redo_stack.clear();
+        }
+    }
+
+    /// Commits the current edit, when the focus has left the edited widget.
+    ///
+    /// Consecutive edits in the same focused widget are one step, not every key stroke.
+    fn update(&mut self, config: &Config, focused: Option<egui::Id>) {
+        if focused.is_none() || focused != self.edit_focus {
+            self.commit(config);
+        }
+
+        self.edit_focus = focused;
+    }
+
+    /// Returns the config to restore by undo, `current` is kept for redo.
+    fn undo(&mut self, current: &Config) -> Option<Config> {
+        self.commit(current);
+
+        let config = self.undo_stack.pop_back()?;
+        self.redo_stack.push_back(current.clone());
+        self.snapshot = Some(config.clone());
+        self.edit_focus = None;
+        Some(config)
+    }
+
+    /// Returns the config to restore by redo, `current` is kept for undo.
+    fn redo(&mut self, current: &Config) -> Option<Config> {
+        self.commit(current);
+
+        let config = self.redo_stack.pop_back()?;
+        self.undo_stack.push_back(current.clone());
+        self.snapshot = Some(config.clone());
+        self.edit_focus = None;
+        Some(config)
+    }
+
+    /// Clears the history, `config` is the snapshot of the next step.
+    fn clear(&mut self, config: Option<&Config>) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.snapshot = config.cloned();
+        self.edit_focus = None;
+    }
+}
+
 /// Defines a `ConfigView`.
 pub struct ConfigView {
     cuba: Arc<RwLock<Cuba>>,
@@ -31,6 +128,8 @@ pub struct ConfigView {
     npath_editor_buffer: NPathEditorBuffer,
     add_entry_type: ConfigEntryType,
     entry_name: String,
+    history: ConfigHistory,
+    config_file: PathBuf,
 }
 
 /// Methods of `ConfigView`.
@@ -49,8 +148,20 @@ impl ConfigView {
             npath_editor_buffer: NPathEditorBuffer::new(),
             add_entry_type: ConfigEntryType::LocalFS,
             entry_name: String::new(),
+            history: ConfigHistory::new(),
+            config_file: PathBuf::from(CONFIG_FILE),
         }
     }
+
+    /// Clears the undo/redo history, e.g. after the config was loaded.
+    pub fn clear_history(&mut self) {
+        self.history.clear(self.cuba.read().unwrap().config());
+    }
+
+    /// Sets the file, to which the config is saved.
+    pub fn set_config_file(&mut self, config_file: &str) {
+        self.config_file = PathBuf::from(config_file);
+    }
 }
 
 /// Methods of `ConfigView`.
@@ -73,7 +184,9 @@ impl ConfigView {
                 if ui.button("Delete Entry").clicked()
                     && let Some(config) = self.cuba.write().unwrap().config_mut()
                 {
+                    self.history.commit(config);
                     config.delete_entry(entry_key);
+                    self.history.commit(config);
                     self.selected_config_entry_key = None;
                     self.entry_name.clear();
                 }
@@ -85,23 +198,433 @@ impl ConfigView {
         // Separator.
         ui.separator();
 
-        // Vertical layout (config entry content).
-        egui::ScrollArea::vertical()
-            .id_salt("Config content")
-            .show(ui, |ui| {
-                if let Some(config) = self.cuba.write().unwrap().config_mut() {
-                    let fs_entries = config.list_fs_keys();
+        // Vertical layout (config entry content).
+        egui::ScrollArea::vertical()
+            .id_salt("Config content")
+            .show(ui, |ui| {
+                if let Some(config) = self.cuba.write().unwrap().config_mut() {
+                    let fs_entries = config.list_fs_keys();
+
+                    if let Some(entry_key) = &self.selected_config_entry_key
+                        && let Some(entry) = config.get_entry_mut(entry_key)
+                    {
+                        match entry {
+                            ConfigEntryMut::LocalFS(local_fs) => {
+                                // Set label width.
+                                let label_width = egui_extras::Size::exact(80.0);
+
+                                // The local fs table.
+                                label_value_table(ui, 3, row_height, |rows| {
+                                    // The name row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Name:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut self.entry_name)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The dir row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Dir:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(PathBrowserWidget::<Dir>::new(
+                                                &entry_key.to_string(),
+                                                &mut local_fs.dir,
+                                                &mut self.npath_editor_buffer,
+                                            ));
+                                        },
+                                    );
+
+                                    // The noatime row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "No atime:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut local_fs.noatime, "");
+                                        },
+                                    );
+                                });
+                            }
+                            ConfigEntryMut::WebDAVFS(webdav_fs) => {
+                                // The label width.
+                                let label_width = egui_extras::Size::exact(120.0);
+
+                                // The WebDAV fs table.
+                                label_value_table(ui, 11, row_height, |rows| {
+                                    // The name row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Name:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut self.entry_name)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The url row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Url:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(NPathEditor::<Abs, Dir>::new(
+                                                &entry_key.to_string(),
+                                                &mut webdav_fs.url,
+                                                &mut self.npath_editor_buffer,
+                                            ));
+                                        },
+                                    );
+
+                                    // The user row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "User:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut webdav_fs.user)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The password id row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Password ID:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            egui::ComboBox::from_id_salt("PasswordID")
+                                                .selected_text(webdav_fs.password_id.to_string())
+                                                .show_ui(ui, |ui| {
+                                                    for password_id in &self.password_ids.get() {
+                                                        ui.selectable_value(
+                                                            &mut webdav_fs.password_id,
+                                                            password_id.to_string(),
+                                                            password_id,
+                                                        );
+                                                    }
+                                                });
+                                        },
+                                    );
+
+                                    // The auth type row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Auth type:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            egui::ComboBox::from_id_salt("AuthType")
+                                                .selected_text(webdav_fs.auth_type.to_string())
+                                                .show_ui(ui, |ui| {
+                                                    for auth_type in AuthType::ALL {
+                                                        ui.selectable_value(
+                                                            &mut webdav_fs.auth_type,
+                                                            auth_type,
+                                                            auth_type.to_string(),
+                                                        );
+                                                    }
+                                                });
+                                        },
+                                    );
+
+                                    // The timeout row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Timeout (secs):",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(egui::DragValue::new(
+                                                &mut webdav_fs.timeout_secs,
+                                            ));
+                                        },
+                                    );
+
+                                    // The follow redirects row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Follow redirects:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut webdav_fs.follow_redirects, "");
+                                        },
+                                    );
+
+                                    // The max redirects row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Max redirects:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(egui::DragValue::new(
+                                                &mut webdav_fs.max_redirects,
+                                            ));
+                                        },
+                                    );
+
+                                    // The retries row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Retries:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(egui::DragValue::new(&mut webdav_fs.retries));
+                                        },
+                                    );
+
+                                    // The retry backoff row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Retry backoff (ms):",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(egui::DragValue::new(
+                                                &mut webdav_fs.retry_backoff_ms,
+                                            ));
+                                        },
+                                    );
+
+                                    // The chunked upload row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Chunked upload:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut webdav_fs.chunked_upload, "");
+                                        },
+                                    );
+                                });
+                            }
+                            ConfigEntryMut::FtpFS(ftp_fs) => {
+                                // The label width.
+                                let label_width = egui_extras::Size::exact(120.0);
+
+                                // The FTP fs table.
+                                label_value_table(ui, 8, row_height, |rows| {
+                                    // The name row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Name:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut self.entry_name)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The host row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Host:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut ftp_fs.host)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The port row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Port:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(egui::DragValue::new(&mut ftp_fs.port));
+                                        },
+                                    );
+
+                                    // The dir row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Dir:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(NPathEditor::<Abs, Dir>::new(
+                                                &entry_key.to_string(),
+                                                &mut ftp_fs.dir,
+                                                &mut self.npath_editor_buffer,
+                                            ));
+                                        },
+                                    );
+
+                                    // The username row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Username:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut ftp_fs.username)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The password id row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Password ID:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            egui::ComboBox::from_id_salt("PasswordID")
+                                                .selected_text(ftp_fs.password_id.to_string())
+                                                .show_ui(ui, |ui| {
+                                                    for password_id in &self.password_ids.get() {
+                                                        ui.selectable_value(
+                                                            &mut ftp_fs.password_id,
+                                                            password_id.to_string(),
+                                                            password_id,
+                                                        );
+                                                    }
+                                                });
+                                        },
+                                    );
+
+                                    // The passive mode row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Passive mode:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut ftp_fs.passive_mode, "");
+                                        },
+                                    );
+
+                                    // The use tls row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Use TLS:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut ftp_fs.use_tls, "");
+                                        },
+                                    );
+                                });
+                            }
+                            ConfigEntryMut::AzureBlobFS(azure_fs) => {
+                                // The label width.
+                                let label_width = egui_extras::Size::exact(120.0);
+
+                                // The Azure blob fs table.
+                                label_value_table(ui, 5, row_height, |rows| {
+                                    // The name row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Name:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut self.entry_name)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The account row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Account:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut azure_fs.account)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The container row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Container:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut azure_fs.container)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The password id row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Password ID:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            egui::ComboBox::from_id_salt("PasswordID")
+                                                .selected_text(azure_fs.password_id.to_string())
+                                                .show_ui(ui, |ui| {
+                                                    for password_id in &self.password_ids.get() {
+                                                        ui.selectable_value(
+                                                            &mut azure_fs.password_id,
+                                                            password_id.to_string(),
+                                                            password_id,
+                                                        );
+                                                    }
+                                                });
+                                        },
+                                    );
 
-                    if let Some(entry_key) = &self.selected_config_entry_key
-                        && let Some(entry) = config.get_entry_mut(entry_key)
-                    {
-                        match entry {
-                            ConfigEntryMut::LocalFS(local_fs) => {
-                                // Set label width.
-                                let label_width = egui_extras::Size::exact(60.0);
+                                    // The SAS token row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "SAS token:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut azure_fs.sas_token, "");
+                                        },
+                                    );
+                                });
+                            }
+                            ConfigEntryMut::RcloneFS(rclone_fs) => {
+                                // The label width.
+                                let label_width = egui_extras::Size::exact(120.0);
 
-                                // The local fs table.
-                                label_value_table(ui, 2, row_height, |rows| {
+                                // The rclone fs table.
+                                label_value_table(ui, 3, row_height, |rows| {
                                     // The name row.
                                     build_row(
                                         rows,
@@ -116,28 +639,51 @@ impl ConfigView {
                                         },
                                     );
 
-                                    // The dir row.
+                                    // The remote row.
                                     build_row(
                                         rows,
                                         label_width,
-                                        "Dir:",
+                                        "Remote:",
                                         egui_extras::Size::remainder(),
                                         |ui| {
-                                            ui.add(NPathEditor::<Abs, Dir>::new(
-                                                &entry_key.to_string(),
-                                                &mut local_fs.dir,
-                                                &mut self.npath_editor_buffer,
-                                            ));
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut rclone_fs.remote)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The rclone flags row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Rclone flags:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            let mut flags = rclone_fs.rclone_flags.join(" ");
+
+                                            if ui
+                                                .add(
+                                                    egui::TextEdit::singleline(&mut flags)
+                                                        .desired_width(f32::INFINITY),
+                                                )
+                                                .changed()
+                                            {
+                                                rclone_fs.rclone_flags = flags
+                                                    .split_whitespace()
+                                                    .map(str::to_string)
+                                                    .collect();
+                                            }
                                         },
                                     );
                                 });
                             }
-                            ConfigEntryMut::WebDAVFS(webdav_fs) => {
+                            ConfigEntryMut::S3FS(s3_fs) => {
                                 // The label width.
                                 let label_width = egui_extras::Size::exact(120.0);
 
-                                // The WebDAV fs table.
-                                label_value_table(ui, 5, row_height, |rows| {
+                                // The S3 fs table.
+                                label_value_table(ui, 7, row_height, |rows| {
                                     // The name row.
                                     build_row(
                                         rows,
@@ -152,35 +698,78 @@ impl ConfigView {
                                         },
                                     );
 
-                                    // The url row.
+                                    // The endpoint row.
                                     build_row(
                                         rows,
                                         label_width,
-                                        "Url:",
+                                        "Endpoint:",
                                         egui_extras::Size::remainder(),
                                         |ui| {
-                                            ui.add(NPathEditor::<Abs, Dir>::new(
-                                                &entry_key.to_string(),
-                                                &mut webdav_fs.url,
-                                                &mut self.npath_editor_buffer,
-                                            ));
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut s3_fs.endpoint)
+                                                    .desired_width(f32::INFINITY),
+                                            );
                                         },
                                     );
 
-                                    // The user row.
+                                    // The region row.
                                     build_row(
                                         rows,
                                         label_width,
-                                        "User:",
+                                        "Region:",
                                         egui_extras::Size::remainder(),
                                         |ui| {
                                             ui.add(
-                                                egui::TextEdit::singleline(&mut webdav_fs.user)
+                                                egui::TextEdit::singleline(&mut s3_fs.region)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The bucket row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Bucket:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut s3_fs.bucket)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The prefix row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Prefix:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut s3_fs.prefix)
                                                     .desired_width(f32::INFINITY),
                                             );
                                         },
                                     );
 
+                                    // The access key id row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Access key ID:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(
+                                                    &mut s3_fs.access_key_id,
+                                                )
+                                                .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
                                     // The password id row.
                                     build_row(
                                         rows,
@@ -189,11 +778,11 @@ impl ConfigView {
                                         egui_extras::Size::remainder(),
                                         |ui| {
                                             egui::ComboBox::from_id_salt("PasswordID")
-                                                .selected_text(webdav_fs.password_id.to_string())
+                                                .selected_text(s3_fs.password_id.to_string())
                                                 .show_ui(ui, |ui| {
                                                     for password_id in &self.password_ids.get() {
                                                         ui.selectable_value(
-                                                            &mut webdav_fs.password_id,
+                                                            &mut s3_fs.password_id,
                                                             password_id.to_string(),
                                                             password_id,
                                                         );
@@ -201,17 +790,36 @@ impl ConfigView {
                                                 });
                                         },
                                     );
+                                });
+                            }
+                            ConfigEntryMut::MemFS(_mem_fs) => {
+                                // The label width.
+                                let label_width = egui_extras::Size::exact(120.0);
 
-                                    // The timeout row.
+                                // The mem fs table.
+                                label_value_table(ui, 2, row_height, |rows| {
+                                    // The name row.
                                     build_row(
                                         rows,
                                         label_width,
-                                        "Timeout (secs):",
+                                        "Name:",
                                         egui_extras::Size::remainder(),
                                         |ui| {
-                                            ui.add(egui::DragValue::new(
-                                                &mut webdav_fs.timeout_secs,
-                                            ));
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut self.entry_name)
+                                                    .desired_width(f32::INFINITY),
+                                            );
+                                        },
+                                    );
+
+                                    // The info row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Content:",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.label("In memory, lost when cuba exits");
                                         },
                                     );
                                 });
@@ -221,7 +829,7 @@ impl ConfigView {
                                 let label_width = egui_extras::Size::exact(120.0);
 
                                 // The backup table.
-                                label_value_table(ui, 10, row_height, |rows| {
+                                label_value_table(ui, 18, row_height, |rows| {
                                     // The name row.
                                     build_row(
                                         rows,
@@ -285,11 +893,14 @@ impl ConfigView {
                                         "Source dir:",
                                         egui_extras::Size::remainder(),
                                         |ui| {
-                                            ui.add(NPathEditor::<Rel, Dir>::new(
-                                                &(entry_key.to_string() + ".src"),
-                                                &mut backup.src_dir,
-                                                &mut self.npath_editor_buffer,
-                                            ));
+                                            ui.add(
+                                                NPathEditor::<Rel, Dir>::new(
+                                                    &(entry_key.to_string() + ".src"),
+                                                    &mut backup.src_dir,
+                                                    &mut self.npath_editor_buffer,
+                                                )
+                                                .warn_empty(true),
+                                            );
                                         },
                                     );
 
@@ -300,11 +911,28 @@ impl ConfigView {
                                         "Destination dir:",
                                         egui_extras::Size::remainder(),
                                         |ui| {
-                                            ui.add(NPathEditor::<Rel, Dir>::new(
-                                                &(entry_key.to_string() + ".dest"),
-                                                &mut backup.dest_dir,
-                                                &mut self.npath_editor_buffer,
-                                            ));
+                                            ui.add(
+                                                NPathEditor::<Rel, Dir>::new(
+                                                    &(entry_key.to_string() + ".dest"),
+                                                    &mut backup.dest_dir,
+                                                    &mut self.npath_editor_buffer,
+                                                )
+                                                .warn_empty(!backup.dest_dir_auto),
+                                            );
+                                        },
+                                    );
+
+                                    // The automatic destination dir row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Auto destination dir",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut backup.dest_dir_auto, "")
+                                                .on_hover_text(
+                                                    "Use <hostname>/<profile> as destination dir",
+                                                );
                                         },
                                     );
 
@@ -315,7 +943,117 @@ impl ConfigView {
                                         "Compression",
                                         egui_extras::Size::remainder(),
                                         |ui| {
-                                            ui.checkbox(&mut backup.compression, "");
+                                            // Without compression, the level is not written.
+                                            if ui.checkbox(&mut backup.compression, "").changed()
+                                                && !backup.compression
+                                            {
+                                                backup.compression_level = None;
+                                            }
+                                        },
+                                    );
+
+                                    // The compression level row.
+                                    if backup.compression {
+                                        build_row(
+                                            rows,
+                                            label_width,
+                                            "Compression level:",
+                                            egui_extras::Size::remainder(),
+                                            |ui| {
+                                                let mut compression_level = backup
+                                                    .compression_level
+                                                    .unwrap_or(DEFAULT_COMPRESSION_LEVEL);
+
+                                                // Set the level only, if it is changed.
+                                                if ui
+                                                    .add(
+                                                        egui::DragValue::new(
+                                                            &mut compression_level,
+                                                        )
+                                                        .range(0..=MAX_COMPRESSION_LEVEL),
+                                                    )
+                                                    .changed()
+                                                {
+                                                    backup.compression_level =
+                                                        Some(compression_level);
+                                                }
+                                            },
+                                        );
+                                    }
+
+                                    // The skip recompression row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Skip recompression",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut backup.skip_recompression, "");
+                                        },
+                                    );
+
+                                    // The delta compression row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Delta compression",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut backup.delta_compression, "");
+                                        },
+                                    );
+
+                                    // The deduplication row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Deduplication",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut backup.dedup_enabled, "");
+                                        },
+                                    );
+
+                                    // The signature algorithm row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Signature algorithm",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            egui::ComboBox::from_id_salt("SignatureAlgo")
+                                                .selected_text(backup.signature_algo.to_string())
+                                                .show_ui(ui, |ui| {
+                                                    for signature_algo in SignatureAlgo::ALL {
+                                                        ui.selectable_value(
+                                                            &mut backup.signature_algo,
+                                                            signature_algo,
+                                                            signature_algo.to_string(),
+                                                        );
+                                                    }
+                                                });
+                                        },
+                                    );
+
+                                    // The verify after backup row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Verify after backup",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut backup.verify_after_backup, "");
+                                        },
+                                    );
+
+                                    // The retry changed files row.
+                                    build_row(
+                                        rows,
+                                        label_width,
+                                        "Retry changed files",
+                                        egui_extras::Size::remainder(),
+                                        |ui| {
+                                            ui.checkbox(&mut backup.retry_changed_files, "");
                                         },
                                     );
 
@@ -501,6 +1239,49 @@ impl ConfigView {
                 }
             });
     }
+
+    /// Undoes (Ctrl+Z) or redoes (Ctrl+Shift+Z) the last change.
+    fn handle_undo_redo(&mut self, ui: &egui::Ui) {
+        let redo_shortcut = egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+            egui::Key::Z,
+        );
+        let undo_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+
+        // Check redo first, since undo matches also with shift.
+        let redo = ui.input_mut(|input| input.consume_shortcut(&redo_shortcut));
+        let undo = !redo && ui.input_mut(|input| input.consume_shortcut(&undo_shortcut));
+
+        if !undo && !redo {
+            return;
+        }
+
+        if let Some(config) = self.cuba.write().unwrap().config_mut() {
+            let restored = if undo {
+                self.history.undo(config)
+            } else {
+                self.history.redo(config)
+            };
+
+            if let Some(restored) = restored {
+                *config = restored;
+
+                // Keep the selection, if the entry still exists.
+                match &self.selected_config_entry_key {
+                    Some(entry_key) if config.list_entry_keys().contains(entry_key) => {
+                        self.entry_name = entry_key.name.clone();
+                    }
+                    _ => {
+                        self.selected_config_entry_key = None;
+                        self.entry_name.clear();
+                    }
+                }
+
+                // The buffers hold the old paths.
+                self.npath_editor_buffer.clear();
+            }
+        }
+    }
 }
 
 /// Impl of `AppView` for `ConfigView`.
@@ -519,6 +1300,9 @@ impl AppView for ConfigView {
     fn ui(&mut self, ui: &mut egui::Ui) {
         let height = ui.available_height();
 
+        // Undo/redo.
+        self.handle_undo_redo(ui);
+
         // Horizontal layout (config entry list, entry content).
         ui.horizontal(|ui| {
             // Vertical layout (heading, list, buttons).
@@ -551,6 +1335,9 @@ impl AppView for ConfigView {
                 // The max scroll area height.
                 let max_scroll_area_height = ui.available_height() - footer_height;
 
+                // The entry to duplicate.
+                let mut duplicate_entry_key: Option<ConfigEntryKey> = None;
+
                 // Entry list.
                 egui::ScrollArea::vertical()
                     .auto_shrink([false; 2])
@@ -562,10 +1349,18 @@ impl AppView for ConfigView {
                                 let selected =
                                     self.selected_config_entry_key == Some(entry_key.clone());
 
-                                if ui
-                                    .selectable_label(selected, format!("{}", entry_key))
-                                    .clicked()
-                                {
+                                let response =
+                                    ui.selectable_label(selected, format!("{}", entry_key));
+
+                                // The context menu of the entry.
+                                response.context_menu(|ui| {
+                                    if ui.button("Duplicate").clicked() {
+                                        duplicate_entry_key = Some(entry_key.clone());
+                                        ui.close();
+                                    }
+                                });
+
+                                if response.clicked() {
                                     self.selected_config_entry_key = Some(entry_key.clone());
                                     self.entry_name = entry_key.name;
                                 }
@@ -573,6 +1368,37 @@ impl AppView for ConfigView {
                         }
                     });
 
+                // Duplicate entry.
+                if let Some(entry_key) = duplicate_entry_key
+                    && let Some(config) = self.cuba.write().unwrap().config_mut()
+                {
+                    let entry_keys = config.list_entry_keys();
+
+                    // Find a free name.
+                    let mut new_name = format!("{} (copy)", entry_key.name);
+                    let mut copy_number = 2;
+
+                    while entry_keys.contains(&ConfigEntryKey::new(
+                        entry_key.entry_type.clone(),
+                        new_name.clone(),
+                    )) {
+                        new_name = format!("{} (copy {})", entry_key.name, copy_number);
+                        copy_number += 1;
+                    }
+
+                    self.history.commit(config);
+
+                    match config.duplicate_entry(&entry_key, &new_name) {
+                        Ok(()) => {
+                            self.history.commit(config);
+                            self.selected_config_entry_key =
+                                Some(ConfigEntryKey::new(entry_key.entry_type, new_name.clone()));
+                            self.entry_name = new_name;
+                        }
+                        Err(err) => send_error!(self.sender, err),
+                    }
+                }
+
                 // Stretches to fill space.
                 let remaining = ui.available_height() - footer_height;
                 ui.add_space(remaining);
@@ -582,13 +1408,12 @@ impl AppView for ConfigView {
 
                 // Horizontal layout (footer).
                 ui.horizontal(|ui| {
-                    if ui.button("+ Add").clicked() {
-                        self.cuba
-                            .write()
-                            .unwrap()
-                            .config_mut()
-                            .unwrap()
-                            .add_new_entry(&self.add_entry_type, "New Entry");
+                    if ui.button("+ Add").clicked()
+                        && let Some(config) = self.cuba.write().unwrap().config_mut()
+                    {
+                        self.history.commit(config);
+                        config.add_new_entry(&self.add_entry_type, "New Entry");
+                        self.history.commit(config);
                     }
 
                     // The entries which can be added
@@ -611,7 +1436,7 @@ impl AppView for ConfigView {
                     if ui.button("Save Config").clicked()
                         && let Some(config) = self.cuba.read().unwrap().config()
                     {
-                        ConfigWriter::write(self.sender.clone(), Path::new("cuba.toml"), config);
+                        ConfigWriter::write(self.sender.clone(), &self.config_file, config);
                     }
                 });
             });
@@ -626,5 +1451,11 @@ impl AppView for ConfigView {
                 self.render_entry_editor(ui);
             });
         });
+
+        // Record the edit for undo, when it ends.
+        if let Some(config) = self.cuba.read().unwrap().config() {
+            self.history
+                .update(config, ui.memory(|memory| memory.focused()));
+        }
     }
 }
@@ -1,10 +1,18 @@
 #![allow(dead_code)]
 
-use cuba_lib::shared::npath::NPath;
+use cuba_lib::{
+    core::cuba::{ConnectionStatus, Cuba},
+    shared::npath::{Abs, Dir, File, NPath, NPathError},
+};
 use egui::{
     Color32, Vec2,
     ahash::{HashMap, HashMapExt},
 };
+use rfd::FileDialog;
+use std::path::PathBuf;
+
+/// The background color of a valid, but likely wrong value (dark orange).
+const WARN_COLOR: Color32 = Color32::from_rgb(140, 70, 0);
 
 /// Defines a `ProgressState`
 #[derive(Clone, Copy)]
@@ -184,6 +192,7 @@ pub struct NPathEditor<'a, NpathK, NpathT> {
     path: &'a mut NPath<NpathK, NpathT>,
     npath_buffer: &'a mut NPathEditorBuffer,
     desired_width: f32,
+    warn_empty: bool,
 }
 
 /// Methods of `NPathEditor`.
@@ -199,6 +208,7 @@ impl<'a, NpathK, NpathT> NPathEditor<'a, NpathK, NpathT> {
             path,
             npath_buffer,
             desired_width: f32::INFINITY,
+            warn_empty: false,
         }
     }
 
@@ -207,12 +217,18 @@ impl<'a, NpathK, NpathT> NPathEditor<'a, NpathK, NpathT> {
         self.desired_width = desired_width;
         self
     }
+
+    /// Highlights an empty path as warning.
+    pub fn warn_empty(mut self, warn_empty: bool) -> Self {
+        self.warn_empty = warn_empty;
+        self
+    }
 }
 
 /// Impl `egui::Widget` for `NPathEditor`.
 impl<NpathK, NpathT> egui::Widget for NPathEditor<'_, NpathK, NpathT>
 where
-    for<'s> NPath<NpathK, NpathT>: TryFrom<&'s str>,
+    for<'s> NPath<NpathK, NpathT>: TryFrom<&'s str, Error = NPathError>,
 {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
         let buffer: &mut String = if let Some(buffer) = self.npath_buffer.buffer.get_mut(self.key) {
@@ -228,27 +244,133 @@ where
             self.npath_buffer.buffer.get_mut(self.key).unwrap()
         };
 
-        let valid;
-
-        match NPath::<NpathK, NpathT>::try_from(buffer.as_str()) {
+        let error = match NPath::<NpathK, NpathT>::try_from(buffer.as_str()) {
             Ok(new_path) => {
                 *self.path = new_path;
-                valid = true;
+                None
             }
-            Err(_) => {
-                valid = false;
-            }
-        }
+            Err(err) => Some(err),
+        };
 
-        let text_edit = if valid {
-            egui::TextEdit::singleline(buffer).desired_width(self.desired_width)
-        } else {
+        let text_edit = if error.is_some() {
             egui::TextEdit::singleline(buffer)
                 .background_color(Color32::DARK_RED)
                 .desired_width(self.desired_width)
+        } else if self.warn_empty && self.path.is_empty() {
+            egui::TextEdit::singleline(buffer)
+                .background_color(WARN_COLOR)
+                .desired_width(self.desired_width)
+        } else {
+            egui::TextEdit::singleline(buffer).desired_width(self.desired_width)
         };
 
-        ui.add(text_edit)
+        // Show why the path is invalid, e.g. if it is too long.
+        match error {
+            Some(err) => ui.add(text_edit).on_hover_text(err.to_string()),
+            None => ui.add(text_edit),
+        }
+    }
+}
+
+/// Defines a `PathPicker`.
+///
+/// Picks a path of the target type with the native file dialog.
+pub trait PathPicker {
+    /// Shows the file dialog and returns the picked path (if any).
+    fn pick(dialog: FileDialog) -> Option<PathBuf>;
+}
+
+/// Impl of `PathPicker` for `Dir`.
+impl PathPicker for Dir {
+    fn pick(dialog: FileDialog) -> Option<PathBuf> {
+        dialog.pick_folder()
+    }
+}
+
+/// Impl of `PathPicker` for `File`.
+impl PathPicker for File {
+    fn pick(dialog: FileDialog) -> Option<PathBuf> {
+        dialog.pick_file()
+    }
+}
+
+/// Defines a `PathBrowserWidget`.
+///
+/// A `NPathEditor` with a button, which opens the native file dialog. The dialog is a folder
+/// picker for `Dir` and a file picker for `File`.
+pub struct PathBrowserWidget<'a, NpathT> {
+    key: &'a str,
+    path: &'a mut NPath<Abs, NpathT>,
+    npath_buffer: &'a mut NPathEditorBuffer,
+    desired_width: f32,
+}
+
+/// Methods of `PathBrowserWidget`.
+impl<'a, NpathT> PathBrowserWidget<'a, NpathT> {
+    /// Creates a new `PathBrowserWidget`.
+    pub fn new(
+        key: &'a str,
+        path: &'a mut NPath<Abs, NpathT>,
+        npath_buffer: &'a mut NPathEditorBuffer,
+    ) -> Self {
+        Self {
+            key,
+            path,
+            npath_buffer,
+            desired_width: f32::INFINITY,
+        }
+    }
+
+    /// Sets the desired width of the widget.
+    pub fn desired_width(mut self, desired_width: f32) -> Self {
+        self.desired_width = desired_width;
+        self
+    }
+}
+
+/// Impl `egui::Widget` for `PathBrowserWidget`.
+impl<NpathT: PathPicker> egui::Widget for PathBrowserWidget<'_, NpathT> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.horizontal(|ui| {
+            // Leave space for the browse button.
+            let editor_width = self.desired_width.min(ui.available_width() - 35.0);
+
+            // Path edit.
+            ui.add(
+                NPathEditor::new(self.key, &mut *self.path, &mut *self.npath_buffer)
+                    .desired_width(editor_width),
+            );
+
+            // Browse button.
+            if ui.button("📂").on_hover_text("Browse").clicked() {
+                let mut dialog = FileDialog::new();
+
+                // Start at the current path.
+                if !self.path.to_unicode().is_empty() {
+                    let current_path = self.path.to_path();
+
+                    if current_path.is_dir() {
+                        dialog = dialog.set_directory(current_path);
+                    } else if let Some(parent) = current_path.parent() {
+                        dialog = dialog.set_directory(parent);
+                    }
+                }
+
+                // Set buffer, so the editor shows the picked path.
+                if let Some(picked_path) = NpathT::pick(dialog) {
+                    let picked_path = picked_path.to_string_lossy().to_string();
+
+                    if let Ok(new_path) = NPath::<Abs, NpathT>::try_from(picked_path.as_str()) {
+                        *self.path = new_path;
+                    }
+
+                    self.npath_buffer
+                        .buffer
+                        .insert(self.key.to_string(), picked_path);
+                }
+            }
+        })
+        .response
     }
 }
 
@@ -286,16 +408,36 @@ impl<'a> egui::Widget for GlobListWidget<'a> {
                     let mut remove_index: Option<usize> = None;
 
                     for (index, glob) in globs.iter_mut().enumerate() {
-                        ui.horizontal(|ui| {
-                            // Glob edit.
-                            let available_width = ui.available_width() - 45.0;
-                            ui.add(egui::TextEdit::singleline(glob).desired_width(available_width));
-
-                            // Remove glob button.
-                            if ui.button("✖").clicked() {
-                                remove_index = Some(index);
-                            }
-                        });
+                        let validation = Cuba::validate_glob(glob);
+
+                        let response = ui
+                            .horizontal(|ui| {
+                                // Glob edit, invalid patterns in red.
+                                let available_width = ui.available_width() - 45.0;
+                                let mut text_edit =
+                                    egui::TextEdit::singleline(glob).desired_width(available_width);
+
+                                if validation.is_err() {
+                                    text_edit = text_edit.text_color(Color32::LIGHT_RED);
+                                }
+
+                                let response = ui.add(text_edit);
+
+                                // Remove glob button.
+                                if ui.button("✖").clicked() {
+                                    remove_index = Some(index);
+                                }
+
+                                response
+                            })
+                            .inner;
+
+                        // Show the parse error of the selected pattern.
+                        if let Err(err) = validation
+                            && response.has_focus()
+                        {
+                            ui.colored_label(Color32::LIGHT_RED, err);
+                        }
                     }
 
                     // Remove glob.
@@ -317,6 +459,19 @@ impl<'a> egui::Widget for GlobListWidget<'a> {
     }
 }
 
+/// Shows a colored dot for a `ConnectionStatus`, the `name` and status are shown on hover.
+pub fn connection_status_dot(ui: &mut egui::Ui, name: &str, status: ConnectionStatus) {
+    let color = match status {
+        ConnectionStatus::Idle => Color32::GRAY,
+        ConnectionStatus::Connecting => Color32::YELLOW,
+        ConnectionStatus::Connected => Color32::GREEN,
+        ConnectionStatus::Error => Color32::RED,
+    };
+
+    ui.label(egui::RichText::new("●").color(color))
+        .on_hover_text(format!("{}: {}", name, status));
+}
+
 /// Builds a table with labels and values.
 pub fn label_value_table(
     ui: &mut egui::Ui,
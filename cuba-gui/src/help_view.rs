@@ -0,0 +1,267 @@
+use std::{path::Path, sync::Arc};
+
+use crossbeam_channel::Sender;
+use cuba_lib::{
+    send_error, send_info,
+    shared::{config::EXAMPLE_CONFIG, message::Message},
+};
+use egui::{
+    Color32,
+    text::{LayoutJob, TextFormat},
+};
+
+use crate::{AppView, ViewId};
+
+/// The file, the example config is saved to.
+const EXAMPLE_CONFIG_FILE: &str = "cuba.toml";
+
+/// Defines a `TomlToken`.
+#[derive(Clone, Copy, PartialEq)]
+enum TomlToken {
+    Table,
+    Key,
+    String,
+    Literal,
+    Comment,
+    Other,
+}
+
+/// Methods of `TomlToken`.
+impl TomlToken {
+    /// Returns the color of the token.
+    fn color(&self, ui: &egui::Ui) -> Color32 {
+        match self {
+            TomlToken::Table => Color32::GOLD,
+            TomlToken::Key => Color32::LIGHT_BLUE,
+            TomlToken::String => Color32::LIGHT_GREEN,
+            TomlToken::Literal => Color32::from_rgb(255, 160, 80),
+            TomlToken::Comment => Color32::GRAY,
+            TomlToken::Other => ui.visuals().text_color(),
+        }
+    }
+}
+
+/// Returns the end of a string, which starts with `quote` at `start`.
+fn string_end(line: &str, start: usize, quote: char) -> usize {
+    let mut escaped = false;
+
+    for (index, char) in line[start + 1..].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if char == '\\' && quote == '"' {
+            escaped = true;
+        } else if char == quote {
+            return start + 1 + index + 1;
+        }
+    }
+
+    // Unterminated, the string runs to the end of the line.
+    line.len()
+}
+
+/// Splits a line of TOML into tokens.
+fn tokenize_toml_line(line: &str) -> Vec<(usize, usize, TomlToken)> {
+    let mut tokens = Vec::new();
+    let is_table = line.trim_start().starts_with('[');
+    let mut after_equals = false;
+    let mut index = 0;
+
+    while index < line.len() {
+        let char = line[index..].chars().next().unwrap();
+
+        let (end, token) = if char == '#' {
+            // A comment runs to the end of the line.
+            (line.len(), TomlToken::Comment)
+        } else if char == '"' || char == '\'' {
+            let token = if is_table || after_equals {
+                TomlToken::String
+            } else {
+                TomlToken::Key
+            };
+
+            (string_end(line, index, char), token)
+        } else if char == '=' && !is_table {
+            after_equals = true;
+            (index + 1, TomlToken::Other)
+        } else if char.is_alphanumeric() || "_-+.:".contains(char) {
+            // A bare word.
+            let end = line[index..]
+                .find(|char: char| !(char.is_alphanumeric() || "_-+.:".contains(char)))
+                .map_or(line.len(), |len| index + len);
+
+            let word = &line[index..end];
+
+            let token = if is_table {
+                TomlToken::Table
+            } else if !after_equals {
+                TomlToken::Key
+            } else if word == "true"
+                || word == "false"
+                || word
+                    .starts_with(|char: char| char.is_ascii_digit() || char == '-' || char == '+')
+            {
+                TomlToken::Literal
+            } else {
+                TomlToken::Other
+            };
+
+            (end, token)
+        } else if is_table && (char == '[' || char == ']') {
+            (index + char.len_utf8(), TomlToken::Table)
+        } else {
+            (index + char.len_utf8(), TomlToken::Other)
+        };
+
+        tokens.push((index, end, token));
+        index = end;
+    }
+
+    tokens
+}
+
+/// Highlights TOML with simple lexer rules.
+fn highlight_toml(ui: &egui::Ui, text: &str, wrap_width: f32) -> LayoutJob {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let mut job = LayoutJob::default();
+
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches('\n');
+
+        for (start, end, token) in tokenize_toml_line(content) {
+            job.append(
+                &content[start..end],
+                0.0,
+                TextFormat::simple(font_id.clone(), token.color(ui)),
+            );
+        }
+
+        if content.len() < line.len() {
+            job.append(
+                "\n",
+                0.0,
+                TextFormat::simple(font_id.clone(), Color32::GRAY),
+            );
+        }
+    }
+
+    job.wrap.max_width = wrap_width;
+    job
+}
+
+/// Defines a `HelpView`.
+pub struct HelpView {
+    sender: Sender<Arc<dyn Message>>,
+    show_example_config: bool,
+    confirm_overwrite: bool,
+}
+
+/// Methods of `HelpView`.
+impl HelpView {
+    /// Creates a new `HelpView`.
+    pub fn new(sender: Sender<Arc<dyn Message>>) -> Self {
+        Self {
+            sender,
+            show_example_config: false,
+            confirm_overwrite: false,
+        }
+    }
+
+    /// Saves the example config.
+    fn save_example_config(&self) {
+        match std::fs::write(EXAMPLE_CONFIG_FILE, EXAMPLE_CONFIG) {
+            Ok(_) => send_info!(
+                self.sender,
+                "Example config written to {}",
+                EXAMPLE_CONFIG_FILE
+            ),
+            Err(err) => send_error!(self.sender, err),
+        }
+    }
+}
+
+/// Impl of `AppView` for `HelpView`.
+impl AppView for HelpView {
+    /// Returns the name of the view.
+    fn name(&self) -> &str {
+        "Help"
+    }
+
+    /// Returns the view id.
+    fn view_id(&self) -> ViewId {
+        ViewId::Help
+    }
+
+    /// Renders the view for egui.
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        // Horizontal layout (topics, buttons).
+        ui.horizontal(|ui| {
+            // The example config button.
+            if ui
+                .selectable_label(self.show_example_config, "Example Config")
+                .clicked()
+            {
+                self.show_example_config = !self.show_example_config;
+                self.confirm_overwrite = false;
+            }
+
+            if self.show_example_config {
+                // Separator.
+                ui.separator();
+
+                // The copy button.
+                if ui.button("📋 Copy").clicked() {
+                    ui.ctx().copy_text(EXAMPLE_CONFIG.to_string());
+                }
+
+                // The save button, asks before overwriting.
+                if self.confirm_overwrite {
+                    ui.label(format!("{} exists. Overwrite?", EXAMPLE_CONFIG_FILE));
+
+                    if ui.button("Yes").clicked() {
+                        self.save_example_config();
+                        self.confirm_overwrite = false;
+                    }
+
+                    if ui.button("No").clicked() {
+                        self.confirm_overwrite = false;
+                    }
+                } else if ui
+                    .button(format!("💾 Save as {}", EXAMPLE_CONFIG_FILE))
+                    .clicked()
+                {
+                    if Path::new(EXAMPLE_CONFIG_FILE).exists() {
+                        self.confirm_overwrite = true;
+                    } else {
+                        self.save_example_config();
+                    }
+                }
+            }
+        });
+
+        // Separator.
+        ui.separator();
+
+        if self.show_example_config {
+            let mut layouter = |ui: &egui::Ui, text: &dyn egui::TextBuffer, wrap_width: f32| {
+                let layout_job = highlight_toml(ui, text.as_str(), wrap_width);
+                ui.fonts_mut(|fonts| fonts.layout_job(layout_job))
+            };
+
+            // The example config is read only.
+            let mut example_config = EXAMPLE_CONFIG;
+
+            egui::ScrollArea::vertical()
+                .auto_shrink(false)
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut example_config)
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(f32::INFINITY)
+                            .layouter(&mut layouter),
+                    );
+                });
+        } else {
+            ui.label("Select a topic above.");
+        }
+    }
+}
@@ -4,6 +4,7 @@ use core::f32;
 use std::sync::{Arc, RwLock};
 
 use cuba_lib::core::cuba::Cuba;
+use egui::{Color32, RichText};
 use secrecy::{ExposeSecret, SecretString};
 
 use crate::{
@@ -65,6 +66,14 @@ impl AppView for KeyringView {
                 // Separator.
                 ui.separator();
 
+                // The ids used by the config, none without config.
+                let used_ids = self
+                    .cuba
+                    .read()
+                    .unwrap()
+                    .config()
+                    .map(|config| self.password_ids.used_by(config));
+
                 // Entry list.
                 egui::ScrollArea::vertical()
                     .auto_shrink([false; 2])
@@ -73,7 +82,16 @@ impl AppView for KeyringView {
                         for id in self.password_ids.get() {
                             let selected = self.password_id == id;
 
-                            if ui.selectable_label(selected, id.clone()).clicked() {
+                            // Mark unused ids.
+                            let label = match &used_ids {
+                                Some(used_ids) if !used_ids.contains(&id) => {
+                                    RichText::new(format!("{} (unused)", id))
+                                        .color(Color32::DARK_GRAY)
+                                }
+                                _ => RichText::new(id.clone()),
+                            };
+
+                            if ui.selectable_label(selected, label).clicked() {
                                 // Set password id.
                                 self.password_id = id;
 
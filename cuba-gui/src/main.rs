@@ -2,42 +2,61 @@
 
 mod about;
 mod backup_view;
+mod backup_window;
 mod config_view;
 mod egui_widgets;
+mod help_view;
 mod keyring_view;
 mod msg_log_views;
 mod password_ids;
 mod restore_view;
+mod settings;
 mod task_progress;
+mod tray;
 mod util;
 
 use std::{
     collections::HashMap,
     path::Path,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 
 use crate::{
     about::show_about,
-    backup_view::BackupView,
+    backup_view::{BackupView, DraggedProfile},
+    backup_window::BackupWindow,
     config_view::ConfigView,
+    egui_widgets::connection_status_dot,
+    help_view::HelpView,
     keyring_view::KeyringView,
-    msg_log_views::{MsgLogLevel, MsgLogView},
+    msg_log_views::{DEFAULT_MAX_MESSAGES, MsgLogLevel, MsgLogView},
     password_ids::PasswordIDs,
     restore_view::RestoreView,
+    settings::{CubaGuiSettings, SETTINGS_FILE, show_settings},
+    tray::CubaTray,
 };
 
 use crossbeam_channel::{Sender, unbounded};
 use cuba_lib::{
-    core::cuba::Cuba,
+    core::cuba::{ConnectionStatus, Cuba, RunHandle},
     send_error,
-    shared::{config::load_config_from_file, message::Message, msg_dispatcher::MsgDispatcher},
+    shared::{
+        config::load_config_from_file,
+        message::Message,
+        msg_dispatcher::MsgDispatcher,
+        npath::{PLATFORM_MAX_PATH_LEN, set_max_path_len},
+    },
 };
 use eframe::egui;
 use egui::{FontData, FontDefinitions, FontFamily};
 use egui_dock::{DockArea, DockState, NodeIndex, Style, TabViewer};
+use rfd::FileDialog;
 use serde::{Deserialize, Serialize};
 
+/// The config file.
+const CONFIG_FILE: &str = "cuba.toml";
+
 /// The layout file.
 const LAYOUT_FILE: &str = "cuba-gui-layout.json";
 
@@ -64,6 +83,9 @@ fn setup_fonts(ctx: &egui::Context) {
 }
 
 fn main() -> eframe::Result<()> {
+    // Limit the paths to the length of the platform.
+    set_max_path_len(PLATFORM_MAX_PATH_LEN);
+
     // Build viewport.
     let viewport = egui::ViewportBuilder::default()
         .with_inner_size(egui::vec2(1200.0, 800.0))
@@ -108,12 +130,14 @@ impl UpdateHandler {
 }
 
 // Defines the different views in the app.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 enum ViewId {
     Backup,
+    ProfileBackup(String),
     Restore,
     Config,
     Keyring,
+    Help,
     InfoLog,
     WarningLog,
     ErrorLog,
@@ -129,6 +153,11 @@ trait AppView {
 
     // Returns the view id.
     fn view_id(&self) -> ViewId;
+
+    /// Returns the `RunHandle` of the view, if the view runs operations.
+    fn run_handle(&self) -> Option<&RunHandle> {
+        None
+    }
 }
 
 /// Defines a `AppViewer`.
@@ -157,12 +186,21 @@ impl<'a> TabViewer for AppViewer<'a> {
 struct CubaGui {
     sender: Sender<Arc<dyn Message>>,
     cuba: Arc<RwLock<Cuba>>,
-    _msg_dispatcher: Arc<MsgDispatcher<Arc<dyn Message>>>,
+    egui_context: egui::Context,
+    msg_dispatcher: Arc<MsgDispatcher<Arc<dyn Message>>>,
     app_views: HashMap<ViewId, Arc<RwLock<dyn AppView>>>,
+    config_view: Arc<RwLock<ConfigView>>,
     dock_state: DockState<ViewId>,
     post_init_done: bool,
     show_about: bool,
+    show_settings: bool,
+    settings: CubaGuiSettings,
+    last_autosave: Instant,
+    backup_windows: Vec<BackupWindow>,
+    backup_window_count: usize,
     icon_texture: egui::TextureHandle,
+    config_file: String,
+    tray: CubaTray,
 }
 
 /// Methods of `CubaGui`.
@@ -187,39 +225,16 @@ impl CubaGui {
         // Setup fonts.
         setup_fonts(&creation_ctx.egui_ctx);
 
-        // Set fonts.
-        let mut style = (*creation_ctx.egui_ctx.style()).clone();
-
-        style.text_styles = [
-            (
-                egui::TextStyle::Heading,
-                egui::FontId::new(20.0, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Body,
-                egui::FontId::new(16.0, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Button,
-                egui::FontId::new(16.0, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Small,
-                egui::FontId::new(13.0, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Monospace,
-                egui::FontId::new(14.0, egui::FontFamily::Monospace),
-            ),
-        ]
-        .into();
-
-        // Set style.
-        creation_ctx.egui_ctx.set_style(style);
+        // Set the default settings, until the settings file is loaded.
+        let settings = CubaGuiSettings::default();
+        settings.apply(&creation_ctx.egui_ctx);
 
         // Sender and receiver for messages between the GUI and the Cuba instance.
         let (sender, receiver) = unbounded::<Arc<dyn Message>>();
 
+        // The tray icon.
+        let tray = CubaTray::new(&creation_ctx.egui_ctx, sender.clone());
+
         // The message dispatcher.
         let mut msg_dispatcher = MsgDispatcher::new(receiver.clone());
         msg_dispatcher.start();
@@ -261,11 +276,15 @@ impl CubaGui {
             password_ids.clone(),
         )));
 
+        // The help view.
+        let help_view = Arc::new(RwLock::new(HelpView::new(sender.clone())));
+
         // The infos view.
         let infos_view = Arc::new(RwLock::new(MsgLogView::new(
             creation_ctx.egui_ctx.clone(),
             MsgLogLevel::Info,
             arc_msg_dispatcher.clone(),
+            DEFAULT_MAX_MESSAGES,
         )));
 
         // The warnings view.
@@ -273,6 +292,7 @@ impl CubaGui {
             creation_ctx.egui_ctx.clone(),
             MsgLogLevel::Warning,
             arc_msg_dispatcher.clone(),
+            DEFAULT_MAX_MESSAGES,
         )));
 
         // The errors view.
@@ -280,13 +300,15 @@ impl CubaGui {
             creation_ctx.egui_ctx.clone(),
             MsgLogLevel::Error,
             arc_msg_dispatcher.clone(),
+            DEFAULT_MAX_MESSAGES,
         )));
 
         let mut app_views = HashMap::<ViewId, Arc<RwLock<dyn AppView>>>::new();
         app_views.insert(ViewId::Backup, backup_view);
         app_views.insert(ViewId::Restore, restore_view);
-        app_views.insert(ViewId::Config, config_view);
+        app_views.insert(ViewId::Config, config_view.clone());
         app_views.insert(ViewId::Keyring, keyring_view);
+        app_views.insert(ViewId::Help, help_view);
         app_views.insert(ViewId::InfoLog, infos_view);
         app_views.insert(ViewId::WarningLog, warnings_view);
         app_views.insert(ViewId::ErrorLog, errors_view);
@@ -298,12 +320,21 @@ impl CubaGui {
         Self {
             sender: sender.clone(),
             cuba: cuba.clone(),
-            _msg_dispatcher: arc_msg_dispatcher,
+            egui_context: creation_ctx.egui_ctx.clone(),
+            msg_dispatcher: arc_msg_dispatcher,
             app_views,
+            config_view,
             dock_state,
             post_init_done: false,
             show_about: false,
+            show_settings: false,
+            settings,
+            last_autosave: Instant::now(),
+            backup_windows: Vec::new(),
+            backup_window_count: 0,
             icon_texture,
+            config_file: CONFIG_FILE.to_string(),
+            tray,
         }
     }
 
@@ -334,6 +365,42 @@ impl CubaGui {
         }
     }
 
+    /// Inserts a backup view for the pinned `profile`, if it does not exist.
+    fn insert_profile_view(&mut self, profile: &str) {
+        let view_id = ViewId::ProfileBackup(profile.to_string());
+
+        if !self.app_views.contains_key(&view_id) {
+            let profile_view = Arc::new(RwLock::new(
+                BackupView::new(
+                    self.egui_context.clone(),
+                    self.sender.clone(),
+                    self.cuba.clone(),
+                    self.msg_dispatcher.clone(),
+                )
+                .pinned(profile),
+            ));
+
+            self.app_views.insert(view_id, profile_view);
+        }
+    }
+
+    /// Pins the backup `profile` as a separate tab.
+    fn pin_profile(&mut self, profile: &str) {
+        let view_id = ViewId::ProfileBackup(profile.to_string());
+
+        self.insert_profile_view(profile);
+
+        if self
+            .dock_state
+            .find_tab_from(|existing_view_id: &ViewId| *existing_view_id == view_id)
+            .is_some()
+        {
+            self.set_active_view(&view_id);
+        } else {
+            self.dock_state.push_to_focused_leaf(view_id);
+        }
+    }
+
     /// Reset the default layout of the GUI.
     pub fn reset_default_layout(&mut self) {
         self.dock_state = egui_dock::DockState::new(Vec::new());
@@ -348,6 +415,7 @@ impl CubaGui {
         surface.push_to_first_leaf(ViewId::Restore);
         surface.push_to_first_leaf(ViewId::Config);
         surface.push_to_first_leaf(ViewId::Keyring);
+        surface.push_to_first_leaf(ViewId::Help);
 
         let bottom = surface.split_below(NodeIndex::root(), 0.6, vec![ViewId::InfoLog]);
 
@@ -356,8 +424,9 @@ impl CubaGui {
 
     /// Post initialization.
     fn post_init(&mut self) {
-        if let Some(config) = load_config_from_file(self.sender.clone(), "cuba.toml") {
+        if let Some(config) = load_config_from_file(self.sender.clone(), CONFIG_FILE) {
             self.cuba.write().unwrap().set_config(config);
+            self.config_view.write().unwrap().clear_history();
         }
 
         // Set active view.
@@ -367,6 +436,28 @@ impl CubaGui {
         if Path::new(LAYOUT_FILE).exists() {
             self.load_layout();
         }
+
+        // Load settings if they exist.
+        if Path::new(SETTINGS_FILE).exists() {
+            self.load_settings();
+        }
+    }
+
+    /// Opens the config file at `path` and adds it to the recent files.
+    fn open_config(&mut self, path: &str) {
+        if let Some(config) = load_config_from_file(self.sender.clone(), path) {
+            self.cuba.write().unwrap().set_config(config);
+
+            {
+                let mut config_view = self.config_view.write().unwrap();
+                config_view.clear_history();
+                config_view.set_config_file(path);
+            }
+
+            self.config_file = path.to_string();
+            self.settings.add_recent_file(path);
+            self.save_settings();
+        }
     }
 
     /// Save the current layout state to a file.
@@ -396,9 +487,106 @@ impl CubaGui {
 
         match serde_json::from_str(&serialized) {
             Ok(dock_state) => self.dock_state = dock_state,
+            Err(err) => {
+                send_error!(self.sender, err);
+                return;
+            }
+        }
+
+        // Create the views of the pinned profiles.
+        let profiles: Vec<String> = self
+            .dock_state
+            .iter_all_tabs()
+            .filter_map(|(_, view_id)| match view_id {
+                ViewId::ProfileBackup(profile) => Some(profile.clone()),
+                _ => None,
+            })
+            .collect();
+
+        for profile in profiles {
+            self.insert_profile_view(&profile);
+        }
+    }
+
+    /// Save the settings to a file.
+    pub fn save_settings(&self) {
+        let serialized = match serde_json::to_string_pretty(&self.settings) {
+            Ok(serialized) => serialized,
+            Err(err) => {
+                send_error!(self.sender, err);
+                return;
+            }
+        };
+
+        if let Err(err) = std::fs::write(SETTINGS_FILE, serialized) {
+            send_error!(self.sender, err);
+        }
+    }
+
+    /// Load the settings from a file.
+    pub fn load_settings(&mut self) {
+        let serialized = match std::fs::read_to_string(SETTINGS_FILE) {
+            Ok(serialized) => serialized,
+            Err(err) => {
+                send_error!(self.sender, err);
+                return;
+            }
+        };
+
+        match serde_json::from_str(&serialized) {
+            Ok(settings) => self.settings = settings,
             Err(err) => send_error!(self.sender, err),
         }
     }
+
+    /// Shows the source and destination connection status of each active view.
+    ///
+    /// A view is active while it runs or if a connection of its last run failed.
+    fn connection_status_ui(&self, ui: &mut egui::Ui) {
+        let mut active = false;
+
+        for app_view in self.app_views.values() {
+            let app_view = app_view.read().unwrap();
+
+            if let Some(run_handle) = app_view.run_handle() {
+                let src_status = run_handle.src_status();
+                let dest_status = run_handle.dest_status();
+
+                if run_handle.is_running()
+                    || src_status == ConnectionStatus::Error
+                    || dest_status == ConnectionStatus::Error
+                {
+                    // Right to left, the destination is shown last.
+                    connection_status_dot(
+                        ui,
+                        &format!("{} destination", app_view.name()),
+                        dest_status,
+                    );
+                    connection_status_dot(ui, &format!("{} source", app_view.name()), src_status);
+                    active = true;
+                }
+            }
+        }
+
+        if !active {
+            connection_status_dot(ui, "No operation", ConnectionStatus::Idle);
+        }
+    }
+
+    /// Saves the layout, if the autosave interval has elapsed.
+    fn autosave(&mut self, ctx: &egui::Context) {
+        if self.settings.autosave_interval_secs > 0 {
+            let interval = Duration::from_secs(self.settings.autosave_interval_secs);
+
+            if self.last_autosave.elapsed() >= interval {
+                self.save_layout();
+                self.last_autosave = Instant::now();
+            }
+
+            // Wake up for the next autosave.
+            ctx.request_repaint_after(interval.saturating_sub(self.last_autosave.elapsed()));
+        }
+    }
 }
 
 /// Impl of `eframe::App` for `CubaGui`.
@@ -406,15 +594,44 @@ impl eframe::App for CubaGui {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         if !self.post_init_done {
             self.post_init();
+            self.settings.apply(ctx);
             self.post_init_done = true;
         }
 
         egui::TopBottomPanel::top("Menu").show(ctx, |ui| {
             egui::MenuBar::new().ui(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.button("Open Config…").clicked()
+                        && let Some(path) =
+                            FileDialog::new().add_filter("TOML", &["toml"]).pick_file()
+                    {
+                        self.open_config(&path.to_string_lossy());
+                    }
+
+                    ui.add_enabled_ui(!self.settings.recent_files.is_empty(), |ui| {
+                        ui.menu_button("Recent", |ui| {
+                            for recent_file in self.settings.recent_files.clone() {
+                                if ui.button(recent_file.as_str()).clicked() {
+                                    self.open_config(&recent_file);
+                                }
+                            }
+                        });
+                    });
+                });
+
                 if ui.button("Quit").clicked() {
                     ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 };
 
+                if ui.button("New Window").clicked() {
+                    self.backup_window_count += 1;
+                    self.backup_windows.push(BackupWindow::new(
+                        ctx.clone(),
+                        self.backup_window_count,
+                        &self.config_file,
+                    ));
+                };
+
                 ui.menu_button("Views", |ui| {
                     for app_view in self.app_views.clone().values() {
                         self.add_view_button(app_view, ui);
@@ -435,12 +652,42 @@ impl eframe::App for CubaGui {
                     }
                 });
 
+                if ui.button("Settings").clicked() {
+                    self.show_settings = true;
+                };
+
                 if ui.button("About").clicked() {
                     self.show_about = true;
                 };
+
+                // The connection status of the running operations.
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    self.connection_status_ui(ui);
+                });
             });
         });
 
+        // The settings dialog, saves on close.
+        if self.show_settings {
+            let previous_settings = self.settings.clone();
+
+            if show_settings(ctx, &mut self.show_settings, &mut self.settings) {
+                self.save_settings();
+            }
+
+            if self.settings != previous_settings {
+                self.settings.apply(ctx);
+            }
+        }
+
+        // The backup windows, closed ones are removed after their run has finished.
+        for backup_window in &mut self.backup_windows {
+            backup_window.show(ctx);
+        }
+
+        self.backup_windows
+            .retain(|backup_window| backup_window.is_open() || backup_window.is_running());
+
         // The about dialog.
         if self.show_about {
             show_about(ctx, &mut self.show_about, &self.icon_texture);
@@ -454,5 +701,26 @@ impl eframe::App for CubaGui {
                     app_views: &self.app_views,
                 },
             );
+
+        // Pin a profile dropped onto the dock area.
+        if ctx.input(|input| input.pointer.any_released())
+            && let Some(dragged_profile) = egui::DragAndDrop::take_payload::<DraggedProfile>(ctx)
+        {
+            self.pin_profile(&dragged_profile.0);
+        }
+
+        // Autosave the layout.
+        self.autosave(ctx);
+
+        // Show the status of the last run in the tray icon.
+        self.tray.update();
+
+        // Hide the minimized window, the tray icon shows it again.
+        if self.settings.minimize_to_tray
+            && self.tray.is_available()
+            && ctx.input(|input| input.viewport().minimized == Some(true))
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+        }
     }
 }
@@ -1,6 +1,10 @@
 use std::{
+    collections::VecDeque,
     error::Error,
-    sync::{Arc, RwLock},
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
 use cuba_lib::shared::{
@@ -8,28 +12,38 @@ use cuba_lib::shared::{
     msg_dispatcher::MsgDispatcher,
     msg_receiver::{MsgHandler, MsgReceiver, trace_error},
     npath::{Rel, UNPath},
+    rich_error::RichError,
 };
 
 use crate::{AppView, UpdateHandler, ViewId};
 
+/// The default maximum number of messages kept in the history.
+pub const DEFAULT_MAX_MESSAGES: usize = 10_000;
+
 /// Defines a `MsgLogView`.
 pub struct MsgLogView {
     log_level: MsgLogLevel,
     _msg_receiver: MsgReceiver,
     msg_log: Arc<MsgLog>,
+    snapshot: String,
+    snapshot_generation: Option<usize>,
 }
 
 /// Methods of `MsgLogView`.
 impl MsgLogView {
-    /// Creates a new `MsgLogView`.
+    /// Creates a new `MsgLogView`, which keeps at most `max_messages` messages.
+    ///
+    /// The subscriber is created here, so that no messages are lost before the view is shown.
     pub fn new(
         egui_context: egui::Context,
         log_level: MsgLogLevel,
         msg_dispatcher: Arc<MsgDispatcher<Arc<dyn Message>>>,
+        max_messages: usize,
     ) -> Self {
         let msg_log = Arc::new(MsgLog::new(
             UpdateHandler::new(egui_context.clone()),
             log_level.clone(),
+            max_messages,
         ));
         let mut msg_receiver = MsgReceiver::new(msg_dispatcher.subscribe(), msg_log.clone());
 
@@ -39,6 +53,18 @@ impl MsgLogView {
             log_level,
             _msg_receiver: msg_receiver,
             msg_log,
+            snapshot: String::new(),
+            snapshot_generation: None,
+        }
+    }
+
+    /// Rebuilds the snapshot, if the messages have changed.
+    fn update_snapshot(&mut self) {
+        let generation = self.msg_log.generation();
+
+        if self.snapshot_generation != Some(generation) {
+            self.snapshot = self.msg_log.snapshot();
+            self.snapshot_generation = Some(generation);
         }
     }
 }
@@ -77,27 +103,27 @@ impl AppView for MsgLogView {
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 // Clear button.
                 if ui.small_button("🗑 Clear").clicked() {
-                    self.msg_log.messages.write().unwrap().clear();
-                    self.msg_log.update_handler.update();
+                    self.msg_log.clear();
                 }
 
                 // Copy button.
                 if ui.small_button("📋 Copy").clicked() {
-                    let text = self.msg_log.snapshot();
-                    ui.ctx().copy_text(text);
+                    self.update_snapshot();
+                    ui.ctx().copy_text(self.snapshot.clone());
                 }
             });
         });
 
-        let mut messages = self.msg_log.snapshot();
+        self.update_snapshot();
         let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
         let desired_rows = (ui.available_height() / row_height) as usize;
 
+        // Sticks to the bottom only, if the user was already at the bottom.
         egui::ScrollArea::vertical()
             .auto_shrink(false)
             .stick_to_bottom(true)
             .show(ui, |ui| {
-                egui::TextEdit::multiline(&mut messages)
+                egui::TextEdit::multiline(&mut self.snapshot.as_str())
                     .font(egui::TextStyle::Monospace)
                     .text_color(text_color)
                     .desired_width(ui.available_width())
@@ -117,26 +143,70 @@ pub enum MsgLogLevel {
 }
 
 /// Defines a `MsgLog`.
+///
+/// Keeps the messages of its level, the generation counts the changes.
 pub struct MsgLog {
     log_level: MsgLogLevel,
-    messages: RwLock<String>,
+    max_messages: usize,
+    messages: RwLock<VecDeque<String>>,
+    generation: AtomicUsize,
     update_handler: UpdateHandler,
 }
 
 /// Methods of `MsgLog`.
 impl MsgLog {
     /// Creates a new `MsgLog`.
-    pub fn new(update_handler: UpdateHandler, log_level: MsgLogLevel) -> Self {
+    pub fn new(update_handler: UpdateHandler, log_level: MsgLogLevel, max_messages: usize) -> Self {
         MsgLog {
             log_level,
-            messages: RwLock::new(String::new()),
+            max_messages: max_messages.max(1),
+            messages: RwLock::new(VecDeque::new()),
+            generation: AtomicUsize::new(0),
             update_handler,
         }
     }
 
+    /// Returns the generation, which changes with the messages.
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
     /// Returns a snapshot of the `MsgLog`.
     pub fn snapshot(&self) -> String {
-        self.messages.read().unwrap().clone()
+        let mut snapshot = String::new();
+
+        for message in self.messages.read().unwrap().iter() {
+            snapshot.push_str(message);
+            snapshot.push('\n');
+        }
+
+        snapshot
+    }
+
+    /// Pushes a message, if it has the level of the `MsgLog`.
+    fn push(&self, level: MsgLogLevel, message: String) {
+        if self.log_level == level {
+            let mut messages = self.messages.write().unwrap();
+
+            // Drop the oldest messages.
+            while messages.len() >= self.max_messages {
+                messages.pop_front();
+            }
+
+            messages.push_back(message);
+            self.generation.fetch_add(1, Ordering::Release);
+            drop(messages);
+
+            self.update_handler.update();
+        }
+    }
+
+    /// Clears the messages.
+    pub fn clear(&self) {
+        self.messages.write().unwrap().clear();
+        self.generation.fetch_add(1, Ordering::Release);
+
+        self.update_handler.update();
     }
 }
 
@@ -149,13 +219,7 @@ impl MsgHandler for MsgLog {
         rel_path: &UNPath<Rel>,
         info: &(dyn Info + Send + Sync),
     ) {
-        if self.log_level == MsgLogLevel::Info {
-            self.messages
-                .write()
-                .unwrap()
-                .push_str(&format!("{:?} : {}\n", rel_path, info));
-            self.update_handler.update();
-        }
+        self.push(MsgLogLevel::Info, format!("{:?} : {}", rel_path, info));
     }
 
     /// Handles a `TaskInfo::Verified` message.
@@ -165,85 +229,42 @@ impl MsgHandler for MsgLog {
         rel_path: &UNPath<Rel>,
         info: &(dyn Info + Send + Sync),
     ) {
-        if self.log_level == MsgLogLevel::Info {
-            self.messages
-                .write()
-                .unwrap()
-                .push_str(&format!("{:?} : {}\n", rel_path, info));
-            self.update_handler.update();
-        }
+        self.push(MsgLogLevel::Info, format!("{:?} : {}", rel_path, info));
     }
 
     /// Handles a `TaskMessage` with error.
-    fn task_error(
-        &self,
-        _thread_number: usize,
-        rel_path: &UNPath<Rel>,
-        error: &(dyn Error + Send + Sync),
-    ) {
-        if self.log_level == MsgLogLevel::Error {
-            self.messages.write().unwrap().push_str(&format!(
-                "{:?} : {}\n",
-                rel_path,
-                trace_error(error)
-            ));
-            self.update_handler.update();
-        }
+    fn task_error(&self, _thread_number: usize, rel_path: &UNPath<Rel>, error: &RichError) {
+        self.push(
+            MsgLogLevel::Error,
+            format!("{:?} : {}", rel_path, trace_error(error)),
+        );
     }
 
     /// Handles a `CleanInfo::Removed` message.
     fn clean_removed(&self, rel_path: &UNPath<Rel>, info: &(dyn Info + Send + Sync)) {
-        if self.log_level == MsgLogLevel::Info {
-            self.messages
-                .write()
-                .unwrap()
-                .push_str(&format!("{:?} : {}\n", rel_path, info));
-            self.update_handler.update();
-        }
+        self.push(MsgLogLevel::Info, format!("{:?} : {}", rel_path, info));
     }
 
     /// Handles a `CleanMessage` with error.
     fn clean_error(&self, rel_path: &UNPath<Rel>, error: &(dyn Error + Send + Sync)) {
-        if self.log_level == MsgLogLevel::Error {
-            self.messages.write().unwrap().push_str(&format!(
-                "{:?} : {}\n",
-                rel_path,
-                trace_error(error)
-            ));
-            self.update_handler.update();
-        }
+        self.push(
+            MsgLogLevel::Error,
+            format!("{:?} : {}", rel_path, trace_error(error)),
+        );
     }
 
     /// Handles a `InfoMessage`.
     fn info(&self, info: &(dyn Info + Send + Sync)) {
-        if self.log_level == MsgLogLevel::Info {
-            self.messages
-                .write()
-                .unwrap()
-                .push_str(&format!("{}\n", info));
-            self.update_handler.update();
-        }
+        self.push(MsgLogLevel::Info, format!("{}", info));
     }
 
     /// Handles a `WarnMessage`.
     fn warn(&self, warning: &(dyn Info + Send + Sync)) {
-        if self.log_level == MsgLogLevel::Warning {
-            self.messages
-                .write()
-                .unwrap()
-                .push_str(&format!("{}\n", warning));
-            self.update_handler.update();
-        }
+        self.push(MsgLogLevel::Warning, format!("{}", warning));
     }
 
     /// Handles a `ErrorMessage`.
     fn error(&self, error: &(dyn Error + Send + Sync)) {
-        if self.log_level == MsgLogLevel::Error {
-            self.messages
-                .write()
-                .unwrap()
-                .push_str(&format!("{}\n", trace_error(error)));
-            self.update_handler.update();
-        }
+        self.push(MsgLogLevel::Error, trace_error(error));
     }
 }
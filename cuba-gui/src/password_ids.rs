@@ -3,7 +3,7 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use cuba_lib::core::cuba::Cuba;
+use cuba_lib::{core::cuba::Cuba, shared::config::Config};
 
 /// Defines a `PasswordIDs`.
 pub struct PasswordIDs {
@@ -26,6 +26,21 @@ impl PasswordIDs {
         self.ids.read().unwrap().clone()
     }
 
+    /// Returns the password ids, which are used by the config.
+    pub fn used_by(&self, config: &Config) -> Vec<String> {
+        let mut used_ids: Vec<String> = self
+            .ids
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|id| config.has_password_id(id))
+            .cloned()
+            .collect();
+
+        used_ids.sort();
+        used_ids
+    }
+
     /// Refresh password ids from keyring.
     pub fn update(&self) {
         let mut ids = self.ids.write().unwrap();
@@ -6,15 +6,21 @@ use std::{
 use crossbeam_channel::Sender;
 use cuba_lib::{
     core::cuba::{Cuba, RunHandle},
-    shared::{message::Message, msg_dispatcher::MsgDispatcher},
+    send_error,
+    shared::{
+        message::Message,
+        msg_dispatcher::MsgDispatcher,
+        npath::{Abs, File, NPath, Rel},
+    },
 };
 use egui::Color32;
+use rfd::FileDialog;
 
 use crate::{
     AppView, UpdateHandler, ViewId,
     egui_widgets::ProgressSpinner,
     task_progress::{TaskMessageType, TaskProgress},
-    util::make_cuba_runner,
+    util::{RunResult, RunThread, make_cuba_runner, run_result_label},
 };
 
 /// Defines a `RestoreView`.
@@ -23,8 +29,11 @@ pub struct RestoreView {
     sender: Sender<Arc<dyn Message>>,
     cuba: Arc<RwLock<Cuba>>,
     selected_profiles: HashSet<String>,
+    restore_file: String,
     msg_dispatcher: Arc<MsgDispatcher<Arc<dyn Message>>>,
     task_progress: Arc<TaskProgress>,
+    run_result: RunResult,
+    run_thread: RunThread,
 }
 
 /// Methods of `RestoreView`.
@@ -43,8 +52,11 @@ impl RestoreView {
             sender,
             cuba,
             selected_profiles: HashSet::new(),
+            restore_file: String::new(),
             msg_dispatcher,
             task_progress,
+            run_result: RunResult::default(),
+            run_thread: RunThread::default(),
         }
     }
 }
@@ -61,6 +73,11 @@ impl AppView for RestoreView {
         ViewId::Restore
     }
 
+    /// Returns the `RunHandle` of the view.
+    fn run_handle(&self) -> Option<&RunHandle> {
+        Some(&self.run_handle)
+    }
+
     /// Renders the view for egui.
     fn ui(&mut self, ui: &mut egui::Ui) {
         // Set height.
@@ -180,17 +197,35 @@ impl AppView for RestoreView {
                         ),
                     );
 
+                    // The result banner of the finished run.
+                    run_result_label(ui, &self.run_handle, &self.run_result);
+
+                    // Separator.
+                    ui.separator();
+
+                    // The single file to restore.
+                    ui.horizontal(|ui| {
+                        ui.label("File:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.restore_file)
+                                .hint_text("path in the source of the backup"),
+                        );
+                    });
+
                     // Separator.
                     ui.separator();
 
                     // Prepare a runner.
                     let run = make_cuba_runner(
+                        ui.ctx().clone(),
                         self.run_handle.clone(),
                         self.sender.clone(),
                         self.cuba.clone(),
                         self.selected_profiles.clone(),
                         self.msg_dispatcher.clone(),
                         self.task_progress.clone(),
+                        self.run_result.clone(),
+                        self.run_thread.clone(),
                     );
 
                     // Horizontal layout (run buttons).
@@ -210,10 +245,44 @@ impl AppView for RestoreView {
                                 run(
                                     "Restore".to_string(),
                                     Box::new(|cuba, run_handle, profile| {
-                                        cuba.read().unwrap().run_restore(run_handle, &profile)
+                                        cuba.read()
+                                            .unwrap()
+                                            .run_restore(run_handle, &profile, None, None)
                                     }),
                                 );
                             }
+
+                            // The restore file button, asks for the alternative dest.
+                            if ui
+                                .add_enabled(
+                                    !self.restore_file.is_empty(),
+                                    egui::Button::new("Save to…"),
+                                )
+                                .clicked()
+                                && let Some(path) = FileDialog::new().save_file()
+                            {
+                                let restore_file =
+                                    NPath::<Rel, File>::try_from(self.restore_file.as_str());
+                                let restore_to =
+                                    NPath::<Abs, File>::try_from(path.to_string_lossy().as_ref());
+
+                                match (restore_file, restore_to) {
+                                    (Ok(restore_file), Ok(restore_to)) => {
+                                        run(
+                                            "Restore".to_string(),
+                                            Box::new(move |cuba, run_handle, profile| {
+                                                cuba.read().unwrap().run_restore(
+                                                    run_handle,
+                                                    &profile,
+                                                    Some(&restore_file),
+                                                    Some(&restore_to),
+                                                )
+                                            }),
+                                        );
+                                    }
+                                    (Err(err), _) | (_, Err(err)) => send_error!(self.sender, err),
+                                }
+                            }
                         }
                     });
                 }
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+
+use crate::egui_widgets::{build_row, label_value_table};
+
+/// The settings file.
+pub const SETTINGS_FILE: &str = "cuba-gui-settings.json";
+
+/// The maximum number of recent files.
+const MAX_RECENT_FILES: usize = 5;
+
+/// The id of the notify on complete setting in the egui context.
+const NOTIFY_ON_COMPLETE_ID: &str = "notify_on_complete";
+
+/// Defines a `Theme`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    System,
+    Light,
+    Dark,
+}
+
+/// Methods of `Theme`.
+impl Theme {
+    /// All themes.
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    /// Returns the name of the theme.
+    pub fn name(&self) -> &str {
+        match self {
+            Theme::System => "System",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+}
+
+/// Impl of `From<Theme>` for `egui::ThemePreference`.
+impl From<Theme> for egui::ThemePreference {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Light => egui::ThemePreference::Light,
+            Theme::Dark => egui::ThemePreference::Dark,
+        }
+    }
+}
+
+/// Defines the `CubaGuiSettings`.
+///
+/// The user preferences, which are kept apart from the layout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CubaGuiSettings {
+    /// The font size of the body text, the other text styles are derived.
+    pub font_size: f32,
+
+    /// The theme.
+    pub theme: Theme,
+
+    /// Notify, when a backup or restore is complete.
+    pub notify_on_complete: bool,
+
+    /// Minimize to the tray instead of the taskbar.
+    pub minimize_to_tray: bool,
+
+    /// The interval in seconds to save the layout, 0 disables it.
+    pub autosave_interval_secs: u64,
+
+    /// The recently opened config files, the most recent first.
+    pub recent_files: Vec<String>,
+}
+
+/// Impl of `Default` for `CubaGuiSettings`.
+impl Default for CubaGuiSettings {
+    fn default() -> Self {
+        Self {
+            font_size: 16.0,
+            theme: Theme::System,
+            notify_on_complete: false,
+            minimize_to_tray: false,
+            autosave_interval_secs: 300,
+            recent_files: Vec::new(),
+        }
+    }
+}
+
+/// Methods of `CubaGuiSettings`.
+impl CubaGuiSettings {
+    /// Adds a recently opened config file, keeps the last `MAX_RECENT_FILES`.
+    pub fn add_recent_file(&mut self, path: &str) {
+        self.recent_files.retain(|recent_file| recent_file != path);
+        self.recent_files.insert(0, path.to_string());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Applies the settings to the egui context.
+    pub fn apply(&self, ctx: &egui::Context) {
+        let font_size = self.font_size;
+
+        // Set theme.
+        ctx.set_theme(self.theme);
+
+        // Set notify on complete, the runs read it from the context.
+        ctx.data_mut(|data| {
+            data.insert_temp(
+                egui::Id::new(NOTIFY_ON_COMPLETE_ID),
+                self.notify_on_complete,
+            )
+        });
+
+        // Set fonts for all themes.
+        ctx.all_styles_mut(|style| {
+            style.text_styles = [
+                (
+                    egui::TextStyle::Heading,
+                    egui::FontId::new(font_size + 4.0, egui::FontFamily::Proportional),
+                ),
+                (
+                    egui::TextStyle::Body,
+                    egui::FontId::new(font_size, egui::FontFamily::Proportional),
+                ),
+                (
+                    egui::TextStyle::Button,
+                    egui::FontId::new(font_size, egui::FontFamily::Proportional),
+                ),
+                (
+                    egui::TextStyle::Small,
+                    egui::FontId::new(font_size - 3.0, egui::FontFamily::Proportional),
+                ),
+                (
+                    egui::TextStyle::Monospace,
+                    egui::FontId::new(font_size - 2.0, egui::FontFamily::Monospace),
+                ),
+            ]
+            .into();
+        });
+    }
+}
+
+/// Returns true, if the user is notified when a run is complete.
+pub fn notify_on_complete(ctx: &egui::Context) -> bool {
+    ctx.data(|data| data.get_temp(egui::Id::new(NOTIFY_ON_COMPLETE_ID)))
+        .unwrap_or(false)
+}
+
+/// Show the settings dialog.
+///
+/// Returns true, when the dialog was closed.
+pub fn show_settings(
+    ctx: &egui::Context,
+    show_settings: &mut bool,
+    settings: &mut CubaGuiSettings,
+) -> bool {
+    let mut closed = false;
+
+    egui::Window::new("Settings")
+        .collapsible(false)
+        .resizable(false)
+        .default_size([500.0, 200.0])
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            // Define widths.
+            let row_height: f32 = 25.0;
+            let label_width = egui_extras::Size::exact(180.0);
+            let value_width = egui_extras::Size::exact(250.0);
+
+            // The settings table.
+            label_value_table(ui, 5, row_height, |rows| {
+                // The font size row.
+                build_row(rows, label_width, "Font size:", value_width, |ui| {
+                    ui.add(egui::Slider::new(&mut settings.font_size, 10.0..=24.0));
+                });
+
+                // The theme row.
+                build_row(rows, label_width, "Theme:", value_width, |ui| {
+                    egui::ComboBox::from_id_salt("Theme")
+                        .selected_text(settings.theme.name())
+                        .show_ui(ui, |ui| {
+                            for theme in Theme::ALL {
+                                ui.selectable_value(&mut settings.theme, theme, theme.name());
+                            }
+                        });
+                });
+
+                // The notify on complete row.
+                build_row(
+                    rows,
+                    label_width,
+                    "Notify on complete:",
+                    value_width,
+                    |ui| {
+                        ui.checkbox(&mut settings.notify_on_complete, "");
+                    },
+                );
+
+                // The minimize to tray row.
+                build_row(rows, label_width, "Minimize to tray:", value_width, |ui| {
+                    ui.checkbox(&mut settings.minimize_to_tray, "");
+                });
+
+                // The autosave interval row.
+                build_row(
+                    rows,
+                    label_width,
+                    "Autosave interval (s):",
+                    value_width,
+                    |ui| {
+                        ui.add(egui::DragValue::new(&mut settings.autosave_interval_secs));
+                    },
+                );
+            });
+
+            ui.add_space(12.0);
+
+            ui.allocate_ui_with_layout(
+                egui::vec2(ui.available_width(), 0.0),
+                egui::Layout::centered_and_justified(egui::Direction::LeftToRight),
+                |ui| {
+                    if ui.button("OK").clicked() {
+                        *show_settings = false;
+                        closed = true;
+                    }
+                },
+            );
+        });
+
+    closed
+}
@@ -1,13 +1,24 @@
-use std::{error::Error, sync::RwLock};
+use std::{
+    error::Error,
+    sync::{
+        RwLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
 
 use cuba_lib::shared::{
     message::Info,
     msg_receiver::MsgHandler,
     npath::{Rel, UNPath},
+    progress_rate::ProgressRate,
+    rich_error::RichError,
 };
 
 use crate::{UpdateHandler, egui_widgets::ProgressState};
 
+/// The maximum length of a displayed path.
+const COMPACT_PATH_LEN: usize = 60;
+
 /// Defines a `TaskMessageType`.
 #[derive(Clone, Copy)]
 pub enum TaskMessageType {
@@ -58,7 +69,11 @@ pub struct TaskProgress {
     transfer_threads: RwLock<usize>,
     task_progress: RwLock<Box<[RwLock<ProgressState>]>>,
     task_message: RwLock<Box<[RwLock<TaskMessage>]>>,
+    task_fraction: RwLock<Box<[RwLock<Option<f32>>]>>,
     total_progress: RwLock<ProgressState>,
+    progress_rate: RwLock<ProgressRate>,
+    error_count: AtomicUsize,
+    finished: AtomicBool,
     update_handler: UpdateHandler,
 }
 
@@ -69,7 +84,11 @@ impl TaskProgress {
             transfer_threads: RwLock::new(0),
             task_progress: RwLock::new(TaskProgress::init(0)),
             task_message: RwLock::new(TaskProgress::init(0)),
+            task_fraction: RwLock::new(TaskProgress::init(0)),
             total_progress: RwLock::new(ProgressState::default()),
+            progress_rate: RwLock::new(ProgressRate::new()),
+            error_count: AtomicUsize::new(0),
+            finished: AtomicBool::new(false),
             update_handler,
         }
     }
@@ -79,6 +98,7 @@ impl TaskProgress {
         *self.transfer_threads.write().unwrap() = transfer_threads;
         *self.task_progress.write().unwrap() = TaskProgress::init(transfer_threads);
         *self.task_message.write().unwrap() = TaskProgress::init(transfer_threads);
+        *self.task_fraction.write().unwrap() = TaskProgress::init(transfer_threads);
     }
 
     // Returns the transfer threads.
@@ -101,11 +121,33 @@ impl TaskProgress {
             .clone()
     }
 
+    /// Returns the fraction of the file, which is transferred by the task, if known.
+    pub fn get_task_fraction(&self, thread_number: usize) -> Option<f32> {
+        *self.task_fraction.read().unwrap()[thread_number]
+            .read()
+            .unwrap()
+    }
+
     /// Returns the total progress.
     pub fn get_total_progress(&self) -> ProgressState {
         *self.total_progress.read().unwrap()
     }
 
+    /// Returns the progress rate, with the elapsed time, the eta and the throughput.
+    pub fn get_progress_rate(&self) -> ProgressRate {
+        self.progress_rate.read().unwrap().clone()
+    }
+
+    /// Returns the number of errors of the last run.
+    pub fn error_count(&self) -> usize {
+        self.error_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns true, if the last run has finished.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
     /// Initializes a vector of `RwLock<T>` with a default value.
     fn init<T: Default>(size: usize) -> Box<[RwLock<T>]> {
         let mut vec = Vec::with_capacity(size);
@@ -128,7 +170,7 @@ impl TaskProgress {
             .write()
             .unwrap() = TaskMessage::new(
             TaskMessageType::Info,
-            rel_path.compact_unicode(),
+            rel_path.compact_display(COMPACT_PATH_LEN),
             info.to_string(),
         );
         self.update_handler.update();
@@ -141,11 +183,13 @@ impl TaskProgress {
         rel_path: &UNPath<Rel>,
         error: &(dyn Error + Send + Sync),
     ) {
+        self.error_count.fetch_add(1, Ordering::Relaxed);
+
         *self.task_message.read().unwrap()[thread_number]
             .write()
             .unwrap() = TaskMessage::new(
             TaskMessageType::Error,
-            rel_path.compact_unicode(),
+            rel_path.compact_display(COMPACT_PATH_LEN),
             error.to_string(),
         );
         self.update_handler.update();
@@ -155,7 +199,7 @@ impl TaskProgress {
     fn handle_clean_info(&self, rel_path: &UNPath<Rel>, info: &(dyn Info + Send + Sync)) {
         *self.task_message.read().unwrap()[0].write().unwrap() = TaskMessage::new(
             TaskMessageType::Info,
-            rel_path.compact_unicode(),
+            rel_path.compact_display(COMPACT_PATH_LEN),
             info.to_string(),
         );
 
@@ -169,9 +213,11 @@ impl TaskProgress {
 
     /// Handles a clean error.
     fn handle_clean_error(&self, rel_path: &UNPath<Rel>, error: &(dyn Error + Send + Sync)) {
+        self.error_count.fetch_add(1, Ordering::Relaxed);
+
         *self.task_message.read().unwrap()[0].write().unwrap() = TaskMessage::new(
             TaskMessageType::Error,
-            rel_path.compact_unicode(),
+            rel_path.compact_display(COMPACT_PATH_LEN),
             error.to_string(),
         );
 
@@ -189,6 +235,9 @@ impl MsgHandler for TaskProgress {
     /// Called when the `MsgHandler` has started.
     fn started(&self) {
         self.total_progress.write().unwrap().clear();
+        self.progress_rate.write().unwrap().restart();
+        self.error_count.store(0, Ordering::Relaxed);
+        self.finished.store(false, Ordering::Relaxed);
 
         for thread_number in 0..*self.transfer_threads.read().unwrap() {
             *self.task_message.read().unwrap()[thread_number]
@@ -197,6 +246,12 @@ impl MsgHandler for TaskProgress {
         }
     }
 
+    /// Called after the `MsgReceiver` has stopped.
+    fn stopped(&self) {
+        self.finished.store(true, Ordering::Relaxed);
+        self.update_handler.update();
+    }
+
     /// Handles a `TaskInfo::Start` message.
     fn task_start(
         &self,
@@ -208,6 +263,9 @@ impl MsgHandler for TaskProgress {
             .write()
             .unwrap()
             .clear();
+        *self.task_fraction.read().unwrap()[thread_number]
+            .write()
+            .unwrap() = None;
         self.handle_task_info(thread_number, rel_path, info);
     }
 
@@ -232,6 +290,9 @@ impl MsgHandler for TaskProgress {
             .write()
             .unwrap()
             .clear();
+        *self.task_fraction.read().unwrap()[thread_number]
+            .write()
+            .unwrap() = None;
         self.handle_task_info(thread_number, rel_path, info);
     }
 
@@ -259,6 +320,14 @@ impl MsgHandler for TaskProgress {
         self.update_handler.update();
     }
 
+    /// Handles a `TaskInfo::Progress` message.
+    fn task_progress(&self, thread_number: usize, _rel_path: &UNPath<Rel>, done: u64, total: u64) {
+        *self.task_fraction.read().unwrap()[thread_number]
+            .write()
+            .unwrap() = Some(done as f32 / total.max(1) as f32);
+        self.update_handler.update();
+    }
+
     /// Handles a `TaskInfo::UpToDate` message.
     fn task_up_to_date(
         &self,
@@ -280,12 +349,7 @@ impl MsgHandler for TaskProgress {
     }
 
     /// Handles a `TaskMessage` with error.
-    fn task_error(
-        &self,
-        thread_number: usize,
-        rel_path: &UNPath<Rel>,
-        error: &(dyn Error + Send + Sync),
-    ) {
+    fn task_error(&self, thread_number: usize, rel_path: &UNPath<Rel>, error: &RichError) {
         self.handle_task_error(thread_number, rel_path, error);
     }
 
@@ -315,4 +379,15 @@ impl MsgHandler for TaskProgress {
         self.total_progress.write().unwrap().set_duration(ticks);
         self.update_handler.update();
     }
+
+    /// Handles a `ProgressInfo::ByteProgress` message.
+    fn progress_bytes(&self, transferred: u64, _total: u64, _info: &(dyn Info + Send + Sync)) {
+        self.progress_rate.write().unwrap().update(transferred);
+        self.update_handler.update();
+    }
+
+    /// Handles a `ErrorMessage`.
+    fn error(&self, _error: &(dyn Error + Send + Sync)) {
+        self.error_count.fetch_add(1, Ordering::Relaxed);
+    }
 }
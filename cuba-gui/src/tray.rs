@@ -0,0 +1,279 @@
+use std::{
+    error::Error,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use cuba_lib::shared::message::{ErrorMessage, Message};
+use image::RgbaImage;
+use tray_icon::{
+    BadIcon, Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent,
+    menu::{Menu, MenuEvent, MenuItem},
+};
+
+/// The id of the show menu item.
+const SHOW_MENU_ID: &str = "show";
+
+/// The id of the quit menu item.
+const QUIT_MENU_ID: &str = "quit";
+
+/// The tooltip of the tray icon.
+const TRAY_TOOLTIP: &str = "Cuba";
+
+/// The interval to poll the status in the gtk event loop.
+#[cfg(target_os = "linux")]
+const STATUS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// The id of the tray status sender in the egui context.
+const TRAY_STATUS_ID: &str = "tray_status";
+
+/// Defines a `TrayStatus`.
+///
+/// The status of the last run, which the tray icon shows.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrayStatus {
+    /// The run with the name is running.
+    Running(String),
+
+    /// The run with the name is complete.
+    Complete(String),
+
+    /// The run with the name failed with the error.
+    Failed(String, String),
+}
+
+/// Methods of `TrayStatus`.
+impl TrayStatus {
+    /// Returns the tooltip of the status.
+    fn tooltip(&self) -> String {
+        match self {
+            TrayStatus::Running(name) => format!("{} - {} running", TRAY_TOOLTIP, name),
+            TrayStatus::Complete(name) => format!("{} - ✓ {} complete", TRAY_TOOLTIP, name),
+            TrayStatus::Failed(name, err) => {
+                format!("{} - ✗ {} failed: {}", TRAY_TOOLTIP, name, err)
+            }
+        }
+    }
+
+    /// Returns the color of the status badge.
+    fn color(&self) -> egui::Color32 {
+        match self {
+            TrayStatus::Running(_) => egui::Color32::LIGHT_BLUE,
+            TrayStatus::Complete(_) => egui::Color32::LIGHT_GREEN,
+            TrayStatus::Failed(_, _) => egui::Color32::LIGHT_RED,
+        }
+    }
+}
+
+/// Sets the status of the tray icon, does nothing without a tray.
+pub fn set_tray_status(ctx: &egui::Context, status: TrayStatus) {
+    if let Some(status_sender) =
+        ctx.data(|data| data.get_temp::<Sender<TrayStatus>>(egui::Id::new(TRAY_STATUS_ID)))
+    {
+        let _ = status_sender.send(status);
+    }
+
+    ctx.request_repaint();
+}
+
+/// Defines a `StatusTrayIcon`.
+///
+/// The tray icon, which applies the received status.
+struct StatusTrayIcon {
+    tray_icon: TrayIcon,
+    image: RgbaImage,
+    status_receiver: Receiver<TrayStatus>,
+    sender: Sender<Arc<dyn Message>>,
+}
+
+/// Methods of `StatusTrayIcon`.
+impl StatusTrayIcon {
+    /// Applies the received status to the icon and the tooltip.
+    fn update(&self) {
+        for status in self.status_receiver.try_iter() {
+            let result = status_icon(&self.image, status.color())
+                .map_err(|err| Box::new(err) as Box<dyn Error + Send + Sync>)
+                .and_then(|icon| Ok(self.tray_icon.set_icon(Some(icon))?))
+                .and_then(|()| Ok(self.tray_icon.set_tooltip(Some(status.tooltip()))?));
+
+            if let Err(err) = result {
+                self.sender
+                    .send(Arc::new(ErrorMessage::new(err.into())))
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// Defines a `CubaTray`.
+///
+/// The tray icon, which shows the status of the last run and the hidden window again. On
+/// Linux the tray icon lives in a gtk thread, on the other platforms in the thread of the
+/// event loop.
+pub struct CubaTray {
+    available: Arc<AtomicBool>,
+
+    #[cfg(not(target_os = "linux"))]
+    status_tray_icon: Option<StatusTrayIcon>,
+}
+
+/// Methods of `CubaTray`.
+impl CubaTray {
+    /// Creates a new `CubaTray`, errors are sent to `sender`.
+    pub fn new(ctx: &egui::Context, sender: Sender<Arc<dyn Message>>) -> Self {
+        let available = Arc::new(AtomicBool::new(false));
+
+        // The runs send their status by the context.
+        let (status_sender, status_receiver) = unbounded::<TrayStatus>();
+        ctx.data_mut(|data| data.insert_temp(egui::Id::new(TRAY_STATUS_ID), status_sender));
+
+        // Show the window on a left click, not emitted on Linux.
+        let click_ctx = ctx.clone();
+        TrayIconEvent::set_event_handler(Some(move |event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_window(&click_ctx);
+            }
+        }));
+
+        // Handle the menu items.
+        let menu_ctx = ctx.clone();
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            if event.id == SHOW_MENU_ID {
+                show_window(&menu_ctx);
+            } else if event.id == QUIT_MENU_ID {
+                show_window(&menu_ctx);
+                menu_ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }));
+
+        #[cfg(target_os = "linux")]
+        {
+            let thread_available = available.clone();
+
+            // The tray icon needs a running gtk event loop in its thread.
+            std::thread::spawn(move || {
+                if let Err(err) = gtk::init() {
+                    sender
+                        .send(Arc::new(ErrorMessage::new(Arc::new(err))))
+                        .unwrap();
+                    return;
+                }
+
+                match build_tray_icon(status_receiver, sender.clone()) {
+                    Ok(status_tray_icon) => {
+                        thread_available.store(true, Ordering::SeqCst);
+
+                        // Poll the status in the gtk event loop.
+                        gtk::glib::timeout_add_local(STATUS_POLL_INTERVAL, move || {
+                            status_tray_icon.update();
+                            gtk::glib::ControlFlow::Continue
+                        });
+
+                        gtk::main();
+                    }
+                    Err(err) => sender
+                        .send(Arc::new(ErrorMessage::new(err.into())))
+                        .unwrap(),
+                }
+            });
+
+            Self { available }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let status_tray_icon = match build_tray_icon(status_receiver, sender.clone()) {
+                Ok(status_tray_icon) => {
+                    available.store(true, Ordering::SeqCst);
+                    Some(status_tray_icon)
+                }
+                Err(err) => {
+                    sender
+                        .send(Arc::new(ErrorMessage::new(err.into())))
+                        .unwrap();
+                    None
+                }
+            };
+
+            Self {
+                available,
+                status_tray_icon,
+            }
+        }
+    }
+
+    /// Returns true, if the tray icon is shown.
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::SeqCst)
+    }
+
+    /// Applies the received status to the tray icon, on Linux the gtk thread applies it.
+    pub fn update(&self) {
+        #[cfg(not(target_os = "linux"))]
+        if let Some(status_tray_icon) = &self.status_tray_icon {
+            status_tray_icon.update();
+        }
+    }
+}
+
+/// Builds the tray icon with its menu.
+fn build_tray_icon(
+    status_receiver: Receiver<TrayStatus>,
+    sender: Sender<Arc<dyn Message>>,
+) -> Result<StatusTrayIcon, Box<dyn Error + Send + Sync>> {
+    let image = image::load_from_memory(include_bytes!("../assets/icons/icon.png"))?.to_rgba8();
+    let icon = Icon::from_rgba(image.to_vec(), image.width(), image.height())?;
+
+    // The menu, Linux shows only the menu on a click.
+    let menu = Menu::new();
+    menu.append(&MenuItem::with_id(SHOW_MENU_ID, "Show Cuba", true, None))?;
+    menu.append(&MenuItem::with_id(QUIT_MENU_ID, "Quit", true, None))?;
+
+    let tray_icon = TrayIconBuilder::new()
+        .with_icon(icon)
+        .with_tooltip(TRAY_TOOLTIP)
+        .with_menu(Box::new(menu))
+        .build()?;
+
+    Ok(StatusTrayIcon {
+        tray_icon,
+        image,
+        status_receiver,
+        sender,
+    })
+}
+
+/// Returns the icon of `image` with a badge of `color` in the bottom right corner.
+fn status_icon(image: &RgbaImage, color: egui::Color32) -> Result<Icon, BadIcon> {
+    let mut image = image.clone();
+    let radius = image.width().min(image.height()) as f32 / 4.0;
+    let center_x = image.width() as f32 - radius;
+    let center_y = image.height() as f32 - radius;
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let dx = x as f32 + 0.5 - center_x;
+        let dy = y as f32 + 0.5 - center_y;
+
+        if dx * dx + dy * dy <= radius * radius {
+            pixel.0 = color.to_array();
+        }
+    }
+
+    Icon::from_rgba(image.to_vec(), image.width(), image.height())
+}
+
+/// Shows and focuses the hidden or minimized window.
+fn show_window(ctx: &egui::Context) {
+    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+    ctx.request_repaint();
+}
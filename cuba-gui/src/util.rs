@@ -1,33 +1,55 @@
 use std::{
     collections::HashSet,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
+    thread::JoinHandle,
 };
 
 use crossbeam_channel::Sender;
 use cuba_lib::{
-    core::cuba::{Cuba, RunHandle},
+    core::cuba::{Cuba, RunError, RunHandle},
     send_info,
     shared::{message::Message, msg_dispatcher::MsgDispatcher, msg_receiver::MsgReceiver},
 };
 
+use crate::settings::notify_on_complete;
 use crate::task_progress::TaskProgress;
+use crate::tray::{TrayStatus, set_tray_status};
 
 type CubaArc = Arc<RwLock<Cuba>>;
 
-type RunFn = dyn Fn(CubaArc, RunHandle, String) + Send + 'static;
+type RunFn = dyn Fn(CubaArc, RunHandle, String) -> Result<(), RunError> + Send + 'static;
+
+/// The name and the result of the last run, a failure of a profile is kept.
+pub type RunResult = Arc<RwLock<Option<(String, Result<(), RunError>)>>>;
+
+/// The thread of the last run.
+pub type RunThread = Arc<Mutex<Option<JoinHandle<()>>>>;
+
+/// Returns true, if the thread of the last run has not finished.
+pub fn is_run_thread_alive(run_thread: &RunThread) -> bool {
+    run_thread
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|thread| !thread.is_finished())
+}
 
 /// Creates a cuba runner.
 #[allow(clippy::too_many_arguments)]
 pub fn make_cuba_runner(
+    ctx: egui::Context,
     run_handle: RunHandle,
     sender: Sender<Arc<dyn Message>>,
     cuba: Arc<RwLock<Cuba>>,
     selected_profiles: HashSet<String>,
     msg_dispatcher: Arc<MsgDispatcher<Arc<dyn Message>>>,
     task_progress: Arc<TaskProgress>,
+    run_result: RunResult,
+    run_thread: RunThread,
 ) -> impl Fn(String, Box<RunFn>) {
     move |name, call_run| {
         cuba_run(
+            ctx.clone(),
             name,
             run_handle.clone(),
             sender.clone(),
@@ -35,14 +57,20 @@ pub fn make_cuba_runner(
             selected_profiles.clone(),
             msg_dispatcher.clone(),
             task_progress.clone(),
+            run_result.clone(),
+            run_thread.clone(),
             call_run,
         )
     }
 }
 
 /// Runs a cuba command on the selected profiles.
+///
+/// Shows the status in the tray icon and requests the attention of the user, when the run is
+/// complete and notify on complete is set.
 #[allow(clippy::too_many_arguments)]
 pub fn cuba_run<RunFunc>(
+    ctx: egui::Context,
     name: String,
     run_handle: RunHandle,
     sender: Sender<Arc<dyn Message>>,
@@ -50,23 +78,94 @@ pub fn cuba_run<RunFunc>(
     selected_profiles: HashSet<String>,
     msg_dispatcher: Arc<MsgDispatcher<Arc<dyn Message>>>,
     task_progress: Arc<TaskProgress>,
+    run_result: RunResult,
+    run_thread: RunThread,
     call_run: RunFunc,
 ) where
-    RunFunc: Fn(Arc<RwLock<Cuba>>, RunHandle, String) + Send + 'static,
+    RunFunc: Fn(Arc<RwLock<Cuba>>, RunHandle, String) -> Result<(), RunError> + Send + 'static,
 {
-    std::thread::spawn(move || {
+    let mut run_thread = run_thread.lock().unwrap();
+
+    // Cancel and wait for the previous run, it shares the run handle.
+    if let Some(previous_thread) = run_thread.take() {
+        if !previous_thread.is_finished() {
+            run_handle.request_cancel();
+        }
+
+        let _ = previous_thread.join();
+    }
+
+    *run_result.write().unwrap() = None;
+
+    *run_thread = Some(std::thread::spawn(move || {
         let mut msg_receiver = MsgReceiver::new(msg_dispatcher.subscribe(), task_progress.clone());
 
         msg_receiver.start();
 
+        set_tray_status(&ctx, TrayStatus::Running(name.clone()));
+
         for profile in selected_profiles {
             send_info!(sender, "Start {} of {}", name.to_lowercase(), profile);
 
-            call_run(cuba.clone(), run_handle.clone(), profile);
+            let result = call_run(cuba.clone(), run_handle.clone(), profile);
+            let canceled = matches!(result, Err(RunError::Canceled));
 
             send_info!(sender, "{} finished", name);
+
+            // Keep the first failure of the selected profiles.
+            let mut run_result = run_result.write().unwrap();
+
+            if !matches!(*run_result, Some((_, Err(_)))) {
+                *run_result = Some((name.clone(), result));
+            }
+
+            // A cancel stops the remaining profiles.
+            if canceled {
+                break;
+            }
         }
 
+        msg_dispatcher.flush();
         msg_receiver.stop();
-    });
+
+        // Show the result in the tray icon.
+        match &*run_result.read().unwrap() {
+            Some((_, Ok(()))) => set_tray_status(&ctx, TrayStatus::Complete(name)),
+            Some((_, Err(err))) => {
+                set_tray_status(&ctx, TrayStatus::Failed(name, err.to_string()));
+            }
+            None => {}
+        }
+
+        // Notify the user.
+        if notify_on_complete(&ctx) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+                egui::UserAttentionType::Informational,
+            ));
+        }
+
+        ctx.request_repaint();
+    }));
+}
+
+/// Shows the result banner of the finished run.
+pub fn run_result_label(ui: &mut egui::Ui, run_handle: &RunHandle, run_result: &RunResult) {
+    if !run_handle.is_running()
+        && let Some((name, result)) = &*run_result.read().unwrap()
+    {
+        match result {
+            Ok(()) => {
+                ui.label(
+                    egui::RichText::new(format!("✓ {} complete", name))
+                        .color(egui::Color32::LIGHT_GREEN),
+                );
+            }
+            Err(err) => {
+                ui.label(
+                    egui::RichText::new(format!("✗ {} failed: {}", name, err))
+                        .color(egui::Color32::LIGHT_RED),
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,29 @@
+#include <stdio.h>
+
+#include "cuba-ffi.h"
+
+int main(int argc, char **argv) {
+    if (argc != 3) {
+        fprintf(stderr, "Usage: %s <cuba.toml> <backup profile>\n", argv[0]);
+        return 2;
+    }
+
+    CubaHandle *cuba = cuba_load_config(argv[1]);
+
+    if (cuba == NULL) {
+        fprintf(stderr, "Loading the config failed: %s\n", cuba_last_error());
+        return 1;
+    }
+
+    CubaResult result = cuba_run_backup(cuba, argv[2]);
+
+    if (result != CUBA_OK) {
+        fprintf(stderr, "Backup failed: %s\n", cuba_last_error());
+    } else {
+        printf("Backup %s finished\n", argv[2]);
+    }
+
+    cuba_free(cuba);
+
+    return result == CUBA_OK ? 0 : 1;
+}
@@ -0,0 +1,56 @@
+/*
+ * C API of cuba-lib.
+ *
+ * Build the shared library with `cargo rustc --release -p cuba-lib --crate-type cdylib`.
+ */
+
+#ifndef CUBA_FFI_H
+#define CUBA_FFI_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The cuba api with its loaded config. */
+typedef struct CubaHandle CubaHandle;
+
+/* The result of a cuba function. */
+typedef enum CubaResult {
+    CUBA_OK = 0,
+    CUBA_ERROR = 1,
+    CUBA_INVALID_ARGUMENT = 2,
+} CubaResult;
+
+/*
+ * Loads the config from the file at `path`.
+ *
+ * Returns NULL on failure or panic, the reason is returned by `cuba_last_error`.
+ * The handle must be freed with `cuba_free`.
+ */
+CubaHandle *cuba_load_config(const char *path);
+
+/*
+ * Runs the backup of the backup profile `profile`.
+ *
+ * Blocks until the backup is finished. If any error occurred during the
+ * backup, the first one is returned by `cuba_last_error`. A panic is
+ * returned as CUBA_ERROR.
+ */
+CubaResult cuba_run_backup(CubaHandle *handle, const char *profile);
+
+/*
+ * Returns the last error of the calling thread or NULL.
+ *
+ * The string is owned by cuba and valid until the next call of a cuba
+ * function on the same thread.
+ */
+const char *cuba_last_error(void);
+
+/* Frees a handle of `cuba_load_config`. */
+void cuba_free(CubaHandle *handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CUBA_FFI_H */
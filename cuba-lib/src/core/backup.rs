@@ -1,117 +1,342 @@
 use crossbeam_channel::Sender;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::RwLock;
 
-use crate::core::run_state::RunState;
+use crate::core::run_state::{RunError, RunState};
 use crate::send_error;
-use crate::shared::message::Message;
+use crate::send_info;
+use crate::send_warn;
+use crate::shared::config::SignatureAlgo;
+use crate::shared::message::{Message, StringError};
 use crate::shared::npath::Dir;
-use crate::shared::npath::File;
 use crate::shared::npath::NPath;
 use crate::shared::npath::Rel;
 use crate::shared::npath::Symlink;
 use crate::shared::npath::UNPath;
-use crate::shared::progress_message::ProgressInfo;
-use crate::shared::progress_message::ProgressMessage;
 
+use super::cuba_json::CubaJsonCipher;
+use super::cuba_json::cuba_json_exists;
 use super::cuba_json::read_cuba_json;
 use super::cuba_json::write_cuba_json;
+use super::dedup_index::{DEDUP_REL_DIR, DedupIndex};
+use super::filename_cipher::FilenameCipher;
 use super::fs::fs_base::FSConnection;
+use super::fs::fs_base::FSMount;
 use super::glob_matcher::ExcludeMatcher;
 use super::glob_matcher::GlobMatcher;
 use super::glob_matcher::IncludeMatcher;
+use super::journal::{Journal, clean_journals};
 use super::password_cache::PasswordCache;
+use super::process_data::throttle_proc::Throttle;
 use super::tasks::directory_backup_task::directory_backup_task;
-use super::tasks::file_backup_task::file_backup_task;
+use super::tasks::file_backup_task::{
+    SrcFileQueue, backup_flags_of_file, file_backup_task, unchanged_src_signature,
+};
+use super::tasks::progress_tracker::{GlobalProgressTracker, ProgressTicks};
 use super::tasks::symlink_backup_task::symlink_backup_task;
 use super::tasks::task_worker::TaskWorker;
+use super::transferred_node::Backup;
 use super::transferred_node::Flags;
 use super::transferred_node::MaskedFlags;
+use super::transferred_node::TransferNodesStats;
+use super::transferred_node::TransferredNodes;
 use super::util::move_rel_npaths;
 
+/// Defines a `BackupStats`.
+///
+/// The statistics of a completed backup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackupStats {
+    /// The number of found files.
+    pub files: usize,
+
+    /// The number of found directories.
+    pub directories: usize,
+
+    /// The number of found symlinks.
+    pub symlinks: usize,
+
+    /// The number of processed files, directories and symlinks.
+    pub processed: usize,
+
+    /// The metrics of the transferred nodes after the backup.
+    pub nodes: TransferNodesStats,
+}
+
+/// The estimated ratio of compressed to uncompressed data.
+const ESTIMATED_COMPRESSION_RATIO: f64 = 0.5;
+
+/// Creates the include and exclude matcher of the patterns.
+fn create_matchers(
+    include_patterns: &Option<Vec<String>>,
+    exclude_patterns: &Option<Vec<String>>,
+    glob_case_sensitive: bool,
+) -> Result<(Option<IncludeMatcher>, Option<ExcludeMatcher>), globset::Error> {
+    // Note: a include matcher does include all predecessor directories of a glob statement.
+    let include_matcher = include_patterns
+        .as_ref()
+        .map(|patterns| GlobMatcher::new(patterns, glob_case_sensitive))
+        .transpose()?
+        .map(|matcher| matcher.include_matcher());
+
+    let exclude_matcher = exclude_patterns
+        .as_ref()
+        .map(|patterns| GlobMatcher::new(patterns, glob_case_sensitive))
+        .transpose()?
+        .map(|matcher| matcher.exclude_matcher());
+
+    Ok((include_matcher, exclude_matcher))
+}
+
+/// Returns true if `rel_path` is included and not excluded.
+///
+/// The exclude wins, if both match. The walks do not descend into unselected directories,
+/// so excluded trees are never listed.
+fn is_selected(
+    rel_path: &UNPath<Rel>,
+    include_matcher: &Option<IncludeMatcher>,
+    exclude_matcher: &Option<ExcludeMatcher>,
+) -> bool {
+    let included = include_matcher
+        .as_ref()
+        .is_none_or(|matcher| matcher.is_match(rel_path));
+
+    let excluded = exclude_matcher
+        .as_ref()
+        .is_some_and(|matcher| matcher.is_match(rel_path));
+
+    included && !excluded
+}
+
+/// Estimates the bytes a backup transfers.
+///
+/// Sums the sizes of the selected src files, without computing signatures.
+/// If `compression` is enabled, an estimated compression ratio is applied.
+pub fn estimate_backup_size(
+    compression: bool,
+    include_patterns: &Option<Vec<String>>,
+    exclude_patterns: &Option<Vec<String>>,
+    glob_case_sensitive: bool,
+    src_mnt: &FSMount,
+    sender: Sender<Arc<dyn Message>>,
+) -> Result<u64, RunError> {
+    let (include_matcher, exclude_matcher) =
+        create_matchers(include_patterns, exclude_patterns, glob_case_sensitive)
+            .map_err(|err| RunError::fatal(err, &sender))?;
+
+    let mut fs = src_mnt.fs.write().unwrap();
+
+    fs.connect().map_err(|err| RunError::fatal(err, &sender))?;
+
+    let mut size: u64 = 0;
+
+    let walked = fs.walk_dir_rec(
+        &src_mnt.abs_dir_path,
+        &mut |abs_path| match abs_path.strip_prefix(&src_mnt.abs_dir_path) {
+            Ok(rel_path) => {
+                let selected = is_selected(&rel_path, &include_matcher, &exclude_matcher);
+
+                // Only files are transferred with data.
+                if selected && rel_path.is_file() {
+                    match fs.meta(&abs_path) {
+                        Ok(meta) => size += meta.size.unwrap_or(0),
+                        Err(err) => send_error!(sender, err),
+                    }
+                }
+
+                selected
+            }
+            Err(err) => {
+                send_error!(sender, err);
+                true
+            }
+        },
+        &|err| send_error!(sender, err),
+    );
+
+    if let Err(err) = fs.disconnect() {
+        send_error!(sender, err);
+    }
+
+    walked.map_err(|err| RunError::fatal(err, &sender))?;
+
+    if compression {
+        size = (size as f64 * ESTIMATED_COMPRESSION_RATIO) as u64;
+    }
+
+    Ok(size)
+}
+
 #[allow(clippy::too_many_arguments)]
 /// Runs the backup process.
 pub fn run_backup(
     run_state: Arc<RunState>,
     threads: usize,
+    max_bandwidth_bytes_per_sec: Option<u64>,
     compression: bool,
+    compression_level: Option<u32>,
+    skip_recompression: bool,
+    compression_exclude_extensions: &Option<Vec<String>>,
+    delta_compression: bool,
+    dedup_enabled: bool,
     encrypt: bool,
     password_id: &Option<String>,
+    encrypt_filename: bool,
     include_patterns: &Option<Vec<String>>,
     exclude_patterns: &Option<Vec<String>>,
+    glob_case_sensitive: bool,
+    max_file_size_bytes: Option<u64>,
+    retry_changed_files: bool,
+    journal_retention_days: u32,
+    signature_algo: SignatureAlgo,
     fs_conn: &FSConnection,
     sender: Sender<Arc<dyn Message>>,
-) {
+) -> Result<BackupStats, RunError> {
     // Set running to true.
     run_state.start();
 
-    let mut include_matcher: Option<IncludeMatcher> = None;
-    let mut exclude_matcher: Option<ExcludeMatcher> = None;
+    // Create include and exclude matcher.
+    let (include_matcher, exclude_matcher) =
+        match create_matchers(include_patterns, exclude_patterns, glob_case_sensitive) {
+            Ok(matchers) => matchers,
+            Err(err) => {
+                run_state.stop();
+                return Err(RunError::fatal(err, &sender));
+            }
+        };
+
+    // Create password cache.
+    let mut password_cache = PasswordCache::new();
 
-    // Create include matcher.
-    if let Some(include_patterns) = include_patterns {
-        include_matcher = match GlobMatcher::new(include_patterns) {
-            // Note: a include matcher does include all predecessor directories of a glob statement.
-            Ok(matcher) => Some(matcher.include_matcher()),
+    // Create the cuba json cipher - if enabled.
+    let cuba_json_cipher = if encrypt {
+        match CubaJsonCipher::from_password_id(password_id, &mut password_cache) {
+            Ok(cuba_json_cipher) => Some(cuba_json_cipher),
             Err(err) => {
-                send_error!(sender, err);
-                return;
+                run_state.stop();
+                return Err(RunError::fatal(err, &sender));
             }
         }
+    } else {
+        None
     };
 
-    // Create exclude matcher.
-    if let Some(exclude_patterns) = exclude_patterns {
-        exclude_matcher = match GlobMatcher::new(exclude_patterns) {
-            Ok(matcher) => Some(matcher.exclude_matcher()),
-            Err(err) => {
-                send_error!(sender, err);
-                return;
+    // Open connection.
+    if let Err(err) = fs_conn.open(&run_state) {
+        run_state.stop();
+        return Err(RunError::fatal(err, &sender));
+    }
+
+    // Read cuba json, only a missing cuba json starts with an empty index.
+    let mut transferred_nodes = if cuba_json_exists(&fs_conn.dest_mnt) {
+        match read_cuba_json(&fs_conn.dest_mnt, &mut password_cache, &sender) {
+            Some((transferred_nodes, _)) => transferred_nodes,
+            None => {
+                if let Err(err) = fs_conn.close() {
+                    send_error!(sender, err);
+                }
+
+                run_state.stop();
+                return Err(RunError::fatal(
+                    StringError::new("The cuba json exists, but can't be read".to_string()),
+                    &sender,
+                ));
             }
         }
+    } else {
+        TransferredNodes::default()
     };
 
-    // Open connection.
-    if let Err(err) = fs_conn.open() {
-        send_error!(sender, err);
-        return;
+    // Decrypt the names, if encrypted.
+    if let Err(err) = FilenameCipher::decrypt_nodes(&mut transferred_nodes, &mut password_cache) {
+        if let Err(err) = fs_conn.close() {
+            send_error!(sender, err);
+        }
+
+        run_state.stop();
+        return Err(RunError::fatal(err, &sender));
     }
 
-    // Read cuba json.
-    let mut transferred_nodes = read_cuba_json(&fs_conn.dest_mnt, &sender).unwrap_or_default();
+    // Create the filename cipher with the salt of the cuba json - if enabled.
+    let filename_cipher = if encrypt_filename {
+        match FilenameCipher::from_password_id(password_id, &transferred_nodes, &mut password_cache)
+        {
+            Ok(filename_cipher) => Some(Arc::new(filename_cipher)),
+            Err(err) => {
+                if let Err(err) = fs_conn.close() {
+                    send_error!(sender, err);
+                }
+
+                run_state.stop();
+                return Err(RunError::fatal(err, &sender));
+            }
+        }
+    } else {
+        None
+    };
 
-    // Collect source files, directories and symlinks.
-    let mut src_rel_files: VecDeque<NPath<Rel, File>> = VecDeque::new();
+    // Collect source files with their metadata, directories and symlinks.
+    let mut src_rel_files: SrcFileQueue = VecDeque::new();
     let mut src_rel_directories: VecDeque<NPath<Rel, Dir>> = VecDeque::new();
     let mut src_rel_symlinks: VecDeque<NPath<Rel, Symlink>> = VecDeque::new();
 
-    fs_conn
-        .src_mnt
-        .fs
-        .read()
-        .unwrap()
-        .walk_dir_rec(
+    {
+        let src_fs = fs_conn.src_mnt.fs.read().unwrap();
+
+        // List the directories in parallel, the order of the entries does not matter.
+        src_fs.walk_dir_rec_parallel(
             &fs_conn.src_mnt.abs_dir_path,
+            threads,
             &mut |abs_path| {
-                let mut included = true;
-                let mut excluded = false;
+                // Stop the walk, if canceled.
+                if run_state.is_canceled() {
+                    return false;
+                }
 
-                match abs_path.sub_abs_dir(&fs_conn.src_mnt.abs_dir_path) {
+                match abs_path.strip_prefix(&fs_conn.src_mnt.abs_dir_path) {
                     Ok(rel_path) => {
-                        if let Some(ref matcher) = include_matcher {
-                            included = matcher.is_match(&rel_path);
-                        }
-
-                        if let Some(ref matcher) = exclude_matcher {
-                            excluded = matcher.is_match(&rel_path);
-                        }
+                        let selected = is_selected(&rel_path, &include_matcher, &exclude_matcher);
+
+                        if selected {
+                            // Backup paths with invalid unicode under the escaped name.
+                            if rel_path.is_lossy() {
+                                send_warn!(
+                                    sender,
+                                    "The path {} is not valid unicode, it is backed up with an escaped name",
+                                    rel_path.to_unicode()
+                                );
+                            }
 
-                        if included && !excluded {
                             match &rel_path {
                                 UNPath::File(rel_file_path) => {
-                                    src_rel_files.push_back(rel_file_path.clone());
+                                    let metadata = match src_fs.meta(&abs_path) {
+                                        Ok(metadata) => metadata,
+                                        Err(err) => {
+                                            send_error!(sender, err);
+                                            return true;
+                                        }
+                                    };
+
+                                    let size = metadata.size.unwrap_or(0);
+
+                                    // Skip oversized files, but report them.
+                                    if let Some(max_file_size_bytes) = max_file_size_bytes
+                                        && size > max_file_size_bytes
+                                    {
+                                        send_warn!(
+                                            sender,
+                                            "The file {} is skipped, its size of {} bytes exceeds the maximum of {} bytes",
+                                            rel_file_path.to_unicode(),
+                                            size,
+                                            max_file_size_bytes
+                                        );
+                                    } else {
+                                        src_rel_files
+                                            .push_back((rel_file_path.clone(), metadata));
+                                    }
                                 }
                                 UNPath::Dir(rel_dir_path) => {
                                     src_rel_directories.push_back(rel_dir_path.clone());
@@ -121,32 +346,63 @@ pub fn run_backup(
                                 }
                             }
                         }
+
+                        // Unselected directories are not descended.
+                        selected
                     }
                     Err(err) => {
                         send_error!(sender, err);
+                        true
                     }
                 }
-
-                included && !excluded
             },
             &|err| send_error!(sender, err),
         )
         .unwrap();
+    }
+
+    // Sort, so that the backup order is deterministic and parents are processed before their children.
+    src_rel_files
+        .make_contiguous()
+        .sort_by(|(rel_file_path_a, _), (rel_file_path_b, _)| rel_file_path_a.cmp(rel_file_path_b));
+    src_rel_directories.make_contiguous().sort();
+    src_rel_symlinks.make_contiguous().sort();
+
+    // Create the dedup store and index - if enabled.
+    let dedup_index = if dedup_enabled {
+        let dedup_abs_dir_path = fs_conn.dest_mnt.abs_dir_path.add_rel_dir(&DEDUP_REL_DIR);
+        let dest_fs = fs_conn.dest_mnt.fs.read().unwrap();
+
+        if dest_fs.meta(&dedup_abs_dir_path.clone().into()).is_err()
+            && let Err(err) = dest_fs.mkdir(&dedup_abs_dir_path)
+        {
+            // Without dedup store, files are transferred as usual.
+            send_error!(sender, err);
+            None
+        } else {
+            Some(Arc::new(DedupIndex::from_transferred_nodes(
+                &transferred_nodes,
+            )))
+        }
+    } else {
+        None
+    };
+
+    // The orphans of the last backup, a canceled backup keeps only them as orphans.
+    let last_orphans: HashSet<UNPath<Rel>> = transferred_nodes
+        .iter()
+        .filter(|(_, transferred_node)| transferred_node.flags.contains(Flags::ORPHAN))
+        .map(|(rel_path, _)| rel_path.clone())
+        .collect();
 
     // Before backup, set all nodes to be an orphan.
     transferred_nodes.insert_flags(Flags::ORPHAN);
 
-    // Create password cache.
-    let password_cache = PasswordCache::new();
-
     let arc_mutex_src_rel_files = Arc::new(Mutex::new(src_rel_files));
     let arc_mutex_src_rel_symlinks = Arc::new(Mutex::new(src_rel_symlinks));
     let arc_rwlock_transferred_nodes = Arc::new(RwLock::new(transferred_nodes));
     let arc_mutex_password_cache = Arc::new(Mutex::new(password_cache));
 
-    // Init task worker.
-    let task_worker = TaskWorker::new(fs_conn.clone(), sender.clone());
-
     // Init dir backup flags.
     let dir_backup_flags: MaskedFlags = MaskedFlags::new().with_mask(Flags::VERIFY_ERROR);
 
@@ -169,21 +425,82 @@ pub fn run_backup(
         file_backup_flags.insert(Flags::ENCRYPTED);
     }
 
+    // The total bytes of the source files, which are read. Unchanged files are not read.
+    let total_bytes: u64 = {
+        let transferred_nodes_read = arc_rwlock_transferred_nodes.read().unwrap();
+        let backup_view = transferred_nodes_read.view::<Backup>();
+        let compression_exclude_extensions =
+            compression_exclude_extensions.clone().unwrap_or_default();
+
+        arc_mutex_src_rel_files
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(src_rel_file_path, src_file_metadata)| {
+                backup_view
+                    .get_node_for_src(&src_rel_file_path.clone().into())
+                    .and_then(|transferred_node| {
+                        unchanged_src_signature(
+                            transferred_node,
+                            &backup_flags_of_file(
+                                file_backup_flags,
+                                src_rel_file_path,
+                                &compression_exclude_extensions,
+                            ),
+                            password_id,
+                            signature_algo,
+                            src_file_metadata,
+                        )
+                    })
+                    .is_none()
+            })
+            .filter_map(|(_, src_file_metadata)| src_file_metadata.size)
+            .sum()
+    };
+
+    // Announce the found items, before the workers start.
+    let files = arc_mutex_src_rel_files.lock().unwrap().len();
+    let directories = src_rel_directories.len();
+    let symlinks = arc_mutex_src_rel_symlinks.lock().unwrap().len();
+
+    send_info!(
+        sender,
+        "Found {} files, {} directories and {} symlinks to backup",
+        files,
+        directories,
+        symlinks
+    );
+
     // Progress duration.
-    let items = src_rel_directories.len()
-        + arc_mutex_src_rel_files.lock().unwrap().len()
-        + arc_mutex_src_rel_symlinks.lock().unwrap().len();
-    sender
-        .send(Arc::new(ProgressMessage::new(
-            Arc::new(ProgressInfo::Duration),
-            items as u64,
-        )))
-        .unwrap();
+    let items = directories + files + symlinks;
+    let progress_ticks = Arc::new(ProgressTicks::new(items, sender.clone()));
+
+    // Progress milestones.
+    let progress_tracker = Arc::new(GlobalProgressTracker::new("Backup", items, sender.clone()));
+
+    // The journal of this run, there is none if the names are encrypted.
+    let journal = filename_cipher
+        .is_none()
+        .then(|| Journal::open(&fs_conn.dest_mnt, &sender).map(Arc::new))
+        .flatten();
+
+    // Init task worker.
+    let mut task_worker = TaskWorker::new(fs_conn.clone(), sender.clone())
+        .with_progress_tracker(progress_tracker.clone())
+        .with_progress_ticks(progress_ticks.clone())
+        .with_total_bytes(total_bytes);
+
+    if let Some(journal) = &journal {
+        task_worker = task_worker.with_journal(journal.clone());
+    }
 
     // We cannot process dir list parallel, because if dir A is subdir of dir B: B must be processed before A.
     // But we can process all dirs of the same depth parallel.
     let mut depth = 1;
 
+    // The processed work items.
+    let mut processed = 0;
+
     while !src_rel_directories.is_empty() {
         let mut depth_src_rel_dirs: VecDeque<NPath<Rel, Dir>> = VecDeque::new();
 
@@ -194,13 +511,15 @@ pub fn run_backup(
             let arc_mutex_depth_src_rel_dirs = Arc::new(Mutex::new(depth_src_rel_dirs));
 
             // Run directory backup.
-            task_worker.run(
+            processed += task_worker.run(
                 run_state.clone(),
                 depth_threads,
+                "directory_backup",
                 Arc::new(directory_backup_task(
                     arc_mutex_depth_src_rel_dirs,
                     arc_rwlock_transferred_nodes.clone(),
                     dir_backup_flags,
+                    filename_cipher.clone(),
                 )),
             );
         }
@@ -209,39 +528,94 @@ pub fn run_backup(
     }
 
     // Run file backup.
-    task_worker.run(
+    processed += task_worker.run(
         run_state.clone(),
         threads,
+        "file_backup",
         Arc::new(file_backup_task(
             arc_mutex_src_rel_files,
             arc_rwlock_transferred_nodes.clone(),
             file_backup_flags,
             arc_mutex_password_cache.clone(),
             password_id.clone(),
+            delta_compression,
+            dedup_index,
+            retry_changed_files,
+            compression_level,
+            skip_recompression,
+            compression_exclude_extensions.clone().unwrap_or_default(),
+            filename_cipher.clone(),
+            max_bandwidth_bytes_per_sec.map(|max| Arc::new(Throttle::new(max))),
+            signature_algo,
         )),
     );
 
     // Run symlink backup.
-    task_worker.run(
+    processed += task_worker.run(
         run_state.clone(),
         threads,
+        "symlink_backup",
         Arc::new(symlink_backup_task(
             arc_mutex_src_rel_symlinks,
             arc_rwlock_transferred_nodes.clone(),
             sym_backup_flags,
+            filename_cipher.clone(),
         )),
     );
 
     // Drop task worker.
     drop(task_worker);
 
+    // Complete the progress.
     if !run_state.is_canceled() {
-        // Write cuba json.
-        write_cuba_json(
-            &fs_conn.dest_mnt,
-            &arc_rwlock_transferred_nodes.read().unwrap(),
-            &sender,
-        );
+        progress_ticks.complete();
+        progress_tracker.complete();
+    }
+
+    // Take the metrics of the transferred nodes.
+    let nodes = arc_rwlock_transferred_nodes.read().unwrap().statistics();
+
+    // Write the transferred nodes also of a canceled backup, so that the next backup resumes.
+    {
+        let mut transferred_nodes = arc_rwlock_transferred_nodes.write().unwrap();
+
+        // The nodes not reached by a canceled backup are no orphans.
+        if run_state.is_canceled() {
+            for (rel_path, transferred_node) in transferred_nodes.iter_mut() {
+                if !last_orphans.contains(rel_path) {
+                    transferred_node.flags.remove(Flags::ORPHAN);
+                }
+            }
+        }
+
+        // Write cuba json, with encrypted names and encrypted - if enabled.
+        match &filename_cipher {
+            Some(filename_cipher) => match filename_cipher.encrypt_names(&transferred_nodes) {
+                Ok(encrypted_nodes) => {
+                    write_cuba_json(
+                        &fs_conn.dest_mnt,
+                        &encrypted_nodes,
+                        cuba_json_cipher.as_ref(),
+                        &sender,
+                    );
+                }
+                Err(err) => send_error!(sender, err),
+            },
+            None => {
+                write_cuba_json(
+                    &fs_conn.dest_mnt,
+                    &transferred_nodes,
+                    cuba_json_cipher.as_ref(),
+                    &sender,
+                );
+            }
+        }
+    }
+
+    // Finish the journal, also of a canceled run.
+    if let Some(journal) = &journal {
+        journal.finish();
+        clean_journals(&fs_conn.dest_mnt, journal_retention_days, &sender);
     }
 
     // Close connection.
@@ -251,4 +625,16 @@ pub fn run_backup(
 
     // Set running to false.
     run_state.stop();
+
+    if run_state.is_canceled() {
+        return Err(RunError::Canceled);
+    }
+
+    Ok(BackupStats {
+        files,
+        directories,
+        symlinks,
+        processed,
+        nodes,
+    })
 }
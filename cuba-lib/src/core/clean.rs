@@ -1,19 +1,22 @@
 use crossbeam_channel::Sender;
+use std::collections::HashSet;
 use std::sync::Arc;
 
-use crate::core::run_state::RunState;
+use crate::core::run_state::{RunError, RunState};
 use crate::core::transferred_node::Backup;
 use crate::send_error;
 use crate::shared::clean_message::{CleanError, CleanInfo, CleanMessage};
-use crate::shared::message::Message;
-use crate::shared::npath::{Abs, Rel, UNPath};
-use crate::shared::progress_message::ProgressInfo;
-use crate::shared::progress_message::ProgressMessage;
+use crate::shared::message::{Message, StringError};
+use crate::shared::npath::{Abs, File, NPath, Rel, UNPath};
 
-use super::cuba_json::CUBA_JSON_REL_PATH;
+use super::cuba_json::is_cuba_json;
 use super::cuba_json::read_cuba_json;
 use super::cuba_json::write_cuba_json;
+use super::dedup_index::DEDUP_REL_DIR;
 use super::fs::fs_base::FSMount;
+use super::journal::JOURNAL_REL_DIR;
+use super::password_cache::PasswordCache;
+use super::tasks::progress_tracker::ProgressTicks;
 use super::transferred_node::{Flags, MaskedFlags, Restore, TransferredNodes};
 
 /// Runs the clean process.
@@ -22,24 +25,43 @@ use super::transferred_node::{Flags, MaskedFlags, Restore, TransferredNodes};
 /// - Files/directories that are not in the backup index are deleted from the backup
 /// - Files/directories/symlinks that are marked as ophans (not in the source anymore) are
 ///   deleted from the backup
-pub fn run_clean(run_state: Arc<RunState>, fs_mnt: FSMount, sender: Sender<Arc<dyn Message>>) {
+/// - Files that are the delta base or dedup blob of a kept node are kept
+pub fn run_clean(
+    run_state: Arc<RunState>,
+    fs_mnt: FSMount,
+    sender: Sender<Arc<dyn Message>>,
+) -> Result<(), RunError> {
     // Set running to true.
     run_state.start();
 
     // Connect fs.
-    if let Err(err) = fs_mnt.fs.write().unwrap().connect() {
-        send_error!(sender, err);
-        return;
+    if let Err(err) =
+        fs_mnt.ensure_connected_with_status(|status| run_state.set_dest_status(status))
+    {
+        run_state.stop();
+        return Err(RunError::fatal(err, &sender));
     }
 
-    // Read cuba json.
-    let transferred_nodes_read = match read_cuba_json(&fs_mnt, &sender) {
-        Some(nodes) => nodes,
-        None => return,
-    };
+    // Read cuba json, the cipher keeps an encrypted cuba json encrypted.
+    let (transferred_nodes_read, cuba_json_cipher) =
+        match read_cuba_json(&fs_mnt, &mut PasswordCache::new(), &sender) {
+            Some(cuba_json) => cuba_json,
+            None => {
+                if let Err(err) = fs_mnt.fs.write().unwrap().disconnect() {
+                    send_error!(sender, err);
+                }
+
+                run_state.stop();
+                return Err(RunError::fatal(
+                    StringError::new("The cuba json can't be read".to_string()),
+                    &sender,
+                ));
+            }
+        };
 
     // Create the transferred nodes write
     let mut transferred_nodes_write = TransferredNodes::new();
+    transferred_nodes_write.set_name_salt(transferred_nodes_read.name_salt().map(str::to_string));
 
     // Make clean flags.
     let clean_flags: MaskedFlags = MaskedFlags::new()
@@ -47,17 +69,14 @@ pub fn run_clean(run_state: Arc<RunState>, fs_mnt: FSMount, sender: Sender<Arc<d
         .with_mask(Flags::ORPHAN);
 
     // Progress duration.
-    sender
-        .send(Arc::new(ProgressMessage::new(
-            Arc::new(ProgressInfo::Duration),
-            transferred_nodes_read.node_count() as u64,
-        )))
-        .unwrap();
+    let progress_ticks = ProgressTicks::new(transferred_nodes_read.node_count(), sender.clone());
 
-    // Symlinks do not exist as backup files, so we have to treat them in a different way.
+    // Symlinks and files in the dedup store do not exist as backup files, so we have to treat
+    // them in a different way.
     for (src_rel_path, transferred_node) in transferred_nodes_read.iter() {
-        // If symlink and clean flags do not match, keep the symlink.
-        if transferred_node.src_symlink_meta.is_some()
+        // If symlink or dedup file and clean flags do not match, keep the node.
+        if (transferred_node.src_symlink_meta.is_some()
+            || transferred_node.dedup_blob_rel_path.is_some())
             && !clean_flags.matches(transferred_node.flags)
         {
             transferred_nodes_write
@@ -65,15 +84,15 @@ pub fn run_clean(run_state: Arc<RunState>, fs_mnt: FSMount, sender: Sender<Arc<d
                 .set_transferred_node(src_rel_path, transferred_node);
 
             // Progress tick.
-            sender
-                .send(Arc::new(ProgressMessage::new(
-                    Arc::new(ProgressInfo::Ticks),
-                    1,
-                )))
-                .unwrap();
+            progress_ticks.tick(1);
         }
     }
 
+    // The delta bases and blobs, which the kept nodes refer to.
+    let kept_referred_rel_file_paths: HashSet<&NPath<Rel, File>> = transferred_nodes_read
+        .view::<Restore>()
+        .referred_rel_file_paths(|transferred_node| !clean_flags.matches(transferred_node.flags));
+
     fs_mnt
         .fs
         .read()
@@ -82,17 +101,12 @@ pub fn run_clean(run_state: Arc<RunState>, fs_mnt: FSMount, sender: Sender<Arc<d
             &fs_mnt.abs_dir_path,
             &mut |abs_path| {
                 // Progress tick.
-                sender
-                    .send(Arc::new(ProgressMessage::new(
-                        Arc::new(ProgressInfo::Ticks),
-                        1,
-                    )))
-                    .unwrap();
+                progress_ticks.tick(1);
 
                 if run_state.is_canceled() {
                     false
                 } else {
-                    match abs_path.sub_abs_dir(&fs_mnt.abs_dir_path) {
+                    match abs_path.strip_prefix(&fs_mnt.abs_dir_path) {
                         Ok(node_rel_path) => {
                             if let Some(transferred_node) = transferred_nodes_read
                                 .view::<Restore>()
@@ -126,6 +140,32 @@ pub fn run_clean(run_state: Arc<RunState>, fs_mnt: FSMount, sender: Sender<Arc<d
 
                                     return true;
                                 }
+                            } else if node_rel_path == UNPath::Dir(JOURNAL_REL_DIR.clone()) {
+                                // Keep the journals, they are cleaned by the backup.
+                                sender
+                                    .send(Arc::new(CleanMessage::new(
+                                        &node_rel_path,
+                                        None,
+                                        Some(Arc::new(CleanInfo::Ok)),
+                                    )))
+                                    .unwrap();
+
+                                // Do not walk into the directory.
+                                return false;
+                            } else if node_rel_path == UNPath::Dir(DEDUP_REL_DIR.clone())
+                                || matches!(&node_rel_path, UNPath::File(rel_file_path)
+                                    if kept_referred_rel_file_paths.contains(rel_file_path))
+                            {
+                                // Keep the dedup store and the delta bases and blobs of kept nodes.
+                                sender
+                                    .send(Arc::new(CleanMessage::new(
+                                        &node_rel_path,
+                                        None,
+                                        Some(Arc::new(CleanInfo::Ok)),
+                                    )))
+                                    .unwrap();
+
+                                return true;
                             } else {
                                 // If node not in backup index, remove node.
                                 return remove_node(
@@ -149,8 +189,16 @@ pub fn run_clean(run_state: Arc<RunState>, fs_mnt: FSMount, sender: Sender<Arc<d
         .unwrap();
 
     if !run_state.is_canceled() {
+        // Complete the progress.
+        progress_ticks.complete();
+
         // Write cuba json.
-        write_cuba_json(&fs_mnt, &transferred_nodes_write, &sender);
+        write_cuba_json(
+            &fs_mnt,
+            &transferred_nodes_write,
+            cuba_json_cipher.as_ref(),
+            &sender,
+        );
     }
 
     // Disconnect fs.
@@ -160,10 +208,16 @@ pub fn run_clean(run_state: Arc<RunState>, fs_mnt: FSMount, sender: Sender<Arc<d
 
     // Set running to false.
     run_state.stop();
+
+    if run_state.is_canceled() {
+        return Err(RunError::Canceled);
+    }
+
+    Ok(())
 }
 
 /// Removes a node.
-fn remove_node(
+pub(super) fn remove_node(
     abs_path: &UNPath<Abs>,
     rel_path: &UNPath<Rel>,
     fs_mnt: FSMount,
@@ -171,7 +225,7 @@ fn remove_node(
 ) -> bool {
     match abs_path {
         UNPath::File(abs_file_path) => {
-            if !abs_file_path.ends_with(&CUBA_JSON_REL_PATH.clone()) {
+            if !is_cuba_json(abs_file_path) {
                 if fs_mnt.fs.read().unwrap().remove_file(abs_file_path).is_ok() {
                     sender
                         .send(Arc::new(CleanMessage::new(
@@ -0,0 +1,59 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use super::transferred_node::{Backup, Flags, TransferredNodes, list_src_paths};
+
+/// The columns of the csv export.
+const CSV_HEADER: [&str; 8] = [
+    "src_path",
+    "dest_path",
+    "size_bytes",
+    "flags",
+    "encrypted",
+    "compressed",
+    "signature_hex",
+    "last_seen",
+];
+
+/// Returns the names of the flags, separated by `|`.
+pub(super) fn flag_names(flags: Flags) -> String {
+    flags
+        .iter_names()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Writes the transferred nodes as csv to the file at `output_path`.
+///
+/// One row per src path, sorted by the src path.
+pub fn export_csv(
+    transferred_nodes: &TransferredNodes,
+    output_path: &str,
+) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_path(output_path)?;
+    let view = transferred_nodes.view::<Backup>();
+
+    writer.write_record(CSV_HEADER)?;
+
+    for src_rel_path in list_src_paths(transferred_nodes) {
+        if let Some(node) = view.get_node_for_src(&src_rel_path) {
+            writer.write_record([
+                src_rel_path.to_unicode().to_string(),
+                view.get_dest_rel_path(node).to_unicode().to_string(),
+                node.src_size.map_or(String::new(), |size| size.to_string()),
+                flag_names(node.flags),
+                node.flags.contains(Flags::ENCRYPTED).to_string(),
+                node.flags.contains(Flags::COMPRESSED).to_string(),
+                node.src_signature.map_or(String::new(), |signature| {
+                    blake3::Hash::from_bytes(signature).to_hex().to_string()
+                }),
+                node.backup_timestamp.map_or(String::new(), |timestamp| {
+                    DateTime::<Utc>::from(timestamp).to_rfc3339_opts(SecondsFormat::Secs, true)
+                }),
+            ])?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
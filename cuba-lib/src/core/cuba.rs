@@ -1,49 +1,148 @@
 #![allow(unused)]
 
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Sender, unbounded};
 use secrecy::SecretString;
 use std::collections::HashSet;
+use std::io::{BufReader, BufWriter};
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, RwLock};
+use std::thread;
 
 use crate::core::run_state::RunState;
 use crate::send_error;
 use crate::send_info;
 use crate::shared::{
-    config::Config,
-    message::{Message, StringError},
-    npath::{Dir, NPath, Rel},
+    config::{BackupConfig, Config, RestoreConfig},
+    message::{Message, StringError, StringInfo, WarnMessage},
+    msg_receiver::trace_error,
+    npath::{Abs, Dir, File, NPath, Rel, UNPath, default_case_sensitive},
 };
 
-use super::backup::run_backup;
+pub use super::backup::BackupStats;
+use super::backup::{estimate_backup_size, run_backup};
 use super::clean::run_clean;
+use super::csv_export::export_csv;
+use super::cuba_json::{CubaJsonError, load_cuba_json, read_cuba_json, save_cuba_json};
+use super::filename_cipher::FilenameCipher;
+pub use super::fs::fs_base::{FSConnection, FSMount};
 use super::fs::{
-    fs_base::{FSConnection, FSMount},
+    azure_blob_fs::AzureBlobFS,
+    ftp_fs::FtpFS,
     local_fs::LocalFS,
-    webdav_fs::WebDAVFS,
+    mem_fs::MemFS,
+    rclone_fs::RcloneFS,
+    s3_fs::S3FS,
+    traced_fs::TracedFS,
+    webdav_fs::{WebDAVFS, WebDAVRetry, WebDAVTimeouts},
 };
+pub use super::manifest_export::ManifestFormat;
+pub use super::run_state::{ConnectionStatus, RunError};
+pub use super::transferred_node::TransferNodesStats;
+pub use super::verify::VerifyMode;
 
+use super::glob_matcher::{match_names, read_patterns_file, validate_pattern};
+use super::manifest_export::export_manifest;
+use super::password_cache::PasswordCache;
+use super::prune::run_prune;
 use super::restore::run_restore;
+use super::transferred_node::{TransferredNodes, list_src_paths};
 use super::verify::run_verify;
 
+/// Creates the filesystem mount of the backup destination from the config.
+///
+/// With `dest_dir_auto` the destination directory is `<hostname>/<backup_name>`,
+/// instead of `dest_dir`.
+fn create_dest_fs_mount(
+    config: &Config,
+    sender: &Sender<Arc<dyn Message>>,
+    backup: &BackupConfig,
+    backup_name: &str,
+) -> Result<FSMount, Arc<dyn std::error::Error + Send + Sync + 'static>> {
+    if backup.dest_dir_auto {
+        let hostname = gethostname::gethostname();
+
+        let dest_dir =
+            NPath::<Rel, Dir>::try_from(format!("{}/{}", hostname.to_string_lossy(), backup_name))
+                .map_err(|err| Arc::new(err) as Arc<dyn std::error::Error + Send + Sync>)?;
+
+        create_fs_mount(config, sender, &backup.dest_fs, &dest_dir)
+    } else {
+        create_fs_mount(config, sender, &backup.dest_fs, &backup.dest_dir)
+    }
+}
+
+/// Creates the `<hostname>/<backup_name>` directories of a destination with `dest_dir_auto`.
+///
+/// Directories, which already exist, are kept.
+fn create_auto_dest_dirs(
+    dest_mnt: &FSMount,
+    backup_name: &str,
+) -> Result<(), Arc<dyn std::error::Error + Send + Sync + 'static>> {
+    let host_abs_dir = NPath::<Rel, Dir>::try_from(backup_name)
+        .and_then(|backup_rel_dir| dest_mnt.abs_dir_path.sub_rel_dir(&backup_rel_dir))
+        .map_err(|err| Arc::new(err) as Arc<dyn std::error::Error + Send + Sync>)?;
+
+    let mut fs = dest_mnt.fs.write().unwrap();
+
+    let created = fs.connect().and_then(|_| {
+        for abs_dir_path in [&host_abs_dir, &*dest_mnt.abs_dir_path] {
+            // A failed mkdir of an existing directory is ignored.
+            if let Err(err) = fs.mkdir(abs_dir_path)
+                && fs.meta(&abs_dir_path.clone().into()).is_err()
+            {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    });
+
+    let disconnected = fs.disconnect();
+
+    created
+        .and(disconnected)
+        .map_err(|err| Arc::new(err) as Arc<dyn std::error::Error + Send + Sync>)
+}
+
 /// Creates a filesystem mount from the config.
 fn create_fs_mount(
     config: &Config,
+    sender: &Sender<Arc<dyn Message>>,
     fs: &str,
     rel_dir_path: &NPath<Rel, Dir>,
 ) -> Result<FSMount, Arc<dyn std::error::Error + Send + Sync + 'static>> {
     if let Some(local_fs) = config.filesystem.local.get(fs) {
-        let fs = Arc::new(RwLock::new(LocalFS::new()));
+        let fs = Arc::new(RwLock::new(TracedFS::new(
+            "local",
+            LocalFS::new(local_fs.noatime),
+        )));
         let abs_dir_path = Arc::new(local_fs.dir.add_rel_dir(rel_dir_path));
 
         Ok(FSMount::new(fs, abs_dir_path))
     } else if let Some(webdav_fs) = config.filesystem.webdav.get(fs) {
         match crate::core::keyring::get_password(&webdav_fs.password_id) {
             Ok(password) => {
-                let fs = Arc::new(RwLock::new(WebDAVFS::new(
-                    &webdav_fs.user,
-                    &password,
-                    webdav_fs.timeout_secs,
+                let fs = Arc::new(RwLock::new(TracedFS::new(
+                    "webdav",
+                    WebDAVFS::new(
+                        &webdav_fs.url,
+                        &webdav_fs.user,
+                        &password,
+                        webdav_fs.auth_type,
+                        WebDAVTimeouts::from_secs(
+                            webdav_fs.connect_timeout_secs(),
+                            webdav_fs.read_timeout_secs(),
+                            webdav_fs.write_timeout_secs(),
+                        ),
+                        webdav_fs.follow_redirects,
+                        webdav_fs.max_redirects,
+                    )
+                    .with_retry(WebDAVRetry::from_millis(
+                        webdav_fs.retries,
+                        webdav_fs.retry_backoff_ms,
+                    ))
+                    .with_chunked_upload(webdav_fs.chunked_upload)
+                    .with_sender(sender.clone()),
                 )));
 
                 let abs_dir_path = Arc::new(webdav_fs.url.add_rel_dir(rel_dir_path));
@@ -51,6 +150,81 @@ fn create_fs_mount(
             }
             Err(err) => Err(Arc::new(err)),
         }
+    } else if let Some(ftp_fs) = config.filesystem.ftp.get(fs) {
+        match crate::core::keyring::get_password(&ftp_fs.password_id) {
+            Ok(password) => {
+                let fs = Arc::new(RwLock::new(TracedFS::new(
+                    "ftp",
+                    FtpFS::new(
+                        &ftp_fs.host,
+                        ftp_fs.port,
+                        ftp_fs.passive_mode,
+                        ftp_fs.use_tls,
+                        &ftp_fs.username,
+                        &password,
+                    ),
+                )));
+
+                let abs_dir_path = Arc::new(ftp_fs.dir.add_rel_dir(rel_dir_path));
+                Ok(FSMount::new(fs, abs_dir_path))
+            }
+            Err(err) => Err(Arc::new(err)),
+        }
+    } else if let Some(azure_fs) = config.filesystem.azure.get(fs) {
+        match crate::core::keyring::get_password(&azure_fs.password_id) {
+            Ok(password) => {
+                let fs = Arc::new(RwLock::new(TracedFS::new(
+                    "azure",
+                    AzureBlobFS::new(
+                        &azure_fs.account,
+                        &azure_fs.container,
+                        &password,
+                        azure_fs.sas_token,
+                    ),
+                )));
+
+                // Blob names are relative to the container.
+                let abs_dir_path = Arc::new(NPath::<Abs, Dir>::default().add_rel_dir(rel_dir_path));
+                Ok(FSMount::new(fs, abs_dir_path))
+            }
+            Err(err) => Err(Arc::new(err)),
+        }
+    } else if let Some(rclone_fs) = config.filesystem.rclone.get(fs) {
+        let fs = Arc::new(RwLock::new(TracedFS::new(
+            "rclone",
+            RcloneFS::new(&rclone_fs.remote, &rclone_fs.rclone_flags),
+        )));
+
+        // Paths are relative to the remote.
+        let abs_dir_path = Arc::new(NPath::<Abs, Dir>::default().add_rel_dir(rel_dir_path));
+        Ok(FSMount::new(fs, abs_dir_path))
+    } else if let Some(s3_fs) = config.filesystem.s3.get(fs) {
+        match crate::core::keyring::get_password(&s3_fs.password_id) {
+            Ok(secret_key) => {
+                let fs = Arc::new(RwLock::new(TracedFS::new(
+                    "s3",
+                    S3FS::new(
+                        &s3_fs.endpoint,
+                        &s3_fs.region,
+                        &s3_fs.bucket,
+                        &s3_fs.prefix,
+                        &s3_fs.access_key_id,
+                        &secret_key,
+                    ),
+                )));
+
+                // Keys are relative to the prefix of the bucket.
+                let abs_dir_path = Arc::new(NPath::<Abs, Dir>::default().add_rel_dir(rel_dir_path));
+                Ok(FSMount::new(fs, abs_dir_path))
+            }
+            Err(err) => Err(Arc::new(err)),
+        }
+    } else if config.filesystem.mem.contains_key(fs) {
+        let fs = Arc::new(RwLock::new(TracedFS::new("mem", MemFS::named(fs))));
+
+        // Paths are relative to the root of the store.
+        let abs_dir_path = Arc::new(NPath::<Abs, Dir>::default().add_rel_dir(rel_dir_path));
+        Ok(FSMount::new(fs, abs_dir_path))
     } else {
         Err(Arc::new(StringError::new(format!(
             "No filesystem with the name {:?} found",
@@ -88,6 +262,16 @@ impl RunHandle {
     pub fn is_running(&self) -> bool {
         self.state.is_running()
     }
+
+    /// Returns the connection status of the source filesystem.
+    pub fn src_status(&self) -> ConnectionStatus {
+        self.state.src_status()
+    }
+
+    /// Returns the connection status of the destination filesystem.
+    pub fn dest_status(&self) -> ConnectionStatus {
+        self.state.dest_status()
+    }
 }
 
 /// Default for `RunHandle`.
@@ -144,6 +328,105 @@ impl Cuba {
         }
     }
 
+    /// Returns the config for a run, or a fatal `RunError`.
+    fn run_config(&self) -> Result<&Config, RunError> {
+        self.requires_config().ok_or_else(|| {
+            RunError::Fatal(Arc::new(StringError::new(
+                "A config is required".to_string(),
+            )))
+        })
+    }
+
+    /// Returns the fatal `RunError`, that no `kind` profile with the name `name` exists.
+    fn no_profile_error(&self, kind: &str, name: &str) -> RunError {
+        RunError::fatal(
+            StringError::new(format!(
+                "No {} profile with the name {:?} found",
+                kind, name
+            )),
+            &self.sender,
+        )
+    }
+
+    /// Validates a glob pattern of include or exclude.
+    ///
+    /// Returns the reason, if the pattern is invalid.
+    pub fn validate_glob(pattern: &str) -> Result<(), String> {
+        validate_pattern(pattern).map_err(|err| err.kind().to_string())
+    }
+
+    /// Returns the sorted names of the backup profiles, which match a glob pattern.
+    pub fn match_backup_profiles(&self, pattern: &str) -> Option<Vec<String>> {
+        let config = self.requires_config()?;
+
+        match match_names(pattern, config.backup.keys()) {
+            Ok(profiles) => Some(profiles),
+            Err(err) => {
+                send_error!(self.sender, err);
+                None
+            }
+        }
+    }
+
+    /// Merges the cuba json files `first` and `second` into `output`.
+    ///
+    /// For nodes in both, the node of the newer backup wins. The output is encrypted like
+    /// `first`, or like `second` if only `second` is encrypted.
+    pub fn merge_index(&self, first: &str, second: &str, output: &str) -> Result<(), RunError> {
+        let mut password_cache = PasswordCache::new();
+
+        let mut read_index = |path: &str| {
+            std::fs::File::open(path)
+                .map_err(CubaJsonError::from)
+                .and_then(|file| {
+                    load_cuba_json(
+                        Box::new(BufReader::new(file)),
+                        &mut password_cache,
+                        &self.sender,
+                    )
+                })
+                .map_err(|err| {
+                    RunError::fatal(
+                        StringError::new(format!("Failed to read {:?}: {}", path, err)),
+                        &self.sender,
+                    )
+                })
+        };
+
+        let (mut transferred_nodes, first_cipher) = read_index(first)?;
+        let (other_nodes, second_cipher) = read_index(second)?;
+
+        transferred_nodes
+            .merge(&other_nodes)
+            .map_err(|err| RunError::fatal(err, &self.sender))?;
+
+        std::fs::File::create(output)
+            .map_err(CubaJsonError::from)
+            .and_then(|file| {
+                save_cuba_json(
+                    &transferred_nodes,
+                    first_cipher.as_ref().or(second_cipher.as_ref()),
+                    BufWriter::new(file),
+                    &self.sender,
+                )
+            })
+            .map_err(|err| {
+                RunError::fatal(
+                    StringError::new(format!("Failed to write {:?}: {}", output, err)),
+                    &self.sender,
+                )
+            })?;
+
+        send_info!(
+            self.sender,
+            "Merged {} nodes into {:?}",
+            transferred_nodes.node_count(),
+            output
+        );
+
+        Ok(())
+    }
+
     /// Sets a password for the given id.
     pub fn set_password(&self, id: &str, password: &SecretString) {
         match crate::core::keyring::store_password(id, password) {
@@ -190,95 +473,302 @@ impl Cuba {
         }
     }
 
-    /// Runs the backup with the given backup profile name.
-    pub fn run_backup(&self, run_handle: RunHandle, backup_name: &str) {
-        if let Some(config) = self.requires_config() {
-            match config.backup.get(backup_name) {
-                Some(backup) => {
-                    let src_mnt = match create_fs_mount(config, &backup.src_fs, &backup.src_dir) {
-                        Ok(mount) => mount,
-                        Err(err) => {
-                            send_error!(self.sender, err);
-                            return;
-                        }
-                    };
-
-                    let dest_mnt = match create_fs_mount(config, &backup.dest_fs, &backup.dest_dir)
-                    {
-                        Ok(mount) => mount,
-                        Err(err) => {
-                            send_error!(self.sender, err);
-                            return;
-                        }
-                    };
-
-                    run_backup(
-                        run_handle.state.clone(),
-                        config.transfer_threads,
-                        backup.compression,
-                        backup.encrypt,
-                        &backup.password_id,
-                        &backup.include,
-                        &backup.exclude,
-                        &FSConnection::new(src_mnt, dest_mnt),
-                        self.sender.clone(),
-                    );
-                }
-                None => {
-                    send_error!(
-                        self.sender,
-                        StringError::new(format!(
-                            "No backup profile with the name {:?} found",
-                            backup_name
-                        ))
-                    );
-                }
+    /// Returns the sorted password ids, which have a stored password.
+    pub fn list_passwords(&self) -> Option<Vec<String>> {
+        match crate::core::keyring::list_passwords() {
+            Ok(password_ids) => Some(password_ids),
+            Err(err) => {
+                send_error!(self.sender, err);
+                None
             }
         }
     }
 
-    /// Runs the restore with the given restore profile name.
-    pub fn run_restore(&self, run_handle: RunHandle, restore_name: &str) {
-        if let Some(config) = self.requires_config() {
-            match config.restore.get(restore_name) {
-                Some(restore) => {
-                    let src_mnt = match create_fs_mount(config, &restore.src_fs, &restore.src_dir) {
-                        Ok(mount) => mount,
-                        Err(err) => {
-                            send_error!(self.sender, err);
-                            return;
-                        }
-                    };
-
-                    let dest_mnt =
-                        match create_fs_mount(config, &restore.dest_fs, &restore.dest_dir) {
-                            Ok(mount) => mount,
-                            Err(err) => {
-                                send_error!(self.sender, err);
-                                return;
-                            }
-                        };
-
-                    run_restore(
-                        run_handle.state.clone(),
-                        config.transfer_threads,
-                        &restore.include,
-                        &restore.exclude,
-                        FSConnection::new(src_mnt, dest_mnt),
-                        self.sender.clone(),
-                    );
+    /// Returns the password ids of the backup profile, which have no stored password.
+    ///
+    /// These are the password ids of the source and destination filesystem and the
+    /// password id of the encryption.
+    pub fn check_passwords(&self, backup_name: &str) -> Vec<String> {
+        let mut missing_password_ids = Vec::new();
+
+        if let Some(config) = self.requires_config()
+            && let Some(backup) = config.backup.get(backup_name)
+        {
+            let mut password_ids = Vec::new();
+
+            password_ids.extend(config.filesystem.password_id(&backup.src_fs));
+            password_ids.extend(config.filesystem.password_id(&backup.dest_fs));
+
+            if backup.encrypt {
+                password_ids.extend(backup.password_id.as_deref());
+            }
+
+            for password_id in password_ids {
+                // Check each id only once.
+                if crate::core::keyring::get_password(password_id).is_err()
+                    && !missing_password_ids.iter().any(|id| id == password_id)
+                {
+                    missing_password_ids.push(password_id.to_string());
                 }
-                None => {
-                    send_error!(
-                        self.sender,
+            }
+        }
+
+        missing_password_ids
+    }
+
+    /// Returns the transfer threads of the config, without config the available parallelism.
+    fn transfer_threads(&self) -> usize {
+        match &self.config {
+            Some(config) => config.transfer_threads,
+            None => thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+
+    /// Returns the bandwidth limit of the config, without config there is no limit.
+    fn max_bandwidth_bytes_per_sec(&self) -> Option<u64> {
+        self.config
+            .as_ref()
+            .and_then(|config| config.max_bandwidth_bytes_per_sec)
+    }
+
+    /// Runs the backup with the given backup profile name.
+    ///
+    /// Returns the statistics of the backup, or the first fatal error. Errors of single files
+    /// do not fail the backup, they are only sent as messages.
+    pub fn run_backup(
+        &self,
+        run_handle: RunHandle,
+        backup_name: &str,
+    ) -> Result<BackupStats, RunError> {
+        let config = self.run_config()?;
+
+        let Some(backup) = config.backup.get(backup_name) else {
+            return Err(self.no_profile_error("backup", backup_name));
+        };
+
+        // Fail early instead of in the middle of the transfer.
+        let missing_password_ids = self.check_passwords(backup_name);
+
+        if !missing_password_ids.is_empty() {
+            return Err(RunError::fatal(
+                StringError::new(format!(
+                    "No passwords stored for the password ids {:?}",
+                    missing_password_ids
+                )),
+                &self.sender,
+            ));
+        }
+
+        let src_mnt = create_fs_mount(config, &self.sender, &backup.src_fs, &backup.src_dir)
+            .map_err(|err| RunError::fatal(err, &self.sender))?;
+
+        let dest_mnt = create_dest_fs_mount(config, &self.sender, backup, backup_name)
+            .map_err(|err| RunError::fatal(err, &self.sender))?;
+
+        // The connection test requires the destination directory.
+        if backup.dest_dir_auto {
+            create_auto_dest_dirs(&dest_mnt, backup_name)
+                .map_err(|err| RunError::fatal(err, &self.sender))?;
+        }
+
+        self.backup_with(run_handle, backup, FSConnection::new(src_mnt, dest_mnt))
+    }
+
+    /// Runs a backup with the backup config `backup` from `fs_conn.src_mnt` to `fs_conn.dest_mnt`.
+    ///
+    /// The filesystem names of `backup` are only used for messages, so the config and the
+    /// mounts can be made without a config file. The transfer settings are the ones of the
+    /// loaded config, if any.
+    pub fn backup_with(
+        &self,
+        run_handle: RunHandle,
+        backup: &BackupConfig,
+        fs_conn: FSConnection,
+    ) -> Result<BackupStats, RunError> {
+        // Fail fast, instead of timing out in the middle of the backup.
+        for (mount, fs, role) in [
+            (&fs_conn.src_mnt, &backup.src_fs, "source"),
+            (&fs_conn.dest_mnt, &backup.dest_fs, "destination"),
+        ] {
+            if let Err(err) = mount.test_connection() {
+                return Err(RunError::fatal(
+                    StringError::new(format!(
+                        "The {} {:?} is not reachable: {}",
+                        role,
+                        fs,
+                        trace_error(&err)
+                    )),
+                    &self.sender,
+                ));
+            }
+        }
+
+        let exclude = self.backup_exclude_patterns(backup)?;
+
+        let stats = run_backup(
+            run_handle.state.clone(),
+            self.transfer_threads(),
+            self.max_bandwidth_bytes_per_sec(),
+            backup.compression,
+            backup.compression_level,
+            backup.skip_recompression,
+            &backup.compression_exclude_extensions,
+            backup.delta_compression,
+            backup.dedup_enabled,
+            backup.encrypt,
+            &backup.password_id,
+            backup.encrypt_filename,
+            &backup.include,
+            &exclude,
+            backup
+                .glob_case_sensitive
+                .unwrap_or_else(default_case_sensitive),
+            backup.max_file_size_bytes,
+            backup.retry_changed_files,
+            backup.journal_retention_days,
+            backup.signature_algo,
+            &fs_conn,
+            self.sender.clone(),
+        )?;
+
+        if backup.verify_after_backup {
+            self.verify_after_backup(run_handle, fs_conn);
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the estimated bytes, which the backup with the given name transfers.
+    ///
+    /// Walks the src of the backup without computing signatures, see `estimate_backup_size`.
+    pub fn get_backup_size_estimate(&self, backup_name: &str) -> Option<u64> {
+        let config = self.requires_config()?;
+
+        let Some(backup) = config.backup.get(backup_name) else {
+            send_error!(
+                self.sender,
+                StringError::new(format!(
+                    "No backup profile with the name {:?} found",
+                    backup_name
+                ))
+            );
+            return None;
+        };
+
+        let exclude = self.backup_exclude_patterns(backup).ok()?;
+
+        let src_mnt = match create_fs_mount(config, &self.sender, &backup.src_fs, &backup.src_dir) {
+            Ok(mount) => mount,
+            Err(err) => {
+                send_error!(self.sender, err);
+                return None;
+            }
+        };
+
+        estimate_backup_size(
+            backup.compression,
+            &backup.include,
+            &exclude,
+            backup
+                .glob_case_sensitive
+                .unwrap_or_else(default_case_sensitive),
+            &src_mnt,
+            self.sender.clone(),
+        )
+        .ok()
+    }
+
+    /// Returns the exclude patterns of a backup, with the patterns of its exclude patterns file.
+    fn backup_exclude_patterns(
+        &self,
+        backup: &BackupConfig,
+    ) -> Result<Option<Vec<String>>, RunError> {
+        let mut exclude = backup.exclude.clone();
+
+        if let Some(exclude_patterns_file) = &backup.exclude_patterns_file {
+            match read_patterns_file(exclude_patterns_file) {
+                Ok(patterns) => exclude.get_or_insert_with(Vec::new).extend(patterns),
+                Err(err) => {
+                    return Err(RunError::fatal(
                         StringError::new(format!(
-                            "No restore profile with the name {:?} found",
-                            restore_name
-                        ))
-                    );
+                            "Failed to read the exclude patterns file {:?}: {}",
+                            exclude_patterns_file.to_unicode(),
+                            err
+                        )),
+                        &self.sender,
+                    ));
                 }
             }
         }
+
+        Ok(exclude)
+    }
+
+    /// Runs the restore with the given restore profile name.
+    ///
+    /// With `restore_file` only this file (its path in the source of the backup) is
+    /// restored, with `restore_to` to this path on the dest filesystem instead of the
+    /// dest dir of the profile.
+    pub fn run_restore(
+        &self,
+        run_handle: RunHandle,
+        restore_name: &str,
+        restore_file: Option<&NPath<Rel, File>>,
+        restore_to: Option<&NPath<Abs, File>>,
+    ) -> Result<(), RunError> {
+        let config = self.run_config()?;
+
+        let Some(restore) = config.restore.get(restore_name) else {
+            return Err(self.no_profile_error("restore", restore_name));
+        };
+
+        let src_mnt = create_fs_mount(config, &self.sender, &restore.src_fs, &restore.src_dir)
+            .map_err(|err| RunError::fatal(err, &self.sender))?;
+
+        let dest_mnt = create_fs_mount(config, &self.sender, &restore.dest_fs, &restore.dest_dir)
+            .map_err(|err| RunError::fatal(err, &self.sender))?;
+
+        self.restore_with(
+            run_handle,
+            restore,
+            FSConnection::new(src_mnt, dest_mnt),
+            restore_file,
+            restore_to,
+        )
+    }
+
+    /// Runs a restore with the restore config `restore` from `fs_conn.src_mnt` to
+    /// `fs_conn.dest_mnt`.
+    ///
+    /// The filesystem names of `restore` are not used, so the config and the mounts can be
+    /// made without a config file. For `restore_file` and `restore_to` see `run_restore`.
+    pub fn restore_with(
+        &self,
+        run_handle: RunHandle,
+        restore: &RestoreConfig,
+        fs_conn: FSConnection,
+        restore_file: Option<&NPath<Rel, File>>,
+        restore_to: Option<&NPath<Abs, File>>,
+    ) -> Result<(), RunError> {
+        // An alternative dest is only possible for a single file.
+        if restore_to.is_some() && restore_file.is_none() {
+            return Err(RunError::fatal(
+                StringError::new("Restore to an alternative dest requires a file".to_string()),
+                &self.sender,
+            ));
+        }
+
+        run_restore(
+            run_handle.state.clone(),
+            self.transfer_threads(),
+            self.max_bandwidth_bytes_per_sec(),
+            &restore.include,
+            &restore.exclude,
+            restore_file,
+            restore_to,
+            fs_conn,
+            self.sender.clone(),
+        )
     }
 
     /// Runs the verify with the given backup profile name.
@@ -286,37 +776,121 @@ impl Cuba {
     /// Verify means to check the integrity of the backup. In detail:
     /// - Throws an error if a file/directories is in the index but not in the backup
     /// - Throws an error if a hash of a file and its index hash is not the same
-    pub fn run_verify(&self, run_handle: RunHandle, backup_name: &str, verify_all: &bool) {
-        if let Some(config) = self.requires_config() {
-            match config.backup.get(backup_name) {
-                Some(backup) => {
-                    let fs_mnt = match create_fs_mount(config, &backup.dest_fs, &backup.dest_dir) {
-                        Ok(mount) => mount,
-                        Err(err) => {
-                            send_error!(self.sender, err);
-                            return;
-                        }
-                    };
-
-                    run_verify(
-                        run_handle.state.clone(),
-                        config.transfer_threads,
-                        fs_mnt,
-                        *verify_all,
-                        self.sender.clone(),
-                    );
-                }
-                None => {
-                    send_error!(
-                        self.sender,
-                        StringError::new(format!(
-                            "No backup profile with the name {:?} found",
-                            backup_name
-                        ))
-                    );
+    ///
+    /// The `verify_mode` selects the nodes, which are verified. With `full`, all files are
+    /// compared against a freshly computed signature of the source file instead of the stored one.
+    pub fn run_verify(
+        &self,
+        run_handle: RunHandle,
+        backup_name: &str,
+        verify_mode: &VerifyMode,
+        full: &bool,
+    ) -> Result<(), RunError> {
+        self.verify(
+            run_handle,
+            backup_name,
+            verify_mode,
+            full,
+            self.sender.clone(),
+        )
+    }
+
+    /// Runs a verify of the backup at `fs_conn.dest_mnt` with the source `fs_conn.src_mnt`.
+    ///
+    /// For `verify_mode` and `full` see `run_verify`.
+    pub fn verify_with(
+        &self,
+        run_handle: RunHandle,
+        fs_conn: FSConnection,
+        verify_mode: VerifyMode,
+        full: bool,
+    ) -> Result<(), RunError> {
+        run_verify(
+            run_handle.state.clone(),
+            self.transfer_threads(),
+            fs_conn.dest_mnt,
+            Some(fs_conn.src_mnt),
+            verify_mode,
+            full,
+            self.sender.clone(),
+        )
+    }
+
+    /// Runs the verify of the backup at `fs_conn.dest_mnt` after a backup.
+    ///
+    /// Errors of the verify are sent as warnings, they do not fail the backup.
+    fn verify_after_backup(&self, run_handle: RunHandle, fs_conn: FSConnection) {
+        send_info!(
+            self.sender,
+            "Verifying the backup {:?}",
+            fs_conn.dest_mnt.abs_dir_path.to_unicode()
+        );
+
+        let (verify_sender, verify_receiver) = unbounded::<Arc<dyn Message>>();
+        let sender = self.sender.clone();
+
+        // Forward the messages of the verify, errors as warnings.
+        let forwarder = thread::spawn(move || {
+            for msg in verify_receiver.iter() {
+                match msg.err() {
+                    Some(err) => {
+                        let info = Arc::new(StringInfo::new(trace_error(err)));
+                        sender.send(Arc::new(WarnMessage::new(info))).unwrap();
+                    }
+                    None => sender.send(msg).unwrap(),
                 }
             }
-        }
+        });
+
+        // The errors are forwarded as warnings, so the result is ignored.
+        let _ = run_verify(
+            run_handle.state.clone(),
+            self.transfer_threads(),
+            fs_conn.dest_mnt,
+            Some(fs_conn.src_mnt),
+            VerifyMode::New,
+            false,
+            verify_sender,
+        );
+
+        // The verify sender is dropped, so the forwarder ends.
+        forwarder.join().unwrap();
+    }
+
+    /// Runs the verify with the given backup profile name and message sender.
+    fn verify(
+        &self,
+        run_handle: RunHandle,
+        backup_name: &str,
+        verify_mode: &VerifyMode,
+        full: &bool,
+        sender: Sender<Arc<dyn Message>>,
+    ) -> Result<(), RunError> {
+        let config = self.run_config()?;
+
+        let Some(backup) = config.backup.get(backup_name) else {
+            return Err(self.no_profile_error("backup", backup_name));
+        };
+
+        let fs_mnt = create_dest_fs_mount(config, &sender, backup, backup_name)
+            .map_err(|err| RunError::fatal(err, &sender))?;
+
+        // The source is only required for a full verify.
+        let src_mnt = match create_fs_mount(config, &sender, &backup.src_fs, &backup.src_dir) {
+            Ok(mount) => Some(mount),
+            Err(err) if *full => return Err(RunError::fatal(err, &sender)),
+            Err(_) => None,
+        };
+
+        run_verify(
+            run_handle.state.clone(),
+            self.transfer_threads(),
+            fs_mnt,
+            src_mnt,
+            *verify_mode,
+            *full,
+            sender,
+        )
     }
 
     /// Runs the clean with the given backup profile name.
@@ -325,29 +899,140 @@ impl Cuba {
     /// - Files/directories that are not in the backup index are deleted from the backup
     /// - Files/directories/symlinks that are marked as ophans (not in the source anymore) are
     ///   deleted from the backup
-    pub fn run_clean(&self, run_handle: RunHandle, backup_name: &str) {
-        if let Some(config) = self.requires_config() {
-            match config.backup.get(backup_name) {
-                Some(backup) => {
-                    let fs_mnt = match create_fs_mount(config, &backup.dest_fs, &backup.dest_dir) {
-                        Ok(mount) => mount,
-                        Err(err) => {
-                            send_error!(self.sender, err);
-                            return;
-                        }
-                    };
-
-                    run_clean(run_handle.state.clone(), fs_mnt, self.sender.clone());
+    pub fn run_clean(&self, run_handle: RunHandle, backup_name: &str) -> Result<(), RunError> {
+        let config = self.run_config()?;
+
+        let Some(backup) = config.backup.get(backup_name) else {
+            return Err(self.no_profile_error("backup", backup_name));
+        };
+
+        let fs_mnt = create_dest_fs_mount(config, &self.sender, backup, backup_name)
+            .map_err(|err| RunError::fatal(err, &self.sender))?;
+
+        run_clean(run_handle.state.clone(), fs_mnt, self.sender.clone())
+    }
+
+    /// Runs the prune with the given backup profile name.
+    ///
+    /// Prune applies the retention policy of the backup profile to the orphans (not in the
+    /// source anymore). Orphans outside of the policy are deleted from the backup.
+    pub fn run_prune(&self, run_handle: RunHandle, backup_name: &str) -> Result<(), RunError> {
+        let config = self.run_config()?;
+
+        let Some(backup) = config.backup.get(backup_name) else {
+            return Err(self.no_profile_error("backup", backup_name));
+        };
+
+        let fs_mnt = create_dest_fs_mount(config, &self.sender, backup, backup_name)
+            .map_err(|err| RunError::fatal(err, &self.sender))?;
+
+        run_prune(
+            run_handle.state.clone(),
+            fs_mnt,
+            &backup.retention,
+            self.sender.clone(),
+        )
+    }
+
+    /// Reads the transferred nodes from the dest of a backup profile.
+    fn read_backup_index(&self, backup_name: &str) -> Option<TransferredNodes> {
+        let config = self.requires_config()?;
+
+        match config.backup.get(backup_name) {
+            Some(backup) => {
+                let fs_mnt = match create_dest_fs_mount(config, &self.sender, backup, backup_name) {
+                    Ok(mount) => mount,
+                    Err(err) => {
+                        send_error!(self.sender, err);
+                        return None;
+                    }
+                };
+
+                // Connect fs.
+                if let Err(err) = fs_mnt.fs.write().unwrap().connect() {
+                    send_error!(self.sender, err);
+                    return None;
                 }
-                None => {
-                    send_error!(
-                        self.sender,
-                        StringError::new(format!(
-                            "No backup profile with the name {:?} found",
-                            backup_name
-                        ))
-                    );
+
+                // Create password cache.
+                let mut password_cache = PasswordCache::new();
+
+                // Read cuba json, encrypted or plain.
+                let mut transferred_nodes =
+                    read_cuba_json(&fs_mnt, &mut password_cache, &self.sender)
+                        .map(|(nodes, _)| nodes);
+
+                // Decrypt the names, if encrypted.
+                if let Some(nodes) = &mut transferred_nodes
+                    && let Err(err) = FilenameCipher::decrypt_nodes(nodes, &mut password_cache)
+                {
+                    send_error!(self.sender, err);
+                    transferred_nodes = None;
+                }
+
+                // Disconnect fs.
+                if let Err(err) = fs_mnt.fs.write().unwrap().disconnect() {
+                    send_error!(self.sender, err);
                 }
+
+                transferred_nodes
+            }
+            None => {
+                send_error!(
+                    self.sender,
+                    StringError::new(format!(
+                        "No backup profile with the name {:?} found",
+                        backup_name
+                    ))
+                );
+                None
+            }
+        }
+    }
+
+    /// Lists the src paths of the backup with the given backup profile name.
+    pub fn list_backup_contents(&self, backup_name: &str) -> Option<Vec<UNPath<Rel>>> {
+        self.read_backup_index(backup_name)
+            .map(|transferred_nodes| list_src_paths(&transferred_nodes))
+    }
+
+    /// Returns the aggregate metrics of the backup with the given backup profile name.
+    pub fn get_backup_statistics(&self, backup_name: &str) -> Option<TransferNodesStats> {
+        self.read_backup_index(backup_name)
+            .map(|transferred_nodes| transferred_nodes.statistics())
+    }
+
+    /// Exports the transferred nodes of a backup profile as csv to `output_path`.
+    pub fn export_backup_csv(&self, backup_name: &str, output_path: &str) {
+        if let Some(transferred_nodes) = self.read_backup_index(backup_name) {
+            match export_csv(&transferred_nodes, output_path) {
+                Ok(()) => send_info!(
+                    self.sender,
+                    "Exported {} nodes to {:?}",
+                    transferred_nodes.node_count(),
+                    output_path
+                ),
+                Err(err) => send_error!(self.sender, err),
+            }
+        }
+    }
+
+    /// Exports the signatures of a backup profile as manifest to `output_path`.
+    pub fn export_backup_manifest(
+        &self,
+        backup_name: &str,
+        output_path: &str,
+        format: ManifestFormat,
+    ) {
+        if let Some(transferred_nodes) = self.read_backup_index(backup_name) {
+            match export_manifest(&transferred_nodes, output_path, format) {
+                Ok(entry_count) => send_info!(
+                    self.sender,
+                    "Exported {} signatures to {:?}",
+                    entry_count,
+                    output_path
+                ),
+                Err(err) => send_error!(self.sender, err),
             }
         }
     }
@@ -1,14 +1,19 @@
 use crossbeam_channel::Sender;
-use flate2::{Compression, bufread::GzDecoder, write::GzEncoder};
 use lazy_static::lazy_static;
-use std::{
-    io::{BufReader, BufWriter},
-    sync::Arc,
-};
+use secrecy::SecretString;
+use std::io::{self, Cursor, Read, Write};
+use std::sync::Arc;
+use thiserror::Error;
 
 use crate::{
-    core::{fs::fs_base::FSMount, transferred_node::TransferredNodes},
-    send_error,
+    core::{
+        fs::fs_base::{FS, FSError, FSMount},
+        keyring::KeyringError,
+        password_cache::PasswordCache,
+        process_data::cipher_procs::{decrypt_proc, encrypt_proc},
+        transferred_node::TransferredNodes,
+    },
+    send_error, send_warn,
     shared::{
         message::Message,
         npath::{Abs, File, NPath, Rel},
@@ -21,67 +26,347 @@ lazy_static! {
         NPath::<Rel, File>::try_from("cuba.json.gz").unwrap();
 }
 
+// The backup of the previous cuba json as rel path.
+lazy_static! {
+    pub static ref CUBA_JSON_BAK_REL_PATH: NPath<Rel, File> =
+        NPath::<Rel, File>::try_from("cuba.json.gz.bak").unwrap();
+}
+
+// The temp file of the cuba json as rel path, which replaces the cuba json.
+lazy_static! {
+    pub static ref CUBA_JSON_TMP_REL_PATH: NPath<Rel, File> =
+        NPath::<Rel, File>::try_from("cuba.json.gz.tmp").unwrap();
+}
+
+/// The magic of an encrypted cuba json, a plain cuba json starts with the gzip magic.
+const ENCRYPTED_MAGIC: &[u8; 8] = b"CUBAENC1";
+
+/// Defines a `CubaJsonError`.
+#[derive(Error, Debug)]
+pub enum CubaJsonError {
+    #[error("No password id for the encrypted cuba json")]
+    NoPasswordId,
+
+    #[error("The cuba json is encrypted with the password {0:?}, which is not available")]
+    NoPassword(String, #[source] KeyringError),
+
+    #[error("The header of the encrypted cuba json is invalid")]
+    InvalidHeader,
+
+    #[error("The written cuba json differs from the encoded one")]
+    VerifyFailed,
+
+    #[error(transparent)]
+    FS(#[from] FSError),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Defines a `CubaJsonCipher`.
+///
+/// Encrypts the cuba json with the password of a password id. The password id is stored
+/// in front of the encrypted cuba json, so that a restore finds the password.
+#[derive(Clone)]
+pub struct CubaJsonCipher {
+    password_id: String,
+    password: SecretString,
+}
+
+/// Methods of `CubaJsonCipher`.
+impl CubaJsonCipher {
+    /// Creates a new `CubaJsonCipher` with the password of `password_id`.
+    pub fn from_password_id(
+        password_id: &Option<String>,
+        password_cache: &mut PasswordCache,
+    ) -> Result<Self, CubaJsonError> {
+        let password_id = password_id.as_ref().ok_or(CubaJsonError::NoPasswordId)?;
+
+        let password = password_cache
+            .get_password(password_id)
+            .map_err(|err| CubaJsonError::NoPassword(password_id.clone(), err))?;
+
+        Ok(Self {
+            password_id: password_id.clone(),
+            password: password.clone(),
+        })
+    }
+}
+
+/// Saves the transferred nodes as encrypted cuba json to `writer`.
+///
+/// The cuba json is preceded by the magic and the password id.
+pub fn save_encrypted(
+    transferred_nodes: &TransferredNodes,
+    cipher: &CubaJsonCipher,
+    mut writer: impl Write,
+    sender: &Sender<Arc<dyn Message>>,
+) -> Result<(), CubaJsonError> {
+    let password_id = cipher.password_id.as_bytes();
+    let password_id_len =
+        u16::try_from(password_id.len()).map_err(|_| CubaJsonError::InvalidHeader)?;
+
+    // Encode the cuba json.
+    let mut data: Vec<u8> = Vec::new();
+    transferred_nodes.to_cuba_json(&mut data)?;
+
+    // Encrypt the cuba json.
+    let mut encrypted =
+        encrypt_proc(cipher.password.clone())(sender.clone(), Box::new(Cursor::new(data)), None);
+
+    // Write header and encrypted cuba json.
+    writer.write_all(ENCRYPTED_MAGIC)?;
+    writer.write_all(&password_id_len.to_be_bytes())?;
+    writer.write_all(password_id)?;
+    io::copy(&mut encrypted, &mut writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Loads the transferred nodes from the encrypted cuba json of `reader`, after the magic.
+///
+/// Returns the nodes and the cipher, so that the cuba json can be written encrypted again.
+pub fn load_encrypted(
+    mut reader: Box<dyn Read + Send>,
+    password_cache: &mut PasswordCache,
+    sender: &Sender<Arc<dyn Message>>,
+) -> Result<(TransferredNodes, CubaJsonCipher), CubaJsonError> {
+    // Read the password id.
+    let mut password_id_len = [0u8; 2];
+    reader
+        .read_exact(&mut password_id_len)
+        .map_err(|_| CubaJsonError::InvalidHeader)?;
+
+    let mut password_id = vec![0u8; u16::from_be_bytes(password_id_len) as usize];
+    reader
+        .read_exact(&mut password_id)
+        .map_err(|_| CubaJsonError::InvalidHeader)?;
+
+    let password_id = String::from_utf8(password_id).map_err(|_| CubaJsonError::InvalidHeader)?;
+    let cipher = CubaJsonCipher::from_password_id(&Some(password_id), password_cache)?;
+
+    // Decrypt the cuba json.
+    let decrypted = decrypt_proc(cipher.password.clone())(sender.clone(), reader, None);
+
+    Ok((TransferredNodes::from_cuba_json(decrypted)?, cipher))
+}
+
+/// Returns true, if the cuba json or its backup exists in `fs_mnt`.
+pub fn cuba_json_exists(fs_mnt: &FSMount) -> bool {
+    let fs = fs_mnt.fs.read().unwrap();
+
+    [&*CUBA_JSON_REL_PATH, &*CUBA_JSON_BAK_REL_PATH]
+        .into_iter()
+        .any(|rel_file_path| {
+            fs.meta(&fs_mnt.abs_dir_path.add_rel_file(rel_file_path).into())
+                .is_ok()
+        })
+}
+
+/// Returns true, if `abs_file_path` is the cuba json or its backup.
+pub fn is_cuba_json(abs_file_path: &NPath<Abs, File>) -> bool {
+    abs_file_path.ends_with(&CUBA_JSON_REL_PATH.clone())
+        || abs_file_path.ends_with(&CUBA_JSON_BAK_REL_PATH.clone())
+}
+
+/// Loads the transferred nodes from the plain or encrypted cuba json of `reader`.
+///
+/// Returns the cipher of an encrypted cuba json with the transferred nodes.
+pub fn load_cuba_json(
+    mut reader: Box<dyn Read + Send>,
+    password_cache: &mut PasswordCache,
+    sender: &Sender<Arc<dyn Message>>,
+) -> Result<(TransferredNodes, Option<CubaJsonCipher>), CubaJsonError> {
+    // Read the magic.
+    let mut magic: Vec<u8> = Vec::new();
+    reader
+        .by_ref()
+        .take(ENCRYPTED_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+
+    if magic == ENCRYPTED_MAGIC {
+        let (transferred_nodes, cipher) = load_encrypted(reader, password_cache, sender)?;
+        Ok((transferred_nodes, Some(cipher)))
+    } else {
+        // Plain, the magic is part of the cuba json.
+        let transferred_nodes = TransferredNodes::from_cuba_json(Cursor::new(magic).chain(reader))?;
+        Ok((transferred_nodes, None))
+    }
+}
+
+/// Saves the transferred nodes as cuba json to `writer`, encrypted with `cipher` - if any.
+pub fn save_cuba_json(
+    transferred_nodes: &TransferredNodes,
+    cipher: Option<&CubaJsonCipher>,
+    writer: impl Write,
+    sender: &Sender<Arc<dyn Message>>,
+) -> Result<(), CubaJsonError> {
+    match cipher {
+        Some(cipher) => save_encrypted(transferred_nodes, cipher, writer, sender),
+        None => transferred_nodes
+            .to_cuba_json(writer)
+            .map_err(CubaJsonError::from),
+    }
+}
+
+/// Reads and parses the file `abs_file_path` as plain or encrypted cuba json.
+fn read_file(
+    fs_mnt: &FSMount,
+    abs_file_path: &NPath<Abs, File>,
+    password_cache: &mut PasswordCache,
+    sender: &Sender<Arc<dyn Message>>,
+) -> Result<(TransferredNodes, Option<CubaJsonCipher>), CubaJsonError> {
+    let reader = fs_mnt.fs.read().unwrap().read_data(abs_file_path)?;
+
+    load_cuba_json(reader, password_cache, sender)
+}
+
+/// Writes `data` to the file `abs_file_path`, synced to the disk if the fs supports it.
+fn write_file(fs: &dyn FS, abs_file_path: &NPath<Abs, File>, data: &[u8]) -> Result<(), FSError> {
+    let mut writer = fs.write_data(abs_file_path)?;
+
+    writer
+        .write_all(data)
+        .and_then(|()| writer.finish_synced())
+        .map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))
+}
+
+/// Verifies that the file `abs_file_path` contains `data`.
+fn verify_file(
+    fs: &dyn FS,
+    abs_file_path: &NPath<Abs, File>,
+    data: &[u8],
+) -> Result<(), CubaJsonError> {
+    let mut written: Vec<u8> = Vec::new();
+    fs.read_data(abs_file_path)?.read_to_end(&mut written)?;
+
+    if written != data {
+        return Err(CubaJsonError::VerifyFailed);
+    }
+
+    Ok(())
+}
+
+/// Moves the file `src_abs_file_path` to `dest_abs_file_path`.
+///
+/// If the fs can't rename, the data is copied and the source is removed.
+fn move_file(
+    fs: &dyn FS,
+    src_abs_file_path: &NPath<Abs, File>,
+    dest_abs_file_path: &NPath<Abs, File>,
+) -> Result<(), FSError> {
+    match fs.rename_file(src_abs_file_path, dest_abs_file_path) {
+        Err(FSError::NotSupported(_)) => {
+            let mut reader = fs.read_data(src_abs_file_path)?;
+            let mut writer = fs.write_data(dest_abs_file_path)?;
+
+            std::io::copy(&mut reader, &mut writer)
+                .and_then(|_| writer.finish())
+                .map_err(|err| FSError::WriteFailed(dest_abs_file_path.clone(), err.into()))?;
+
+            fs.remove_file(src_abs_file_path)
+        }
+        result => result,
+    }
+}
+
 /// Read the cuba json.
+///
+/// If the cuba json can't be read, e.g. corrupted by a crash, its backup is read.
+/// Returns the cipher of an encrypted cuba json with the transferred nodes.
 pub fn read_cuba_json(
     fs_mnt: &FSMount,
+    password_cache: &mut PasswordCache,
     sender: &Sender<Arc<dyn Message>>,
-) -> Option<TransferredNodes> {
+) -> Option<(TransferredNodes, Option<CubaJsonCipher>)> {
     // Create cuba json abs path.
     let cuba_json_abs_path: NPath<Abs, File> =
         fs_mnt.abs_dir_path.add_rel_file(&CUBA_JSON_REL_PATH);
 
-    match fs_mnt.fs.read().unwrap().read_data(&cuba_json_abs_path) {
-        Ok(reader) => {
-            // Create buf reader.
-            let buf_reader = BufReader::new(reader);
-
-            // Create decoder
-            let decoder = GzDecoder::new(buf_reader);
+    match read_file(fs_mnt, &cuba_json_abs_path, password_cache, sender) {
+        Ok(cuba_json) => Some(cuba_json),
+        Err(err) => {
+            // Create cuba json backup abs path.
+            let cuba_json_bak_abs_path: NPath<Abs, File> =
+                fs_mnt.abs_dir_path.add_rel_file(&CUBA_JSON_BAK_REL_PATH);
 
-            // Read data.
-            return match serde_json::from_reader(decoder) {
-                Ok(transferred_nodes) => Some(transferred_nodes),
-                Err(err) => {
+            match read_file(fs_mnt, &cuba_json_bak_abs_path, password_cache, sender) {
+                Ok(cuba_json) => {
+                    send_warn!(
+                        sender,
+                        "The cuba json is not readable ({}), the backup {:?} is used",
+                        err,
+                        cuba_json_bak_abs_path
+                    );
+                    Some(cuba_json)
+                }
+                Err(_) => {
                     send_error!(sender, err);
                     None
                 }
-            };
-        }
-        Err(err) => {
-            send_error!(sender, err);
+            }
         }
     }
-
-    None
 }
 
-/// Write the cuba json.
+/// Write the cuba json, encrypted with `cipher` - if any.
+///
+/// The cuba json is written to a temp file, which replaces the cuba json once it is
+/// verified. The previous cuba json is kept as backup. Returns true, if the cuba json is
+/// written.
 pub fn write_cuba_json(
     fs_mnt: &FSMount,
     transferred_node: &TransferredNodes,
+    cipher: Option<&CubaJsonCipher>,
     sender: &Sender<Arc<dyn Message>>,
-) {
-    // Create cuba json abs path.
+) -> bool {
+    // Encode the cuba json, before any file is touched.
+    let mut data: Vec<u8> = Vec::new();
+
+    if let Err(err) = save_cuba_json(transferred_node, cipher, &mut data, sender) {
+        send_error!(sender, err);
+        return false;
+    }
+
+    // Create cuba json abs paths.
     let cuba_json_abs_path: NPath<Abs, File> =
         fs_mnt.abs_dir_path.add_rel_file(&CUBA_JSON_REL_PATH);
+    let cuba_json_bak_abs_path: NPath<Abs, File> =
+        fs_mnt.abs_dir_path.add_rel_file(&CUBA_JSON_BAK_REL_PATH);
+    let cuba_json_tmp_abs_path: NPath<Abs, File> =
+        fs_mnt.abs_dir_path.add_rel_file(&CUBA_JSON_TMP_REL_PATH);
 
-    // Write cuba json.
-    match fs_mnt.fs.read().unwrap().write_data(&cuba_json_abs_path) {
-        Ok(writer) => {
-            // Create buf writer.
-            let buf_writer = BufWriter::new(writer);
+    let fs = fs_mnt.fs.read().unwrap();
 
-            // Create encoder.
-            let encoder = GzEncoder::new(buf_writer, Compression::default());
+    // Write the temp file.
+    if let Err(err) = write_file(&*fs, &cuba_json_tmp_abs_path, &data) {
+        send_error!(sender, err);
+        return false;
+    }
 
-            // Write data.
-            match serde_json::to_writer(encoder, transferred_node) {
-                Ok(()) => (),
-                Err(err) => send_error!(sender, err),
-            }
-        }
-        Err(err) => {
-            send_error!(sender, err);
-        }
+    // Verify the temp file, before the previous cuba json is touched.
+    if let Err(err) = verify_file(&*fs, &cuba_json_tmp_abs_path, &data) {
+        send_error!(sender, err);
+        return false;
     }
+
+    // Keep the previous cuba json as backup.
+    if fs.meta(&(&cuba_json_abs_path).into()).is_ok()
+        && let Err(err) = move_file(&*fs, &cuba_json_abs_path, &cuba_json_bak_abs_path)
+    {
+        send_error!(sender, err);
+        return false;
+    }
+
+    // Replace the cuba json by the temp file.
+    if let Err(err) = move_file(&*fs, &cuba_json_tmp_abs_path, &cuba_json_abs_path) {
+        send_error!(sender, err);
+        return false;
+    }
+
+    true
 }
@@ -0,0 +1,188 @@
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Condvar, Mutex};
+
+use crate::shared::config::SignatureAlgo;
+use crate::shared::npath::{Dir, File, NPath, Rel};
+
+use super::transferred_node::{Flags, MaskedFlags, TransferredNodes};
+
+// The dedup store as rel path.
+lazy_static! {
+    pub static ref DEDUP_REL_DIR: NPath<Rel, Dir> =
+        NPath::<Rel, Dir>::try_from("cuba-dedup").unwrap();
+}
+
+/// Defines a `DedupBlob`.
+///
+/// A blob in the dedup store.
+#[derive(Clone)]
+struct DedupBlob {
+    rel_path: NPath<Rel, File>,
+    signature_algo: SignatureAlgo,
+    flags: Flags,
+    password_id: Option<String>,
+}
+
+/// Defines a `DedupBlobs`.
+///
+/// The stored blobs and the signatures of the blobs, which are uploaded.
+#[derive(Default)]
+struct DedupBlobs {
+    stored: HashMap<[u8; 32], DedupBlob>,
+    reserved: HashSet<[u8; 32]>,
+}
+
+/// Defines a `DedupIndex`.
+///
+/// Maps the signatures of the src files to the blobs in the dedup store. A signature is
+/// reserved while its blob is uploaded, so the workers upload the same content only once.
+pub struct DedupIndex {
+    blobs: Mutex<DedupBlobs>,
+    released: Condvar,
+}
+
+/// Defines a `DedupLookup`.
+pub enum DedupLookup<'a> {
+    /// The blob is in the dedup store.
+    Stored(NPath<Rel, File>),
+    /// The caller uploads the blob.
+    Reserved(DedupReservation<'a>),
+}
+
+/// Defines a `DedupReservation`.
+///
+/// The reservation of a signature, which is released if dropped without inserting the blob.
+pub struct DedupReservation<'a> {
+    index: &'a DedupIndex,
+    signature: [u8; 32],
+}
+
+/// Methods of `DedupReservation`.
+impl DedupReservation<'_> {
+    /// Inserts the uploaded blob and releases the reservation.
+    pub fn insert_blob(
+        self,
+        signature_algo: SignatureAlgo,
+        rel_path: &NPath<Rel, File>,
+        flags: Flags,
+        password_id: &Option<String>,
+    ) {
+        self.index.blobs.lock().unwrap().stored.insert(
+            self.signature,
+            DedupBlob {
+                rel_path: rel_path.clone(),
+                signature_algo,
+                flags,
+                password_id: password_id.clone(),
+            },
+        );
+    }
+}
+
+/// Drops the `DedupReservation`.
+impl Drop for DedupReservation<'_> {
+    fn drop(&mut self) {
+        // Wake up the workers, which wait for the blob.
+        self.index
+            .blobs
+            .lock()
+            .unwrap()
+            .reserved
+            .remove(&self.signature);
+        self.index.released.notify_all();
+    }
+}
+
+/// Methods of `DedupIndex`.
+impl DedupIndex {
+    /// Creates a new `DedupIndex` from the blobs referenced by the transferred nodes.
+    pub fn from_transferred_nodes(transferred_nodes: &TransferredNodes) -> Self {
+        let mut blobs = DedupBlobs::default();
+
+        for transferred_node in transferred_nodes.values() {
+            if let (Some(signature), Some(rel_path)) = (
+                transferred_node.src_signature,
+                &transferred_node.dedup_blob_rel_path,
+            ) {
+                blobs.stored.insert(
+                    signature,
+                    DedupBlob {
+                        rel_path: rel_path.clone(),
+                        signature_algo: transferred_node.signature_algo,
+                        flags: transferred_node.flags,
+                        password_id: transferred_node.password_id.clone(),
+                    },
+                );
+            }
+        }
+
+        DedupIndex {
+            blobs: Mutex::new(blobs),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Returns the rel path of a new blob for `signature`.
+    pub fn blob_rel_path(signature: &[u8; 32]) -> NPath<Rel, File> {
+        let file_name = blake3::Hash::from_bytes(*signature).to_hex();
+
+        NPath::<Rel, File>::try_from(format!("{}/{}", DEDUP_REL_DIR.to_unicode(), file_name))
+            .unwrap()
+    }
+
+    /// Returns the dest rel path of a src file, which refers to a blob.
+    ///
+    /// The dest rel path gets the extensions of the blob, so that a restore
+    /// pops them like for a transferred file.
+    pub fn dest_rel_path(
+        src_rel_file_path: &NPath<Rel, File>,
+        blob_rel_path: &NPath<Rel, File>,
+    ) -> NPath<Rel, File> {
+        let mut dest_rel_file_path = src_rel_file_path.clone();
+
+        if let Some(file_name) = blob_rel_path.to_unicode().rsplit('/').next() {
+            for extension in file_name.split('.').skip(1) {
+                dest_rel_file_path.push_extension(extension);
+            }
+        }
+
+        dest_rel_file_path
+    }
+
+    /// Looks up the blob for `signature`, which matches algorithm, flags and password id.
+    ///
+    /// If the blob is not stored, the signature is reserved for the caller. If another worker
+    /// holds the reservation, this waits until it is released.
+    pub fn lookup_blob(
+        &self,
+        signature: &[u8; 32],
+        signature_algo: SignatureAlgo,
+        backup_flags: &MaskedFlags,
+        password_id: &Option<String>,
+    ) -> DedupLookup<'_> {
+        let mut blobs = self.blobs.lock().unwrap();
+
+        while blobs.reserved.contains(signature) {
+            blobs = self.released.wait(blobs).unwrap();
+        }
+
+        let stored_blob = blobs.stored.get(signature).filter(|blob| {
+            signature_algo == blob.signature_algo
+                && backup_flags.matches(blob.flags)
+                && *password_id == blob.password_id
+        });
+
+        match stored_blob {
+            Some(blob) => DedupLookup::Stored(blob.rel_path.clone()),
+            None => {
+                blobs.reserved.insert(*signature);
+
+                DedupLookup::Reserved(DedupReservation {
+                    index: self,
+                    signature: *signature,
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use argon2::Argon2;
+use rand::{RngCore, rng};
+use secrecy::{ExposeSecret, SecretString};
+use thiserror::Error;
+
+use crate::shared::npath::{Dir, File, NPath, NPathError, Rel, Symlink, UNPath};
+
+use super::keyring::KeyringError;
+use super::password_cache::PasswordCache;
+use super::transferred_node::TransferredNodes;
+
+/// The size of the random salt of the key derivation.
+///
+/// The salt is stored in the cuba json, so that the hashed names of unchanged nodes are the
+/// same in each run.
+const KEY_SALT_SIZE: usize = 16;
+
+const NONCE_SIZE: usize = 12; // AES-GCM nonce size
+
+/// Defines a `FilenameCipherError`.
+#[derive(Debug, Error)]
+pub enum FilenameCipherError {
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
+
+    #[error("Encryption of the name {0:?} failed")]
+    Encrypt(String),
+
+    #[error("Decryption of an encrypted name failed, the password may be wrong")]
+    Decrypt,
+
+    #[error("No password id for the encrypted names")]
+    NoPasswordId,
+
+    #[error("The salt of the encrypted names is invalid")]
+    InvalidSalt,
+
+    #[error(transparent)]
+    Password(#[from] KeyringError),
+
+    #[error(transparent)]
+    NPath(#[from] NPathError),
+}
+
+/// Defines a `FilenameCipher`.
+///
+/// Hides the names of a backup. The dest names are replaced by a keyed hash of the src path,
+/// the src paths are stored encrypted in the cuba json.
+pub struct FilenameCipher {
+    hash_key: [u8; 32],
+    cipher: Aes256Gcm,
+    salt: Vec<u8>,
+}
+
+/// Methods of `FilenameCipher`.
+impl FilenameCipher {
+    /// Creates a new `FilenameCipher` with keys derived from `password` and `salt`.
+    pub fn new(password: &SecretString, salt: &[u8]) -> Result<Self, FilenameCipherError> {
+        let mut key_bytes = [0u8; 64];
+
+        Argon2::default()
+            .hash_password_into(password.expose_secret().as_bytes(), salt, &mut key_bytes)
+            .map_err(|err| FilenameCipherError::KeyDerivation(err.to_string()))?;
+
+        let (hash_key, cipher_key) = key_bytes.split_at(32);
+
+        Ok(Self {
+            hash_key: hash_key.try_into().unwrap(),
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(cipher_key)),
+            salt: salt.to_vec(),
+        })
+    }
+
+    /// Creates a new `FilenameCipher` with the password of `password_id`.
+    ///
+    /// The salt is the one of `transferred_nodes`, a backup without salt gets a new random salt.
+    pub fn from_password_id(
+        password_id: &Option<String>,
+        transferred_nodes: &TransferredNodes,
+        password_cache: &mut PasswordCache,
+    ) -> Result<Self, FilenameCipherError> {
+        let password_id = password_id
+            .as_ref()
+            .ok_or(FilenameCipherError::NoPasswordId)?;
+
+        let salt = match transferred_nodes.name_salt() {
+            Some(name_salt) => decode_hex(name_salt).ok_or(FilenameCipherError::InvalidSalt)?,
+            None => {
+                let mut salt = vec![0u8; KEY_SALT_SIZE];
+                rng().fill_bytes(&mut salt);
+                salt
+            }
+        };
+
+        Self::new(password_cache.get_password(password_id)?, &salt)
+    }
+
+    /// Decrypts the names of `transferred_nodes`, if they are encrypted.
+    ///
+    /// The password is the one of the encrypted files.
+    pub fn decrypt_nodes(
+        transferred_nodes: &mut TransferredNodes,
+        password_cache: &mut PasswordCache,
+    ) -> Result<(), FilenameCipherError> {
+        let mut encrypted_nodes = transferred_nodes
+            .values()
+            .filter(|node| node.encrypted_src_path.is_some())
+            .peekable();
+
+        if encrypted_nodes.peek().is_none() {
+            return Ok(());
+        }
+
+        // Only the nodes of files have a password id.
+        let password_id = encrypted_nodes.find_map(|node| node.password_id.clone());
+
+        Self::from_password_id(&password_id, transferred_nodes, password_cache)?
+            .decrypt_names(transferred_nodes)
+    }
+
+    /// Returns the hashed name of `rel_path`.
+    pub fn hash_rel_path<T>(&self, rel_path: &NPath<Rel, T>) -> NPath<Rel, T>
+    where
+        NPath<Rel, T>: TryFrom<String, Error = NPathError>,
+    {
+        let hash = blake3::keyed_hash(&self.hash_key, rel_path.to_nfc().as_bytes());
+
+        NPath::<Rel, T>::try_from(hash.to_hex().to_string()).unwrap()
+    }
+
+    /// Returns the transferred nodes with hashed keys and encrypted src paths, and the salt.
+    pub fn encrypt_names(
+        &self,
+        transferred_nodes: &TransferredNodes,
+    ) -> Result<TransferredNodes, FilenameCipherError> {
+        let mut encrypted_nodes = TransferredNodes::new();
+        encrypted_nodes.set_name_salt(Some(encode_hex(&self.salt)));
+
+        for (src_rel_path, node) in transferred_nodes.iter() {
+            let hashed_rel_path: UNPath<Rel> = match src_rel_path {
+                UNPath::File(path) => self.hash_rel_path(path).into(),
+                UNPath::Dir(path) => self.hash_rel_path(path).into(),
+                UNPath::Symlink(path) => self.hash_rel_path(path).into(),
+            };
+
+            let mut encrypted_node = node.clone();
+            encrypted_node.encrypted_src_path = Some(self.encrypt(src_rel_path.to_unicode())?);
+
+            encrypted_nodes.insert(hashed_rel_path, encrypted_node);
+        }
+
+        Ok(encrypted_nodes)
+    }
+
+    /// Replaces the hashed keys of `transferred_nodes` by the decrypted src paths.
+    pub fn decrypt_names(
+        &self,
+        transferred_nodes: &mut TransferredNodes,
+    ) -> Result<(), FilenameCipherError> {
+        let mut decrypted_nodes = TransferredNodes::new();
+        decrypted_nodes.set_name_salt(Some(encode_hex(&self.salt)));
+
+        for (rel_path, mut node) in transferred_nodes.drain() {
+            let src_rel_path = match node.encrypted_src_path.take() {
+                Some(encrypted_src_path) => {
+                    let src_path = self.decrypt(&encrypted_src_path)?;
+
+                    match rel_path {
+                        UNPath::File(_) => NPath::<Rel, File>::try_from(src_path)?.into(),
+                        UNPath::Dir(_) => NPath::<Rel, Dir>::try_from(src_path)?.into(),
+                        UNPath::Symlink(_) => NPath::<Rel, Symlink>::try_from(src_path)?.into(),
+                    }
+                }
+                None => rel_path,
+            };
+
+            decrypted_nodes.insert(src_rel_path, node);
+        }
+
+        *transferred_nodes = decrypted_nodes;
+
+        Ok(())
+    }
+
+    /// Encrypts `name`, the result is the hex encoded nonce and ciphertext.
+    fn encrypt(&self, name: &str) -> Result<String, FilenameCipherError> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        rng().fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), name.as_bytes())
+            .map_err(|_| FilenameCipherError::Encrypt(name.to_string()))?;
+
+        Ok(encode_hex(&[nonce.as_slice(), &ciphertext].concat()))
+    }
+
+    /// Decrypts a name, which was encrypted by `encrypt`.
+    fn decrypt(&self, encrypted: &str) -> Result<String, FilenameCipherError> {
+        let bytes = decode_hex(encrypted).ok_or(FilenameCipherError::Decrypt)?;
+
+        if bytes.len() < NONCE_SIZE {
+            return Err(FilenameCipherError::Decrypt);
+        }
+
+        let (nonce, ciphertext) = bytes.split_at(NONCE_SIZE);
+
+        let name = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| FilenameCipherError::Decrypt)?;
+
+        String::from_utf8(name).map_err(|_| FilenameCipherError::Decrypt)
+    }
+}
+
+/// Encodes `bytes` as hex string.
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decodes a hex string, returns `None` if it is not valid hex.
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
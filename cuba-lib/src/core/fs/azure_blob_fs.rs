@@ -0,0 +1,424 @@
+use azure_core::StatusCode;
+use azure_core::error::ErrorKind;
+use azure_storage::StorageCredentials;
+use azure_storage_blobs::prelude::{
+    BlobBlockType, BlockId, BlockList, ClientBuilder, ContainerClient,
+};
+use crossbeam_channel::{Receiver, bounded};
+use futures::StreamExt;
+use secrecy::{ExposeSecret, SecretString};
+use std::error::Error;
+use std::io::{Read, pipe};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::thread;
+use std::time::SystemTime;
+use tokio::runtime::Runtime;
+
+use crate::core::fs::fs_metadata::FSMetaData;
+use crate::core::fs::fs_symlink_meta::FSSymlinkMeta;
+use crate::shared::npath::{Abs, Dir, File, NPath, Rel, Symlink, UNPath};
+
+use super::fs_base::{FS, FSBlockSize, FSError, FSWrite};
+
+/// The size of a block of a block blob.
+const BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+/// The number of chunks buffered by a `AzureBlobRead`.
+const READ_BUFFER_CHUNKS: usize = 4;
+
+/// Returns the blob name of an abs path.
+fn blob_name(unicode: &str) -> String {
+    unicode.trim_start_matches('/').to_string()
+}
+
+/// Returns the blob prefix of an abs dir path.
+///
+/// Directories are emulated by the prefix convention `dir/`. The root has an empty prefix.
+fn blob_prefix(abs_dir_path: &NPath<Abs, Dir>) -> String {
+    let name = blob_name(abs_dir_path.to_unicode());
+
+    if name.is_empty() { name } else { name + "/" }
+}
+
+/// Returns true if the error is a `404 Not Found` response.
+fn is_not_found(err: &azure_core::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::HttpResponse {
+            status: StatusCode::NotFound,
+            ..
+        }
+    )
+}
+
+/// Reads up to `buf.len()` bytes, until `buf` is full or the end is reached.
+fn read_block(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+
+    Ok(filled)
+}
+
+/// Defines a `AzureBlobRead`.
+///
+/// A reader of the chunks, which are downloaded by a thread.
+struct AzureBlobRead {
+    receiver: Receiver<std::io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+/// Impl of `Read` for `AzureBlobRead`.
+impl Read for AzureBlobRead {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos >= self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Ok(Err(err)) => return Err(err),
+                // All chunks are read.
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+/// Defines a `AzureBlobFS`.
+///
+/// Azure Blob Storage has no directories. They are emulated via the blob prefix `dir/` and an
+/// empty marker blob `dir/`, so that empty directories persist.
+pub struct AzureBlobFS {
+    account: String,
+    container: String,
+    password: SecretString,
+    sas_token: bool,
+    runtime: Option<Arc<Runtime>>,
+    container_client: Option<ContainerClient>,
+}
+
+/// Methods of `AzureBlobFS`.
+impl AzureBlobFS {
+    pub fn new(account: &str, container: &str, password: &SecretString, sas_token: bool) -> Self {
+        AzureBlobFS {
+            account: account.to_owned(),
+            container: container.to_owned(),
+            password: password.to_owned(),
+            sas_token,
+            runtime: None,
+            container_client: None,
+        }
+    }
+
+    /// Returns the runtime and the container client, if connected.
+    fn client(&self) -> Result<(&Arc<Runtime>, &ContainerClient), FSError> {
+        match (&self.runtime, &self.container_client) {
+            (Some(runtime), Some(container_client)) => Ok((runtime, container_client)),
+            _ => Err(FSError::NotConnected),
+        }
+    }
+
+    /// Creates the storage credentials from the account key or the SAS token.
+    fn credentials(&self) -> azure_core::Result<StorageCredentials> {
+        if self.sas_token {
+            StorageCredentials::sas_token(self.password.expose_secret())
+        } else {
+            Ok(StorageCredentials::access_key(
+                self.account.clone(),
+                self.password.expose_secret().to_string(),
+            ))
+        }
+    }
+
+    /// Returns true if a blob with the prefix exists.
+    fn prefix_exists(&self, prefix: &str) -> Result<bool, Box<dyn Error + Send + Sync + 'static>> {
+        let (runtime, container_client) = self.client()?;
+
+        runtime.block_on(async {
+            let mut pages = container_client
+                .list_blobs()
+                .prefix(prefix.to_string())
+                .max_results(NonZeroU32::new(1).unwrap())
+                .into_stream();
+
+            match pages.next().await {
+                Some(page) => Ok(page?.blobs.blobs().next().is_some()),
+                None => Ok(false),
+            }
+        })
+    }
+}
+
+/// Impl of `FS` for `AzureBlobFS`.
+impl FS for AzureBlobFS {
+    fn connect(&mut self) -> Result<(), FSError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .map_err(|err| FSError::ConnectionFailed(err.into()))?;
+
+        let credentials = self
+            .credentials()
+            .map_err(|err| FSError::ConnectionFailed(err.into()))?;
+
+        let container_client =
+            ClientBuilder::new(self.account.clone(), credentials).container_client(&self.container);
+
+        // Check, that the container is accessible.
+        runtime
+            .block_on(async { container_client.get_properties().await })
+            .map_err(|err| FSError::ConnectionFailed(err.into()))?;
+
+        self.runtime = Some(Arc::new(runtime));
+        self.container_client = Some(container_client);
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), FSError> {
+        if !self.is_connected() {
+            return Err(FSError::NotConnected);
+        }
+
+        self.container_client = None;
+        self.runtime = None;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.container_client.is_some()
+    }
+
+    fn block_size(&self) -> FSBlockSize {
+        FSBlockSize::new(None, 128 * 1024, None)
+    }
+
+    fn meta(&self, abs_path: &UNPath<Abs>) -> Result<FSMetaData, FSError> {
+        let (runtime, container_client) = self.client()?;
+
+        match abs_path {
+            UNPath::File(abs_file_path) => {
+                let blob_client =
+                    container_client.blob_client(blob_name(abs_file_path.to_unicode()));
+
+                let response = runtime
+                    .block_on(async { blob_client.get_properties().await })
+                    .map_err(|err| FSError::MetaFailed(abs_path.clone(), err.into()))?;
+
+                let properties = response.blob.properties;
+
+                Ok(FSMetaData::new(
+                    Some(SystemTime::from(properties.creation_time)),
+                    Some(SystemTime::from(properties.last_modified)),
+                    Some(properties.content_length),
+                    None,
+                ))
+            }
+            UNPath::Dir(abs_dir_path) => {
+                let prefix = blob_prefix(abs_dir_path);
+
+                // The root always exists.
+                if prefix.is_empty()
+                    || self
+                        .prefix_exists(&prefix)
+                        .map_err(|err| FSError::MetaFailed(abs_path.clone(), err))?
+                {
+                    Ok(FSMetaData::new(None, None, None, None))
+                } else {
+                    Err(FSError::MetaFailed(
+                        abs_path.clone(),
+                        "Path does not exist".into(),
+                    ))
+                }
+            }
+            UNPath::Symlink(_abs_sym_path) => Err(FSError::MetaFailed(
+                abs_path.clone(),
+                "Symlinks are not supported by Azure Blob Storage".into(),
+            )),
+        }
+    }
+
+    fn list_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<Vec<UNPath<Abs>>, FSError> {
+        let (runtime, container_client) = self.client()?;
+        let prefix = blob_prefix(abs_dir_path);
+
+        let result: Result<Vec<UNPath<Abs>>, Box<dyn Error + Send + Sync>> =
+            runtime.block_on(async {
+                let mut entries = Vec::new();
+
+                let mut pages = container_client
+                    .list_blobs()
+                    .prefix(prefix.clone())
+                    .delimiter("/")
+                    .into_stream();
+
+                while let Some(page) = pages.next().await {
+                    let page = page?;
+
+                    for blob in page.blobs.blobs() {
+                        // Skip the directory marker.
+                        if blob.name == prefix {
+                            continue;
+                        }
+
+                        let rel_file_path =
+                            NPath::<Rel, File>::try_from(&blob.name[prefix.len()..])?;
+                        entries.push(UNPath::File(abs_dir_path.add_rel_file(&rel_file_path)));
+                    }
+
+                    for blob_prefix in page.blobs.prefixes() {
+                        let rel_dir_path = NPath::<Rel, Dir>::try_from(
+                            blob_prefix.name[prefix.len()..].trim_end_matches('/'),
+                        )?;
+                        entries.push(UNPath::Dir(abs_dir_path.add_rel_dir(&rel_dir_path)));
+                    }
+                }
+
+                Ok(entries)
+            });
+
+        result.map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err))
+    }
+
+    fn remove_file(&self, abs_file_path: &NPath<Abs, File>) -> Result<(), FSError> {
+        let (runtime, container_client) = self.client()?;
+        let blob_client = container_client.blob_client(blob_name(abs_file_path.to_unicode()));
+
+        runtime
+            .block_on(async { blob_client.delete().await })
+            .map(|_response| ())
+            .map_err(|err| FSError::RemoveFileFailed(abs_file_path.clone(), err.into()))
+    }
+
+    fn remove_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        let (runtime, container_client) = self.client()?;
+        let blob_client = container_client.blob_client(blob_prefix(abs_dir_path));
+
+        // A directory without marker only exists by its blobs.
+        match runtime.block_on(async { blob_client.delete().await }) {
+            Ok(_response) => Ok(()),
+            Err(err) if is_not_found(&err) => Ok(()),
+            Err(err) => Err(FSError::RemoveDirFailed(abs_dir_path.clone(), err.into())),
+        }
+    }
+
+    fn mkdir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        let (runtime, container_client) = self.client()?;
+        let blob_client = container_client.blob_client(blob_prefix(abs_dir_path));
+
+        runtime
+            .block_on(async { blob_client.put_block_blob(Vec::new()).await })
+            .map(|_response| ())
+            .map_err(|err| FSError::MkDirFailed(abs_dir_path.clone(), err.into()))
+    }
+
+    fn mklink(
+        &self,
+        _abs_sym_path: &NPath<Abs, Symlink>,
+        _symlink_meta: &FSSymlinkMeta,
+    ) -> Result<(), FSError> {
+        self.client()?;
+
+        Err(FSError::NotSupported(
+            "Symlinks are not supported by Azure Blob Storage".into(),
+        ))
+    }
+
+    fn read_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<Box<dyn Read + Send>, FSError> {
+        let (runtime, container_client) = self.client()?;
+        let blob_client = container_client.blob_client(blob_name(abs_file_path.to_unicode()));
+
+        // Fail early, if the blob does not exist.
+        runtime
+            .block_on(async { blob_client.get_properties().await })
+            .map_err(|err| FSError::ReadFailed(abs_file_path.clone(), err.into()))?;
+
+        let runtime = runtime.clone();
+        let (sender, receiver) = bounded(READ_BUFFER_CHUNKS);
+
+        thread::spawn(move || {
+            runtime.block_on(async {
+                let mut pages = blob_client.get().into_stream();
+
+                while let Some(page) = pages.next().await {
+                    let chunk = match page {
+                        Ok(page) => page.data.collect().await,
+                        Err(err) => Err(err),
+                    };
+
+                    let chunk = chunk
+                        .map(|bytes| bytes.to_vec())
+                        .map_err(std::io::Error::other);
+                    let failed = chunk.is_err();
+
+                    // Stop, if the reader is dropped or the download failed.
+                    if sender.send(chunk).is_err() || failed {
+                        break;
+                    }
+                }
+            });
+        });
+
+        Ok(Box::new(AzureBlobRead {
+            receiver,
+            chunk: Vec::new(),
+            pos: 0,
+        }))
+    }
+
+    fn write_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<FSWrite, FSError> {
+        let (runtime, container_client) = self.client()?;
+        let blob_client = container_client.blob_client(blob_name(abs_file_path.to_unicode()));
+        let runtime = runtime.clone();
+
+        let (mut reader, writer) =
+            pipe().map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))?;
+
+        // Stream the data as blocks and commit the block list at the end.
+        let thread_handle = thread::spawn(move || {
+            let result: Result<(), Box<dyn Error + Send + Sync>> = runtime.block_on(async {
+                let mut block_list = BlockList::default();
+                let mut buf = vec![0u8; BLOCK_SIZE];
+
+                loop {
+                    let len = read_block(&mut reader, &mut buf)?;
+
+                    if len == 0 {
+                        break;
+                    }
+
+                    let block_id = BlockId::new(format!("{:08}", block_list.blocks.len()));
+
+                    blob_client
+                        .put_block(block_id.clone(), buf[..len].to_vec())
+                        .await?;
+
+                    block_list
+                        .blocks
+                        .push(BlobBlockType::new_uncommitted(block_id));
+                }
+
+                blob_client.put_block_list(block_list).await?;
+                Ok(())
+            });
+
+            result.map_err(std::io::Error::other)
+        });
+
+        Ok(FSWrite::new(Box::new(writer), Some(thread_handle)))
+    }
+}
@@ -1,12 +1,17 @@
+use crossbeam_channel::unbounded;
 use std::error::Error;
 use std::io::{Read, Write};
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
+use std::thread;
 use std::thread::JoinHandle;
 use thiserror::Error;
 
 use crate::core::fs::fs_metadata::FSMetaData;
 use crate::core::fs::fs_symlink_meta::FSSymlinkMeta;
+use crate::core::run_state::{ConnectionStatus, RunState};
 use crate::shared::npath::{Abs, Dir, File, NPath, Symlink, UNPath};
+use crate::shared::rich_error::RichError;
 
 pub type FSHandle = Arc<RwLock<dyn FS>>;
 
@@ -21,6 +26,46 @@ impl FSMount {
     pub fn new(fs: FSHandle, abs_dir_path: Arc<NPath<Abs, Dir>>) -> Self {
         FSMount { fs, abs_dir_path }
     }
+
+    /// Connects the fs, if it is not connected.
+    pub fn ensure_connected(&self) -> Result<(), FSError> {
+        if !self.fs.read().unwrap().is_connected() {
+            self.fs.write().unwrap().connect()?;
+        }
+
+        Ok(())
+    }
+
+    /// Connects the fs like `ensure_connected` and reports the status with `set_status`.
+    pub fn ensure_connected_with_status(
+        &self,
+        set_status: impl Fn(ConnectionStatus),
+    ) -> Result<(), FSError> {
+        set_status(ConnectionStatus::Connecting);
+
+        let result = self.ensure_connected();
+
+        set_status(match result {
+            Ok(_) => ConnectionStatus::Connected,
+            Err(_) => ConnectionStatus::Error,
+        });
+
+        result
+    }
+
+    /// Tests the connection.
+    ///
+    /// Connects the fs, lists the directory of the mount and disconnects. Returns the first error.
+    pub fn test_connection(&self) -> Result<(), FSError> {
+        let mut fs = self.fs.write().unwrap();
+
+        fs.connect()?;
+
+        let listed = fs.list_dir(&self.abs_dir_path).map(|_| ());
+        let disconnected = fs.disconnect();
+
+        listed.and(disconnected)
+    }
 }
 
 impl Clone for FSMount {
@@ -47,16 +92,13 @@ impl FSConnection {
     }
 
     /// Opens the connection. This means to ensure both file systems are connected.
-    pub fn open(&self) -> Result<(), FSError> {
-        if !self.src_mnt.fs.read().unwrap().is_connected() {
-            self.src_mnt.fs.write().unwrap().connect()?;
-        }
-
-        if !self.dest_mnt.fs.read().unwrap().is_connected() {
-            self.dest_mnt.fs.write().unwrap().connect()?;
-        }
-
-        Ok(())
+    ///
+    /// The connection status of both file systems is tracked in `run_state`.
+    pub fn open(&self, run_state: &RunState) -> Result<(), FSError> {
+        self.src_mnt
+            .ensure_connected_with_status(|status| run_state.set_src_status(status))?;
+        self.dest_mnt
+            .ensure_connected_with_status(|status| run_state.set_dest_status(status))
     }
 
     /// Closes the connection. This means to ensure both file systems are disconnected.
@@ -140,31 +182,107 @@ impl FSBlockSize {
 }
 
 /// Defines a writer for the fs.
+///
+/// If a temp path and a final path are set, the data is written to the temp path and renamed to
+/// the final path by [`FSWrite::finish`]. If the `FSWrite` is dropped without `finish`, the temp
+/// file is removed.
+///
+/// A writing thread returns the result of the upload, an error fails [`FSWrite::finish`].
 pub struct FSWrite {
     writer: Option<Box<dyn Write + Send>>,
-    thread_handle: Option<JoinHandle<()>>,
+    thread_handle: Option<JoinHandle<std::io::Result<()>>>,
+    temp_path: Option<PathBuf>,
+    final_path: Option<PathBuf>,
+    sync_file: Option<std::fs::File>,
+    bytes_written: u64,
 }
 
 impl FSWrite {
     /// Creates a new `FSWrite`.
-    pub fn new(writer: Box<dyn Write + Send>, thread_handle: Option<JoinHandle<()>>) -> Self {
+    pub fn new(
+        writer: Box<dyn Write + Send>,
+        thread_handle: Option<JoinHandle<std::io::Result<()>>>,
+    ) -> Self {
         FSWrite {
             writer: Some(writer),
             thread_handle,
+            temp_path: None,
+            final_path: None,
+            sync_file: None,
+            bytes_written: 0,
         }
     }
 
+    /// With temp path, which is renamed to final path on finish.
+    pub fn with_rename(mut self, temp_path: PathBuf, final_path: PathBuf) -> Self {
+        self.temp_path = Some(temp_path);
+        self.final_path = Some(final_path);
+        self
+    }
+
+    /// With the written file, which is synced to the disk by [`FSWrite::finish_synced`].
+    pub fn with_sync_file(mut self, sync_file: std::fs::File) -> Self {
+        self.sync_file = Some(sync_file);
+        self
+    }
+
+    /// Returns the number of bytes written so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
     /// Finishes the `FSWrite`.
-    pub fn finish(mut self) {
-        // Close the write side
+    pub fn finish(self) -> std::io::Result<()> {
+        self.finish_with(false)
+    }
+
+    /// Finishes the `FSWrite`, a written file is synced to the disk before the rename.
+    pub fn finish_synced(self) -> std::io::Result<()> {
+        self.finish_with(true)
+    }
+
+    /// Finishes the `FSWrite`, optionally synced.
+    fn finish_with(mut self, synced: bool) -> std::io::Result<()> {
+        // Close the write side.
+        self.close()?;
+
+        // Sync the data, so that the rename does not replace a file by an unwritten one.
+        if synced && let Some(sync_file) = self.sync_file.take() {
+            sync_file.sync_all()?;
+        }
+
+        // Rename temp path to final path.
+        if let (Some(temp_path), Some(final_path)) = (self.temp_path.take(), self.final_path.take())
+            && let Err(err) = std::fs::rename(&temp_path, &final_path)
+        {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Closes the write side and waits for the writing thread.
+    fn close(&mut self) -> std::io::Result<()> {
+        let mut result = Ok(());
+
         if let Some(mut writer) = self.writer.take() {
-            let _ = writer.flush();
+            result = writer.flush();
             // Dropping happens here when it goes out of scope
         }
 
+        // Wait for the writing thread, its error is reported, if the flush succeeded.
         if let Some(handle) = self.thread_handle.take() {
-            let _ = handle.join();
+            let joined = handle
+                .join()
+                .unwrap_or_else(|_| Err(std::io::Error::other("The writing thread panicked")));
+
+            if result.is_ok() {
+                result = joined;
+            }
         }
+
+        result
     }
 }
 
@@ -172,7 +290,9 @@ impl FSWrite {
 impl Write for FSWrite {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         if let Some(writer) = self.writer.as_mut() {
-            writer.write(buf)
+            let written = writer.write(buf)?;
+            self.bytes_written += written as u64;
+            Ok(written)
         } else {
             Err(std::io::Error::new(
                 std::io::ErrorKind::BrokenPipe,
@@ -196,14 +316,12 @@ impl Write for FSWrite {
 /// Drops the `FSWrite`.
 impl Drop for FSWrite {
     fn drop(&mut self) {
-        // Close the write side
-        if let Some(mut writer) = self.writer.take() {
-            let _ = writer.flush();
-            // Dropping happens here when it goes out of scope
-        }
+        // Close the write side.
+        let _ = self.close();
 
-        if let Some(handle) = self.thread_handle.take() {
-            let _ = handle.join();
+        // Not finished, remove the temp file.
+        if let Some(temp_path) = self.temp_path.take() {
+            let _ = std::fs::remove_file(temp_path);
         }
     }
 }
@@ -221,8 +339,8 @@ pub enum FSError {
     NotConnected,
 
     /// Error when the operation is not supported.
-    #[error("Operation not supported.")]
-    NotSupported,
+    #[error("Operation not supported: {0}")]
+    NotSupported(String),
 
     /// Error when the metadata of the file, directory or symlink cannot be retreived, including the destination path.
     #[error("Failed to retrieve meta data {0:?}")]
@@ -255,6 +373,76 @@ pub enum FSError {
     /// Error when writing data to a file fails, including the destination file path.
     #[error("Failed to write data to file {0:?}")]
     WriteFailed(NPath<Abs, File>, #[source] Box<dyn Error + Send + Sync>),
+
+    /// Error when a file cannot be renamed, including the source file path.
+    #[error("Failed to rename file {0:?}")]
+    RenameFailed(NPath<Abs, File>, #[source] Box<dyn Error + Send + Sync>),
+
+    /// Error when the metadata of a file, directory or symlink cannot be set, including the destination path.
+    #[error("Failed to set meta data {0:?}")]
+    SetMetaFailed(UNPath<Abs>, #[source] Box<dyn Error + Send + Sync>),
+}
+
+/// Methods of `FSError`.
+impl FSError {
+    /// Returns the name of the failed operation.
+    pub fn operation(&self) -> &'static str {
+        match self {
+            FSError::ConnectionFailed(_) => "connect",
+            FSError::NotConnected => "connect",
+            FSError::NotSupported(_) => "unsupported",
+            FSError::MetaFailed(..) => "meta",
+            FSError::ListDirFailed(..) => "list_dir",
+            FSError::RemoveFileFailed(..) => "remove_file",
+            FSError::RemoveDirFailed(..) => "remove_dir",
+            FSError::MkDirFailed(..) => "mkdir",
+            FSError::MkLinkFailed(..) => "mklink",
+            FSError::ReadFailed(..) => "read_data",
+            FSError::WriteFailed(..) => "write_data",
+            FSError::RenameFailed(..) => "rename_file",
+            FSError::SetMetaFailed(..) => "set_meta",
+        }
+    }
+
+    /// Returns the abs path of the failed operation (if any).
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            FSError::ConnectionFailed(_) | FSError::NotConnected | FSError::NotSupported(_) => None,
+            FSError::MetaFailed(abs_path, _) => Some(abs_path.to_unicode()),
+            FSError::ListDirFailed(abs_dir_path, _) => Some(abs_dir_path.to_unicode()),
+            FSError::RemoveFileFailed(abs_file_path, _) => Some(abs_file_path.to_unicode()),
+            FSError::RemoveDirFailed(abs_dir_path, _) => Some(abs_dir_path.to_unicode()),
+            FSError::MkDirFailed(abs_dir_path, _) => Some(abs_dir_path.to_unicode()),
+            FSError::MkLinkFailed(abs_sym_path, _) => Some(abs_sym_path.to_unicode()),
+            FSError::ReadFailed(abs_file_path, _) => Some(abs_file_path.to_unicode()),
+            FSError::WriteFailed(abs_file_path, _) => Some(abs_file_path.to_unicode()),
+            FSError::RenameFailed(abs_file_path, _) => Some(abs_file_path.to_unicode()),
+            FSError::SetMetaFailed(abs_path, _) => Some(abs_path.to_unicode()),
+        }
+    }
+}
+
+/// Wraps an error in a `RichError`, which takes over the operation and path of a `FSError`.
+pub fn rich_error(error: Arc<dyn Error + Send + Sync>) -> RichError {
+    let (operation, path) = match error.downcast_ref::<FSError>() {
+        Some(fs_error) => (
+            Some(fs_error.operation()),
+            fs_error.path().map(|path| path.to_string()),
+        ),
+        None => (None, None),
+    };
+
+    let mut rich_error = RichError::new(error);
+
+    if let Some(operation) = operation {
+        rich_error = rich_error.with_operation(operation);
+    }
+
+    if let Some(path) = path {
+        rich_error = rich_error.with_path(&path);
+    }
+
+    rich_error
 }
 
 /// Defines the interface (trait) that a fs must implement.
@@ -358,6 +546,90 @@ pub trait FS: Send + Sync {
         Ok(())
     }
 
+    /// Walks through a directory recursively like `walk_dir_rec`, but lists the directories
+    /// in parallel.
+    ///
+    /// The listings are dispatched to a pool of `threads` workers, the callbacks are executed
+    /// on the calling thread. The entries are not in the order of the tree.
+    ///
+    /// # Arguments
+    ///
+    /// - `abs_dir_path` - The root path where traversal starts.
+    /// - `threads` - The number of workers, which list the directories.
+    /// - `callback` - A function that will be executed for each encountered file or directory.
+    ///
+    /// If callback returns true on a directory, walk continues traversing the directory.
+    /// `error_callback` - A function that will be executed for each encountered error.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`FSError::NotConnected`] when the fs is not connected.
+    fn walk_dir_rec_parallel(
+        &self,
+        abs_dir_path: &NPath<Abs, Dir>,
+        threads: usize,
+        callback: &mut dyn FnMut(UNPath<Abs>) -> bool,
+        error_callback: &dyn Fn(FSError),
+    ) -> Result<(), FSError> {
+        if !self.is_connected() {
+            return Err(FSError::NotConnected);
+        }
+
+        let (dir_sender, dir_receiver) = unbounded::<NPath<Abs, Dir>>();
+        let (listing_sender, listing_receiver) = unbounded::<Result<Vec<UNPath<Abs>>, FSError>>();
+
+        thread::scope(|scope| {
+            // Start the workers, they list the directories until the dir sender is dropped.
+            for _ in 0..threads.max(1) {
+                let dir_receiver = dir_receiver.clone();
+                let listing_sender = listing_sender.clone();
+
+                scope.spawn(move || {
+                    for abs_dir_path in dir_receiver {
+                        if listing_sender.send(self.list_dir(&abs_dir_path)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            // The number of directories, which are listed by the workers.
+            let mut pending: usize = 1;
+            dir_sender.send(abs_dir_path.clone()).unwrap();
+
+            while pending > 0 {
+                let listing = listing_receiver.recv().unwrap();
+                pending -= 1;
+
+                match listing {
+                    Ok(entries) => {
+                        for abs_path in entries {
+                            match &abs_path {
+                                UNPath::Dir(abs_dir_path) => {
+                                    if callback(abs_path.clone()) {
+                                        dir_sender.send(abs_dir_path.clone()).unwrap();
+                                        pending += 1;
+                                    }
+                                }
+                                _ => {
+                                    callback(abs_path);
+                                }
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        error_callback(err);
+                    }
+                }
+            }
+
+            // Stop the workers.
+            drop(dir_sender);
+        });
+
+        Ok(())
+    }
+
     /// Removes the file at the specified `abs_file_path`.
     ///
     /// # Errors
@@ -411,4 +683,51 @@ pub trait FS: Send + Sync {
     /// - Returns [`FSError::NotConnected`] when the fs is not connected.
     /// - Returns [`FSError::WriteFailed`] when `write_data` failed.
     fn write_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<FSWrite, FSError>;
+
+    /// Copies the file `src_abs_file_path` to `dest_abs_file_path` on the server side,
+    /// without transferring the data.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`FSError::NotSupported`] when the fs can't copy the file, which is the default.
+    /// - Returns [`FSError::NotConnected`] when the fs is not connected.
+    /// - Returns [`FSError::WriteFailed`] when `copy_file` failed.
+    fn copy_file(
+        &self,
+        _src_abs_file_path: &NPath<Abs, File>,
+        _dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        Err(FSError::NotSupported(
+            "Files can't be copied on the server side".into(),
+        ))
+    }
+
+    /// Renames the file `src_abs_file_path` to `dest_abs_file_path` on the server side,
+    /// an existing destination file is replaced.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`FSError::NotSupported`] when the fs can't rename the file, which is the default.
+    /// - Returns [`FSError::NotConnected`] when the fs is not connected.
+    /// - Returns [`FSError::RenameFailed`] when `rename_file` failed.
+    fn rename_file(
+        &self,
+        _src_abs_file_path: &NPath<Abs, File>,
+        _dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        Err(FSError::NotSupported(
+            "Files can't be renamed on the server side".into(),
+        ))
+    }
+
+    /// Sets the modified time and the permission bits of `metadata` to `abs_path`.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`FSError::NotSupported`] when the fs can't set metadata, which is the default.
+    /// - Returns [`FSError::NotConnected`] when the fs is not connected.
+    /// - Returns [`FSError::SetMetaFailed`] when `set_meta` failed.
+    fn set_meta(&self, _abs_path: &UNPath<Abs>, _metadata: &FSMetaData) -> Result<(), FSError> {
+        Err(FSError::NotSupported("Metadata can't be set".into()))
+    }
 }
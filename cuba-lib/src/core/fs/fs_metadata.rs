@@ -14,6 +14,9 @@ pub struct FSMetaData {
     /// Size in bytes.
     pub size: Option<u64>,
 
+    /// Permission bits (Unix only).
+    pub mode: Option<u32>,
+
     // Symlink meta.
     pub symlink_meta: Option<FSSymlinkMeta>,
 }
@@ -31,7 +34,14 @@ impl FSMetaData {
             created,
             modified,
             size,
+            mode: None,
             symlink_meta,
         }
     }
+
+    /// Sets the permission bits.
+    pub fn with_mode(mut self, mode: Option<u32>) -> Self {
+        self.mode = mode;
+        self
+    }
 }
@@ -18,6 +18,10 @@ pub struct FSSymlinkMeta {
 
     /// Target type.
     pub target_type: FSSymlinkType,
+
+    /// Permission bits (Unix only).
+    #[serde(default)]
+    pub mode: Option<u32>,
 }
 
 /// Methods for `FSSymlinkMeta`.
@@ -27,6 +31,13 @@ impl FSSymlinkMeta {
         Self {
             target_path,
             target_type,
+            mode: None,
         }
     }
+
+    /// Sets the permission bits.
+    pub fn with_mode(mut self, mode: Option<u32>) -> Self {
+        self.mode = mode;
+        self
+    }
 }
@@ -0,0 +1,440 @@
+use secrecy::{ExposeSecret, SecretString};
+use std::error::Error;
+use std::io::{Read, pipe};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use suppaftp::list::File as ListFile;
+use suppaftp::native_tls::TlsConnector;
+use suppaftp::types::FileType;
+use suppaftp::{FtpError, Mode, NativeTlsConnector, NativeTlsFtpStream, Status};
+
+use crate::core::fs::fs_metadata::FSMetaData;
+use crate::core::fs::fs_symlink_meta::{FSSymlinkMeta, FSSymlinkType};
+use crate::shared::npath::{Abs, Dir, File, NPath, NPathError, Rel, Symlink, UNPath};
+
+use super::fs_base::{FS, FSBlockSize, FSError, FSWrite};
+
+/// Returns the path on the server. An empty path is the root.
+fn ftp_path(unicode: &str) -> &str {
+    if unicode.is_empty() { "/" } else { unicode }
+}
+
+/// Lists the entries of a directory.
+///
+/// `MLSD` is preferred, since its format is standardized. If the server does not support it,
+/// the `LIST` response is parsed.
+fn list_entries(stream: &mut NativeTlsFtpStream, path: &str) -> Result<Vec<ListFile>, FtpError> {
+    let entries: Vec<ListFile> = match stream.mlsd(Some(path)) {
+        Ok(lines) => lines
+            .iter()
+            .filter_map(|line| ListFile::from_mlsx_line(line).ok())
+            .collect(),
+        Err(FtpError::UnexpectedResponse(_)) => stream
+            .list(Some(path))?
+            .iter()
+            .filter_map(|line| ListFile::try_from(line.as_str()).ok())
+            .collect(),
+        Err(err) => return Err(err),
+    };
+
+    // Skip the current and parent directory.
+    Ok(entries
+        .into_iter()
+        .filter(|entry| entry.name() != "." && entry.name() != "..")
+        .collect())
+}
+
+/// Reads the entry of a path by `MLST`.
+///
+/// Returns `None`, if the server does not support `MLST` or the entry can't be parsed. Then
+/// the entry must be looked up in the listing of the parent.
+fn mlst_entry(
+    stream: &mut NativeTlsFtpStream,
+    path: &str,
+    mlst_supported: &AtomicBool,
+) -> Result<Option<ListFile>, FtpError> {
+    match stream.mlst(Some(path)) {
+        Ok(line) => Ok(ListFile::from_mlsx_line(&line).ok()),
+        Err(FtpError::UnexpectedResponse(response))
+            if matches!(response.status, Status::BadCommand | Status::NotImplemented) =>
+        {
+            // The server does not know `MLST`, don't try again.
+            mlst_supported.store(false, Ordering::Relaxed);
+            Ok(None)
+        }
+        Err(FtpError::UnexpectedResponse(response))
+            if matches!(
+                response.status,
+                Status::BadArguments | Status::NotImplementedParameter
+            ) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Makes an abs path from a list entry.
+fn make_abs_path_from_entry(
+    abs_dir_path: &NPath<Abs, Dir>,
+    entry: &ListFile,
+) -> Result<UNPath<Abs>, NPathError> {
+    let name = entry.name();
+
+    if entry.is_directory() {
+        let rel_dir_path = NPath::<Rel, Dir>::try_from(name)?;
+        Ok(UNPath::Dir(abs_dir_path.add_rel_dir(&rel_dir_path)))
+    } else if entry.is_symlink() {
+        let rel_sym_path = NPath::<Rel, Symlink>::try_from(name)?;
+        Ok(UNPath::Symlink(abs_dir_path.add_rel_symlink(&rel_sym_path)))
+    } else {
+        let rel_file_path = NPath::<Rel, File>::try_from(name)?;
+        Ok(UNPath::File(abs_dir_path.add_rel_file(&rel_file_path)))
+    }
+}
+
+/// Makes the metadata from a list entry.
+fn make_metadata_from_entry(entry: &ListFile) -> FSMetaData {
+    let size = if entry.is_directory() {
+        None
+    } else {
+        Some(entry.size() as u64)
+    };
+
+    let symlink_meta = entry
+        .symlink()
+        .map(|target| FSSymlinkMeta::new(target.to_path_buf(), FSSymlinkType::Unknown));
+
+    FSMetaData::new(None, Some(entry.modified()), size, symlink_meta)
+}
+
+/// Defines a `FtpSettings`.
+#[derive(Clone)]
+struct FtpSettings {
+    host: String,
+    port: u16,
+    passive_mode: bool,
+    use_tls: bool,
+    username: String,
+    password: SecretString,
+}
+
+/// Methods of `FtpSettings`.
+impl FtpSettings {
+    /// Opens a new logged in stream.
+    fn open_stream(&self) -> Result<NativeTlsFtpStream, FtpError> {
+        let mut stream = NativeTlsFtpStream::connect((self.host.as_str(), self.port))?;
+
+        // Switch to explicit TLS (FTPS).
+        if self.use_tls {
+            let connector =
+                TlsConnector::new().map_err(|err| FtpError::SecureError(err.to_string()))?;
+            stream = stream.into_secure(NativeTlsConnector::from(connector), &self.host)?;
+        }
+
+        stream.login(self.username.as_str(), self.password.expose_secret())?;
+
+        if self.passive_mode {
+            stream.set_mode(Mode::Passive);
+        } else {
+            stream.set_mode(Mode::Active);
+        }
+
+        stream.transfer_type(FileType::Binary)?;
+
+        Ok(stream)
+    }
+}
+
+/// Defines a `FtpRead`.
+///
+/// A reader of a `RETR` data stream, which owns its own connection.
+struct FtpRead {
+    stream: NativeTlsFtpStream,
+    data: Option<Box<dyn Read + Send>>,
+}
+
+/// Impl of `Read` for `FtpRead`.
+impl Read for FtpRead {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.data.as_mut() {
+            Some(data) => data.read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
+/// Drops the `FtpRead`.
+impl Drop for FtpRead {
+    fn drop(&mut self) {
+        if let Some(data) = self.data.take() {
+            let _ = self.stream.finalize_retr_stream(data);
+        }
+
+        let _ = self.stream.quit();
+    }
+}
+
+/// Defines a `FtpFS`.
+///
+/// Metadata and directory operations share one control connection. Since a FTP connection can
+/// only transfer one file at once, each `read_data` and `write_data` opens its own connection.
+/// The metadata is read by `MLST`, servers without `MLST` list the parent instead.
+pub struct FtpFS {
+    settings: FtpSettings,
+    control: Mutex<Option<NativeTlsFtpStream>>,
+    mlst_supported: AtomicBool,
+    connected: bool,
+}
+
+/// Methods of `FtpFS`.
+impl FtpFS {
+    pub fn new(
+        host: &str,
+        port: u16,
+        passive_mode: bool,
+        use_tls: bool,
+        username: &str,
+        password: &SecretString,
+    ) -> Self {
+        FtpFS {
+            settings: FtpSettings {
+                host: host.to_owned(),
+                port,
+                passive_mode,
+                use_tls,
+                username: username.to_owned(),
+                password: password.to_owned(),
+            },
+            control: Mutex::new(None),
+            mlst_supported: AtomicBool::new(true),
+            connected: false,
+        }
+    }
+
+    /// Runs `op` on the control connection.
+    fn with_control<R>(
+        &self,
+        op: impl FnOnce(&mut NativeTlsFtpStream) -> Result<R, FtpError>,
+    ) -> Result<R, Box<dyn Error + Send + Sync>> {
+        match self.control.lock().unwrap().as_mut() {
+            Some(stream) => op(stream).map_err(|err| err.into()),
+            None => Err("No control connection".into()),
+        }
+    }
+}
+
+/// Impl of `FS` for `FtpFS`.
+impl FS for FtpFS {
+    fn connect(&mut self) -> Result<(), FSError> {
+        let stream = self
+            .settings
+            .open_stream()
+            .map_err(|err| FSError::ConnectionFailed(err.into()))?;
+
+        *self.control.lock().unwrap() = Some(stream);
+        self.connected = true;
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        if let Some(mut stream) = self.control.lock().unwrap().take() {
+            let _ = stream.quit();
+        }
+
+        self.connected = false;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    fn block_size(&self) -> FSBlockSize {
+        FSBlockSize::new(None, 64 * 1024, None)
+    }
+
+    fn meta(&self, abs_path: &UNPath<Abs>) -> Result<FSMetaData, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let path = Path::new(ftp_path(abs_path.to_unicode()));
+
+        // The root has no parent to list.
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Ok(FSMetaData::new(None, None, None, None));
+        };
+
+        // Read the entry by `MLST`, if supported.
+        let mut entry = None;
+
+        if self.mlst_supported.load(Ordering::Relaxed) {
+            entry = self
+                .with_control(|stream| {
+                    mlst_entry(stream, &path.to_string_lossy(), &self.mlst_supported)
+                })
+                .map_err(|err| FSError::MetaFailed(abs_path.clone(), err))?;
+        }
+
+        // Otherwise look up the entry in the listing of the parent.
+        if entry.is_none() {
+            let parent = parent.to_string_lossy();
+            let name = name.to_string_lossy();
+
+            entry = self
+                .with_control(|stream| list_entries(stream, ftp_path(&parent)))
+                .map_err(|err| FSError::MetaFailed(abs_path.clone(), err))?
+                .into_iter()
+                .find(|entry| entry.name() == name);
+        }
+
+        match entry {
+            Some(entry) => {
+                // Type of entry and abs_path must be the same.
+                if entry.is_directory() != abs_path.is_dir() {
+                    return Err(FSError::MetaFailed(
+                        abs_path.clone(),
+                        "Path target mismatch".into(),
+                    ));
+                }
+
+                Ok(make_metadata_from_entry(&entry))
+            }
+            None => Err(FSError::MetaFailed(
+                abs_path.clone(),
+                "Path does not exist".into(),
+            )),
+        }
+    }
+
+    fn list_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<Vec<UNPath<Abs>>, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let entries = self
+            .with_control(|stream| list_entries(stream, ftp_path(abs_dir_path.to_unicode())))
+            .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err))?;
+
+        entries
+            .iter()
+            .map(|entry| {
+                make_abs_path_from_entry(abs_dir_path, entry)
+                    .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))
+            })
+            .collect()
+    }
+
+    fn remove_file(&self, abs_file_path: &NPath<Abs, File>) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        self.with_control(|stream| stream.rm(ftp_path(abs_file_path.to_unicode())))
+            .map_err(|err| FSError::RemoveFileFailed(abs_file_path.clone(), err))
+    }
+
+    fn rename_file(
+        &self,
+        src_abs_file_path: &NPath<Abs, File>,
+        dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        self.with_control(|stream| {
+            stream.rename(
+                ftp_path(src_abs_file_path.to_unicode()),
+                ftp_path(dest_abs_file_path.to_unicode()),
+            )
+        })
+        .map_err(|err| FSError::RenameFailed(src_abs_file_path.clone(), err))
+    }
+
+    fn remove_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        self.with_control(|stream| stream.rmdir(ftp_path(abs_dir_path.to_unicode())))
+            .map_err(|err| FSError::RemoveDirFailed(abs_dir_path.clone(), err))
+    }
+
+    fn mkdir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        self.with_control(|stream| stream.mkdir(ftp_path(abs_dir_path.to_unicode())))
+            .map_err(|err| FSError::MkDirFailed(abs_dir_path.clone(), err))
+    }
+
+    fn mklink(
+        &self,
+        _abs_sym_path: &NPath<Abs, Symlink>,
+        _symlink_meta: &FSSymlinkMeta,
+    ) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        Err(FSError::NotSupported(
+            "Symlinks are not supported by FTP".into(),
+        ))
+    }
+
+    fn read_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<Box<dyn Read + Send>, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let mut stream = self
+            .settings
+            .open_stream()
+            .map_err(|err| FSError::ReadFailed(abs_file_path.clone(), err.into()))?;
+
+        let data = stream
+            .retr_as_stream(ftp_path(abs_file_path.to_unicode()))
+            .map_err(|err| FSError::ReadFailed(abs_file_path.clone(), err.into()))?;
+
+        Ok(Box::new(FtpRead {
+            stream,
+            data: Some(Box::new(data)),
+        }))
+    }
+
+    fn write_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<FSWrite, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let mut stream = self
+            .settings
+            .open_stream()
+            .map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))?;
+
+        let path = ftp_path(abs_file_path.to_unicode()).to_string();
+
+        let (mut reader, writer) =
+            pipe().map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))?;
+
+        let thread_handle = thread::spawn(move || {
+            let result = stream
+                .put_file(path, &mut reader)
+                .map(|_bytes| ())
+                .map_err(std::io::Error::other);
+            let _ = stream.quit();
+            result
+        });
+
+        Ok(FSWrite::new(Box::new(writer), Some(thread_handle)))
+    }
+}
@@ -1,29 +1,43 @@
 use crate::core::fs::fs_metadata::FSMetaData;
 use crate::core::fs::fs_symlink_meta::{FSSymlinkMeta, FSSymlinkType};
-use crate::shared::npath::{Abs, Dir, File, NPath, Symlink, UNPath};
+use crate::shared::npath::{Abs, Dir, File, NPath, Symlink, UNPath, unescape_os_path};
 use std::fs::FileType;
 use std::io::{self, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use super::fs_base::FSBlockSize;
 use super::fs_base::{FS, FSError, FSWrite};
 
 /// Defines a `LocalFS`.
 pub struct LocalFS {
+    noatime: bool,
     connected: bool,
 }
 
 /// Methods of `LocalFS`.
 impl LocalFS {
     /// Creates a new `LocalFS`.
-    pub fn new() -> Self {
-        LocalFS { connected: false }
+    ///
+    /// If `noatime` is true, files are read without updating their access time (Linux only).
+    /// A file, which the user does not own, falls back to a standard open.
+    pub fn new(noatime: bool) -> Self {
+        LocalFS {
+            noatime,
+            connected: false,
+        }
+    }
+
+    /// Returns the os path of `path`.
+    ///
+    /// Paths, which are listed with a lossy conversion, are unescaped to the original path.
+    fn os_path(&self, path: PathBuf) -> PathBuf {
+        unescape_os_path(path)
     }
 }
 
 impl Default for LocalFS {
     fn default() -> Self {
-        Self::new()
+        Self::new(true)
     }
 }
 
@@ -54,7 +68,7 @@ impl FS for LocalFS {
             return Err(FSError::NotConnected);
         }
 
-        let metadata = std::fs::symlink_metadata(abs_path.as_os_path())
+        let metadata = std::fs::symlink_metadata(self.os_path(abs_path.as_os_path()))
             .map_err(|err| FSError::MetaFailed(abs_path.clone(), err.into()))?;
 
         // Target of metadata and abs_path must be the same.
@@ -65,6 +79,7 @@ impl FS for LocalFS {
             // Set metadata.
             let created = metadata.created().ok();
             let modified = metadata.modified().ok();
+            let mode = file_mode(&metadata);
             let mut size = None;
             let mut symlink = None;
 
@@ -75,14 +90,14 @@ impl FS for LocalFS {
 
             // Is symlink?
             if metadata.is_symlink() {
-                let target_path = std::fs::read_link(abs_path.as_os_path())
+                let target_path = std::fs::read_link(self.os_path(abs_path.as_os_path()))
                     .map_err(|err| FSError::MetaFailed(abs_path.clone(), err.into()))?;
 
                 let target_type = symlink_type(&metadata.file_type());
-                symlink = Some(FSSymlinkMeta::new(target_path, target_type));
+                symlink = Some(FSSymlinkMeta::new(target_path, target_type).with_mode(mode));
             }
 
-            let meta = FSMetaData::new(created, modified, size, symlink);
+            let meta = FSMetaData::new(created, modified, size, symlink).with_mode(mode);
 
             Ok(meta)
         } else {
@@ -98,7 +113,7 @@ impl FS for LocalFS {
             return Err(FSError::NotConnected);
         }
 
-        let entries = std::fs::read_dir(abs_dir_path.as_os_path())
+        let entries = std::fs::read_dir(self.os_path(abs_dir_path.as_os_path()))
             .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?;
 
         let mut paths = Vec::new();
@@ -110,43 +125,36 @@ impl FS for LocalFS {
             let metadata = std::fs::symlink_metadata(entry.path())
                 .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?;
 
-            match entry.path().to_str() {
-                Some(entry_str) => {
-                    // Only process files and directories, skip symlinks and others.
-                    if metadata.file_type().is_file() {
-                        let entry_abs_path =
-                            UNPath::File(NPath::<Abs, File>::try_from(entry_str).map_err(
-                                |err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()),
-                            )?);
-
-                        paths.push(entry_abs_path);
-                    } else if metadata.file_type().is_dir() {
-                        let entry_abs_path =
-                            UNPath::Dir(NPath::<Abs, Dir>::try_from(entry_str).map_err(|err| {
-                                FSError::ListDirFailed(abs_dir_path.clone(), err.into())
-                            })?);
-
-                        paths.push(entry_abs_path);
-                    } else if metadata.file_type().is_symlink() {
-                        let entry_abs_path =
-                            UNPath::Symlink(NPath::<Abs, Symlink>::try_from(entry_str).map_err(
-                                |err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()),
-                            )?);
-
-                        paths.push(entry_abs_path);
-                    } else {
-                        return Err(FSError::ListDirFailed(
-                            abs_dir_path.clone(),
-                            "Unkown file type".into(),
-                        ));
-                    }
-                }
-                None => {
-                    return Err(FSError::ListDirFailed(
-                        abs_dir_path.clone(),
-                        "Path is not in valid unicode".into(),
-                    ));
-                }
+            // Invalid unicode is escaped, so the original path can be restored.
+            let entry_path = entry.path();
+
+            // Only process files and directories, skip symlinks and others.
+            if metadata.file_type().is_file() {
+                let entry_abs_path = UNPath::File(
+                    NPath::<Abs, File>::from_os_str_lossy(entry_path.as_os_str())
+                        .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?,
+                );
+
+                paths.push(entry_abs_path);
+            } else if metadata.file_type().is_dir() {
+                let entry_abs_path = UNPath::Dir(
+                    NPath::<Abs, Dir>::from_os_str_lossy(entry_path.as_os_str())
+                        .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?,
+                );
+
+                paths.push(entry_abs_path);
+            } else if metadata.file_type().is_symlink() {
+                let entry_abs_path = UNPath::Symlink(
+                    NPath::<Abs, Symlink>::from_os_str_lossy(entry_path.as_os_str())
+                        .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?,
+                );
+
+                paths.push(entry_abs_path);
+            } else {
+                return Err(FSError::ListDirFailed(
+                    abs_dir_path.clone(),
+                    "Unkown file type".into(),
+                ));
             }
         }
 
@@ -158,7 +166,7 @@ impl FS for LocalFS {
             return Err(FSError::NotConnected);
         }
 
-        match std::fs::remove_file(abs_file_path.as_os_path()) {
+        match std::fs::remove_file(self.os_path(abs_file_path.as_os_path())) {
             Ok(_) => Ok(()),
             Err(err) => Err(FSError::RemoveFileFailed(abs_file_path.clone(), err.into())),
         }
@@ -169,7 +177,7 @@ impl FS for LocalFS {
             return Err(FSError::NotConnected);
         }
 
-        match std::fs::remove_dir(abs_dir_path.as_os_path()) {
+        match std::fs::remove_dir(self.os_path(abs_dir_path.as_os_path())) {
             Ok(_) => Ok(()),
             Err(err) => Err(FSError::RemoveDirFailed(abs_dir_path.clone(), err.into())),
         }
@@ -180,7 +188,7 @@ impl FS for LocalFS {
             return Err(FSError::NotConnected);
         }
 
-        match std::fs::create_dir(abs_dir_path.as_os_path()) {
+        match std::fs::create_dir(self.os_path(abs_dir_path.as_os_path())) {
             Ok(_) => Ok(()),
             Err(err) => Err(FSError::MkDirFailed(abs_dir_path.clone(), err.into())),
         }
@@ -196,7 +204,7 @@ impl FS for LocalFS {
         }
 
         match create_symlink(
-            &abs_sym_path.as_os_path(),
+            &self.os_path(abs_sym_path.as_os_path()),
             &symlink_meta.target_path,
             &symlink_meta.target_type,
         ) {
@@ -211,7 +219,7 @@ impl FS for LocalFS {
         }
 
         // Attempt to open the file.
-        let file = std::fs::File::open(abs_file_path.as_os_path())
+        let file = open_file(&self.os_path(abs_file_path.as_os_path()), self.noatime)
             .map_err(|err| FSError::ReadFailed(abs_file_path.clone(), err.into()))?;
 
         // Return the file as a `Box<dyn Read>`.
@@ -223,12 +231,74 @@ impl FS for LocalFS {
             return Err(FSError::NotConnected);
         }
 
-        // Attempt to open the file in write mode (create if doesn't exist).
-        let file = std::fs::File::create(abs_file_path.as_os_path())
+        // Write to a temp file, which is renamed on finish.
+        let final_path = self.os_path(abs_file_path.as_os_path());
+        let temp_path = temp_path_for(&final_path);
+
+        // Attempt to open the temp file in write mode.
+        let file = std::fs::File::create(&temp_path)
+            .map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))?;
+
+        // Keep a handle of the file to sync it.
+        let sync_file = file
+            .try_clone()
             .map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))?;
 
         // Return the file wrapped in a `Box<dyn Write>`.
-        Ok(FSWrite::new(Box::new(file), None)) // This is where the `Box<dyn Write>` comes in.
+        Ok(FSWrite::new(Box::new(file), None)
+            .with_rename(temp_path, final_path)
+            .with_sync_file(sync_file))
+    }
+
+    fn rename_file(
+        &self,
+        src_abs_file_path: &NPath<Abs, File>,
+        dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        // The rename replaces the destination atomically.
+        match std::fs::rename(
+            self.os_path(src_abs_file_path.as_os_path()),
+            self.os_path(dest_abs_file_path.as_os_path()),
+        ) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(FSError::RenameFailed(src_abs_file_path.clone(), err.into())),
+        }
+    }
+
+    fn set_meta(&self, abs_path: &UNPath<Abs>, metadata: &FSMetaData) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        // The mode of a symlink is the mode of its target.
+        if abs_path.is_symlink() {
+            return Ok(());
+        }
+
+        let os_path = self.os_path(abs_path.as_os_path());
+
+        // Set modified time, before the mode may make the file read only.
+        if abs_path.is_file()
+            && let Some(modified) = metadata.modified
+        {
+            std::fs::File::options()
+                .write(true)
+                .open(&os_path)
+                .and_then(|file| file.set_modified(modified))
+                .map_err(|err| FSError::SetMetaFailed(abs_path.clone(), err.into()))?;
+        }
+
+        // Set mode.
+        if let Some(mode) = metadata.mode {
+            set_file_mode(&os_path, mode)
+                .map_err(|err| FSError::SetMetaFailed(abs_path.clone(), err.into()))?;
+        }
+
+        Ok(())
     }
 }
 
@@ -245,6 +315,57 @@ fn symlink_type(file_type: &FileType) -> FSSymlinkType {
     }
 }
 
+/// Returns the permission bits.
+fn file_mode(metadata: &std::fs::Metadata) -> Option<u32> {
+    #[cfg(windows)]
+    {
+        let _ = metadata;
+        None
+    }
+
+    #[cfg(unix)]
+    {
+        Some(unix::file_mode(metadata))
+    }
+}
+
+/// Sets the permission bits.
+fn set_file_mode(path: &Path, mode: u32) -> io::Result<()> {
+    #[cfg(windows)]
+    {
+        let _ = (path, mode);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    {
+        unix::set_file_mode(path, mode)
+    }
+}
+
+/// Returns a temp path `<filename>.cuba-tmp-<random>` next to the path.
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".cuba-tmp-{:08x}", rand::random::<u32>()));
+
+    path.with_file_name(file_name)
+}
+
+/// Opens a file for reading.
+fn open_file(path: &Path, noatime: bool) -> io::Result<std::fs::File> {
+    #[cfg(target_os = "linux")]
+    {
+        if noatime {
+            return linux::open_file_noatime(path);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    let _ = noatime;
+
+    std::fs::File::open(path)
+}
+
 /// Creates a symlink.
 fn create_symlink(
     link_path: &Path,
@@ -265,21 +386,59 @@ fn create_symlink(
 #[cfg(unix)]
 mod unix {
     use crate::core::fs::fs_symlink_meta::FSSymlinkType;
-    use std::fs::FileType;
+    use std::fs::{FileType, Metadata, Permissions};
     use std::io;
+    use std::os::unix::fs::PermissionsExt;
     use std::path::Path;
 
+    /// The permission bits, including setuid, setgid and sticky.
+    const MODE_MASK: u32 = 0o7777;
+
     /// Returns a symlink type.
     pub fn symlink_type(_file_type: &FileType) -> FSSymlinkType {
         FSSymlinkType::Unknown
     }
 
+    /// Returns the permission bits.
+    pub fn file_mode(metadata: &Metadata) -> u32 {
+        metadata.permissions().mode() & MODE_MASK
+    }
+
+    /// Sets the permission bits.
+    pub fn set_file_mode(path: &Path, mode: u32) -> io::Result<()> {
+        std::fs::set_permissions(path, Permissions::from_mode(mode & MODE_MASK))
+    }
+
     /// Creates a symlink.
     pub fn create_symlink(link_path: &Path, target_path: &Path) -> io::Result<()> {
         std::os::unix::fs::symlink(target_path, link_path)
     }
 }
 
+/// Config for Linux
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs::OpenOptions;
+    use std::io;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::path::Path;
+
+    /// Opens a file without updating its access time.
+    ///
+    /// `O_NOATIME` is only permitted for the owner of the file, so this falls back to a
+    /// standard open if the permission is denied.
+    pub fn open_file_noatime(path: &Path) -> io::Result<std::fs::File> {
+        match OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOATIME)
+            .open(path)
+        {
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => std::fs::File::open(path),
+            result => result,
+        }
+    }
+}
+
 /// Config for Windows
 #[cfg(windows)]
 mod windows {
@@ -0,0 +1,507 @@
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Read, Write};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+
+use crate::core::fs::fs_metadata::FSMetaData;
+use crate::core::fs::fs_symlink_meta::FSSymlinkMeta;
+use crate::shared::npath::{Abs, Dir, File, NPath, Rel, Symlink, UNPath};
+
+use super::fs_base::{FS, FSBlockSize, FSError, FSWrite};
+
+/// The nodes of a `MemFS`, the key is the path without root.
+type MemStore = Arc<RwLock<BTreeMap<String, MemNode>>>;
+
+// The stores of the named mem filesystems.
+lazy_static! {
+    static ref MEM_STORES: Mutex<HashMap<String, MemStore>> = Mutex::new(HashMap::new());
+}
+
+/// Defines a `MemNodeKind`.
+#[derive(Clone)]
+enum MemNodeKind {
+    Dir,
+    File(Vec<u8>),
+    Symlink(FSSymlinkMeta),
+}
+
+/// Defines a `MemNode`.
+#[derive(Clone)]
+struct MemNode {
+    kind: MemNodeKind,
+    created: SystemTime,
+    modified: SystemTime,
+}
+
+/// Methods of `MemNode`.
+impl MemNode {
+    /// Creates a new `MemNode`, created and modified now.
+    fn new(kind: MemNodeKind) -> Self {
+        let now = SystemTime::now();
+
+        Self {
+            kind,
+            created: now,
+            modified: now,
+        }
+    }
+
+    /// Returns true, if the node has the same target as `abs_path`.
+    fn matches(&self, abs_path: &UNPath<Abs>) -> bool {
+        match self.kind {
+            MemNodeKind::Dir => abs_path.is_dir(),
+            MemNodeKind::File(_) => abs_path.is_file(),
+            MemNodeKind::Symlink(_) => abs_path.is_symlink(),
+        }
+    }
+
+    /// Returns the metadata of the node.
+    fn metadata(&self) -> FSMetaData {
+        let (size, symlink_meta) = match &self.kind {
+            MemNodeKind::Dir => (None, None),
+            MemNodeKind::File(data) => (Some(data.len() as u64), None),
+            MemNodeKind::Symlink(symlink_meta) => (None, Some(symlink_meta.clone())),
+        };
+
+        FSMetaData::new(Some(self.created), Some(self.modified), size, symlink_meta)
+    }
+}
+
+/// Returns the key of a path, which is the path without root and empty segments.
+fn mem_key(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns the key of the parent and the name of a key.
+fn split_key(key: &str) -> (&str, &str) {
+    key.rsplit_once('/').unwrap_or(("", key))
+}
+
+/// Inserts the missing parent dirs of `key`.
+///
+/// Fails, if a parent is not a dir.
+fn insert_parents(nodes: &mut BTreeMap<String, MemNode>, key: &str) -> Result<(), String> {
+    let (parent_key, _name) = split_key(key);
+
+    // The root always exists.
+    if parent_key.is_empty() {
+        return Ok(());
+    }
+
+    match nodes.get(parent_key) {
+        Some(MemNode {
+            kind: MemNodeKind::Dir,
+            ..
+        }) => Ok(()),
+        Some(_) => Err(format!("The parent {} is not a directory", parent_key)),
+        None => {
+            insert_parents(nodes, parent_key)?;
+            nodes.insert(parent_key.to_string(), MemNode::new(MemNodeKind::Dir));
+            Ok(())
+        }
+    }
+}
+
+/// Defines a `MemWrite`.
+///
+/// Collects the data, which is stored to the file on drop.
+struct MemWrite {
+    store: MemStore,
+    key: String,
+    data: Vec<u8>,
+}
+
+/// Impl of `Write` for `MemWrite`.
+impl Write for MemWrite {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Impl of `Drop` for `MemWrite`.
+impl Drop for MemWrite {
+    fn drop(&mut self) {
+        let mut nodes = self.store.write().unwrap();
+        let data = std::mem::take(&mut self.data);
+
+        // Keep the created time of an existing file.
+        match nodes.get_mut(&self.key) {
+            Some(node) => {
+                node.kind = MemNodeKind::File(data);
+                node.modified = SystemTime::now();
+            }
+            None => {
+                nodes.insert(self.key.clone(), MemNode::new(MemNodeKind::File(data)));
+            }
+        }
+    }
+}
+
+/// Defines a `MemFS`.
+///
+/// A fs, which keeps all nodes in memory. The nodes of a named `MemFS` are shared by all
+/// instances with the same name and exist for the lifetime of the process. Parent dirs are
+/// created on demand, like in an object store.
+pub struct MemFS {
+    store: MemStore,
+    connected: bool,
+}
+
+/// Methods of `MemFS`.
+impl MemFS {
+    /// Creates a new empty `MemFS`.
+    pub fn new() -> Self {
+        MemFS {
+            store: Arc::new(RwLock::new(BTreeMap::new())),
+            connected: false,
+        }
+    }
+
+    /// Creates a `MemFS`, which shares its nodes with all `MemFS` of the same `name`.
+    pub fn named(name: &str) -> Self {
+        let store = MEM_STORES
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone();
+
+        MemFS {
+            store,
+            connected: false,
+        }
+    }
+}
+
+/// Impl of `Default` for `MemFS`.
+impl Default for MemFS {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Impl of `FS` for `MemFS`.
+impl FS for MemFS {
+    fn connect(&mut self) -> Result<(), FSError> {
+        // Set connection state to true.
+        self.connected = true;
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), FSError> {
+        // Set connection state to false.
+        self.connected = false;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    fn block_size(&self) -> FSBlockSize {
+        FSBlockSize::new(None, 64 * 1024, None)
+    }
+
+    fn meta(&self, abs_path: &UNPath<Abs>) -> Result<FSMetaData, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let key = mem_key(abs_path.to_nfc());
+
+        // The root is always a dir.
+        if key.is_empty() {
+            return if abs_path.is_dir() {
+                Ok(FSMetaData::new(None, None, None, None))
+            } else {
+                Err(FSError::MetaFailed(
+                    abs_path.clone(),
+                    "Path target mismatch".into(),
+                ))
+            };
+        }
+
+        match self.store.read().unwrap().get(&key) {
+            Some(node) if node.matches(abs_path) => Ok(node.metadata()),
+            Some(_) => Err(FSError::MetaFailed(
+                abs_path.clone(),
+                "Path target mismatch".into(),
+            )),
+            None => Err(FSError::MetaFailed(abs_path.clone(), "Not found".into())),
+        }
+    }
+
+    fn list_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<Vec<UNPath<Abs>>, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let key = mem_key(abs_dir_path.to_nfc());
+        let nodes = self.store.read().unwrap();
+
+        // A missing dir is empty, like a prefix in an object store.
+        if nodes
+            .get(&key)
+            .is_some_and(|node| !node.matches(&abs_dir_path.into()))
+        {
+            return Err(FSError::ListDirFailed(
+                abs_dir_path.clone(),
+                "Not a directory".into(),
+            ));
+        }
+
+        let prefix = if key.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", key)
+        };
+
+        let mut abs_paths = Vec::new();
+
+        for (child_key, node) in nodes.range(prefix.clone()..) {
+            let Some(name) = child_key.strip_prefix(&prefix) else {
+                break;
+            };
+
+            // Only direct children.
+            if name.contains('/') {
+                continue;
+            }
+
+            let abs_path = match node.kind {
+                MemNodeKind::Dir => NPath::<Rel, Dir>::try_from(name)
+                    .map(|rel_dir_path| UNPath::Dir(abs_dir_path.add_rel_dir(&rel_dir_path))),
+                MemNodeKind::File(_) => NPath::<Rel, File>::try_from(name)
+                    .map(|rel_file_path| UNPath::File(abs_dir_path.add_rel_file(&rel_file_path))),
+                MemNodeKind::Symlink(_) => {
+                    NPath::<Rel, Symlink>::try_from(name).map(|rel_sym_path| {
+                        UNPath::Symlink(abs_dir_path.add_rel_symlink(&rel_sym_path))
+                    })
+                }
+            }
+            .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?;
+
+            abs_paths.push(abs_path);
+        }
+
+        Ok(abs_paths)
+    }
+
+    fn remove_file(&self, abs_file_path: &NPath<Abs, File>) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let key = mem_key(abs_file_path.to_nfc());
+        let mut nodes = self.store.write().unwrap();
+
+        match nodes.get(&key) {
+            Some(MemNode {
+                kind: MemNodeKind::File(_) | MemNodeKind::Symlink(_),
+                ..
+            }) => {
+                nodes.remove(&key);
+                Ok(())
+            }
+            Some(_) => Err(FSError::RemoveFileFailed(
+                abs_file_path.clone(),
+                "Not a file".into(),
+            )),
+            None => Err(FSError::RemoveFileFailed(
+                abs_file_path.clone(),
+                "Not found".into(),
+            )),
+        }
+    }
+
+    fn remove_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let key = mem_key(abs_dir_path.to_nfc());
+        let mut nodes = self.store.write().unwrap();
+
+        match nodes.get(&key) {
+            Some(MemNode {
+                kind: MemNodeKind::Dir,
+                ..
+            }) => {
+                // The dir must be empty.
+                let prefix = format!("{}/", key);
+
+                if nodes
+                    .range(prefix.clone()..)
+                    .next()
+                    .is_some_and(|(child_key, _node)| child_key.starts_with(&prefix))
+                {
+                    return Err(FSError::RemoveDirFailed(
+                        abs_dir_path.clone(),
+                        "Directory is not empty".into(),
+                    ));
+                }
+
+                nodes.remove(&key);
+                Ok(())
+            }
+            Some(_) => Err(FSError::RemoveDirFailed(
+                abs_dir_path.clone(),
+                "Not a directory".into(),
+            )),
+            None => Err(FSError::RemoveDirFailed(
+                abs_dir_path.clone(),
+                "Not found".into(),
+            )),
+        }
+    }
+
+    fn mkdir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let key = mem_key(abs_dir_path.to_nfc());
+        let mut nodes = self.store.write().unwrap();
+
+        if key.is_empty() || nodes.contains_key(&key) {
+            return Err(FSError::MkDirFailed(
+                abs_dir_path.clone(),
+                "Already exists".into(),
+            ));
+        }
+
+        insert_parents(&mut nodes, &key)
+            .map_err(|err| FSError::MkDirFailed(abs_dir_path.clone(), err.into()))?;
+
+        nodes.insert(key, MemNode::new(MemNodeKind::Dir));
+        Ok(())
+    }
+
+    fn mklink(
+        &self,
+        abs_sym_path: &NPath<Abs, Symlink>,
+        symlink_meta: &FSSymlinkMeta,
+    ) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let key = mem_key(abs_sym_path.to_nfc());
+        let mut nodes = self.store.write().unwrap();
+
+        if key.is_empty() || nodes.contains_key(&key) {
+            return Err(FSError::MkLinkFailed(
+                abs_sym_path.clone(),
+                "Already exists".into(),
+            ));
+        }
+
+        insert_parents(&mut nodes, &key)
+            .map_err(|err| FSError::MkLinkFailed(abs_sym_path.clone(), err.into()))?;
+
+        nodes.insert(
+            key,
+            MemNode::new(MemNodeKind::Symlink(symlink_meta.clone())),
+        );
+        Ok(())
+    }
+
+    fn read_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<Box<dyn Read + Send>, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        match self
+            .store
+            .read()
+            .unwrap()
+            .get(&mem_key(abs_file_path.to_nfc()))
+        {
+            Some(MemNode {
+                kind: MemNodeKind::File(data),
+                ..
+            }) => Ok(Box::new(Cursor::new(data.clone()))),
+            Some(_) => Err(FSError::ReadFailed(
+                abs_file_path.clone(),
+                "Not a file".into(),
+            )),
+            None => Err(FSError::ReadFailed(
+                abs_file_path.clone(),
+                "Not found".into(),
+            )),
+        }
+    }
+
+    fn rename_file(
+        &self,
+        src_abs_file_path: &NPath<Abs, File>,
+        dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let src_key = mem_key(src_abs_file_path.to_nfc());
+        let dest_key = mem_key(dest_abs_file_path.to_nfc());
+        let mut nodes = self.store.write().unwrap();
+
+        // Only a file can be renamed and replaced.
+        if !matches!(nodes.get(&src_key), Some(node) if matches!(node.kind, MemNodeKind::File(_)))
+            || dest_key.is_empty()
+            || matches!(nodes.get(&dest_key), Some(node) if !matches!(node.kind, MemNodeKind::File(_)))
+        {
+            return Err(FSError::RenameFailed(
+                src_abs_file_path.clone(),
+                "Not a file".into(),
+            ));
+        }
+
+        insert_parents(&mut nodes, &dest_key)
+            .map_err(|err| FSError::RenameFailed(src_abs_file_path.clone(), err.into()))?;
+
+        let node = nodes.remove(&src_key).unwrap();
+        nodes.insert(dest_key, node);
+
+        Ok(())
+    }
+
+    fn write_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<FSWrite, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let key = mem_key(abs_file_path.to_nfc());
+        let mut nodes = self.store.write().unwrap();
+
+        // Only a file can be overwritten.
+        if key.is_empty()
+            || matches!(nodes.get(&key), Some(node) if !matches!(node.kind, MemNodeKind::File(_)))
+        {
+            return Err(FSError::WriteFailed(
+                abs_file_path.clone(),
+                "Not a file".into(),
+            ));
+        }
+
+        insert_parents(&mut nodes, &key)
+            .map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))?;
+
+        Ok(FSWrite::new(
+            Box::new(MemWrite {
+                store: self.store.clone(),
+                key,
+                data: Vec::new(),
+            }),
+            None,
+        ))
+    }
+}
@@ -1,6 +1,22 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod azure_blob_fs;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod fs_base;
 pub mod fs_metadata;
 pub mod fs_symlink_meta;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ftp_fs;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod local_fs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mem_fs;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod null_fs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rclone_fs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod s3_fs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod traced_fs;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod webdav_fs;
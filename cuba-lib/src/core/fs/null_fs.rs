@@ -88,7 +88,9 @@ impl FS for NullFS {
             return Err(FSError::NotConnected);
         }
 
-        Err(FSError::NotSupported)
+        Err(FSError::NotSupported(
+            "Listing directories is not supported by the null fs".into(),
+        ))
     }
 
     fn remove_file(&self, _abs_file_path: &NPath<Abs, File>) -> Result<(), FSError> {
@@ -96,7 +98,9 @@ impl FS for NullFS {
             return Err(FSError::NotConnected);
         }
 
-        Err(FSError::NotSupported)
+        Err(FSError::NotSupported(
+            "Removing files is not supported by the null fs".into(),
+        ))
     }
 
     fn remove_dir(&self, _abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
@@ -104,7 +108,9 @@ impl FS for NullFS {
             return Err(FSError::NotConnected);
         }
 
-        Err(FSError::NotSupported)
+        Err(FSError::NotSupported(
+            "Removing directories is not supported by the null fs".into(),
+        ))
     }
 
     fn mkdir(&self, _abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
@@ -112,7 +118,9 @@ impl FS for NullFS {
             return Err(FSError::NotConnected);
         }
 
-        Err(FSError::NotSupported)
+        Err(FSError::NotSupported(
+            "Creating directories is not supported by the null fs".into(),
+        ))
     }
 
     fn mklink(
@@ -124,7 +132,9 @@ impl FS for NullFS {
             return Err(FSError::NotConnected);
         }
 
-        Err(FSError::NotSupported)
+        Err(FSError::NotSupported(
+            "Creating symlinks is not supported by the null fs".into(),
+        ))
     }
 
     fn read_data(
@@ -135,7 +145,9 @@ impl FS for NullFS {
             return Err(FSError::NotConnected);
         }
 
-        Err(FSError::NotSupported)
+        Err(FSError::NotSupported(
+            "Reading data is not supported by the null fs".into(),
+        ))
     }
 
     fn write_data(&self, _abs_file_path: &NPath<Abs, File>) -> Result<FSWrite, FSError> {
@@ -0,0 +1,368 @@
+use chrono::DateTime;
+use serde::Deserialize;
+use std::error::Error;
+use std::io::Read;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::thread;
+use std::time::SystemTime;
+
+use crate::core::fs::fs_metadata::FSMetaData;
+use crate::core::fs::fs_symlink_meta::FSSymlinkMeta;
+use crate::shared::npath::{Abs, Dir, File, NPath, NPathError, Rel, Symlink, UNPath};
+
+use super::fs_base::{FS, FSBlockSize, FSError, FSWrite};
+
+/// The rclone executable.
+const RCLONE: &str = "rclone";
+
+/// Defines a `LsJsonEntry`.
+///
+/// An entry of the `rclone lsjson` output.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LsJsonEntry {
+    name: String,
+    size: i64,
+    mod_time: Option<String>,
+    is_dir: bool,
+}
+
+/// Methods of `LsJsonEntry`.
+impl LsJsonEntry {
+    /// Returns the metadata of the entry.
+    fn metadata(&self) -> FSMetaData {
+        let modified = self
+            .mod_time
+            .as_deref()
+            .and_then(|mod_time| DateTime::parse_from_rfc3339(mod_time).ok())
+            .map(SystemTime::from);
+
+        // Directories and unknown sizes are reported as -1.
+        let size = if self.is_dir || self.size < 0 {
+            None
+        } else {
+            Some(self.size as u64)
+        };
+
+        FSMetaData::new(None, modified, size, None)
+    }
+
+    /// Returns the abs path of the entry in `abs_dir_path`.
+    fn abs_path(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<UNPath<Abs>, NPathError> {
+        if self.is_dir {
+            let rel_dir_path = NPath::<Rel, Dir>::try_from(self.name.as_str())?;
+            Ok(UNPath::Dir(abs_dir_path.add_rel_dir(&rel_dir_path)))
+        } else {
+            let rel_file_path = NPath::<Rel, File>::try_from(self.name.as_str())?;
+            Ok(UNPath::File(abs_dir_path.add_rel_file(&rel_file_path)))
+        }
+    }
+}
+
+/// Defines a `RcloneRead`.
+///
+/// A reader of the stdout of `rclone cat`.
+struct RcloneRead {
+    child: Child,
+    stdout: ChildStdout,
+}
+
+/// Impl of `Read` for `RcloneRead`.
+impl Read for RcloneRead {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.stdout.read(buf)?;
+
+        // At the end, check that rclone did not fail.
+        if len == 0 && !buf.is_empty() {
+            let status = self.child.wait()?;
+
+            if !status.success() {
+                return Err(std::io::Error::other(format!(
+                    "rclone cat failed with {}",
+                    status
+                )));
+            }
+        }
+
+        Ok(len)
+    }
+}
+
+/// Drops the `RcloneRead`.
+impl Drop for RcloneRead {
+    fn drop(&mut self) {
+        // Stop rclone, if not all data was read.
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// Defines a `RcloneFS`.
+///
+/// Invokes `rclone` as subprocess, which gives access to all its storage providers.
+pub struct RcloneFS {
+    remote: String,
+    rclone_flags: Vec<String>,
+    connected: bool,
+}
+
+/// Methods of `RcloneFS`.
+impl RcloneFS {
+    pub fn new(remote: &str, rclone_flags: &[String]) -> Self {
+        RcloneFS {
+            remote: remote.to_owned(),
+            rclone_flags: rclone_flags.to_vec(),
+            connected: false,
+        }
+    }
+
+    /// Returns the rclone path (`remote:path`) of an abs path.
+    fn remote_path(&self, unicode: &str) -> String {
+        let path = unicode.trim_start_matches('/');
+
+        if path.is_empty() {
+            self.remote.clone()
+        } else if self.remote.ends_with(':') || self.remote.ends_with('/') {
+            format!("{}{}", self.remote, path)
+        } else {
+            format!("{}/{}", self.remote, path)
+        }
+    }
+
+    /// Creates a rclone command with the configured flags.
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(RCLONE);
+        command.args(args).args(&self.rclone_flags);
+        command
+    }
+
+    /// Runs a rclone command and returns its stdout.
+    fn run(&self, args: &[&str]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let output = self.command(args).stdin(Stdio::null()).output()?;
+
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(format!(
+                "rclone {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into())
+        }
+    }
+}
+
+/// Impl of `FS` for `RcloneFS`.
+impl FS for RcloneFS {
+    fn connect(&mut self) -> Result<(), FSError> {
+        // Check, that rclone is installed.
+        self.run(&["version"]).map_err(FSError::ConnectionFailed)?;
+
+        self.connected = true;
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+        self.connected = false;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    fn block_size(&self) -> FSBlockSize {
+        FSBlockSize::new(None, 128 * 1024, None)
+    }
+
+    fn meta(&self, abs_path: &UNPath<Abs>) -> Result<FSMetaData, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let remote_path = self.remote_path(abs_path.to_unicode());
+
+        let stdout = self
+            .run(&["lsjson", "--stat", &remote_path])
+            .map_err(|err| FSError::MetaFailed(abs_path.clone(), err))?;
+
+        let entry: LsJsonEntry = serde_json::from_slice(&stdout)
+            .map_err(|err| FSError::MetaFailed(abs_path.clone(), err.into()))?;
+
+        // Type of entry and abs_path must be the same.
+        if entry.is_dir != abs_path.is_dir() {
+            return Err(FSError::MetaFailed(
+                abs_path.clone(),
+                "Path target mismatch".into(),
+            ));
+        }
+
+        Ok(entry.metadata())
+    }
+
+    fn list_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<Vec<UNPath<Abs>>, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let remote_path = self.remote_path(abs_dir_path.to_unicode());
+
+        let stdout = self
+            .run(&["lsjson", &remote_path])
+            .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err))?;
+
+        let entries: Vec<LsJsonEntry> = serde_json::from_slice(&stdout)
+            .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?;
+
+        entries
+            .iter()
+            .map(|entry| {
+                entry
+                    .abs_path(abs_dir_path)
+                    .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))
+            })
+            .collect()
+    }
+
+    fn remove_file(&self, abs_file_path: &NPath<Abs, File>) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let remote_path = self.remote_path(abs_file_path.to_unicode());
+
+        self.run(&["deletefile", &remote_path])
+            .map(|_stdout| ())
+            .map_err(|err| FSError::RemoveFileFailed(abs_file_path.clone(), err))
+    }
+
+    fn rename_file(
+        &self,
+        src_abs_file_path: &NPath<Abs, File>,
+        dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let src_remote_path = self.remote_path(src_abs_file_path.to_unicode());
+        let dest_remote_path = self.remote_path(dest_abs_file_path.to_unicode());
+
+        self.run(&["moveto", &src_remote_path, &dest_remote_path])
+            .map(|_stdout| ())
+            .map_err(|err| FSError::RenameFailed(src_abs_file_path.clone(), err))
+    }
+
+    fn remove_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let remote_path = self.remote_path(abs_dir_path.to_unicode());
+
+        self.run(&["rmdir", &remote_path])
+            .map(|_stdout| ())
+            .map_err(|err| FSError::RemoveDirFailed(abs_dir_path.clone(), err))
+    }
+
+    fn mkdir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let remote_path = self.remote_path(abs_dir_path.to_unicode());
+
+        self.run(&["mkdir", &remote_path])
+            .map(|_stdout| ())
+            .map_err(|err| FSError::MkDirFailed(abs_dir_path.clone(), err))
+    }
+
+    fn mklink(
+        &self,
+        _abs_sym_path: &NPath<Abs, Symlink>,
+        _symlink_meta: &FSSymlinkMeta,
+    ) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        Err(FSError::NotSupported(
+            "Symlinks are not supported by rclone".into(),
+        ))
+    }
+
+    fn read_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<Box<dyn Read + Send>, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let remote_path = self.remote_path(abs_file_path.to_unicode());
+
+        let mut child = self
+            .command(&["cat", &remote_path])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| FSError::ReadFailed(abs_file_path.clone(), err.into()))?;
+
+        match child.stdout.take() {
+            Some(stdout) => Ok(Box::new(RcloneRead { child, stdout })),
+            None => Err(FSError::ReadFailed(
+                abs_file_path.clone(),
+                "No stdout of rclone cat".into(),
+            )),
+        }
+    }
+
+    fn write_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<FSWrite, FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        let remote_path = self.remote_path(abs_file_path.to_unicode());
+
+        let mut child = self
+            .command(&["rcat", &remote_path])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))?;
+
+        match child.stdin.take() {
+            Some(stdin) => {
+                // Closing stdin finishes the upload.
+                let thread_handle = thread::spawn(move || {
+                    let output = child.wait_with_output()?;
+
+                    // Report a failed upload.
+                    if output.status.success() {
+                        Ok(())
+                    } else {
+                        Err(std::io::Error::other(format!(
+                            "rclone rcat failed with {}: {}",
+                            output.status,
+                            String::from_utf8_lossy(&output.stderr).trim()
+                        )))
+                    }
+                });
+
+                Ok(FSWrite::new(Box::new(stdin), Some(thread_handle)))
+            }
+            None => {
+                let _ = child.kill();
+                Err(FSError::WriteFailed(
+                    abs_file_path.clone(),
+                    "No stdin of rclone rcat".into(),
+                ))
+            }
+        }
+    }
+}
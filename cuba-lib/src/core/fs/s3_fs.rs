@@ -0,0 +1,788 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use quick_xml::Reader;
+use quick_xml::escape::{escape, resolve_predefined_entity};
+use quick_xml::events::Event;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, ETAG, LAST_MODIFIED};
+use reqwest::{Method, Url};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::io::{Read, pipe};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::core::fs::fs_metadata::FSMetaData;
+use crate::core::fs::fs_symlink_meta::FSSymlinkMeta;
+use crate::shared::npath::{Abs, Dir, File, NPath, Rel, Symlink, UNPath};
+
+use super::fs_base::{FS, FSBlockSize, FSError, FSWrite};
+
+/// The size of a part of a multipart upload. Smaller objects are uploaded with a single `PUT`.
+const PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// The timeout to connect the endpoint.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The timeout of sending a request and of each read of a response.
+const READ_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The signed headers of a request.
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// The characters, which are encoded in the query of a request.
+const QUERY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// The characters, which are encoded in the path of a request. The `/` separates the key.
+const PATH_ENCODE_SET: &AsciiSet = &QUERY_ENCODE_SET.remove(b'/');
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Returns `bytes` as lowercase hex.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Returns the SHA-256 of `data` as lowercase hex.
+fn sha256_hex(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+/// Returns the HMAC-SHA256 of `data` with `key`.
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes a key of any size");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Returns the object key of `path` below `prefix`, empty segments are skipped.
+fn object_key(prefix: &str, path: &str) -> String {
+    prefix
+        .split('/')
+        .chain(path.split('/'))
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+/// Decodes a key of a `ListObjectsV2` response with the url encoding type.
+fn decode_key(key: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    Ok(percent_decode_str(&key.replace('+', " "))
+        .decode_utf8()?
+        .into_owned())
+}
+
+/// Reads the next part, which is shorter than `PART_SIZE` only at the end of the data.
+fn read_part(reader: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let mut part = vec![0u8; PART_SIZE];
+    let mut filled = 0;
+
+    while filled < part.len() {
+        match reader.read(&mut part[filled..]) {
+            Ok(0) => break,
+            Ok(bytes_read) => filled += bytes_read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    part.truncate(filled);
+    Ok(part)
+}
+
+/// Returns the texts of the elements of `xml` with their paths, e.g.
+/// `("ListBucketResult/Contents/Key", "dir/file")`.
+fn parse_elements(xml: &str) -> Result<Vec<(String, String)>, Box<dyn Error + Send + Sync>> {
+    let mut reader = Reader::from_str(xml);
+    let mut path: Vec<String> = Vec::new();
+    let mut text = String::new();
+    let mut elements = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => {
+                path.push(String::from_utf8_lossy(element.local_name().as_ref()).into_owned());
+                text.clear();
+            }
+            Event::Text(value) => text.push_str(&value.decode()?),
+            Event::CData(value) => text.push_str(&value.decode()?),
+            Event::GeneralRef(reference) => match reference.resolve_char_ref()? {
+                Some(ch) => text.push(ch),
+                None => {
+                    text.push_str(resolve_predefined_entity(&reference.decode()?).unwrap_or(""))
+                }
+            },
+            Event::End(_) => {
+                elements.push((path.join("/"), std::mem::take(&mut text)));
+                path.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(elements)
+}
+
+/// Returns the text of the element with the `path` in `xml`.
+fn parse_element(xml: &str, path: &str) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+    Ok(parse_elements(xml)?
+        .into_iter()
+        .find(|(element_path, _)| element_path == path)
+        .map(|(_, text)| text))
+}
+
+/// Returns the message of an S3 error response, e.g. `NoSuchBucket: The bucket does not exist`.
+fn parse_error(xml: &str) -> Option<String> {
+    let elements = parse_elements(xml).ok()?;
+
+    let text = |path: &str| {
+        elements
+            .iter()
+            .find(|(element_path, _)| element_path == path)
+            .map(|(_, text)| text.as_str())
+    };
+
+    match (text("Error/Code"), text("Error/Message")) {
+        (Some(code), Some(message)) => Some(format!("{}: {}", code, message)),
+        (Some(code), None) => Some(code.to_string()),
+        _ => None,
+    }
+}
+
+/// Defines a `ListPage`.
+///
+/// A page of a `ListObjectsV2` response.
+#[derive(Default)]
+struct ListPage {
+    keys: Vec<String>,
+    prefixes: Vec<String>,
+    next_token: Option<String>,
+}
+
+/// Methods of `ListPage`.
+impl ListPage {
+    /// Parses the `ListObjectsV2` response `xml`.
+    fn parse(xml: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut page = ListPage::default();
+        let mut truncated = false;
+
+        for (path, text) in parse_elements(xml)? {
+            match path.as_str() {
+                "ListBucketResult/Contents/Key" => page.keys.push(decode_key(&text)?),
+                "ListBucketResult/CommonPrefixes/Prefix" => page.prefixes.push(decode_key(&text)?),
+                "ListBucketResult/IsTruncated" => truncated = text == "true",
+                "ListBucketResult/NextContinuationToken" => page.next_token = Some(text),
+                _ => {}
+            }
+        }
+
+        // The token is only relevant for a truncated list.
+        if !truncated {
+            page.next_token = None;
+        }
+
+        Ok(page)
+    }
+}
+
+/// Defines a `S3Client`.
+///
+/// Sends requests to the bucket, signed with AWS Signature Version 4. The bucket is addressed
+/// path-style, e.g. `https://endpoint/bucket/key`, which all S3-compatible storages support.
+#[derive(Clone)]
+struct S3Client {
+    client: Client,
+    endpoint: Url,
+    region: String,
+    bucket: String,
+    access_key_id: String,
+    secret_key: SecretString,
+}
+
+/// Methods of `S3Client`.
+impl S3Client {
+    /// Returns the AWS Signature Version 4 authorization of a request at the time `now`.
+    fn authorization(
+        &self,
+        method: &Method,
+        path: &str,
+        canonical_query: &str,
+        host: &str,
+        payload_sha256: &str,
+        now: &DateTime<Utc>,
+    ) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method,
+            path,
+            canonical_query,
+            host,
+            payload_sha256,
+            amz_date,
+            SIGNED_HEADERS,
+            payload_sha256
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            sha256_hex(canonical_request.as_bytes())
+        );
+
+        let mut signing_key = hmac_sha256(
+            format!("AWS4{}", self.secret_key.expose_secret()).as_bytes(),
+            &date,
+        );
+
+        for data in [self.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac_sha256(&signing_key, data);
+        }
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            scope,
+            SIGNED_HEADERS,
+            hex(&hmac_sha256(&signing_key, &string_to_sign))
+        )
+    }
+
+    /// Sends a signed request for the object `key` - or the bucket, if empty.
+    ///
+    /// Returns an error, if the response has no success status.
+    fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        payload: Vec<u8>,
+    ) -> Result<Response, Box<dyn Error + Send + Sync>> {
+        let mut url = self.endpoint.clone();
+
+        // Set the path-style path.
+        if key.is_empty() {
+            url.set_path(&format!("/{}", self.bucket));
+        } else {
+            url.set_path(&format!(
+                "/{}/{}",
+                self.bucket,
+                utf8_percent_encode(key, PATH_ENCODE_SET)
+            ));
+        }
+
+        // Set the query, sorted as the canonical query.
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(name, value)| {
+                (
+                    utf8_percent_encode(name, QUERY_ENCODE_SET).to_string(),
+                    utf8_percent_encode(value, QUERY_ENCODE_SET).to_string(),
+                )
+            })
+            .collect();
+        query.sort();
+
+        let canonical_query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<String>>()
+            .join("&");
+
+        url.set_query((!canonical_query.is_empty()).then_some(canonical_query.as_str()));
+
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        // Sign the request.
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_sha256 = sha256_hex(&payload);
+
+        let authorization = self.authorization(
+            &method,
+            url.path(),
+            &canonical_query,
+            &host,
+            &payload_sha256,
+            &now,
+        );
+
+        // Only uploads have a body, but they always need a content length.
+        let has_body = method == Method::PUT || method == Method::POST;
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_sha256)
+            .header(AUTHORIZATION, authorization);
+
+        if has_body {
+            request = request.body(payload);
+        }
+
+        let response = request.send()?;
+        let status = response.status();
+
+        if status.is_success() {
+            Ok(response)
+        } else {
+            let message = response
+                .text()
+                .ok()
+                .and_then(|xml| parse_error(&xml))
+                .unwrap_or_else(|| status.to_string());
+
+            Err(format!("S3 request failed with {}: {}", status, message).into())
+        }
+    }
+
+    /// Lists a page of the objects with the `prefix`, after the continuation `token` - if any.
+    ///
+    /// With `delimiter`, the objects below the next `/` are grouped as common prefixes.
+    fn list_objects(
+        &self,
+        prefix: &str,
+        delimiter: bool,
+        token: Option<&str>,
+        max_keys: Option<&str>,
+    ) -> Result<ListPage, Box<dyn Error + Send + Sync>> {
+        let mut query = vec![
+            ("list-type", "2"),
+            ("encoding-type", "url"),
+            ("prefix", prefix),
+        ];
+
+        if delimiter {
+            query.push(("delimiter", "/"));
+        }
+
+        if let Some(token) = token {
+            query.push(("continuation-token", token));
+        }
+
+        if let Some(max_keys) = max_keys {
+            query.push(("max-keys", max_keys));
+        }
+
+        let response = self.send(Method::GET, "", &query, Vec::new())?;
+
+        ListPage::parse(&response.text()?)
+    }
+
+    /// Uploads the data of `reader` as the object `key`.
+    ///
+    /// Data up to the `PART_SIZE` is uploaded with a single `PUT`, larger data with a
+    /// multipart upload.
+    fn upload(
+        &self,
+        key: &str,
+        reader: &mut impl Read,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let part = read_part(reader)?;
+
+        if part.len() < PART_SIZE {
+            self.send(Method::PUT, key, &[], part)?;
+            return Ok(());
+        }
+
+        // Start the multipart upload.
+        let response = self.send(Method::POST, key, &[("uploads", "")], Vec::new())?;
+
+        let upload_id = parse_element(&response.text()?, "InitiateMultipartUploadResult/UploadId")?
+            .ok_or("No upload id in the response of the multipart upload")?;
+
+        match self.upload_parts(key, &upload_id, part, reader) {
+            Ok(etags) => {
+                let parts: String = etags
+                    .iter()
+                    .enumerate()
+                    .map(|(index, etag)| {
+                        format!(
+                            "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                            index + 1,
+                            escape(etag)
+                        )
+                    })
+                    .collect();
+
+                let body = format!(
+                    "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+                    parts
+                );
+
+                // The completion may fail after the success status, with an error as body.
+                let response = self.send(
+                    Method::POST,
+                    key,
+                    &[("uploadId", &upload_id)],
+                    body.into_bytes(),
+                )?;
+
+                match parse_error(&response.text()?) {
+                    Some(message) => Err(message.into()),
+                    None => Ok(()),
+                }
+            }
+            Err(err) => {
+                // Abort the multipart upload, so that the parts are removed.
+                let _result =
+                    self.send(Method::DELETE, key, &[("uploadId", &upload_id)], Vec::new());
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Uploads the `first` part and the remaining parts of `reader`, returns the ETags.
+    fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        first: Vec<u8>,
+        reader: &mut impl Read,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let mut etags = Vec::new();
+        let mut part = first;
+
+        while !part.is_empty() {
+            let part_number = (etags.len() + 1).to_string();
+
+            let response = self.send(
+                Method::PUT,
+                key,
+                &[("partNumber", &part_number), ("uploadId", upload_id)],
+                part,
+            )?;
+
+            let etag = response
+                .headers()
+                .get(ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .ok_or("No ETag in the response of the part upload")?;
+
+            etags.push(etag.to_string());
+            part = read_part(reader)?;
+        }
+
+        Ok(etags)
+    }
+}
+
+/// Defines a `S3FS`.
+///
+/// S3 has no directories, the paths are mapped as follows:
+/// - A file is the object with the path as key, below the prefix.
+/// - A directory is the key prefix `dir/`. `mkdir` puts an empty marker object `dir/`, so that
+///   empty directories persist, and `remove_dir` deletes it.
+/// - `list_dir` lists the objects with the prefix and the delimiter `/`, the common prefixes are
+///   the subdirectories.
+/// - Symlinks are not supported.
+pub struct S3FS {
+    endpoint: String,
+    region: String,
+    bucket: String,
+    prefix: String,
+    access_key_id: String,
+    secret_key: SecretString,
+    client: Option<S3Client>,
+}
+
+/// Methods of `S3FS`.
+impl S3FS {
+    pub fn new(
+        endpoint: &str,
+        region: &str,
+        bucket: &str,
+        prefix: &str,
+        access_key_id: &str,
+        secret_key: &SecretString,
+    ) -> Self {
+        S3FS {
+            endpoint: endpoint.to_owned(),
+            region: region.to_owned(),
+            bucket: bucket.to_owned(),
+            prefix: prefix.to_owned(),
+            access_key_id: access_key_id.to_owned(),
+            secret_key: secret_key.to_owned(),
+            client: None,
+        }
+    }
+
+    /// Returns the client, if connected.
+    fn client(&self) -> Result<&S3Client, FSError> {
+        self.client.as_ref().ok_or(FSError::NotConnected)
+    }
+
+    /// Returns the object key of an abs path.
+    fn key(&self, unicode: &str) -> String {
+        object_key(&self.prefix, unicode)
+    }
+
+    /// Returns the key prefix of an abs dir path. The root of the bucket has an empty prefix.
+    fn key_prefix(&self, abs_dir_path: &NPath<Abs, Dir>) -> String {
+        let key = self.key(abs_dir_path.to_unicode());
+
+        if key.is_empty() { key } else { key + "/" }
+    }
+
+    /// Lists the files and directories of `abs_dir_path`, page by page.
+    fn list_entries(
+        &self,
+        abs_dir_path: &NPath<Abs, Dir>,
+    ) -> Result<Vec<UNPath<Abs>>, Box<dyn Error + Send + Sync>> {
+        let client = self.client()?;
+        let prefix = self.key_prefix(abs_dir_path);
+
+        let mut entries = Vec::new();
+        let mut token: Option<String> = None;
+
+        loop {
+            let page = client.list_objects(&prefix, true, token.as_deref(), None)?;
+
+            for key in &page.keys {
+                match key.strip_prefix(&prefix) {
+                    // Skip the directory marker.
+                    Some("") | None => {}
+                    Some(name) => {
+                        let rel_file_path = NPath::<Rel, File>::try_from(name)?;
+                        entries.push(UNPath::File(abs_dir_path.add_rel_file(&rel_file_path)));
+                    }
+                }
+            }
+
+            for key_prefix in &page.prefixes {
+                if let Some(name) = key_prefix.strip_prefix(&prefix) {
+                    let rel_dir_path = NPath::<Rel, Dir>::try_from(name.trim_end_matches('/'))?;
+                    entries.push(UNPath::Dir(abs_dir_path.add_rel_dir(&rel_dir_path)));
+                }
+            }
+
+            match page.next_token {
+                Some(next_token) => token = Some(next_token),
+                None => break,
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Impl of `FS` for `S3FS`.
+impl FS for S3FS {
+    fn connect(&mut self) -> Result<(), FSError> {
+        let endpoint =
+            Url::parse(&self.endpoint).map_err(|err| FSError::ConnectionFailed(err.into()))?;
+
+        // The blocking client applies the timeout to the send and to each read, so it limits
+        // stalled transfers only. A request body is at most one part.
+        let client = Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(READ_TIMEOUT)
+            .build()
+            .map_err(|err| FSError::ConnectionFailed(err.into()))?;
+
+        let client = S3Client {
+            client,
+            endpoint,
+            region: self.region.clone(),
+            bucket: self.bucket.clone(),
+            access_key_id: self.access_key_id.clone(),
+            secret_key: self.secret_key.clone(),
+        };
+
+        // Check, that the bucket is accessible.
+        client
+            .send(Method::HEAD, "", &[], Vec::new())
+            .map_err(FSError::ConnectionFailed)?;
+
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), FSError> {
+        if !self.is_connected() {
+            return Err(FSError::NotConnected);
+        }
+
+        self.client = None;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn block_size(&self) -> FSBlockSize {
+        FSBlockSize::new(None, 128 * 1024, None)
+    }
+
+    fn meta(&self, abs_path: &UNPath<Abs>) -> Result<FSMetaData, FSError> {
+        let client = self.client()?;
+
+        match abs_path {
+            UNPath::File(abs_file_path) => {
+                let response = client
+                    .send(
+                        Method::HEAD,
+                        &self.key(abs_file_path.to_unicode()),
+                        &[],
+                        Vec::new(),
+                    )
+                    .map_err(|err| FSError::MetaFailed(abs_path.clone(), err))?;
+
+                let headers = response.headers();
+
+                let modified = headers
+                    .get(LAST_MODIFIED)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+                    .map(SystemTime::from);
+
+                let size = headers
+                    .get(CONTENT_LENGTH)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok());
+
+                // S3 has no creation time.
+                Ok(FSMetaData::new(None, modified, size, None))
+            }
+            UNPath::Dir(abs_dir_path) => {
+                let prefix = self.key_prefix(abs_dir_path);
+
+                // The root always exists.
+                if prefix.is_empty() {
+                    return Ok(FSMetaData::new(None, None, None, None));
+                }
+
+                let page = client
+                    .list_objects(&prefix, false, None, Some("1"))
+                    .map_err(|err| FSError::MetaFailed(abs_path.clone(), err))?;
+
+                if page.keys.is_empty() && page.prefixes.is_empty() {
+                    Err(FSError::MetaFailed(
+                        abs_path.clone(),
+                        "Path does not exist".into(),
+                    ))
+                } else {
+                    Ok(FSMetaData::new(None, None, None, None))
+                }
+            }
+            UNPath::Symlink(_abs_sym_path) => Err(FSError::MetaFailed(
+                abs_path.clone(),
+                "Symlinks are not supported by S3".into(),
+            )),
+        }
+    }
+
+    fn list_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<Vec<UNPath<Abs>>, FSError> {
+        self.client()?;
+
+        self.list_entries(abs_dir_path)
+            .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err))
+    }
+
+    fn remove_file(&self, abs_file_path: &NPath<Abs, File>) -> Result<(), FSError> {
+        let client = self.client()?;
+
+        client
+            .send(
+                Method::DELETE,
+                &self.key(abs_file_path.to_unicode()),
+                &[],
+                Vec::new(),
+            )
+            .map(|_response| ())
+            .map_err(|err| FSError::RemoveFileFailed(abs_file_path.clone(), err))
+    }
+
+    fn remove_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        let client = self.client()?;
+        let prefix = self.key_prefix(abs_dir_path);
+
+        // The root has no marker.
+        if prefix.is_empty() {
+            return Ok(());
+        }
+
+        // Deleting a missing marker succeeds, a directory without marker only exists by its objects.
+        client
+            .send(Method::DELETE, &prefix, &[], Vec::new())
+            .map(|_response| ())
+            .map_err(|err| FSError::RemoveDirFailed(abs_dir_path.clone(), err))
+    }
+
+    fn mkdir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        let client = self.client()?;
+        let prefix = self.key_prefix(abs_dir_path);
+
+        // The root always exists.
+        if prefix.is_empty() {
+            return Ok(());
+        }
+
+        client
+            .send(Method::PUT, &prefix, &[], Vec::new())
+            .map(|_response| ())
+            .map_err(|err| FSError::MkDirFailed(abs_dir_path.clone(), err))
+    }
+
+    fn mklink(
+        &self,
+        _abs_sym_path: &NPath<Abs, Symlink>,
+        _symlink_meta: &FSSymlinkMeta,
+    ) -> Result<(), FSError> {
+        self.client()?;
+
+        Err(FSError::NotSupported(
+            "Symlinks are not supported by S3".into(),
+        ))
+    }
+
+    fn read_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<Box<dyn Read + Send>, FSError> {
+        let client = self.client()?;
+
+        let response = client
+            .send(
+                Method::GET,
+                &self.key(abs_file_path.to_unicode()),
+                &[],
+                Vec::new(),
+            )
+            .map_err(|err| FSError::ReadFailed(abs_file_path.clone(), err))?;
+
+        Ok(Box::new(response))
+    }
+
+    fn write_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<FSWrite, FSError> {
+        let client = self.client()?.clone();
+        let key = self.key(abs_file_path.to_unicode());
+
+        let (mut reader, writer) =
+            pipe().map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))?;
+
+        // Upload the data, while it is written.
+        let thread_handle = thread::spawn(move || {
+            client
+                .upload(&key, &mut reader)
+                .map_err(std::io::Error::other)
+        });
+
+        Ok(FSWrite::new(Box::new(writer), Some(thread_handle)))
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::Read;
+
+use crate::core::fs::fs_base::{FS, FSBlockSize, FSError, FSWrite};
+use crate::core::fs::fs_metadata::FSMetaData;
+use crate::core::fs::fs_symlink_meta::FSSymlinkMeta;
+use crate::shared::npath::{Abs, Dir, File, NPath, Symlink, UNPath};
+
+/// Defines a `TracedFS`.
+///
+/// Wraps a fs and runs each operation in a `tracing` span.
+pub struct TracedFS<F: FS> {
+    fs: F,
+    name: &'static str,
+}
+
+/// Methods of `TracedFS`.
+impl<F: FS> TracedFS<F> {
+    /// Creates a new `TracedFS`, `name` is the type of the fs in the spans.
+    pub fn new(name: &'static str, fs: F) -> Self {
+        Self { fs, name }
+    }
+
+    /// Returns the span of an operation.
+    fn span(&self, operation: &'static str, path: Option<&str>) -> tracing::Span {
+        tracing::info_span!("fs", fs = self.name, operation, path)
+    }
+}
+
+/// Impl of `FS` for `TracedFS`.
+impl<F: FS> FS for TracedFS<F> {
+    fn connect(&mut self) -> Result<(), FSError> {
+        let _entered = self.span("connect", None).entered();
+        self.fs.connect()
+    }
+
+    fn disconnect(&mut self) -> Result<(), FSError> {
+        let _entered = self.span("disconnect", None).entered();
+        self.fs.disconnect()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.fs.is_connected()
+    }
+
+    fn block_size(&self) -> FSBlockSize {
+        self.fs.block_size()
+    }
+
+    fn meta(&self, abs_path: &UNPath<Abs>) -> Result<FSMetaData, FSError> {
+        let _entered = self.span("meta", Some(abs_path.to_unicode())).entered();
+        self.fs.meta(abs_path)
+    }
+
+    fn list_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<Vec<UNPath<Abs>>, FSError> {
+        let _entered = self
+            .span("list_dir", Some(abs_dir_path.to_unicode()))
+            .entered();
+        self.fs.list_dir(abs_dir_path)
+    }
+
+    fn walk_dir_rec(
+        &self,
+        abs_dir_path: &NPath<Abs, Dir>,
+        callback: &mut dyn FnMut(UNPath<Abs>) -> bool,
+        error_callback: &dyn Fn(FSError),
+    ) -> Result<(), FSError> {
+        let _entered = self
+            .span("walk_dir_rec", Some(abs_dir_path.to_unicode()))
+            .entered();
+        self.fs.walk_dir_rec(abs_dir_path, callback, error_callback)
+    }
+
+    fn walk_dir_rec_parallel(
+        &self,
+        abs_dir_path: &NPath<Abs, Dir>,
+        threads: usize,
+        callback: &mut dyn FnMut(UNPath<Abs>) -> bool,
+        error_callback: &dyn Fn(FSError),
+    ) -> Result<(), FSError> {
+        let _entered = self
+            .span("walk_dir_rec_parallel", Some(abs_dir_path.to_unicode()))
+            .entered();
+        self.fs
+            .walk_dir_rec_parallel(abs_dir_path, threads, callback, error_callback)
+    }
+
+    fn remove_file(&self, abs_file_path: &NPath<Abs, File>) -> Result<(), FSError> {
+        let _entered = self
+            .span("remove_file", Some(abs_file_path.to_unicode()))
+            .entered();
+        self.fs.remove_file(abs_file_path)
+    }
+
+    fn remove_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        let _entered = self
+            .span("remove_dir", Some(abs_dir_path.to_unicode()))
+            .entered();
+        self.fs.remove_dir(abs_dir_path)
+    }
+
+    fn mkdir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<(), FSError> {
+        let _entered = self
+            .span("mkdir", Some(abs_dir_path.to_unicode()))
+            .entered();
+        self.fs.mkdir(abs_dir_path)
+    }
+
+    fn mklink(
+        &self,
+        abs_sym_path: &NPath<Abs, Symlink>,
+        symlink_meta: &FSSymlinkMeta,
+    ) -> Result<(), FSError> {
+        let _entered = self
+            .span("mklink", Some(abs_sym_path.to_unicode()))
+            .entered();
+        self.fs.mklink(abs_sym_path, symlink_meta)
+    }
+
+    fn read_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<Box<dyn Read + Send>, FSError> {
+        let _entered = self
+            .span("read_data", Some(abs_file_path.to_unicode()))
+            .entered();
+        self.fs.read_data(abs_file_path)
+    }
+
+    fn write_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<FSWrite, FSError> {
+        let _entered = self
+            .span("write_data", Some(abs_file_path.to_unicode()))
+            .entered();
+        self.fs.write_data(abs_file_path)
+    }
+
+    fn copy_file(
+        &self,
+        src_abs_file_path: &NPath<Abs, File>,
+        dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        let _entered = self
+            .span("copy_file", Some(dest_abs_file_path.to_unicode()))
+            .entered();
+        self.fs.copy_file(src_abs_file_path, dest_abs_file_path)
+    }
+
+    fn rename_file(
+        &self,
+        src_abs_file_path: &NPath<Abs, File>,
+        dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        let _entered = self
+            .span("rename_file", Some(src_abs_file_path.to_unicode()))
+            .entered();
+        self.fs.rename_file(src_abs_file_path, dest_abs_file_path)
+    }
+
+    fn set_meta(&self, abs_path: &UNPath<Abs>, metadata: &FSMetaData) -> Result<(), FSError> {
+        let _entered = self.span("set_meta", Some(abs_path.to_unicode())).entered();
+        self.fs.set_meta(abs_path, metadata)
+    }
+}
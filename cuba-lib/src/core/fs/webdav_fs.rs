@@ -1,20 +1,32 @@
 use chrono::{DateTime, Utc};
+use crossbeam_channel::Sender;
 use percent_encoding::{NON_ALPHANUMERIC, percent_decode_str, percent_encode};
-use quick_xml::Reader;
+use quick_xml::NsReader;
 use quick_xml::escape::unescape;
 use quick_xml::events::Event;
-use reqwest::blocking::RequestBuilder;
-use reqwest::{Method, Url};
+use quick_xml::name::{Namespace, ResolveResult};
+use reqwest::blocking::{Body, RequestBuilder, Response};
+use reqwest::header::{CONTENT_RANGE, HeaderMap};
+use reqwest::redirect::{Attempt, Policy};
+use reqwest::{Method, StatusCode, Url};
 use secrecy::{ExposeSecret, SecretString};
-use std::io::{Read, pipe};
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{PipeReader, Read, Write, pipe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use unicode_normalization::UnicodeNormalization;
 use url::ParseError;
 
 use crate::core::fs::fs_metadata::FSMetaData;
 use crate::core::fs::fs_symlink_meta::FSSymlinkMeta;
+use crate::send_warn;
+use crate::shared::config::AuthType;
+use crate::shared::message::Message;
 use crate::shared::npath::{
     Abs, Dir, File, NPath, NPathComponent, NPathError, NPathRoot, Rel, Symlink, UNPath,
 };
@@ -117,6 +129,130 @@ pub fn make_rel_path_from_str_path(path: &str) -> Result<UNPath<Rel>, NPathError
     }
 }
 
+/// The DAV namespace uri.
+const DAV_NAMESPACE: &[u8] = b"DAV:";
+
+/// Returns true if the resolved namespace is the DAV namespace.
+fn is_dav_namespace(namespace: &ResolveResult) -> bool {
+    matches!(namespace, ResolveResult::Bound(Namespace(uri)) if *uri == DAV_NAMESPACE)
+}
+
+/// The ownCloud namespace uri, also used by Nextcloud.
+const OC_NAMESPACE: &[u8] = b"http://owncloud.org/ns";
+
+/// Returns true if the resolved namespace is the ownCloud namespace.
+fn is_oc_namespace(namespace: &ResolveResult) -> bool {
+    matches!(namespace, ResolveResult::Bound(Namespace(uri)) if *uri == OC_NAMESPACE)
+}
+
+/// The request header with the token of the requested page of a paginated `PROPFIND`.
+const PAGE_TOKEN_HEADER: &str = "OC-Page-Token";
+
+/// Returns the next page token of a paginated `PROPFIND` response.
+///
+/// The token is the text of a `nextpage-token` element of the ownCloud or DAV namespace.
+fn parse_next_page_token(xml: &str) -> Option<String> {
+    let mut reader = NsReader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut xml_buf = Vec::new();
+    let mut in_token = false;
+
+    loop {
+        match reader.read_resolved_event_into(&mut xml_buf) {
+            Ok((namespace, Event::Start(ref element)))
+                if element.name().local_name().as_ref() == b"nextpage-token"
+                    && (is_dav_namespace(&namespace) || is_oc_namespace(&namespace)) =>
+            {
+                in_token = true;
+            }
+            Ok((_, Event::Text(value))) if in_token => {
+                return value
+                    .xml_content()
+                    .ok()
+                    .map(|token| token.to_string())
+                    .filter(|token| !token.is_empty());
+            }
+            Ok((_, Event::End(_))) => in_token = false,
+            Ok((_, Event::Eof)) | Err(_) => return None,
+            _ => {}
+        }
+
+        xml_buf.clear();
+    }
+}
+
+/// Returns the content length of a `PROPFIND` response with depth 0.
+fn parse_content_length(xml: &str) -> Option<u64> {
+    let mut reader = NsReader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut xml_buf = Vec::new();
+    let mut in_length = false;
+
+    loop {
+        match reader.read_resolved_event_into(&mut xml_buf) {
+            Ok((namespace, Event::Start(ref element)))
+                if element.name().local_name().as_ref() == b"getcontentlength"
+                    && is_dav_namespace(&namespace) =>
+            {
+                in_length = true;
+            }
+            Ok((_, Event::Text(value))) if in_length => {
+                return value
+                    .xml_content()
+                    .ok()
+                    .and_then(|length| length.trim().parse::<u64>().ok());
+            }
+            Ok((_, Event::End(_))) => in_length = false,
+            Ok((_, Event::Eof)) | Err(_) => return None,
+            _ => {}
+        }
+
+        xml_buf.clear();
+    }
+}
+
+/// Returns true if both urls have the same scheme, host and port.
+fn same_origin(url_a: &Url, url_b: &Url) -> bool {
+    url_a.scheme() == url_b.scheme()
+        && url_a.host_str() == url_b.host_str()
+        && url_a.port_or_known_default() == url_b.port_or_known_default()
+}
+
+/// Makes the redirect policy for WebDAV requests.
+///
+/// Reqwest rewrites the method to `GET` on 301, 302 and 303 and drops the `Authorization` header
+/// on cross origin redirects. Both would break WebDAV methods, so only method preserving redirects
+/// (307, 308) within the same origin are followed.
+fn make_redirect_policy(follow_redirects: bool, max_redirects: u32) -> Policy {
+    if !follow_redirects {
+        return Policy::none();
+    }
+
+    Policy::custom(move |attempt: Attempt| {
+        if attempt.previous().len() > max_redirects as usize {
+            return attempt.error(format!("Too many redirects (max {})", max_redirects));
+        }
+
+        let preserves_method = matches!(
+            attempt.status(),
+            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+        );
+
+        let preserves_auth = attempt
+            .previous()
+            .first()
+            .is_some_and(|first_url| same_origin(first_url, attempt.url()));
+
+        if preserves_method && preserves_auth {
+            attempt.follow()
+        } else {
+            attempt.stop()
+        }
+    })
+}
+
 /// Defines a `Context`.
 #[derive(PartialEq)]
 enum Context {
@@ -131,46 +267,613 @@ enum Context {
     Getlastmodified,
 }
 
+/// Defines the `WebDAVCapabilities`.
+///
+/// The capabilities of a WebDAV server, as announced by the response to an `OPTIONS` request.
+#[derive(Clone, Debug, Default)]
+pub struct WebDAVCapabilities {
+    /// True if the server supports locking (DAV class 2).
+    pub supports_locking: bool,
+
+    /// True if the server supports versioning (`version-control`).
+    pub supports_versioning: bool,
+
+    /// The highest announced DAV compliance class.
+    pub dav_level: u8,
+
+    /// The allowed methods, if the server sent an `Allow` header.
+    allowed_methods: Option<HashSet<String>>,
+}
+
+/// Methods of `WebDAVCapabilities`.
+impl WebDAVCapabilities {
+    /// Creates `WebDAVCapabilities` from the headers of an `OPTIONS` response.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut capabilities = WebDAVCapabilities::default();
+
+        // Parse the DAV header, e.g. "1, 2, version-control".
+        for value in headers.get_all("DAV") {
+            if let Ok(value) = value.to_str() {
+                for class in value.split(',').map(|class| class.trim()) {
+                    if let Ok(level) = class.parse::<u8>() {
+                        capabilities.dav_level = capabilities.dav_level.max(level);
+                    } else if class.eq_ignore_ascii_case("version-control") {
+                        capabilities.supports_versioning = true;
+                    }
+                }
+            }
+        }
+
+        // Parse the Allow header, e.g. "OPTIONS, GET, PUT, DELETE, PROPFIND, MKCOL".
+        for value in headers.get_all("Allow") {
+            if let Ok(value) = value.to_str() {
+                let allowed_methods = capabilities.allowed_methods.get_or_insert_default();
+
+                for method in value.split(',').map(|method| method.trim()) {
+                    if !method.is_empty() {
+                        allowed_methods.insert(method.to_ascii_uppercase());
+                    }
+                }
+            }
+        }
+
+        capabilities.supports_locking = capabilities.dav_level >= 2
+            && capabilities.supports_method("LOCK")
+            && capabilities.supports_method("UNLOCK");
+
+        capabilities
+    }
+
+    /// Returns true if the method is supported.
+    ///
+    /// If the server did not announce its allowed methods, all methods are assumed to be supported.
+    pub fn supports_method(&self, method: &str) -> bool {
+        match &self.allowed_methods {
+            Some(allowed_methods) => allowed_methods.contains(&method.to_ascii_uppercase()),
+            None => true,
+        }
+    }
+}
+
+/// Defines the `WebDAVTimeouts`.
+///
+/// The timeouts of the different request types.
+#[derive(Clone, Copy, Debug)]
+pub struct WebDAVTimeouts {
+    /// Timeout for establishing a connection.
+    pub connect: Duration,
+
+    /// Timeout for reading requests (e.g. `PROPFIND`, `GET`).
+    pub read: Duration,
+
+    /// Timeout for writing requests (e.g. `PUT`, `MKCOL`).
+    pub write: Duration,
+}
+
+/// Methods of `WebDAVTimeouts`.
+impl WebDAVTimeouts {
+    /// Creates new `WebDAVTimeouts` from seconds.
+    pub fn from_secs(connect_secs: u64, read_secs: u64, write_secs: u64) -> Self {
+        Self {
+            connect: Duration::from_secs(connect_secs),
+            read: Duration::from_secs(read_secs),
+            write: Duration::from_secs(write_secs),
+        }
+    }
+
+    /// Returns the timeout for the method.
+    pub fn for_method(&self, method: &Method) -> Duration {
+        match method.as_str() {
+            "PUT" | "MKCOL" | "DELETE" | "MOVE" | "COPY" | "PROPPATCH" => self.write,
+            _ => self.read,
+        }
+    }
+}
+
+/// Defines the `WebDAVRetry`.
+///
+/// The retries of requests, which failed with a transient error.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WebDAVRetry {
+    /// The number of retries, 0 disables the retries.
+    pub retries: u32,
+
+    /// The backoff before the first retry, it doubles with each retry.
+    pub backoff: Duration,
+}
+
+/// Methods of `WebDAVRetry`.
+impl WebDAVRetry {
+    /// Creates a new `WebDAVRetry` with the backoff in milliseconds.
+    pub fn from_millis(retries: u32, backoff_ms: u64) -> Self {
+        Self {
+            retries,
+            backoff: Duration::from_millis(backoff_ms),
+        }
+    }
+
+    /// Warns about the retry `attempt` (starting at 0) of `url` and sleeps for the backoff.
+    fn wait(
+        &self,
+        sender: &Option<Sender<Arc<dyn Message>>>,
+        url: &Url,
+        attempt: u32,
+        reason: &str,
+    ) {
+        let backoff = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(attempt.min(16)));
+
+        if let Some(sender) = sender {
+            send_warn!(
+                sender,
+                "WebDAV request {} failed ({}), retry {}/{} in {} ms",
+                url,
+                reason,
+                attempt + 1,
+                self.retries,
+                backoff.as_millis()
+            );
+        }
+
+        thread::sleep(backoff);
+    }
+}
+
+/// Returns the reason to retry a request with `result` or `None`, if it must not be retried.
+///
+/// Server errors (5xx), too many requests (429) and connection errors are transient.
+fn retry_reason(result: &reqwest::Result<Response>) -> Option<String> {
+    match result {
+        Ok(response)
+            if response.status().is_server_error()
+                || response.status() == StatusCode::TOO_MANY_REQUESTS =>
+        {
+            Some(format!("status {}", response.status()))
+        }
+        Ok(_) => None,
+        Err(err) if err.is_connect() || err.is_timeout() || err.is_request() || err.is_body() => {
+            Some(err.to_string())
+        }
+        Err(_) => None,
+    }
+}
+
+/// Defines a `SpoolReader`.
+///
+/// A reader, which writes all read data to a spool file. The spool file holds the
+/// complete body after `drain`, so that a failed upload can be restarted from the beginning.
+struct SpoolReader {
+    input: PipeReader,
+    spool: std::fs::File,
+}
+
+/// Methods of `SpoolReader`.
+impl SpoolReader {
+    /// Reads the remaining input into the spool file.
+    fn drain(&mut self) -> std::io::Result<()> {
+        std::io::copy(&mut self.input, &mut self.spool)?;
+        self.spool.flush()
+    }
+}
+
+/// Impl of `Read` for `SpoolReader`.
+impl Read for SpoolReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes_read = self.input.read(buf)?;
+        self.spool.write_all(&buf[..bytes_read])?;
+
+        Ok(bytes_read)
+    }
+}
+
+/// Defines a `SharedSpoolReader`.
+///
+/// The body of the first upload, the spool reader is kept to drain it after a failure.
+struct SharedSpoolReader(Arc<Mutex<SpoolReader>>);
+
+/// Impl of `Read` for `SharedSpoolReader`.
+impl Read for SharedSpoolReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
+
+/// Creates a new spool file, which is only accessible by the owner.
+fn create_spool(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)
+}
+
+/// Uploads the data of `reader` with the `PUT` requests of `make_put`.
+///
+/// Without retries the data is streamed. With retries the data is also written to a spool
+/// file, a retry uploads the complete spool file, so the upload restarts from the beginning.
+fn put_with_retry(
+    make_put: impl Fn() -> RequestBuilder,
+    reader: PipeReader,
+    retry: WebDAVRetry,
+    sender: &Option<Sender<Arc<dyn Message>>>,
+    url: &Url,
+) -> reqwest::Result<Response> {
+    // Stream without retries.
+    if retry.retries == 0 {
+        return make_put().body(Body::new(reader)).send();
+    }
+
+    let spool_path =
+        std::env::temp_dir().join(format!("cuba-webdav-{:016x}.spool", rand::random::<u64>()));
+
+    let spool = match create_spool(&spool_path) {
+        Ok(spool) => spool,
+        Err(_) => return make_put().body(Body::new(reader)).send(),
+    };
+
+    let spool_reader = Arc::new(Mutex::new(SpoolReader {
+        input: reader,
+        spool,
+    }));
+
+    let mut result = make_put()
+        .body(Body::new(SharedSpoolReader(spool_reader.clone())))
+        .send();
+
+    let mut attempt = 0;
+
+    while attempt < retry.retries
+        && let Some(reason) = retry_reason(&result)
+    {
+        // The spool file must hold the complete body.
+        if spool_reader.lock().unwrap().drain().is_err() {
+            break;
+        }
+
+        retry.wait(sender, url, attempt, &reason);
+
+        result = match std::fs::File::open(&spool_path) {
+            Ok(spool) => make_put().body(spool).send(),
+            Err(_) => break,
+        };
+
+        attempt += 1;
+    }
+
+    let _ = std::fs::remove_file(&spool_path);
+
+    result
+}
+
+/// The size of a chunk of a chunked upload in recommended blocks.
+const CHUNK_BLOCKS: usize = 64;
+
+/// Reads from `reader` until `buf` is full or the end of the data, returns the number of bytes.
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(bytes_read) => filled += bytes_read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(filled)
+}
+
+/// Requests the current length of the file at `url` with a `PROPFIND`.
+fn propfind_length(
+    make_request: &impl Fn(Method) -> RequestBuilder,
+) -> Result<u64, Box<dyn Error + Send + Sync>> {
+    let response = make_request(Method::from_bytes(b"PROPFIND").unwrap())
+        .header("Depth", "0")
+        .send()?
+        .error_for_status()?;
+
+    parse_content_length(&response.text()?).ok_or_else(|| "Response has no content length".into())
+}
+
+/// Uploads the data of `reader` in chunks of `chunk_size` bytes with the requests of `make_request`.
+///
+/// The first chunk is uploaded with a plain `PUT`, which replaces the file. The following chunks
+/// are appended with a `PUT` and a `Content-Range` header. If a chunk failed with a transient
+/// error, the current length of the file is requested with a `PROPFIND` and the upload resumes
+/// from there. Only the current chunk is held in memory, so no spool file is needed. After the
+/// last chunk, the length of the file is verified with a `PROPFIND`.
+fn put_chunked(
+    make_request: impl Fn(Method) -> RequestBuilder,
+    mut reader: PipeReader,
+    chunk_size: usize,
+    retry: WebDAVRetry,
+    sender: &Option<Sender<Arc<dyn Message>>>,
+    url: &Url,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut chunk = vec![0u8; chunk_size];
+    let mut offset: u64 = 0;
+
+    loop {
+        let chunk_len = read_chunk(&mut reader, &mut chunk)?;
+
+        // The first chunk is always uploaded, so that empty files are created.
+        if chunk_len == 0 && offset > 0 {
+            break;
+        }
+
+        // The bytes of the chunk, which are on the server.
+        let mut uploaded = 0;
+        let mut attempt = 0;
+
+        while uploaded < chunk_len || chunk_len == 0 {
+            let start = offset + uploaded as u64;
+            let mut request = make_request(Method::PUT).body(chunk[uploaded..chunk_len].to_vec());
+
+            if start > 0 {
+                request = request.header(
+                    CONTENT_RANGE,
+                    format!("bytes {}-{}/*", start, offset + chunk_len as u64 - 1),
+                );
+            }
+
+            let result = request.send();
+
+            match retry_reason(&result) {
+                Some(reason) if attempt < retry.retries => {
+                    retry.wait(sender, url, attempt, &reason);
+                    attempt += 1;
+
+                    // The first chunk replaces the file, so it is uploaded again.
+                    if offset > 0 {
+                        let length = propfind_length(&make_request)?;
+
+                        if length < offset || length > offset + chunk_len as u64 {
+                            return Err(format!(
+                                "Length {} of the file is outside of the chunk {}-{}",
+                                length,
+                                offset,
+                                offset + chunk_len as u64
+                            )
+                            .into());
+                        }
+
+                        uploaded = (length - offset) as usize;
+                    }
+                }
+                _ => {
+                    result?.error_for_status()?;
+                    uploaded = chunk_len;
+
+                    if chunk_len == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+
+        offset += chunk_len as u64;
+
+        // The last chunk.
+        if chunk_len < chunk_size {
+            break;
+        }
+    }
+
+    // Check that the server holds the complete file.
+    let length = propfind_length(&make_request)?;
+
+    if length != offset {
+        return Err(format!(
+            "Length {} of the file differs from the uploaded {}",
+            length, offset
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
 /// Defines a `Resource`.
 pub struct Resource {
     pub abs_path: UNPath<Abs>,
     pub metadata: FSMetaData,
 }
 
+/// Authorizes a request.
+fn authorize(
+    request: RequestBuilder,
+    auth_type: AuthType,
+    username: &str,
+    password: &SecretString,
+) -> RequestBuilder {
+    match auth_type {
+        AuthType::Basic => request.basic_auth(username, Some(password.expose_secret())),
+        AuthType::Bearer => request.bearer_auth(password.expose_secret()),
+    }
+}
+
 /// Defines a `WebDAVFS`.
 pub struct WebDAVFS {
+    url: NPath<Abs, Dir>,
     username: String,
     password: SecretString,
-    timeout_secs: u64,
-    client: reqwest::blocking::Client,
+    auth_type: AuthType,
+    timeouts: WebDAVTimeouts,
+    retry: WebDAVRetry,
+    chunked_upload: bool,
+    follow_redirects: bool,
+    max_redirects: u32,
+    client: Option<reqwest::blocking::Client>,
+    capabilities: Option<WebDAVCapabilities>,
     connected: bool,
+    sender: Option<Sender<Arc<dyn Message>>>,
+    missing_creationdate_warned: AtomicBool,
 }
 
 /// Methods of `WebDAVFS`.
 impl WebDAVFS {
-    pub fn new(username: &str, password: &SecretString, timeout_secs: u64) -> Self {
+    pub fn new(
+        url: &NPath<Abs, Dir>,
+        username: &str,
+        password: &SecretString,
+        auth_type: AuthType,
+        timeouts: WebDAVTimeouts,
+        follow_redirects: bool,
+        max_redirects: u32,
+    ) -> Self {
         WebDAVFS {
+            url: url.clone(),
             username: username.to_owned(),
             password: password.to_owned(),
-            timeout_secs,
-            client: reqwest::blocking::Client::new(),
+            auth_type,
+            timeouts,
+            retry: WebDAVRetry::default(),
+            chunked_upload: false,
+            follow_redirects,
+            max_redirects,
+            client: None,
+            capabilities: None,
             connected: false,
+            sender: None,
+            missing_creationdate_warned: AtomicBool::new(false),
         }
     }
 
-    fn start_request(&self, method: Method, url: &Url) -> RequestBuilder {
+    /// Sets the retries of requests, which failed with a transient error.
+    pub fn with_retry(mut self, retry: WebDAVRetry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets the chunked upload, which resumes failed uploads.
+    ///
+    /// The server must support `PUT` requests with a `Content-Range` header.
+    pub fn with_chunked_upload(mut self, chunked_upload: bool) -> Self {
+        self.chunked_upload = chunked_upload;
+        self
+    }
+
+    /// Sets the sender for warnings.
+    pub fn with_sender(mut self, sender: Sender<Arc<dyn Message>>) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// Warns once, that the server does not return a creation date.
+    fn warn_missing_creationdate(&self) {
+        if let Some(sender) = &self.sender
+            && !self
+                .missing_creationdate_warned
+                .swap(true, Ordering::Relaxed)
+        {
+            send_warn!(
+                sender,
+                "WebDAV server {} returns no creationdate, the last modification is used instead",
+                self.url.to_unicode()
+            );
+        }
+    }
+
+    /// Returns true if the server supports the method.
+    fn supports_method(&self, method: &str) -> bool {
+        match &self.capabilities {
+            Some(capabilities) => capabilities.supports_method(method),
+            None => true,
+        }
+    }
+
+    /// Detects the server capabilities via an `OPTIONS` request.
+    ///
+    /// The request is sent to the configured url, since `OPTIONS *` can't be expressed as url.
+    fn detect_capabilities(&self) -> Result<WebDAVCapabilities, FSError> {
+        let url = make_url_from_abs(&(&self.url).into())
+            .map_err(|err| FSError::ConnectionFailed(err.into()))?;
+
+        let response = self
+            .start_request(Method::OPTIONS, &url)
+            .send()
+            .map_err(|err| FSError::ConnectionFailed(err.into()))?;
+
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(FSError::ConnectionFailed(
+                format!("Server refused access with status {}", response.status()).into(),
+            )),
+            status if status.is_success() => {
+                Ok(WebDAVCapabilities::from_headers(response.headers()))
+            }
+            // Server does not answer OPTIONS, assume all capabilities.
+            _ => Ok(WebDAVCapabilities::default()),
+        }
+    }
+
+    /// Sends the request of `make_request` and retries it on transient errors.
+    ///
+    /// The last result is returned, if the retries are exhausted.
+    fn send_with_retry(
+        &self,
+        url: &Url,
+        make_request: impl Fn() -> RequestBuilder,
+    ) -> reqwest::Result<Response> {
+        let mut attempt = 0;
+
+        loop {
+            let result = make_request().send();
+
+            match retry_reason(&result) {
+                Some(reason) if attempt < self.retry.retries => {
+                    self.retry.wait(&self.sender, url, attempt, &reason);
+                    attempt += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    /// Builds the client with the timeouts and the redirect policy.
+    fn build_client(&self) -> Result<reqwest::blocking::Client, FSError> {
+        reqwest::blocking::Client::builder()
+            .connect_timeout(self.timeouts.connect)
+            .redirect(make_redirect_policy(
+                self.follow_redirects,
+                self.max_redirects,
+            ))
+            .build()
+            .map_err(|err| FSError::ConnectionFailed(err.into()))
+    }
+
+    /// Returns the client, which is built on connect.
+    fn client(&self) -> &reqwest::blocking::Client {
         self.client
-            .request(method, url.clone())
-            .basic_auth(self.username.as_str(), Some(self.password.expose_secret()))
+            .as_ref()
+            .expect("The client is built on connect")
+    }
+
+    fn start_request(&self, method: Method, url: &Url) -> RequestBuilder {
+        let timeout = self.timeouts.for_method(&method);
+
+        authorize(
+            self.client().request(method, url.clone()).timeout(timeout),
+            self.auth_type,
+            &self.username,
+            &self.password,
+        )
     }
 
     fn get_file_size_with_range(&self, abs_path: &UNPath<Abs>) -> Result<u64, FSError> {
         match make_url_from_abs(abs_path) {
             Ok(url) => {
                 let response = self
-                    .start_request(Method::GET, &url)
-                    .header("Range", "bytes=0-0")
-                    .send()
+                    .send_with_retry(&url, || {
+                        self.start_request(Method::GET, &url)
+                            .header("Range", "bytes=0-0")
+                    })
                     .map_err(|err| FSError::MetaFailed(abs_path.clone(), err.into()))?;
 
                 if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
@@ -212,7 +915,7 @@ impl WebDAVFS {
         include_path: bool,
         xml: &str,
     ) -> Result<Vec<Resource>, FSError> {
-        let mut reader = Reader::from_str(xml);
+        let mut reader = NsReader::from_str(xml);
         reader.config_mut().trim_text(true);
         reader.config_mut().expand_empty_elements = true;
 
@@ -229,9 +932,10 @@ impl WebDAVFS {
         let mut modified: Option<SystemTime> = None;
         let mut href_buf = String::new();
 
-        while let Ok(event) = reader.read_event_into(&mut xml_buf) {
+        while let Ok((namespace, event)) = reader.read_resolved_event_into(&mut xml_buf) {
             match event {
-                Event::Start(ref element) => {
+                // Elements of other namespaces are ignored.
+                Event::Start(ref element) if is_dav_namespace(&namespace) => {
                     let name = element.name();
                     let local_name = name.local_name();
                     let local_name_ref = local_name.as_ref();
@@ -282,7 +986,7 @@ impl WebDAVFS {
                         _ => {}
                     }
                 }
-                Event::End(ref element) => {
+                Event::End(ref element) if is_dav_namespace(&namespace) => {
                     let name = element.name();
                     let local_name = name.local_name();
                     let local_name_ref = local_name.as_ref();
@@ -334,6 +1038,12 @@ impl WebDAVFS {
                                 })?);
                         }
                         b"propstat" if context.last() == Some(&Context::Propstat) => {
+                            // Some servers do not return a creation date.
+                            if created.is_none() && modified.is_some() {
+                                created = modified;
+                                self.warn_missing_creationdate();
+                            }
+
                             metadata = Some(FSMetaData::new(created, modified, size, None));
 
                             context.pop();
@@ -421,14 +1131,8 @@ impl WebDAVFS {
                     },
                     Some(&Context::Creationdate) => match value.xml_content() {
                         Ok(xml_content) => {
-                            if let Some(systime) = parse_webdav_datetime(&xml_content) {
-                                created = Some(systime);
-                            } else {
-                                return Err(FSError::MetaFailed(
-                                    choose_path(abs_path, &entry_rel_path),
-                                    "Empty or invalid creation-date".into(),
-                                ));
-                            }
+                            // An invalid creation date falls back to the last modification.
+                            created = parse_webdav_datetime(&xml_content);
                         }
                         Err(err) => {
                             return Err(FSError::MetaFailed(
@@ -472,9 +1176,16 @@ impl WebDAVFS {
             return Err(FSError::NotConnected);
         }
 
+        if !self.supports_method("DELETE") {
+            return Err(FSError::NotSupported(
+                "Server does not allow DELETE, nodes can't be removed".into(),
+            ));
+        }
+
         match make_url_from_abs(abs_path) {
             Ok(url) => {
-                let response = self.start_request(Method::DELETE, &url).send();
+                let response =
+                    self.send_with_retry(&url, || self.start_request(Method::DELETE, &url));
 
                 match response {
                     Ok(res) => {
@@ -526,6 +1237,16 @@ impl WebDAVFS {
 /// Impl of `FS` for `WebDAVFS`.
 impl FS for WebDAVFS {
     fn connect(&mut self) -> Result<(), FSError> {
+        // Build the client only once.
+        if self.client.is_none() {
+            self.client = Some(self.build_client()?);
+        }
+
+        // Detect the capabilities only once.
+        if self.capabilities.is_none() {
+            self.capabilities = Some(self.detect_capabilities()?);
+        }
+
         self.connected = true;
         Ok(())
     }
@@ -551,12 +1272,19 @@ impl FS for WebDAVFS {
             return Err(FSError::NotConnected);
         }
 
+        if !self.supports_method("PROPFIND") {
+            return Err(FSError::NotSupported(
+                "Server does not allow PROPFIND, metadata can't be retrieved".into(),
+            ));
+        }
+
         match make_url_from_abs(abs_path) {
             Ok(url) => {
                 let response = self
-                    .start_request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
-                    .header("Depth", "0")
-                    .send()
+                    .send_with_retry(&url, || {
+                        self.start_request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
+                            .header("Depth", "0")
+                    })
                     .map_err(|err| FSError::MetaFailed(abs_path.clone(), err.into()))?;
 
                 let xml = response
@@ -598,25 +1326,54 @@ impl FS for WebDAVFS {
             return Err(FSError::NotConnected);
         }
 
+        if !self.supports_method("PROPFIND") {
+            return Err(FSError::NotSupported(
+                "Server does not allow PROPFIND, directories can't be listed".into(),
+            ));
+        }
+
         match make_url_from_abs(&abs_dir_path.into()) {
             Ok(url) => {
-                let response = self
-                    .start_request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
-                    .header("Depth", "1")
-                    .send()
-                    .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?;
+                let mut abs_paths: Vec<UNPath<Abs>> = Vec::new();
+                let mut page_token: Option<String> = None;
+
+                // Request the pages, until no next page token is returned.
+                loop {
+                    let response = self
+                        .send_with_retry(&url, || {
+                            let request = self
+                                .start_request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
+                                .header("Depth", "1");
+
+                            match &page_token {
+                                Some(page_token) => request.header(PAGE_TOKEN_HEADER, page_token),
+                                None => request,
+                            }
+                        })
+                        .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?;
 
-                let xml = response
-                    .text()
-                    .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?;
-
-                match self.parse_response(&abs_dir_path.into(), false, &xml) {
-                    Ok(resources) => Ok(resources
-                        .into_iter()
-                        .map(|resource| resource.abs_path)
-                        .collect()),
-                    Err(err) => Err(FSError::ListDirFailed(abs_dir_path.clone(), err.into())),
+                    let xml = response
+                        .text()
+                        .map_err(|err| FSError::ListDirFailed(abs_dir_path.clone(), err.into()))?;
+
+                    match self.parse_response(&abs_dir_path.into(), false, &xml) {
+                        Ok(resources) => abs_paths
+                            .extend(resources.into_iter().map(|resource| resource.abs_path)),
+                        Err(err) => {
+                            return Err(FSError::ListDirFailed(abs_dir_path.clone(), err.into()));
+                        }
+                    }
+
+                    // A repeated token would never end.
+                    match parse_next_page_token(&xml) {
+                        Some(next_page_token) if page_token.as_ref() != Some(&next_page_token) => {
+                            page_token = Some(next_page_token);
+                        }
+                        _ => break,
+                    }
                 }
+
+                Ok(abs_paths)
             }
             Err(err) => Err(FSError::ListDirFailed(abs_dir_path.clone(), err.into())),
         }
@@ -635,11 +1392,17 @@ impl FS for WebDAVFS {
             return Err(FSError::NotConnected);
         }
 
+        if !self.supports_method("MKCOL") {
+            return Err(FSError::NotSupported(
+                "Server does not allow MKCOL, directories can't be created".into(),
+            ));
+        }
+
         match make_url_from_abs(&abs_dir_path.into()) {
             Ok(url) => {
-                let response = self
-                    .start_request(Method::from_bytes(b"MKCOL").unwrap(), &url)
-                    .send();
+                let response = self.send_with_retry(&url, || {
+                    self.start_request(Method::from_bytes(b"MKCOL").unwrap(), &url)
+                });
 
                 match response {
                     Ok(res) => {
@@ -668,7 +1431,9 @@ impl FS for WebDAVFS {
             return Err(FSError::NotConnected);
         }
 
-        Err(FSError::NotSupported)
+        Err(FSError::NotSupported(
+            "Symlinks are not supported by WebDAV".into(),
+        ))
     }
 
     fn read_data(&self, abs_file_path: &NPath<Abs, File>) -> Result<Box<dyn Read + Send>, FSError> {
@@ -679,9 +1444,7 @@ impl FS for WebDAVFS {
         match make_url_from_abs(&abs_file_path.into()) {
             Ok(url) => {
                 let response = self
-                    .start_request(Method::GET, &url)
-                    .timeout(std::time::Duration::from_secs(self.timeout_secs))
-                    .send()
+                    .send_with_retry(&url, || self.start_request(Method::GET, &url))
                     .map_err(|err| FSError::ReadFailed(abs_file_path.clone(), err.into()))?;
 
                 let response = response
@@ -699,23 +1462,56 @@ impl FS for WebDAVFS {
             return Err(FSError::NotConnected);
         }
 
+        if !self.supports_method("PUT") {
+            return Err(FSError::NotSupported(
+                "Server does not allow PUT, files can't be written".into(),
+            ));
+        }
+
         match make_url_from_abs(&abs_file_path.into()) {
             Ok(url) => {
-                let client = Arc::new(self.client.clone());
+                let client = Arc::new(self.client().clone());
                 let username = self.username.clone();
                 let password = self.password.clone();
-                let timeout_secs = self.timeout_secs;
+                let auth_type = self.auth_type;
+                let timeouts = self.timeouts;
+                let retry = self.retry;
+                let sender = self.sender.clone();
+                let chunk_size = self
+                    .chunked_upload
+                    .then(|| self.block_size().recommended * CHUNK_BLOCKS);
 
                 let (reader, writer) = pipe()
                     .map_err(|err| FSError::WriteFailed(abs_file_path.clone(), err.into()))?;
 
                 let thread_handle = thread::spawn(move || {
-                    let _result = client
-                        .request(Method::PUT, url.clone())
-                        .timeout(std::time::Duration::from_secs(timeout_secs))
-                        .basic_auth(username, Some(password.expose_secret()))
-                        .body(reqwest::blocking::Body::new(reader))
-                        .send();
+                    let make_request = |method: Method| {
+                        let timeout = timeouts.for_method(&method);
+
+                        authorize(
+                            client.request(method, url.clone()).timeout(timeout),
+                            auth_type,
+                            &username,
+                            &password,
+                        )
+                    };
+
+                    match chunk_size {
+                        Some(chunk_size) => {
+                            put_chunked(make_request, reader, chunk_size, retry, &sender, &url)
+                                .map_err(std::io::Error::other)
+                        }
+                        None => put_with_retry(
+                            || make_request(Method::PUT),
+                            reader,
+                            retry,
+                            &sender,
+                            &url,
+                        )
+                        .and_then(|response| response.error_for_status())
+                        .map(|_response| ())
+                        .map_err(std::io::Error::other),
+                    }
                 });
 
                 Ok(FSWrite::new(Box::new(writer), Some(thread_handle)))
@@ -723,4 +1519,87 @@ impl FS for WebDAVFS {
             Err(err) => Err(FSError::WriteFailed(abs_file_path.clone(), err.into())),
         }
     }
+
+    fn copy_file(
+        &self,
+        src_abs_file_path: &NPath<Abs, File>,
+        dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        if !self.supports_method("COPY") {
+            return Err(FSError::NotSupported(
+                "Server does not allow COPY, files can't be copied".into(),
+            ));
+        }
+
+        let src_url = make_url_from_abs(&src_abs_file_path.into())
+            .map_err(|err| FSError::WriteFailed(dest_abs_file_path.clone(), err.into()))?;
+        let dest_url = make_url_from_abs(&dest_abs_file_path.into())
+            .map_err(|err| FSError::WriteFailed(dest_abs_file_path.clone(), err.into()))?;
+
+        // The server can only copy its own files.
+        if !same_origin(&src_url, &dest_url) {
+            return Err(FSError::NotSupported(
+                "The source is not on the same server".into(),
+            ));
+        }
+
+        // Copy on the server (RFC 4918), the source url is the request url.
+        let response = self
+            .start_request(Method::from_bytes(b"COPY").unwrap(), &src_url)
+            .header("Destination", dest_url.as_str())
+            .header("Overwrite", "T")
+            .send()
+            .map_err(|err| FSError::WriteFailed(dest_abs_file_path.clone(), err.into()))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(FSError::WriteFailed(
+                dest_abs_file_path.clone(),
+                format!("Copy was not successful: {}", response.status()).into(),
+            ))
+        }
+    }
+
+    fn rename_file(
+        &self,
+        src_abs_file_path: &NPath<Abs, File>,
+        dest_abs_file_path: &NPath<Abs, File>,
+    ) -> Result<(), FSError> {
+        if !self.connected {
+            return Err(FSError::NotConnected);
+        }
+
+        if !self.supports_method("MOVE") {
+            return Err(FSError::NotSupported(
+                "Server does not allow MOVE, files can't be renamed".into(),
+            ));
+        }
+
+        let src_url = make_url_from_abs(&src_abs_file_path.into())
+            .map_err(|err| FSError::RenameFailed(src_abs_file_path.clone(), err.into()))?;
+        let dest_url = make_url_from_abs(&dest_abs_file_path.into())
+            .map_err(|err| FSError::RenameFailed(src_abs_file_path.clone(), err.into()))?;
+
+        // Move on the server (RFC 4918), the source url is the request url.
+        let response = self
+            .start_request(Method::from_bytes(b"MOVE").unwrap(), &src_url)
+            .header("Destination", dest_url.as_str())
+            .header("Overwrite", "T")
+            .send()
+            .map_err(|err| FSError::RenameFailed(src_abs_file_path.clone(), err.into()))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(FSError::RenameFailed(
+                src_abs_file_path.clone(),
+                format!("Move was not successful: {}", response.status()).into(),
+            ))
+        }
+    }
 }
@@ -1,83 +1,228 @@
 use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use unicode_normalization::UnicodeNormalization;
 
-use crate::shared::npath::{Rel, UNPath};
+use crate::shared::npath::{Abs, File, NPath, Rel, UNPath};
+
+/// Defines a `GlobPattern`.
+///
+/// A pattern normalized to `.gitignore` semantics:
+///
+/// - a leading `/` anchors the pattern to the base directory and is removed
+/// - a trailing `/` matches only directories and is removed
+/// - a pattern without `/` matches at any level, so `**/` is prepended
+struct GlobPattern {
+    glob: String,
+    dir_only: bool,
+}
+
+/// Methods of `GlobPattern`
+impl GlobPattern {
+    /// Creates a new `GlobPattern`
+    fn new(pattern: &str) -> Self {
+        let mut glob: String = pattern.nfc().collect();
+
+        // A trailing slash matches only directories.
+        let dir_only = glob.ends_with('/');
+        let trimmed = glob.trim_end_matches('/');
+
+        glob = if let Some(anchored) = trimmed.strip_prefix('/') {
+            // A leading slash anchors the pattern.
+            anchored.to_string()
+        } else if !trimmed.contains('/') && !trimmed.starts_with("**") {
+            // A pattern without slash matches at any level.
+            format!("**/{}", trimmed)
+        } else {
+            trimmed.to_string()
+        };
+
+        GlobPattern { glob, dir_only }
+    }
+
+    /// Returns the glob matching all descendants.
+    fn descendants_glob(&self) -> String {
+        format!("{}/**", self.glob)
+    }
+
+    /// Returns the globs of all predecessor directories.
+    ///
+    /// For `src/**/*.log` these are `src` and `src/**`, since `**` can be any number of directories.
+    fn predecessor_globs(&self) -> Vec<String> {
+        let mut globs = Vec::new();
+        let mut prefix = String::new();
+
+        let components: Vec<&str> = self.glob.split('/').collect();
+
+        for component in &components[..components.len() - 1] {
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(component);
+
+            if *component == "**" {
+                // All directories below are predecessors.
+                globs.push(prefix.clone());
+                break;
+            }
+
+            globs.push(prefix.clone());
+        }
+
+        globs
+    }
+}
+
+/// Builds a glob.
+fn build_glob(glob: &str, case_sensitive: bool) -> Result<globset::Glob, globset::Error> {
+    GlobBuilder::new(glob)
+        .literal_separator(true)
+        .case_insensitive(!case_sensitive)
+        .build()
+}
+
+/// Validates a pattern, like the `GlobMatcher` would build it.
+pub fn validate_pattern(pattern: &str) -> Result<(), globset::Error> {
+    let glob_pattern = GlobPattern::new(pattern);
+
+    build_glob(&glob_pattern.glob, true)?;
+    build_glob(&glob_pattern.descendants_glob(), true)?;
+
+    for predecessor_glob in glob_pattern.predecessor_globs() {
+        build_glob(&predecessor_glob, true)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the names, which match a pattern, e.g. the names of profiles.
+pub fn match_names<'a>(
+    pattern: &str,
+    names: impl Iterator<Item = &'a String>,
+) -> Result<Vec<String>, globset::Error> {
+    let matcher = build_glob(pattern, true)?.compile_matcher();
+
+    let mut matched_names: Vec<String> = names
+        .filter(|name| matcher.is_match(name.as_str()))
+        .cloned()
+        .collect();
+
+    matched_names.sort();
+    Ok(matched_names)
+}
+
+/// Reads the patterns of a `.gitignore`-style file.
+///
+/// Blank lines and `#` comments are skipped.
+pub fn read_patterns_file(abs_file_path: &NPath<Abs, File>) -> Result<Vec<String>, std::io::Error> {
+    let content = std::fs::read_to_string(abs_file_path.as_os_path())?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
 
 /// Defines a `GlobMatcher`
+#[derive(Clone)]
 pub struct GlobMatcher {
-    patterns: Vec<String>,
     globset: GlobSet,
+    dir_globset: GlobSet,
+    descendants_globset: GlobSet,
+    predecessors_globset: GlobSet,
 }
 
 /// Methods of `GlobMatcher`
 impl GlobMatcher {
     /// Creates a new `GlobMatcher`
-    pub fn new(patterns: &Vec<String>) -> Result<Self, globset::Error> {
+    pub fn new(patterns: &Vec<String>, case_sensitive: bool) -> Result<Self, globset::Error> {
         let mut builder = GlobSetBuilder::new();
+        let mut dir_builder = GlobSetBuilder::new();
+        let mut descendants_builder = GlobSetBuilder::new();
+        let mut predecessors_builder = GlobSetBuilder::new();
 
         for pattern in patterns {
-            let glob = GlobBuilder::new(pattern).literal_separator(true).build()?;
-            builder.add(glob);
-        }
+            let glob_pattern = GlobPattern::new(pattern);
 
-        let globset = builder.build()?;
+            if glob_pattern.dir_only {
+                dir_builder.add(build_glob(&glob_pattern.glob, case_sensitive)?);
+            } else {
+                builder.add(build_glob(&glob_pattern.glob, case_sensitive)?);
+            }
+
+            descendants_builder.add(build_glob(
+                &glob_pattern.descendants_glob(),
+                case_sensitive,
+            )?);
+
+            for predecessor_glob in glob_pattern.predecessor_globs() {
+                predecessors_builder.add(build_glob(&predecessor_glob, case_sensitive)?);
+            }
+        }
 
         Ok(GlobMatcher {
-            patterns: patterns.to_vec(),
-            globset,
+            globset: builder.build()?,
+            dir_globset: dir_builder.build()?,
+            descendants_globset: descendants_builder.build()?,
+            predecessors_globset: predecessors_builder.build()?,
         })
     }
 
+    /// Returns true if a pattern matches `path` or one of its predecessors.
+    fn is_match(&self, path: &UNPath<Rel>) -> bool {
+        let nfc_path = path.to_nfc();
+
+        self.globset.is_match(nfc_path)
+            || (path.is_dir() && self.dir_globset.is_match(nfc_path))
+            || self.descendants_globset.is_match(nfc_path)
+    }
+
     /// Returns a `IncludeMatcher`
     pub fn include_matcher(&self) -> IncludeMatcher {
         IncludeMatcher {
-            patterns: self.patterns.clone(),
-            globset: self.globset.clone(),
+            glob_matcher: self.clone(),
         }
     }
 
     /// Returns a `ExcludeMatcher`
     pub fn exclude_matcher(&self) -> ExcludeMatcher {
         ExcludeMatcher {
-            globset: self.globset.clone(),
+            glob_matcher: self.clone(),
         }
     }
 }
 
 /// Defines a `IncludeMatcher`
 pub struct IncludeMatcher {
-    patterns: Vec<String>,
-    globset: GlobSet,
+    glob_matcher: GlobMatcher,
 }
 
 /// Methods of `IncludeMatcher`
 impl IncludeMatcher {
     /// Returns true if a pattern matches `path`
     pub fn is_match(&self, path: &UNPath<Rel>) -> bool {
-        if self.globset.is_match(path.to_path()) {
+        if self.glob_matcher.is_match(path) {
             true
         } else {
             // All predecessor directories of a pattern must also be matched.
-            for pattern in &self.patterns {
-                if pattern.nfc().to_string().starts_with(path.to_nfc()) {
-                    return true;
-                }
-            }
-
-            false
+            path.is_dir()
+                && self
+                    .glob_matcher
+                    .predecessors_globset
+                    .is_match(path.to_nfc())
         }
     }
 }
 
 /// Defines a `ExcludeMatcher`
 pub struct ExcludeMatcher {
-    globset: GlobSet,
+    glob_matcher: GlobMatcher,
 }
 
 /// Methods of `ExcludeMatcher`
 impl ExcludeMatcher {
-    /// Returns true if a pattern matches `path`    
+    /// Returns true if a pattern matches `path`
     pub fn is_match(&self, path: &UNPath<Rel>) -> bool {
-        self.globset.is_match(path.to_path())
+        self.glob_matcher.is_match(path)
     }
 }
@@ -0,0 +1,189 @@
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, TimeDelta, Utc};
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::error::Error;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use crate::send_error;
+use crate::shared::message::{Info, Message};
+use crate::shared::msg_receiver::trace_error;
+use crate::shared::npath::{Dir, File, NPath, Rel, UNPath};
+use crate::shared::task_message::TaskInfo;
+
+use super::fs::fs_base::{FSMount, FSWrite};
+
+// The journal dir as rel path.
+lazy_static! {
+    pub static ref JOURNAL_REL_DIR: NPath<Rel, Dir> =
+        NPath::<Rel, Dir>::try_from("cuba-journal").unwrap();
+}
+
+/// The prefix of the journal file names.
+const JOURNAL_FILE_PREFIX: &str = "cuba-journal-";
+
+/// The extension of the journal file names.
+const JOURNAL_FILE_EXTENSION: &str = ".jsonl";
+
+/// The timestamp format in the journal file names.
+const JOURNAL_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Defines a `JournalEvent`.
+///
+/// A line of the journal.
+#[derive(Serialize)]
+struct JournalEvent {
+    timestamp: String,
+    path: String,
+    event: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Defines a `Journal`.
+///
+/// Records the task completion events of a run. Each event is appended and flushed as one
+/// json line to `cuba-journal/cuba-journal-<timestamp>.jsonl`, so an aborted run keeps
+/// the events up to the abort.
+pub struct Journal {
+    writer: Mutex<Option<FSWrite>>,
+    sender: Sender<Arc<dyn Message>>,
+}
+
+/// Methods of `Journal`.
+impl Journal {
+    /// Opens a new `Journal` in the journal dir of `fs_mnt`, the run starts now.
+    ///
+    /// Returns `None`, if the journal can't be created.
+    pub fn open(fs_mnt: &FSMount, sender: &Sender<Arc<dyn Message>>) -> Option<Self> {
+        let fs = fs_mnt.fs.read().unwrap();
+
+        // Create the journal dir.
+        let journal_abs_dir_path = fs_mnt.abs_dir_path.add_rel_dir(&JOURNAL_REL_DIR);
+
+        if fs.meta(&journal_abs_dir_path.clone().into()).is_err()
+            && let Err(err) = fs.mkdir(&journal_abs_dir_path)
+        {
+            send_error!(sender, err);
+            return None;
+        }
+
+        // Open journal.
+        match fs.write_data(&fs_mnt.abs_dir_path.add_rel_file(&rel_path(Utc::now()))) {
+            Ok(writer) => Some(Self {
+                writer: Mutex::new(Some(writer)),
+                sender: sender.clone(),
+            }),
+            Err(err) => {
+                send_error!(sender, err);
+                None
+            }
+        }
+    }
+
+    /// Records an info of a task, only completion events are recorded.
+    pub fn record_info(&self, rel_path: &UNPath<Rel>, info: &(dyn Info + Send + Sync)) {
+        if let Some(task_info) = info.as_any().downcast_ref::<TaskInfo>()
+            && matches!(
+                task_info,
+                TaskInfo::Transferred | TaskInfo::UpToDate | TaskInfo::Verified
+            )
+        {
+            self.record(rel_path, task_info.to_string(), None);
+        }
+    }
+
+    /// Records an error of a task.
+    pub fn record_error(&self, rel_path: &UNPath<Rel>, error: &(dyn Error + Send + Sync)) {
+        self.record(rel_path, "Error".to_string(), Some(trace_error(error)));
+    }
+
+    /// Appends an event as json line.
+    ///
+    /// After a failed write, the journal records no more events.
+    fn record(&self, rel_path: &UNPath<Rel>, event: String, error: Option<String>) {
+        let mut line = match serde_json::to_vec(&JournalEvent {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            path: rel_path.to_unicode().to_string(),
+            event,
+            error,
+        }) {
+            Ok(line) => line,
+            Err(err) => {
+                send_error!(self.sender, err);
+                return;
+            }
+        };
+
+        line.push(b'\n');
+
+        let mut writer = self.writer.lock().unwrap();
+
+        if let Some(journal_writer) = writer.as_mut()
+            && let Err(err) = journal_writer
+                .write_all(&line)
+                .and_then(|()| journal_writer.flush())
+        {
+            send_error!(self.sender, err);
+            *writer = None;
+        }
+    }
+
+    /// Finishes the journal, also of a canceled run.
+    pub fn finish(&self) {
+        if let Some(writer) = self.writer.lock().unwrap().take()
+            && let Err(err) = writer.finish()
+        {
+            send_error!(self.sender, err);
+        }
+    }
+}
+
+/// Returns the rel path of the journal file of the run started at `started`.
+fn rel_path(started: DateTime<Utc>) -> NPath<Rel, File> {
+    NPath::<Rel, File>::try_from(format!(
+        "{}/{}{}{}",
+        JOURNAL_REL_DIR.to_unicode(),
+        JOURNAL_FILE_PREFIX,
+        started.format(JOURNAL_TIMESTAMP_FORMAT),
+        JOURNAL_FILE_EXTENSION
+    ))
+    .unwrap()
+}
+
+/// Removes the journals of `fs_mnt`, which are older than `retention_days`.
+///
+/// A retention of 0 days keeps all journals.
+pub fn clean_journals(fs_mnt: &FSMount, retention_days: u32, sender: &Sender<Arc<dyn Message>>) {
+    if retention_days == 0 {
+        return;
+    }
+
+    let fs = fs_mnt.fs.read().unwrap();
+    let journal_abs_dir_path = fs_mnt.abs_dir_path.add_rel_dir(&JOURNAL_REL_DIR);
+    let oldest = Utc::now().naive_utc() - TimeDelta::days(retention_days as i64);
+
+    let abs_paths = match fs.list_dir(&journal_abs_dir_path) {
+        Ok(abs_paths) => abs_paths,
+        Err(err) => {
+            send_error!(sender, err);
+            return;
+        }
+    };
+
+    for abs_path in abs_paths {
+        if let UNPath::File(abs_file_path) = abs_path
+            && let Some(file_name) = abs_file_path.to_unicode().rsplit('/').next()
+            && let Some(timestamp) = file_name
+                .strip_prefix(JOURNAL_FILE_PREFIX)
+                .and_then(|file_name| file_name.strip_suffix(JOURNAL_FILE_EXTENSION))
+            && let Ok(timestamp) =
+                NaiveDateTime::parse_from_str(timestamp, JOURNAL_TIMESTAMP_FORMAT)
+            && timestamp < oldest
+            && let Err(err) = fs.remove_file(&abs_file_path)
+        {
+            send_error!(sender, err);
+        }
+    }
+}
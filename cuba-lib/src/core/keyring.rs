@@ -149,3 +149,23 @@ pub fn get_password_ids() -> Result<HashSet<String>, KeyringError> {
 
     Ok(set)
 }
+
+/// Returns the sorted list of password ids, which have a stored password.
+///
+/// The keyring has no portable way to enumerate the entries of a service,
+/// so the ids of the index are checked against the keyring.
+pub fn list_passwords() -> Result<Vec<String>, KeyringError> {
+    let mut ids = Vec::new();
+
+    for id in get_password_ids()? {
+        match keyring_entry(&id)?.get_password() {
+            Ok(_) => ids.push(id),
+            // Removed outside of cuba.
+            Err(keyring::Error::NoEntry) => {}
+            Err(err) => return Err(KeyringError::RetrieveCredential(err.to_string())),
+        }
+    }
+
+    ids.sort();
+    Ok(ids)
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::shared::config::SignatureAlgo;
+
+use super::csv_export::flag_names;
+use super::transferred_node::{Backup, TransferredNodes, list_src_paths};
+
+/// The header of the manifest, it describes what the signatures cover.
+const MANIFEST_HEADER: &str = "signatures of the source files before compression and \
+    encryption, they match the restored files, or the backup files if stored uncompressed and \
+    unencrypted";
+
+/// Defines a `ManifestFormat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ManifestFormat {
+    /// A `b3sum` or `sha256sum` style line per file, checked with `-c` of the tool.
+    #[default]
+    Text,
+
+    /// A JSON document with size and flags per file.
+    Json,
+}
+
+/// Defines a `ManifestEntry`.
+#[derive(Serialize)]
+struct ManifestEntry {
+    dest_path: String,
+    signature_algo: SignatureAlgo,
+    signature_hex: String,
+    size_bytes: Option<u64>,
+    flags: String,
+}
+
+/// Defines a `Manifest`.
+#[derive(Serialize)]
+struct Manifest {
+    header: String,
+    entries: Vec<ManifestEntry>,
+}
+
+/// Returns the manifest entries of the nodes with a signature, sorted by the src path.
+fn manifest_entries(transferred_nodes: &TransferredNodes) -> Vec<ManifestEntry> {
+    let view = transferred_nodes.view::<Backup>();
+
+    list_src_paths(transferred_nodes)
+        .iter()
+        .filter_map(|src_rel_path| view.get_node_for_src(src_rel_path))
+        .filter_map(|node| {
+            node.src_signature.map(|signature| ManifestEntry {
+                dest_path: view.get_dest_rel_path(node).to_unicode().to_string(),
+                signature_algo: node.signature_algo,
+                signature_hex: blake3::Hash::from_bytes(signature).to_hex().to_string(),
+                size_bytes: node.src_size,
+                flags: flag_names(node.flags),
+            })
+        })
+        .collect()
+}
+
+/// Writes the signatures of the transferred nodes as manifest to the file at `output_path`.
+///
+/// Returns the number of entries. Nodes without a signature (dirs, symlinks) are skipped.
+pub fn export_manifest(
+    transferred_nodes: &TransferredNodes,
+    output_path: &str,
+    format: ManifestFormat,
+) -> std::io::Result<usize> {
+    let entries = manifest_entries(transferred_nodes);
+    let entry_count = entries.len();
+
+    // The header names the algorithms, a backup can have more after a change of the algorithm.
+    let signature_algos = entries
+        .iter()
+        .map(|entry| entry.signature_algo.to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join(", ");
+    let header = format!("{} {}", signature_algos, MANIFEST_HEADER)
+        .trim_start()
+        .to_string();
+
+    let mut writer = BufWriter::new(File::create(output_path)?);
+
+    match format {
+        ManifestFormat::Text => {
+            writeln!(writer, "# {}", header)?;
+
+            for entry in entries {
+                writeln!(writer, "{}  {}", entry.signature_hex, entry.dest_path)?;
+            }
+        }
+        ManifestFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &Manifest { header, entries })?;
+            writeln!(writer)?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(entry_count)
+}
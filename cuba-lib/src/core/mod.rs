@@ -1,16 +1,43 @@
+// Most of the core is only used by `Cuba`, which is not available on wasm.
+#![cfg_attr(target_arch = "wasm32", allow(dead_code))]
+
+#[cfg(not(target_arch = "wasm32"))]
 pub mod cuba;
 
+#[cfg(not(target_arch = "wasm32"))]
 mod backup;
+#[cfg(not(target_arch = "wasm32"))]
 mod clean;
+#[cfg(not(target_arch = "wasm32"))]
+mod csv_export;
+#[cfg(not(target_arch = "wasm32"))]
 mod cuba_json;
-mod fs;
-mod glob_matcher;
+#[cfg(not(target_arch = "wasm32"))]
+mod dedup_index;
+#[cfg(not(target_arch = "wasm32"))]
+mod filename_cipher;
+pub mod fs;
+pub(crate) mod glob_matcher;
+#[cfg(not(target_arch = "wasm32"))]
+mod journal;
+#[cfg(not(target_arch = "wasm32"))]
 mod keyring;
+#[cfg(not(target_arch = "wasm32"))]
+mod manifest_export;
+#[cfg(not(target_arch = "wasm32"))]
 mod password_cache;
+#[cfg(not(target_arch = "wasm32"))]
 mod process_data;
+#[cfg(not(target_arch = "wasm32"))]
+mod prune;
+#[cfg(not(target_arch = "wasm32"))]
 mod restore;
+#[cfg(not(target_arch = "wasm32"))]
 mod run_state;
+#[cfg(not(target_arch = "wasm32"))]
 mod tasks;
-mod transferred_node;
+pub(crate) mod transferred_node;
+#[cfg(not(target_arch = "wasm32"))]
 mod util;
+#[cfg(not(target_arch = "wasm32"))]
 mod verify;
@@ -16,3 +16,38 @@ pub type DataProcessor = Arc<
         + Send
         + Sync,
 >;
+
+/// Defines a `TracedReader`.
+///
+/// A reader that reads the output of a data processor stage in a `tracing` span.
+struct TracedReader {
+    reader: Box<dyn Read + Send>,
+    span: tracing::Span,
+    bytes: u64,
+}
+
+/// Impl of `Read` for `TracedReader`.
+impl Read for TracedReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let _entered = self.span.enter();
+
+        let bytes_read = self.reader.read(buf)?;
+        self.bytes += bytes_read as u64;
+        self.span.record("bytes", self.bytes);
+
+        Ok(bytes_read)
+    }
+}
+
+/// Wraps the output of the data processor `stage` for `path` in a `tracing` span.
+pub fn traced_stage(
+    reader: Box<dyn Read + Send>,
+    stage: usize,
+    path: &str,
+) -> Box<dyn Read + Send> {
+    Box::new(TracedReader {
+        reader,
+        span: tracing::info_span!("data_processor", stage, path, bytes = tracing::field::Empty),
+        bytes: 0,
+    })
+}
@@ -1,6 +1,9 @@
 use crossbeam_channel::Sender;
 use flate2::{Compression, read::GzDecoder, read::GzEncoder};
-use std::{io::Read, sync::Arc};
+use std::{
+    io::{Cursor, Read},
+    sync::Arc,
+};
 
 use crate::shared::{
     message::Message,
@@ -9,6 +12,69 @@ use crate::shared::{
 
 use super::data_processor::DataProcessor;
 
+/// The number of bytes to detect compressed formats.
+const MAGIC_LEN: usize = 8;
+
+/// Returns true if `head` starts like an already compressed format.
+///
+/// Detects ZIP, GZIP, JPEG, MP4 and PDF.
+fn is_compressed_format(head: &[u8]) -> bool {
+    head.starts_with(b"PK\x03\x04")
+        || head.starts_with(&[0x1f, 0x8b])
+        || head.starts_with(&[0xff, 0xd8])
+        || head.get(4..8) == Some(b"ftyp")
+        || head.starts_with(b"%PDF")
+}
+
+/// Defines a `SmartGzEncoder`.
+///
+/// A gz encoder, which detects the format on the first read. Already compressed
+/// formats are stored without compression, so the output is gz in any case.
+struct SmartGzEncoder {
+    input: Option<Box<dyn Read + Send>>,
+    compression: Compression,
+    encoder: Option<Box<dyn Read + Send>>,
+}
+
+/// Methods of `SmartGzEncoder`.
+impl SmartGzEncoder {
+    /// Creates a new `SmartGzEncoder`.
+    fn new(input: Box<dyn Read + Send>, compression: Compression) -> Self {
+        Self {
+            input: Some(input),
+            compression,
+            encoder: None,
+        }
+    }
+}
+
+/// Impl of `Read` for `SmartGzEncoder`.
+impl Read for SmartGzEncoder {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // Detect the format on the first read.
+        if let Some(mut input) = self.input.take() {
+            let mut head = Vec::with_capacity(MAGIC_LEN);
+            (&mut input).take(MAGIC_LEN as u64).read_to_end(&mut head)?;
+
+            let compression = if is_compressed_format(&head) {
+                Compression::none()
+            } else {
+                self.compression
+            };
+
+            self.encoder = Some(Box::new(GzEncoder::new(
+                Cursor::new(head).chain(input),
+                compression,
+            )));
+        }
+
+        match self.encoder.as_mut() {
+            Some(encoder) => encoder.read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
 /// Encode data processor for gz.
 pub fn gz_encode_proc(compression: Compression) -> DataProcessor {
     Arc::new(
@@ -28,6 +94,25 @@ pub fn gz_encode_proc(compression: Compression) -> DataProcessor {
     )
 }
 
+/// Encode data processor for gz, which skips the compression of already compressed formats.
+pub fn gz_encode_smart_proc(compression: Compression) -> DataProcessor {
+    Arc::new(
+        move |_sender: Sender<Arc<dyn Message>>,
+              input: Box<dyn Read + Send>,
+              dest_rel_path: Option<&mut NPath<Rel, File>>|
+              -> Box<dyn Read + Send> {
+            let encoder = Box::new(SmartGzEncoder::new(input, compression));
+
+            // Push extension.
+            if let Some(dest_rel_path) = dest_rel_path {
+                dest_rel_path.push_extension("gz");
+            }
+
+            encoder
+        },
+    )
+}
+
 /// Decode data processor for gz.
 pub fn gz_decode_proc() -> DataProcessor {
     Arc::new(
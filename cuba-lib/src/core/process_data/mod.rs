@@ -3,4 +3,6 @@ pub mod cipher;
 pub mod cipher_procs;
 pub mod data_processor;
 pub mod gz_procs;
+pub mod rdiff_proc;
 pub mod signature_proc;
+pub mod throttle_proc;
@@ -0,0 +1,133 @@
+use crossbeam_channel::Sender;
+use fast_rsync::{Signature, SignatureOptions};
+use std::{
+    io::{Cursor, Read},
+    sync::Arc,
+};
+
+use crate::shared::{
+    message::Message,
+    npath::{File, NPath, Rel},
+};
+
+use super::data_processor::DataProcessor;
+
+/// The block size of the rsync signature.
+const RDIFF_BLOCK_SIZE: u32 = 4096;
+
+/// The size of the strong hash of the rsync signature.
+const RDIFF_CRYPTO_HASH_SIZE: u32 = 8;
+
+/// The max size of a file and its base for delta compression, both are held in memory.
+pub const RDIFF_MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The `RdiffOp` type.
+///
+/// Computes the output of the whole input against the base.
+type RdiffOp = fn(&[u8], &[u8]) -> std::io::Result<Vec<u8>>;
+
+/// Defines a `RdiffReader`.
+///
+/// A reader that applies an `RdiffOp` to the whole input on the first read.
+/// Both the input and the base are held in memory, so the backup delta compresses
+/// only files up to `RDIFF_MAX_SIZE`.
+struct RdiffReader {
+    input: Option<Box<dyn Read + Send>>,
+    base: Arc<Vec<u8>>,
+    op: RdiffOp,
+    output: Cursor<Vec<u8>>,
+}
+
+/// Methods of `RdiffReader`.
+impl RdiffReader {
+    /// Creates a new `RdiffReader`.
+    fn new(input: Box<dyn Read + Send>, base: Arc<Vec<u8>>, op: RdiffOp) -> Self {
+        Self {
+            input: Some(input),
+            base,
+            op,
+            output: Cursor::new(Vec::new()),
+        }
+    }
+}
+
+/// Impl of `Read` for `RdiffReader`.
+impl Read for RdiffReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // Compute the output on the first read.
+        if let Some(mut input) = self.input.take() {
+            let mut data = Vec::new();
+            input.read_to_end(&mut data)?;
+
+            self.output = Cursor::new((self.op)(&self.base, &data)?);
+        }
+
+        self.output.read(buf)
+    }
+}
+
+/// Computes the delta of `data` against `base`.
+fn rdiff_delta(base: &[u8], data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let signature = Signature::calculate(
+        base,
+        SignatureOptions {
+            block_size: RDIFF_BLOCK_SIZE,
+            crypto_hash_size: RDIFF_CRYPTO_HASH_SIZE,
+        },
+    );
+
+    let mut delta = Vec::new();
+    fast_rsync::diff(&signature.index(), data, &mut delta).map_err(std::io::Error::other)?;
+
+    Ok(delta)
+}
+
+/// Applies `delta` to `base`.
+fn rdiff_apply(base: &[u8], delta: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    fast_rsync::apply(base, delta, &mut data).map_err(std::io::Error::other)?;
+
+    Ok(data)
+}
+
+/// Delta data processor for rdiff.
+///
+/// Outputs the delta of the data against the `base` version.
+pub fn rdiff_delta_proc(base: Arc<Vec<u8>>) -> DataProcessor {
+    Arc::new(
+        move |_sender: Sender<Arc<dyn Message>>,
+              input: Box<dyn Read + Send>,
+              dest_rel_path: Option<&mut NPath<Rel, File>>|
+              -> Box<dyn Read + Send> {
+            let reader = Box::new(RdiffReader::new(input, base.clone(), rdiff_delta));
+
+            // Push extension.
+            if let Some(dest_rel_path) = dest_rel_path {
+                dest_rel_path.push_extension("rdiff");
+            }
+
+            reader
+        },
+    )
+}
+
+/// Apply data processor for rdiff.
+///
+/// Outputs the data of a delta applied to the `base` version.
+pub fn rdiff_apply_proc(base: Arc<Vec<u8>>) -> DataProcessor {
+    Arc::new(
+        move |_sender: Sender<Arc<dyn Message>>,
+              input: Box<dyn Read + Send>,
+              dest_rel_path: Option<&mut NPath<Rel, File>>|
+              -> Box<dyn Read + Send> {
+            let reader = Box::new(RdiffReader::new(input, base.clone(), rdiff_apply));
+
+            // Pop extension.
+            if let Some(dest_rel_path) = dest_rel_path {
+                dest_rel_path.pop_extension_if("rdiff");
+            }
+
+            reader
+        },
+    )
+}
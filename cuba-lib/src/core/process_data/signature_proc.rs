@@ -6,6 +6,7 @@ use std::{
 };
 
 use crate::shared::{
+    config::SignatureAlgo,
     message::Message,
     npath::{File, NPath, Rel},
 };
@@ -13,25 +14,58 @@ use crate::shared::{
 use super::data_processor::DataProcessor;
 
 use blake3;
+use sha2::{Digest, Sha256};
+
+/// Defines a `Hasher`.
+///
+/// The hasher of a `SignatureAlgo`.
+enum Hasher {
+    Blake3(Box<blake3::Hasher>),
+    Sha256(Sha256),
+}
+
+/// Methods of `Hasher`.
+impl Hasher {
+    /// Creates a new `Hasher` for `signature_algo`.
+    fn new(signature_algo: SignatureAlgo) -> Self {
+        match signature_algo {
+            SignatureAlgo::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+            SignatureAlgo::Sha256 => Hasher::Sha256(Sha256::new()),
+        }
+    }
+
+    /// Updates the hash with `data`.
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Hasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Returns the hash of the data so far.
+    fn finalize(&self) -> [u8; 32] {
+        match self {
+            Hasher::Blake3(hasher) => *hasher.finalize().as_bytes(),
+            Hasher::Sha256(hasher) => hasher.clone().finalize().into(),
+        }
+    }
+}
 
 /// Defines a `HashingReader`.
 ///
-/// A reader that computes a BLAKE3 hash of the data read.
+/// A reader that updates a shared hasher with the data read.
 struct HashingReader<R: Read + Send> {
     inner: R,
-    hasher: blake3::Hasher,
-    output: Arc<Mutex<[u8; 32]>>,
+    hasher: Arc<Mutex<Hasher>>,
 }
 
 /// Methods of `HashingReader`.
 impl<R: Read + Send> HashingReader<R> {
     /// Creates a new `HashingReader`.
-    fn new(inner: R, output: Arc<Mutex<[u8; 32]>>) -> Self {
-        Self {
-            inner,
-            hasher: blake3::Hasher::new(),
-            output,
-        }
+    fn new(inner: R, hasher: Arc<Mutex<Hasher>>) -> Self {
+        Self { inner, hasher }
     }
 }
 
@@ -41,33 +75,58 @@ impl<R: Read + Send> Read for HashingReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let bytes = self.inner.read(buf)?;
         if bytes > 0 {
-            self.hasher.update(&buf[..bytes]);
+            self.hasher.lock().unwrap().update(&buf[..bytes]);
         }
         Ok(bytes)
     }
 }
 
-/// Impl of `Drop` for `HashingReader`.
-impl<R: Read + Send> Drop for HashingReader<R> {
-    fn drop(&mut self) {
-        // Compute and write the final hash when dropped.
-        let hash = self.hasher.finalize();
-        let bytes = hash.as_bytes();
-        if let Ok(mut guard) = self.output.lock() {
-            guard.copy_from_slice(bytes);
+/// Defines a `SignatureProcessor`.
+///
+/// Computes the signature of the data read by its data processor, with the algorithm given
+/// on creation. The signature is taken explicitly by `finalize`, dropping the reader does nothing.
+pub struct SignatureProcessor {
+    hasher: Arc<Mutex<Hasher>>,
+    signature: Option<[u8; 32]>,
+}
+
+/// Methods of `SignatureProcessor`.
+impl SignatureProcessor {
+    /// Creates a new `SignatureProcessor` for `signature_algo`.
+    pub fn new(signature_algo: SignatureAlgo) -> Self {
+        Self {
+            hasher: Arc::new(Mutex::new(Hasher::new(signature_algo))),
+            signature: None,
         }
     }
+
+    /// Returns the data processor, which hashes the data read.
+    pub fn data_proc(&self) -> DataProcessor {
+        let hasher = self.hasher.clone();
+
+        Arc::new(
+            move |_sender: Sender<Arc<dyn Message>>,
+                  input: Box<dyn Read + Send>,
+                  _dest_rel_path: Option<&mut NPath<Rel, File>>|
+                  -> Box<dyn Read + Send> {
+                Box::new(HashingReader::new(input, hasher.clone()))
+            },
+        )
+    }
+
+    /// Returns the final signature.
+    ///
+    /// The signature is computed on the first call, later calls return the same signature.
+    pub fn finalize(&mut self) -> [u8; 32] {
+        *self
+            .signature
+            .get_or_insert_with(|| self.hasher.lock().unwrap().finalize())
+    }
 }
 
-/// Creates a data processor that computes the BLAKE3 signature of the data read.
-pub fn signature_proc(signature: Arc<Mutex<[u8; 32]>>) -> DataProcessor {
-    Arc::new(
-        move |_sender: Sender<Arc<dyn Message>>,
-              input: Box<dyn Read + Send>,
-              _dest_rel_path: Option<&mut NPath<Rel, File>>|
-              -> Box<dyn Read + Send> {
-            let reader = HashingReader::new(input, signature.clone());
-            Box::new(reader)
-        },
-    )
+/// Impl of `Default` for `SignatureProcessor`.
+impl Default for SignatureProcessor {
+    fn default() -> Self {
+        Self::new(SignatureAlgo::default())
+    }
 }
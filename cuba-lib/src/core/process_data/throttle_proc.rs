@@ -0,0 +1,115 @@
+use crossbeam_channel::Sender;
+use std::{
+    io::Read,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::shared::{
+    message::Message,
+    npath::{File, NPath, Rel},
+};
+
+use super::data_processor::DataProcessor;
+
+/// Defines a `TokenBucket`.
+struct TokenBucket {
+    /// The available bytes, negative if the bytes are owed.
+    tokens: f64,
+
+    /// The time of the last refill.
+    refilled: Instant,
+}
+
+/// Defines a `Throttle`.
+///
+/// A token bucket, which is shared by all transfer threads. It holds at most the bytes
+/// of one second, so that the aggregate rate stays under the limit.
+pub struct Throttle {
+    bytes_per_sec: f64,
+    bucket: Mutex<TokenBucket>,
+}
+
+/// Methods of `Throttle`.
+impl Throttle {
+    /// Creates a new `Throttle` with the limit `max_bytes_per_sec`.
+    pub fn new(max_bytes_per_sec: u64) -> Self {
+        // A limit of 0 would block forever.
+        let bytes_per_sec = max_bytes_per_sec.max(1) as f64;
+
+        Self {
+            bytes_per_sec,
+            bucket: Mutex::new(TokenBucket {
+                tokens: bytes_per_sec,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes `bytes` from the bucket and sleeps, until the bytes are covered by the limit.
+    pub fn acquire(&self, bytes: usize) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+
+            // Refill.
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * self.bytes_per_sec).min(self.bytes_per_sec);
+            bucket.refilled = now;
+
+            // Take, the bytes are owed if the bucket is empty.
+            bucket.tokens -= bytes as f64;
+
+            if bucket.tokens < 0.0 {
+                Duration::from_secs_f64(-bucket.tokens / self.bytes_per_sec)
+            } else {
+                Duration::ZERO
+            }
+        };
+
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// Defines a `ThrottleReader`.
+///
+/// A reader, which limits the rate of the reads by a shared `Throttle`.
+pub struct ThrottleReader {
+    input: Box<dyn Read + Send>,
+    throttle: Arc<Throttle>,
+}
+
+/// Methods of `ThrottleReader`.
+impl ThrottleReader {
+    /// Creates a new `ThrottleReader`.
+    pub fn new(input: Box<dyn Read + Send>, throttle: Arc<Throttle>) -> Self {
+        Self { input, throttle }
+    }
+}
+
+/// Impl of `Read` for `ThrottleReader`.
+impl Read for ThrottleReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes_read = self.input.read(buf)?;
+        self.throttle.acquire(bytes_read);
+
+        Ok(bytes_read)
+    }
+}
+
+/// Data processor, which limits the rate of the data by `throttle`.
+///
+/// Should be the last data processor, so that the transferred bytes are throttled.
+pub fn throttle_proc(throttle: Arc<Throttle>) -> DataProcessor {
+    Arc::new(
+        move |_sender: Sender<Arc<dyn Message>>,
+              input: Box<dyn Read + Send>,
+              _dest_rel_path: Option<&mut NPath<Rel, File>>|
+              -> Box<dyn Read + Send> {
+            Box::new(ThrottleReader::new(input, throttle.clone()))
+        },
+    )
+}
@@ -0,0 +1,324 @@
+use crossbeam_channel::Sender;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::core::run_state::{RunError, RunState};
+use crate::core::transferred_node::Backup;
+use crate::send_error;
+use crate::send_info;
+use crate::shared::clean_message::{CleanInfo, CleanMessage};
+use crate::shared::config::RetentionConfig;
+use crate::shared::message::{Message, StringError};
+use crate::shared::npath::{File, NPath, Rel, UNPath};
+
+use super::clean::remove_node;
+use super::cuba_json::read_cuba_json;
+use super::cuba_json::write_cuba_json;
+use super::fs::fs_base::FSMount;
+use super::password_cache::PasswordCache;
+use super::tasks::progress_tracker::ProgressTicks;
+use super::transferred_node::{Flags, Restore, TransferredNode, TransferredNodes};
+
+/// The seconds of a day.
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Returns the src paths of the orphans, which fall outside of the retention policy.
+///
+/// The nodes in the source are always kept. Of the orphans, the `keep_last` most recently
+/// transferred and all transferred within `keep_within_days` are kept.
+fn select_pruned(
+    transferred_nodes: &TransferredNodes,
+    retention: &RetentionConfig,
+) -> HashSet<UNPath<Rel>> {
+    // The orphans, the most recently transferred first.
+    let mut orphans: Vec<(&UNPath<Rel>, Option<SystemTime>)> = transferred_nodes
+        .iter()
+        .filter(|(_, node)| node.flags.contains(Flags::ORPHAN))
+        .map(|(src_rel_path, node)| (src_rel_path, node.backup_timestamp))
+        .collect();
+
+    orphans.sort_by(|(_, timestamp_a), (_, timestamp_b)| timestamp_b.cmp(timestamp_a));
+
+    let oldest = retention.keep_within_days.and_then(|days| {
+        SystemTime::now().checked_sub(Duration::from_secs(days.saturating_mul(SECS_PER_DAY)))
+    });
+
+    orphans
+        .into_iter()
+        .enumerate()
+        .filter(|(index, (_, timestamp))| {
+            let kept_as_last = retention
+                .keep_last
+                .is_some_and(|keep_last| *index < keep_last);
+
+            let kept_as_within =
+                oldest.is_some_and(|oldest| timestamp.is_some_and(|timestamp| timestamp >= oldest));
+
+            !kept_as_last && !kept_as_within
+        })
+        .map(|(_, (src_rel_path, _))| src_rel_path.clone())
+        .collect()
+}
+
+/// Defines a `NeededPaths`.
+///
+/// The dest paths, which the kept nodes need: their delta bases, dedup blobs and
+/// parent directories.
+#[derive(Default)]
+struct NeededPaths {
+    rel_file_paths: HashSet<NPath<Rel, File>>,
+    rel_dir_paths: HashSet<String>,
+}
+
+/// Methods of `NeededPaths`.
+impl NeededPaths {
+    /// Adds the paths, which the kept `node` at `dest_rel_path` needs.
+    fn add_node(&mut self, node: &TransferredNode, dest_rel_path: &UNPath<Rel>) {
+        self.rel_file_paths.extend(
+            [&node.delta_base_rel_path, &node.dedup_blob_rel_path]
+                .into_iter()
+                .flatten()
+                .cloned(),
+        );
+
+        let dest_rel_path = dest_rel_path.to_unicode();
+
+        for (index, _) in dest_rel_path.match_indices('/') {
+            self.rel_dir_paths
+                .insert(dest_rel_path[..index].to_string());
+        }
+    }
+
+    /// Returns true, if the node at `dest_rel_path` is needed by a kept node.
+    fn contains(&self, dest_rel_path: &UNPath<Rel>) -> bool {
+        match dest_rel_path {
+            UNPath::File(rel_file_path) => self.rel_file_paths.contains(rel_file_path),
+            UNPath::Dir(rel_dir_path) => self.rel_dir_paths.contains(rel_dir_path.to_unicode()),
+            UNPath::Symlink(_) => false,
+        }
+    }
+}
+
+/// Returns the dest paths of the pruned nodes.
+///
+/// Nodes, which are needed by a kept node, are removed from `pruned_src_rel_paths`.
+fn select_pruned_dest(
+    transferred_nodes: &TransferredNodes,
+    pruned_src_rel_paths: &mut HashSet<UNPath<Rel>>,
+) -> HashSet<UNPath<Rel>> {
+    let backup_view = transferred_nodes.view::<Backup>();
+
+    // The paths, which the kept nodes need.
+    let mut needed_paths = NeededPaths::default();
+
+    for (src_rel_path, node) in transferred_nodes.iter() {
+        if !pruned_src_rel_paths.contains(src_rel_path) {
+            needed_paths.add_node(node, &backup_view.get_dest_rel_path(node));
+        }
+    }
+
+    // Repeat until stable, a node kept as needed may need other nodes.
+    loop {
+        let needed_src_rel_paths: Vec<UNPath<Rel>> = pruned_src_rel_paths
+            .iter()
+            .filter(|src_rel_path| {
+                backup_view
+                    .get_node_for_src(src_rel_path)
+                    .is_some_and(|node| needed_paths.contains(&backup_view.get_dest_rel_path(node)))
+            })
+            .cloned()
+            .collect();
+
+        if needed_src_rel_paths.is_empty() {
+            break;
+        }
+
+        for src_rel_path in &needed_src_rel_paths {
+            pruned_src_rel_paths.remove(src_rel_path);
+
+            if let Some(node) = backup_view.get_node_for_src(src_rel_path) {
+                needed_paths.add_node(node, &backup_view.get_dest_rel_path(node));
+            }
+        }
+    }
+
+    pruned_src_rel_paths
+        .iter()
+        .filter_map(|src_rel_path| backup_view.get_node_for_src(src_rel_path))
+        .map(|node| backup_view.get_dest_rel_path(node))
+        .collect()
+}
+
+/// Removes the dedup blobs of `blob_rel_paths`, which no node of `transferred_nodes` refers
+/// to anymore.
+fn remove_unreferred_blobs(
+    fs_mnt: &FSMount,
+    transferred_nodes: &TransferredNodes,
+    blob_rel_paths: HashSet<NPath<Rel, File>>,
+    sender: &Sender<Arc<dyn Message>>,
+) {
+    let referred_rel_file_paths = transferred_nodes
+        .view::<Restore>()
+        .referred_rel_file_paths(|_| true);
+
+    for blob_rel_path in blob_rel_paths
+        .into_iter()
+        .filter(|blob_rel_path| !referred_rel_file_paths.contains(blob_rel_path))
+    {
+        remove_node(
+            &UNPath::File(fs_mnt.abs_dir_path.add_rel_file(&blob_rel_path)),
+            &UNPath::File(blob_rel_path),
+            fs_mnt.clone(),
+            sender.clone(),
+        );
+    }
+}
+
+/// Runs the prune process.
+///
+/// A backup holds a single version of each file, so the retention policy applies to the
+/// orphans (not in the source anymore) of the backup and not to older versions:
+/// - Orphans outside of the policy are deleted from the backup and the backup index
+/// - Files that are the delta base or dedup blob of a kept node are kept
+/// - Directories with kept nodes are kept
+/// - Dedup blobs, which only pruned nodes referred to, are deleted once the backup index is
+///   written
+/// - Files/directories that are not in the backup index are left to the clean
+pub fn run_prune(
+    run_state: Arc<RunState>,
+    fs_mnt: FSMount,
+    retention: &RetentionConfig,
+    sender: Sender<Arc<dyn Message>>,
+) -> Result<(), RunError> {
+    // Set running to true.
+    run_state.start();
+
+    // Without a policy, all orphans are kept.
+    if retention.keep_last.is_none() && retention.keep_within_days.is_none() {
+        send_info!(sender, "No retention policy configured, nothing to prune");
+        run_state.stop();
+        return Ok(());
+    }
+
+    // Connect fs.
+    if let Err(err) =
+        fs_mnt.ensure_connected_with_status(|status| run_state.set_dest_status(status))
+    {
+        run_state.stop();
+        return Err(RunError::fatal(err, &sender));
+    }
+
+    // Read cuba json, the cipher keeps an encrypted cuba json encrypted.
+    let (mut transferred_nodes, cuba_json_cipher) =
+        match read_cuba_json(&fs_mnt, &mut PasswordCache::new(), &sender) {
+            Some(cuba_json) => cuba_json,
+            None => {
+                if let Err(err) = fs_mnt.fs.write().unwrap().disconnect() {
+                    send_error!(sender, err);
+                }
+
+                run_state.stop();
+                return Err(RunError::fatal(
+                    StringError::new("The cuba json can't be read".to_string()),
+                    &sender,
+                ));
+            }
+        };
+
+    // The src paths and dest paths of the pruned nodes.
+    let mut pruned_src_rel_paths = select_pruned(&transferred_nodes, retention);
+    let pruned_dest_rel_paths = select_pruned_dest(&transferred_nodes, &mut pruned_src_rel_paths);
+
+    // Progress duration.
+    let progress_ticks = ProgressTicks::new(transferred_nodes.node_count(), sender.clone());
+
+    fs_mnt
+        .fs
+        .read()
+        .unwrap()
+        .walk_dir_rec(
+            &fs_mnt.abs_dir_path,
+            &mut |abs_path| {
+                // Progress tick.
+                progress_ticks.tick(1);
+
+                if run_state.is_canceled() {
+                    return false;
+                }
+
+                match abs_path.strip_prefix(&fs_mnt.abs_dir_path) {
+                    Ok(node_rel_path) => {
+                        if pruned_dest_rel_paths.contains(&node_rel_path) {
+                            return remove_node(
+                                &abs_path,
+                                &node_rel_path,
+                                fs_mnt.clone(),
+                                sender.clone(),
+                            );
+                        }
+
+                        // Files/directories not in the backup index are left to the clean.
+                        if transferred_nodes
+                            .view::<Restore>()
+                            .get_node_for_src(&node_rel_path)
+                            .is_some()
+                        {
+                            sender
+                                .send(Arc::new(CleanMessage::new(
+                                    &node_rel_path,
+                                    None,
+                                    Some(Arc::new(CleanInfo::Ok)),
+                                )))
+                                .unwrap();
+                        }
+                    }
+                    Err(err) => {
+                        send_error!(sender, err);
+                    }
+                }
+
+                true
+            },
+            &|err| send_error!(sender, err),
+        )
+        .unwrap();
+
+    if !run_state.is_canceled() {
+        // Complete the progress.
+        progress_ticks.complete();
+
+        // Remove the pruned nodes from the index, failed removals are left to the clean.
+        let mut pruned_blob_rel_paths: HashSet<NPath<Rel, File>> = HashSet::new();
+
+        for src_rel_path in &pruned_src_rel_paths {
+            if let Some(node) = transferred_nodes.remove(src_rel_path) {
+                pruned_blob_rel_paths.extend(node.dedup_blob_rel_path);
+            }
+        }
+
+        // Write cuba json, the blobs are removed only if no written index refers to them.
+        if write_cuba_json(
+            &fs_mnt,
+            &transferred_nodes,
+            cuba_json_cipher.as_ref(),
+            &sender,
+        ) {
+            remove_unreferred_blobs(&fs_mnt, &transferred_nodes, pruned_blob_rel_paths, &sender);
+        }
+    }
+
+    // Disconnect fs.
+    if let Err(err) = fs_mnt.fs.write().unwrap().disconnect() {
+        send_error!(sender, err);
+    }
+
+    // Set running to false.
+    run_state.stop();
+
+    if run_state.is_canceled() {
+        return Err(RunError::Canceled);
+    }
+
+    Ok(())
+}
@@ -3,40 +3,50 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 use std::sync::Mutex;
 
-use crate::core::run_state::RunState;
+use crate::core::run_state::{RunError, RunState};
 use crate::send_error;
-use crate::shared::message::Message;
+use crate::shared::message::{Message, StringError};
+use crate::shared::npath::Abs;
 use crate::shared::npath::Dir;
 use crate::shared::npath::File;
 use crate::shared::npath::NPath;
 use crate::shared::npath::Rel;
 use crate::shared::npath::Symlink;
 use crate::shared::npath::UNPath;
-use crate::shared::progress_message::ProgressInfo;
-use crate::shared::progress_message::ProgressMessage;
+use crate::shared::npath::default_case_sensitive;
 
 use super::cuba_json::read_cuba_json;
+use super::filename_cipher::FilenameCipher;
 use super::fs::fs_base::FSConnection;
 use super::glob_matcher::ExcludeMatcher;
 use super::glob_matcher::GlobMatcher;
 use super::glob_matcher::IncludeMatcher;
 use super::password_cache::PasswordCache;
+use super::process_data::throttle_proc::Throttle;
 use super::tasks::directory_restore_task::directory_restore_task;
 use super::tasks::file_restore_task::file_restore_task;
+use super::tasks::progress_tracker::ProgressTicks;
 use super::tasks::symlink_restore_task::symlink_restore_task;
 use super::tasks::task_worker::TaskWorker;
-use super::transferred_node::Restore;
+use super::transferred_node::{Backup, Restore};
 use super::util::move_rel_npaths;
 
+/// Runs the restore process.
+///
+/// With `restore_file` only this file is restored, regardless of the patterns,
+/// optionally to the alternative dest `restore_to`.
 #[allow(clippy::too_many_arguments)]
 pub fn run_restore(
     run_state: Arc<RunState>,
     threads: usize,
+    max_bandwidth_bytes_per_sec: Option<u64>,
     include_patterns: &Option<Vec<String>>,
     exclude_patterns: &Option<Vec<String>>,
+    restore_file: Option<&NPath<Rel, File>>,
+    restore_to: Option<&NPath<Abs, File>>,
     fs_conn: FSConnection,
     sender: Sender<Arc<dyn Message>>,
-) {
+) -> Result<(), RunError> {
     // Set running to true.
     run_state.start();
 
@@ -45,60 +55,124 @@ pub fn run_restore(
 
     // Create include matcher.
     if let Some(include_patterns) = include_patterns {
-        include_matcher = match GlobMatcher::new(include_patterns) {
+        include_matcher = match GlobMatcher::new(include_patterns, default_case_sensitive()) {
             Ok(matcher) => Some(matcher.include_matcher()),
             Err(err) => {
-                send_error!(sender, err);
-                return;
+                run_state.stop();
+                return Err(RunError::fatal(err, &sender));
             }
         }
     };
 
     // Create exclude matcher.
     if let Some(exclude_patterns) = exclude_patterns {
-        exclude_matcher = match GlobMatcher::new(exclude_patterns) {
+        exclude_matcher = match GlobMatcher::new(exclude_patterns, default_case_sensitive()) {
             Ok(matcher) => Some(matcher.exclude_matcher()),
             Err(err) => {
-                send_error!(sender, err);
-                return;
+                run_state.stop();
+                return Err(RunError::fatal(err, &sender));
             }
         }
     };
 
     // Open connection.
-    if let Err(err) = fs_conn.open() {
-        send_error!(sender, err);
-        return;
+    if let Err(err) = fs_conn.open(&run_state) {
+        run_state.stop();
+        return Err(RunError::fatal(err, &sender));
     }
 
-    // Read cuba json.
-    let transferred_nodes_read = match read_cuba_json(&fs_conn.src_mnt, &sender) {
-        Some(nodes) => nodes,
-        None => return,
-    };
+    // Create password cache.
+    let mut password_cache = PasswordCache::new();
+
+    // Read cuba json, encrypted or plain.
+    let mut transferred_nodes_read =
+        match read_cuba_json(&fs_conn.src_mnt, &mut password_cache, &sender) {
+            Some((nodes, _)) => nodes,
+            None => {
+                if let Err(err) = fs_conn.close() {
+                    send_error!(sender, err);
+                }
+
+                run_state.stop();
+                return Err(RunError::fatal(
+                    StringError::new("The cuba json can't be read".to_string()),
+                    &sender,
+                ));
+            }
+        };
+
+    // Decrypt the names, if encrypted.
+    if let Err(err) =
+        FilenameCipher::decrypt_nodes(&mut transferred_nodes_read, &mut password_cache)
+    {
+        if let Err(err) = fs_conn.close() {
+            send_error!(sender, err);
+        }
+
+        run_state.stop();
+        return Err(RunError::fatal(err, &sender));
+    }
 
     // Collect source files, directories and symlinks.
     let mut src_rel_files: VecDeque<NPath<Rel, File>> = VecDeque::new();
     let mut src_rel_directories: VecDeque<NPath<Rel, Dir>> = VecDeque::new();
     let mut src_rel_symlinks: VecDeque<NPath<Rel, Symlink>> = VecDeque::new();
 
-    for src_rel_path in transferred_nodes_read.view::<Restore>().iter_src_nodes() {
+    // The backup view maps the restored path to the path in the backup.
+    let backup_view = transferred_nodes_read.view::<Backup>();
+
+    if let Some(restore_file) = restore_file {
+        match backup_view.get_node_for_src(&restore_file.clone().into()) {
+            Some(transferred_node) => {
+                if let UNPath::File(src_rel_file_path) =
+                    backup_view.get_dest_rel_path(transferred_node)
+                {
+                    src_rel_files.push_back(src_rel_file_path);
+                }
+            }
+            None => {
+                if let Err(err) = fs_conn.close() {
+                    send_error!(sender, err);
+                }
+
+                run_state.stop();
+                return Err(RunError::fatal(
+                    StringError::new(format!(
+                        "No file {:?} found in the backup",
+                        restore_file.to_unicode()
+                    )),
+                    &sender,
+                ));
+            }
+        }
+    }
+
+    for restored_rel_path in backup_view
+        .iter_src_nodes()
+        .filter(|_| restore_file.is_none())
+    {
         let mut included = true;
         let mut excluded = false;
 
         if let Some(ref matcher) = include_matcher {
             // Note: a include matcher does include all predecessor directories of a glob statement.
-            included = matcher.is_match(src_rel_path);
+            included = matcher.is_match(restored_rel_path);
         }
 
         if let Some(ref matcher) = exclude_matcher {
-            excluded = matcher.is_match(src_rel_path);
+            excluded = matcher.is_match(restored_rel_path);
         }
 
         if included && !excluded {
-            match &src_rel_path {
-                UNPath::File(rel_file_path) => {
-                    src_rel_files.push_back(rel_file_path.clone());
+            match &restored_rel_path {
+                // Files are read from the backup file, which may have a hashed name.
+                UNPath::File(_) => {
+                    if let Some(transferred_node) = backup_view.get_node_for_src(restored_rel_path)
+                        && let UNPath::File(src_rel_file_path) =
+                            backup_view.get_dest_rel_path(transferred_node)
+                    {
+                        src_rel_files.push_back(src_rel_file_path);
+                    }
                 }
                 UNPath::Dir(rel_dir_path) => {
                     src_rel_directories.push_back(rel_dir_path.clone());
@@ -110,8 +184,10 @@ pub fn run_restore(
         }
     }
 
-    // Create password cache.
-    let password_cache = PasswordCache::new();
+    // Sort, so that the restore order is deterministic and parents are processed before their children.
+    src_rel_files.make_contiguous().sort();
+    src_rel_directories.make_contiguous().sort();
+    src_rel_symlinks.make_contiguous().sort();
 
     // Create arcs for tasks.
     let arc_mutex_src_rel_files = Arc::new(Mutex::new(src_rel_files));
@@ -119,19 +195,21 @@ pub fn run_restore(
     let arc_transferred_nodes_read = Arc::new(transferred_nodes_read);
     let arc_mutex_password_cache = Arc::new(Mutex::new(password_cache));
 
-    // Init task worker.
-    let task_worker = TaskWorker::new(fs_conn.clone(), sender.clone());
+    // The total bytes of the restored files in the backup.
+    let total_bytes = arc_transferred_nodes_read
+        .view::<Restore>()
+        .total_dest_size(arc_mutex_src_rel_files.lock().unwrap().iter());
 
     // Progress duration.
     let items = src_rel_directories.len()
         + arc_mutex_src_rel_files.lock().unwrap().len()
         + arc_mutex_src_rel_symlinks.lock().unwrap().len();
-    sender
-        .send(Arc::new(ProgressMessage::new(
-            Arc::new(ProgressInfo::Duration),
-            items as u64,
-        )))
-        .unwrap();
+    let progress_ticks = Arc::new(ProgressTicks::new(items, sender.clone()));
+
+    // Init task worker.
+    let task_worker = TaskWorker::new(fs_conn.clone(), sender.clone())
+        .with_progress_ticks(progress_ticks.clone())
+        .with_total_bytes(total_bytes);
 
     // We can not process dir list parallel, because if dir A is subdir of dir B: B must be processed before A.
     // But we can process all dirs of the same depth parallel.
@@ -150,6 +228,7 @@ pub fn run_restore(
             task_worker.run(
                 run_state.clone(),
                 depth_threads,
+                "directory_restore",
                 Arc::new(directory_restore_task(arc_mutex_depth_src_rel_dirs)),
             );
         }
@@ -161,10 +240,13 @@ pub fn run_restore(
     task_worker.run(
         run_state.clone(),
         threads,
+        "file_restore",
         Arc::new(file_restore_task(
             arc_mutex_src_rel_files,
             arc_transferred_nodes_read.clone(),
             arc_mutex_password_cache.clone(),
+            restore_to.cloned(),
+            max_bandwidth_bytes_per_sec.map(|max| Arc::new(Throttle::new(max))),
         )),
     );
 
@@ -172,6 +254,7 @@ pub fn run_restore(
     task_worker.run(
         run_state.clone(),
         threads,
+        "symlink_restore",
         Arc::new(symlink_restore_task(
             arc_mutex_src_rel_symlinks,
             arc_transferred_nodes_read.clone(),
@@ -181,6 +264,11 @@ pub fn run_restore(
     // Drop task worker.
     drop(task_worker);
 
+    // Complete the progress.
+    if !run_state.is_canceled() {
+        progress_ticks.complete();
+    }
+
     // Close connection.
     if let Err(err) = fs_conn.close() {
         send_error!(sender, err);
@@ -188,4 +276,10 @@ pub fn run_restore(
 
     // Set running to false.
     run_state.stop();
+
+    if run_state.is_canceled() {
+        return Err(RunError::Canceled);
+    }
+
+    Ok(())
 }
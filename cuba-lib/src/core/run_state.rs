@@ -1,9 +1,53 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use crossbeam_channel::Sender;
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use strum_macros::Display;
+use thiserror::Error;
+
+use crate::shared::message::{ErrorMessage, Message};
+
+/// Defines a `ConnectionStatus`.
+///
+/// The status of a filesystem connection of a run.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ConnectionStatus {
+    /// No connection is opened.
+    #[strum(to_string = "Idle")]
+    Idle,
+
+    /// The connection is being opened.
+    #[strum(to_string = "Connecting")]
+    Connecting,
+
+    /// The connection is opened.
+    #[strum(to_string = "Connected")]
+    Connected,
+
+    /// The connection failed.
+    #[strum(to_string = "Error")]
+    Error,
+}
+
+/// Impl of `From<u8>` for `ConnectionStatus`.
+impl From<u8> for ConnectionStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => ConnectionStatus::Connecting,
+            2 => ConnectionStatus::Connected,
+            3 => ConnectionStatus::Error,
+            _ => ConnectionStatus::Idle,
+        }
+    }
+}
 
 /// Defines the `RunState`.
 pub struct RunState {
     canceled: AtomicBool,
     running: AtomicBool,
+    src_status: AtomicU8,
+    dest_status: AtomicU8,
 }
 
 /// Methods of `RunState`.
@@ -13,6 +57,8 @@ impl RunState {
         Self {
             canceled: AtomicBool::new(false),
             running: AtomicBool::new(false),
+            src_status: AtomicU8::new(ConnectionStatus::Idle as u8),
+            dest_status: AtomicU8::new(ConnectionStatus::Idle as u8),
         }
     }
 
@@ -20,11 +66,42 @@ impl RunState {
     pub fn start(&self) {
         self.canceled.store(false, Ordering::SeqCst);
         self.running.store(true, Ordering::SeqCst);
+        self.set_src_status(ConnectionStatus::Idle);
+        self.set_dest_status(ConnectionStatus::Idle);
     }
 
     /// Stops a run.
+    ///
+    /// The connections are closed, but a failed connection keeps its error status.
     pub fn stop(&self) {
-        self.running.store(false, Ordering::SeqCst)
+        self.running.store(false, Ordering::SeqCst);
+
+        for status in [&self.src_status, &self.dest_status] {
+            let _ = status.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |value| {
+                (ConnectionStatus::from(value) != ConnectionStatus::Error)
+                    .then_some(ConnectionStatus::Idle as u8)
+            });
+        }
+    }
+
+    /// Sets the connection status of the source filesystem.
+    pub fn set_src_status(&self, status: ConnectionStatus) {
+        self.src_status.store(status as u8, Ordering::SeqCst);
+    }
+
+    /// Sets the connection status of the destination filesystem.
+    pub fn set_dest_status(&self, status: ConnectionStatus) {
+        self.dest_status.store(status as u8, Ordering::SeqCst);
+    }
+
+    /// Returns the connection status of the source filesystem.
+    pub fn src_status(&self) -> ConnectionStatus {
+        ConnectionStatus::from(self.src_status.load(Ordering::SeqCst))
+    }
+
+    /// Returns the connection status of the destination filesystem.
+    pub fn dest_status(&self) -> ConnectionStatus {
+        ConnectionStatus::from(self.dest_status.load(Ordering::SeqCst))
     }
 
     /// Requests a cancel.
@@ -49,3 +126,33 @@ impl Default for RunState {
         Self::new()
     }
 }
+
+/// Defines a `RunError`.
+///
+/// Errors of single nodes do not fail a run, they are only sent as messages.
+#[derive(Error, Debug, Clone)]
+pub enum RunError {
+    /// The run was canceled.
+    #[error("Canceled")]
+    Canceled,
+
+    /// The first fatal error, which stopped the run.
+    #[error("{0}")]
+    Fatal(Arc<dyn Error + Send + Sync>),
+}
+
+/// Methods of `RunError`.
+impl RunError {
+    /// Creates a fatal `RunError` and sends it as error message.
+    pub fn fatal<E>(err: E, sender: &Sender<Arc<dyn Message>>) -> Self
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        let err: Arc<dyn Error + Send + Sync> = Arc::new(err);
+        sender
+            .send(Arc::new(ErrorMessage::new(err.clone())))
+            .unwrap();
+
+        RunError::Fatal(err)
+    }
+}
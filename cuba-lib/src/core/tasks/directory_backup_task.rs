@@ -14,6 +14,7 @@ use crate::shared::npath::NPath;
 use crate::shared::npath::Rel;
 use crate::shared::task_message::TaskInfo;
 
+use super::super::filename_cipher::FilenameCipher;
 use super::super::fs::fs_base::FSConnection;
 use super::super::transferred_node::Backup;
 use super::super::transferred_node::Flags;
@@ -32,6 +33,7 @@ pub fn directory_backup_task(
     src_rel_dirs: Arc<Mutex<VecDeque<NPath<Rel, Dir>>>>,
     transferred_nodes: Arc<RwLock<TransferredNodes>>,
     backup_flags: MaskedFlags,
+    filename_cipher: Option<Arc<FilenameCipher>>,
 ) -> impl Task {
     move |create_task_error_msg: &dyn TaskErrorFn,
           create_task_info_msg: &dyn TaskInfoFn,
@@ -59,9 +61,17 @@ pub fn directory_backup_task(
             let src_abs_dir_path: NPath<Abs, Dir> =
                 fs_conn.src_mnt.abs_dir_path.add_rel_dir(&src_rel_dir_path);
 
+            // Set dest rel dir path, which is hashed if the names are encrypted.
+            let dest_rel_dir_path = match &filename_cipher {
+                Some(filename_cipher) => filename_cipher.hash_rel_path(&src_rel_dir_path),
+                None => src_rel_dir_path.clone(),
+            };
+
             // Create absolut path to the dest dir.
-            let dest_abs_dir_path: NPath<Abs, Dir> =
-                fs_conn.dest_mnt.abs_dir_path.add_rel_dir(&src_rel_dir_path);
+            let dest_abs_dir_path: NPath<Abs, Dir> = fs_conn
+                .dest_mnt
+                .abs_dir_path
+                .add_rel_dir(&dest_rel_dir_path);
 
             // Retrieve metadata for the src dir.
             let src_dir_metadata = match task_handle_error(
@@ -87,23 +97,28 @@ pub fn directory_backup_task(
             // Set transferred node flags to backup flags.
             let mut transferred_node_flags: Flags = backup_flags.flags();
 
-            // If a transferred node exists ...
-            if let Some(transferred_node) = transferred_nodes
-                .read()
-                .unwrap()
-                .view::<Backup>()
-                .get_node_for_src(&src_rel_dir_path.clone().into())
             {
-                // ... and the flags match, ...
-                if backup_flags.matches(transferred_node.flags) {
-                    // ... we do not need to create the dir.
-                    create_dest_dir = false;
+                let transferred_nodes_read = transferred_nodes.read().unwrap();
+                let backup_view = transferred_nodes_read.view::<Backup>();
 
-                    // Update transferred node flags.
-                    transferred_node_flags.insert(transferred_node.flags);
-
-                    // Remove orphan flag.
-                    transferred_node_flags.remove(Flags::ORPHAN);
+                // If a transferred node exists ...
+                if let Some(transferred_node) =
+                    backup_view.get_node_for_src(&src_rel_dir_path.clone().into())
+                {
+                    // ... and the flags and the dest dir match, ...
+                    if backup_flags.matches(transferred_node.flags)
+                        && backup_view.get_dest_rel_path(transferred_node)
+                            == dest_rel_dir_path.clone().into()
+                    {
+                        // ... we do not need to create the dir.
+                        create_dest_dir = false;
+
+                        // Update transferred node flags.
+                        transferred_node_flags.insert(transferred_node.flags);
+
+                        // Remove orphan flag.
+                        transferred_node_flags.remove(Flags::ORPHAN);
+                    }
                 }
             }
 
@@ -172,7 +187,7 @@ pub fn directory_backup_task(
                 .set_transferred_node(
                     &src_rel_dir_path.clone().into(),
                     &TransferredNode::from_dir(
-                        &src_rel_dir_path,
+                        &dest_rel_dir_path,
                         transferred_node_flags,
                         &src_dir_metadata,
                     ),
@@ -1,27 +1,39 @@
 use crossbeam_channel::Sender;
 use flate2::Compression;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::error::Error;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::RwLock;
 
-use crate::core::tasks::task_helpers::task_handle_error;
+use crate::send_warn;
+use crate::shared::config::SignatureAlgo;
 use crate::shared::message::Info;
 use crate::shared::message::Message;
 use crate::shared::npath::Abs;
 use crate::shared::npath::File;
 use crate::shared::npath::NPath;
 use crate::shared::npath::Rel;
+use crate::shared::npath::UNPath;
+use crate::shared::task_message::SourceBytes;
 use crate::shared::task_message::TaskError;
 use crate::shared::task_message::TaskInfo;
+use crate::shared::task_message::TransferredBytes;
 
+use super::super::dedup_index::{DedupIndex, DedupLookup};
+use super::super::filename_cipher::FilenameCipher;
 use super::super::fs::fs_base::FSConnection;
+use super::super::fs::fs_metadata::FSMetaData;
 use super::super::password_cache::PasswordCache;
 use super::super::process_data::age_procs::age_encrypt_proc;
 use super::super::process_data::data_processor::DataProcessor;
 use super::super::process_data::gz_procs::gz_encode_proc;
+use super::super::process_data::gz_procs::gz_encode_smart_proc;
+use super::super::process_data::rdiff_proc::{RDIFF_MAX_SIZE, rdiff_delta_proc};
+use super::super::process_data::throttle_proc::{Throttle, throttle_proc};
 use super::super::transferred_node::Backup;
+use super::super::transferred_node::DeltaBase;
 use super::super::transferred_node::Flags;
 use super::super::transferred_node::MaskedFlags;
 use super::super::transferred_node::TransferredNode;
@@ -29,6 +41,8 @@ use super::super::transferred_node::TransferredNodes;
 use super::super::transferred_node::sig_valid_and_match;
 
 use super::task_helpers::exit_task_and_continue;
+use super::task_helpers::task_read_data;
+use super::task_helpers::task_read_procs;
 use super::task_helpers::task_read_signature;
 use super::task_helpers::task_transfer_file;
 use super::task_helpers::task_transfer_successful;
@@ -36,14 +50,80 @@ use super::task_worker::Task;
 use super::task_worker::TaskErrorFn;
 use super::task_worker::TaskInfoFn;
 
+/// The queue of the src files to backup, with the metadata of the walk.
+pub type SrcFileQueue = VecDeque<(NPath<Rel, File>, FSMetaData)>;
+
+/// Returns true, if the extension of the file is in `extensions`.
+fn compression_excluded(rel_file_path: &NPath<Rel, File>, extensions: &[String]) -> bool {
+    rel_file_path.extension().is_some_and(|extension| {
+        let extension = extension.to_string_lossy();
+
+        extensions.iter().any(|excluded| {
+            excluded
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(&extension)
+        })
+    })
+}
+
+/// Returns the backup flags of a file, a file with an excluded extension is not compressed.
+pub fn backup_flags_of_file(
+    backup_flags: MaskedFlags,
+    rel_file_path: &NPath<Rel, File>,
+    compression_exclude_extensions: &[String],
+) -> MaskedFlags {
+    let mut backup_flags = backup_flags;
+
+    if compression_excluded(rel_file_path, compression_exclude_extensions) {
+        backup_flags.remove(Flags::COMPRESSED);
+    }
+
+    backup_flags
+}
+
+/// Returns the signature of the transferred node, if size and modified of the src file are
+/// unchanged and it has the same flags, password id and algorithm.
+///
+/// The src file is not read for such a node.
+pub fn unchanged_src_signature(
+    transferred_node: &TransferredNode,
+    backup_flags: &MaskedFlags,
+    password_id: &Option<String>,
+    signature_algo: SignatureAlgo,
+    src_file_metadata: &FSMetaData,
+) -> Option<[u8; 32]> {
+    (backup_flags.matches(transferred_node.flags)
+        && *password_id == transferred_node.password_id
+        && signature_algo == transferred_node.signature_algo
+        && transferred_node.src_unchanged(src_file_metadata))
+    .then_some(transferred_node.src_signature)
+    .flatten()
+}
+
+#[allow(clippy::too_many_arguments)]
 /// Task for backup the files.
 pub fn file_backup_task(
-    src_rel_files: Arc<Mutex<VecDeque<NPath<Rel, File>>>>,
+    src_rel_files: Arc<Mutex<SrcFileQueue>>,
     transferred_nodes: Arc<RwLock<TransferredNodes>>,
     backup_flags: MaskedFlags,
     password_cache: Arc<Mutex<PasswordCache>>,
     password_id: Option<String>,
+    delta_compression: bool,
+    dedup_index: Option<Arc<DedupIndex>>,
+    retry_changed_files: bool,
+    compression_level: Option<u32>,
+    skip_recompression: bool,
+    compression_exclude_extensions: Vec<String>,
+    filename_cipher: Option<Arc<FilenameCipher>>,
+    throttle: Option<Arc<Throttle>>,
+    signature_algo: SignatureAlgo,
 ) -> impl Task {
+    // The compression of the files, the default if no level is given.
+    let compression = compression_level.map_or_else(Compression::default, Compression::new);
+
+    // The files, which changed during the backup and were queued again.
+    let retried_files: Arc<Mutex<HashSet<NPath<Rel, File>>>> = Arc::new(Mutex::new(HashSet::new()));
+
     move |create_task_error_msg: &dyn TaskErrorFn,
           create_task_info_msg: &dyn TaskInfoFn,
           fs_conn: FSConnection,
@@ -52,7 +132,7 @@ pub fn file_backup_task(
         let src_rel_files_element = src_rel_files.lock().unwrap().pop_front();
 
         // Process if valid element.
-        if let Some(src_rel_file_path) = src_rel_files_element {
+        if let Some((src_rel_file_path, src_file_metadata)) = src_rel_files_element {
             // Make task messages with fixed path.
             let create_task_error_msg = |error: Arc<dyn Error + Send + Sync>| {
                 create_task_error_msg(&src_rel_file_path.clone().into(), error)
@@ -66,44 +146,60 @@ pub fn file_backup_task(
                 .send(create_task_info_msg(Arc::new(TaskInfo::Start)))
                 .unwrap();
 
+            // Files with an excluded extension are backed up without compression.
+            let backup_flags = backup_flags_of_file(
+                backup_flags,
+                &src_rel_file_path,
+                &compression_exclude_extensions,
+            );
+
+            // Set the name of the dest file, which is hashed if the names are encrypted.
+            let dest_name_rel_file_path = match &filename_cipher {
+                Some(filename_cipher) => filename_cipher.hash_rel_path(&src_rel_file_path),
+                None => src_rel_file_path.clone(),
+            };
+
             // Create absolut path to the src file.
             let src_abs_file_path: NPath<Abs, File> = fs_conn
                 .src_mnt
                 .abs_dir_path
                 .add_rel_file(&src_rel_file_path);
 
-            // Retrieve metadata for the src file.
-            let src_file_metadata = match task_handle_error(
-                fs_conn
-                    .src_mnt
-                    .fs
-                    .read()
-                    .unwrap()
-                    .meta(&src_abs_file_path.clone().into()),
-                &create_task_error_msg,
-                &sender,
-            ) {
-                Some(metadata) => metadata,
-                None => {
-                    // Exit task and continue.
-                    return exit_task_and_continue(&create_task_info_msg, &sender);
-                }
-            };
+            // Take the signature of the transferred node, if size and modified are unchanged
+            // and it has the same algorithm.
+            let unchanged_src_signature = transferred_nodes
+                .read()
+                .unwrap()
+                .view::<Backup>()
+                .get_node_for_src(&src_rel_file_path.clone().into())
+                .and_then(|transferred_node| {
+                    unchanged_src_signature(
+                        transferred_node,
+                        &backup_flags,
+                        &password_id,
+                        signature_algo,
+                        &src_file_metadata,
+                    )
+                });
 
-            // Read src file signature.
-            let src_file_signature = match task_read_signature(
-                &fs_conn.src_mnt,
-                &src_abs_file_path,
-                &create_task_error_msg,
-                &sender,
-            ) {
+            // Read src file signature - if needed.
+            let src_file_signature = match unchanged_src_signature {
                 Some(file_signature) => file_signature,
-                None => {
-                    // Reading signature failed.
+                None => match task_read_signature(
+                    &fs_conn.src_mnt,
+                    &src_abs_file_path,
+                    signature_algo,
+                    &create_task_error_msg,
+                    &sender,
+                ) {
+                    Some(file_signature) => file_signature,
+                    None => {
+                        // Reading signature failed.
 
-                    // Exit task and continue.
-                    return exit_task_and_continue(&create_task_info_msg, &sender);
-                }
+                        // Exit task and continue.
+                        return exit_task_and_continue(&create_task_info_msg, &sender);
+                    }
+                },
             };
 
             // Set transfer_src to true.
@@ -126,7 +222,9 @@ pub fn file_backup_task(
                         // ... and the signature is the same as the src signature, ...
                         if sig_valid_and_match(
                             transferred_node.src_signature,
+                            transferred_node.signature_algo,
                             Some(src_file_signature),
+                            signature_algo,
                         ) {
                             // ... then we don't need to transfer the src.
                             transfer_src = false;
@@ -141,10 +239,102 @@ pub fn file_backup_task(
                 }
             }
 
-            // Transfer source to destination - if needed.
-            if transfer_src {
-                // Set dest rel file path.
-                let mut dest_rel_file_path = src_rel_file_path.clone();
+            // Take a blob with the same content from the dedup store, if dedup is enabled.
+            // Otherwise the signature is reserved, until the new blob is uploaded.
+            let mut dedup_reservation = None;
+
+            let dedup_blob_rel_path = match &dedup_index {
+                Some(dedup_index) if transfer_src => match dedup_index.lookup_blob(
+                    &src_file_signature,
+                    signature_algo,
+                    &backup_flags,
+                    &password_id,
+                ) {
+                    DedupLookup::Stored(blob_rel_path) => Some(blob_rel_path),
+                    DedupLookup::Reserved(reservation) => {
+                        dedup_reservation = Some(reservation);
+                        None
+                    }
+                },
+                _ => None,
+            };
+
+            // Take the previous full version as delta base, if delta compression is enabled.
+            // Dedup takes precedence. The delta is computed in memory, so it is limited in size.
+            let mut delta_base: Option<DeltaBase> = None;
+
+            if transfer_src
+                && delta_compression
+                && dedup_index.is_none()
+                && src_file_metadata
+                    .size
+                    .is_some_and(|size| size <= RDIFF_MAX_SIZE)
+            {
+                let transferred_nodes_read = transferred_nodes.read().unwrap();
+                let backup_view = transferred_nodes_read.view::<Backup>();
+
+                delta_base = backup_view
+                    .get_node_for_src(&src_rel_file_path.clone().into())
+                    .filter(|transferred_node| {
+                        backup_flags.matches(transferred_node.flags)
+                            && password_id == transferred_node.password_id
+                            && transferred_node
+                                .src_size
+                                .is_some_and(|size| size <= RDIFF_MAX_SIZE)
+                    })
+                    .and_then(|transferred_node| match transferred_node.delta_base() {
+                        Some(delta_base) => Some(delta_base),
+                        None => match backup_view.get_dest_rel_path(transferred_node) {
+                            UNPath::File(dest_rel_file_path) => Some(DeltaBase {
+                                rel_path: dest_rel_file_path,
+                                flags: transferred_node.flags,
+                                password_id: transferred_node.password_id.clone(),
+                            }),
+                            _ => None,
+                        },
+                    });
+            }
+
+            // Without transfer, the signature read the source.
+            if unchanged_src_signature.is_none() && (dedup_blob_rel_path.is_some() || !transfer_src)
+            {
+                sender
+                    .send(create_task_info_msg(Arc::new(SourceBytes {
+                        bytes: src_file_metadata.size.unwrap_or(0),
+                    })))
+                    .unwrap();
+            }
+
+            // Refer to the blob or transfer source to destination - if needed.
+            if let Some(blob_rel_path) = dedup_blob_rel_path {
+                // Set transferred file, which refers to the blob, to transferred nodes.
+                transferred_nodes
+                    .write()
+                    .unwrap()
+                    .view_mut::<Backup>()
+                    .set_transferred_node(
+                        &src_rel_file_path.clone().into(),
+                        &TransferredNode::from_file(
+                            &DedupIndex::dest_rel_path(&dest_name_rel_file_path, &blob_rel_path),
+                            transferred_node_flags,
+                            password_id.clone(),
+                            &src_file_signature,
+                            signature_algo,
+                            &src_file_metadata,
+                        )
+                        .with_dedup_blob(Some(blob_rel_path)),
+                    );
+
+                // No transfer was needed, the content is in the backup.
+                sender
+                    .send(create_task_info_msg(Arc::new(TaskInfo::Transferred)))
+                    .unwrap();
+            } else if transfer_src {
+                // Set dest rel file path, new content goes to the dedup store - if enabled.
+                let mut dest_rel_file_path = match &dedup_index {
+                    Some(_) => DedupIndex::blob_rel_path(&src_file_signature),
+                    None => dest_name_rel_file_path.clone(),
+                };
 
                 // Start transferring.
                 sender
@@ -156,7 +346,11 @@ pub fn file_backup_task(
 
                 // Should be compressed?
                 if backup_flags.contains(Flags::COMPRESSED) {
-                    data_procs.push(gz_encode_proc(Compression::default()));
+                    if skip_recompression {
+                        data_procs.push(gz_encode_smart_proc(compression));
+                    } else {
+                        data_procs.push(gz_encode_proc(compression));
+                    }
                 }
 
                 // Should be encypted?
@@ -191,12 +385,50 @@ pub fn file_backup_task(
                     }
                 }
 
+                // Should be delta compressed?
+                if let Some(base) = &delta_base {
+                    // Read the base with the data procs of the base, a failed read falls back to
+                    // a full transfer.
+                    match task_read_procs(
+                        base.flags,
+                        &base.password_id,
+                        &password_cache,
+                        &create_task_error_msg,
+                        &sender,
+                    )
+                    .and_then(|base_data_procs| {
+                        task_read_data(
+                            &fs_conn.dest_mnt,
+                            &fs_conn.dest_mnt.abs_dir_path.add_rel_file(&base.rel_path),
+                            &base_data_procs,
+                            &create_task_error_msg,
+                            &sender,
+                        )
+                    }) {
+                        Some(base) => {
+                            // The delta is computed before compression and encryption.
+                            data_procs.insert(0, rdiff_delta_proc(Arc::new(base)));
+                            transferred_node_flags.insert(Flags::DELTA_COMPRESSED);
+                        }
+                        None => {
+                            delta_base = None;
+                        }
+                    }
+                }
+
+                // Should be throttled? The throttle is the last, so the uploaded bytes are limited.
+                if let Some(throttle) = &throttle {
+                    data_procs.push(throttle_proc(throttle.clone()));
+                }
+
                 // Transfer file.
                 let task_transfer_result = task_transfer_file(
                     &fs_conn,
                     &src_abs_file_path,
+                    src_file_metadata.size,
                     &mut dest_rel_file_path,
                     &data_procs,
+                    None,
                     Some(&create_task_info_msg),
                     &create_task_error_msg,
                     &sender,
@@ -210,26 +442,103 @@ pub fn file_backup_task(
                     &create_task_error_msg,
                     &sender,
                 ) {
-                    // Set transferred file to transferred nodes.
-                    transferred_nodes
-                        .write()
+                    // Compare the metadata after the read, a changed file is inconsistent.
+                    let current_src_file_metadata = fs_conn
+                        .src_mnt
+                        .fs
+                        .read()
                         .unwrap()
-                        .view_mut::<Backup>()
-                        .set_transferred_node(
-                            &src_rel_file_path.clone().into(),
-                            &TransferredNode::from_file(
+                        .meta(&src_abs_file_path.clone().into())
+                        .ok();
+
+                    let src_file_changed =
+                        current_src_file_metadata.as_ref().is_none_or(|metadata| {
+                            metadata.size != src_file_metadata.size
+                                || metadata.modified != src_file_metadata.modified
+                        });
+
+                    if src_file_changed {
+                        // Requires a backup on the next run.
+                        transferred_node_flags.insert(Flags::VERIFY_ERROR);
+
+                        send_warn!(
+                            sender,
+                            "The file {} changed during the backup",
+                            src_rel_file_path.to_unicode()
+                        );
+                    }
+
+                    // Set transferred file to transferred nodes.
+                    let transferred_node = match dedup_reservation.take() {
+                        Some(dedup_reservation) => {
+                            // Add the blob to the dedup index.
+                            dedup_reservation.insert_blob(
+                                signature_algo,
                                 &dest_rel_file_path,
                                 transferred_node_flags,
+                                &password_id,
+                            );
+
+                            TransferredNode::from_file(
+                                &DedupIndex::dest_rel_path(
+                                    &dest_name_rel_file_path,
+                                    &dest_rel_file_path,
+                                ),
+                                transferred_node_flags,
                                 password_id.clone(),
                                 &src_file_signature,
+                                signature_algo,
                                 &src_file_metadata,
-                            ),
-                        );
+                            )
+                            .with_dedup_blob(Some(dest_rel_file_path.clone()))
+                            .with_dest_size(task_transfer_result.map(|bytes| bytes as u64))
+                        }
+                        None => TransferredNode::from_file(
+                            &dest_rel_file_path,
+                            transferred_node_flags,
+                            password_id.clone(),
+                            &src_file_signature,
+                            signature_algo,
+                            &src_file_metadata,
+                        )
+                        .with_delta_base(delta_base)
+                        .with_dest_size(task_transfer_result.map(|bytes| bytes as u64)),
+                    };
+
+                    transferred_nodes
+                        .write()
+                        .unwrap()
+                        .view_mut::<Backup>()
+                        .set_transferred_node(&src_rel_file_path.clone().into(), &transferred_node);
+
+                    // Transferred bytes.
+                    if let Some(bytes) = task_transfer_result {
+                        sender
+                            .send(create_task_info_msg(Arc::new(TransferredBytes {
+                                bytes: bytes as u64,
+                            })))
+                            .unwrap();
+                    }
 
                     // Transfer was successful.
                     sender
                         .send(create_task_info_msg(Arc::new(TaskInfo::Transferred)))
                         .unwrap();
+
+                    // Queue a changed file again with its current metadata, but only once.
+                    if src_file_changed
+                        && retry_changed_files
+                        && let Some(current_src_file_metadata) = current_src_file_metadata
+                        && retried_files
+                            .lock()
+                            .unwrap()
+                            .insert(src_rel_file_path.clone())
+                    {
+                        src_rel_files
+                            .lock()
+                            .unwrap()
+                            .push_back((src_rel_file_path.clone(), current_src_file_metadata));
+                    }
                 } else {
                     // Transfer failed.
                     sender
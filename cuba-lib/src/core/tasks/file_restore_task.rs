@@ -7,24 +7,32 @@ use std::sync::Mutex;
 use crate::shared::message::Info;
 use crate::shared::message::Message;
 use crate::shared::npath::Abs;
+use crate::shared::npath::Dir;
 use crate::shared::npath::File;
 use crate::shared::npath::NPath;
+use crate::shared::npath::NPathError;
 use crate::shared::npath::Rel;
 use crate::shared::npath::UNPath;
 use crate::shared::task_message::TaskError;
 use crate::shared::task_message::TaskInfo;
+use crate::shared::task_message::TransferredBytes;
 
-use super::super::fs::fs_base::FSConnection;
+use super::super::fs::fs_base::{FSConnection, FSError, FSMount};
+use super::super::fs::fs_metadata::FSMetaData;
 use super::super::password_cache::PasswordCache;
 use super::super::process_data::age_procs::age_decrypt_proc;
 use super::super::process_data::data_processor::DataProcessor;
 use super::super::process_data::gz_procs::gz_decode_proc;
+use super::super::process_data::rdiff_proc::rdiff_apply_proc;
+use super::super::process_data::throttle_proc::{Throttle, throttle_proc};
 use super::super::transferred_node::Flags;
 use super::super::transferred_node::Restore;
 use super::super::transferred_node::TransferredNodes;
 use super::super::transferred_node::sig_valid_and_match;
 
 use super::task_helpers::exit_task_and_continue;
+use super::task_helpers::task_read_data;
+use super::task_helpers::task_read_procs;
 use super::task_helpers::task_read_signature;
 use super::task_helpers::task_transfer_file;
 use super::task_helpers::task_transfer_successful;
@@ -32,11 +40,61 @@ use super::task_worker::Task;
 use super::task_worker::TaskErrorFn;
 use super::task_worker::TaskInfoFn;
 
+/// Returns the extensions pushed by the backup to the backup file `src_rel_file_path`.
+///
+/// If the backup file has a hashed name, these are the extensions after the hash.
+fn backup_extensions<'a>(
+    src_rel_file_path: &'a NPath<Rel, File>,
+    dest_rel_path: &UNPath<Rel>,
+) -> &'a str {
+    let src_path = src_rel_file_path.to_unicode();
+
+    src_path
+        .strip_prefix(dest_rel_path.to_unicode())
+        .unwrap_or_else(|| {
+            let file_name = src_path.rsplit('/').next().unwrap_or_default();
+            file_name.find('.').map_or("", |index| &file_name[index..])
+        })
+}
+
+/// Returns the connection and the dest rel file path to restore a file to `restore_to`.
+///
+/// The dest rel file path keeps the extensions of the backup file, which are
+/// popped by the data processors.
+fn restore_to_target(
+    fs_conn: &FSConnection,
+    restore_to: &NPath<Abs, File>,
+    src_rel_file_path: &NPath<Rel, File>,
+    dest_rel_path: &UNPath<Rel>,
+) -> Result<(FSConnection, NPath<Rel, File>), NPathError> {
+    let (abs_dir, file_name) = restore_to
+        .to_unicode()
+        .rsplit_once('/')
+        .ok_or(NPathError::InvalidOperation)?;
+
+    // The extensions pushed by the backup.
+    let extensions = backup_extensions(src_rel_file_path, dest_rel_path);
+
+    let dest_mnt = FSMount::new(
+        fs_conn.dest_mnt.fs.clone(),
+        Arc::new(NPath::<Abs, Dir>::try_from(abs_dir)?),
+    );
+
+    Ok((
+        FSConnection::new(fs_conn.src_mnt.clone(), dest_mnt),
+        NPath::<Rel, File>::try_from(format!("{}{}", file_name, extensions))?,
+    ))
+}
+
 /// Task for restore the files.
+///
+/// With `restore_to` the file is restored to this path instead of the dest dir.
 pub fn file_restore_task(
     src_rel_files: Arc<Mutex<VecDeque<NPath<Rel, File>>>>,
     transferred_nodes_read: Arc<TransferredNodes>,
     password_cache: Arc<Mutex<PasswordCache>>,
+    restore_to: Option<NPath<Abs, File>>,
+    throttle: Option<Arc<Throttle>>,
 ) -> impl Task {
     move |create_task_error_msg: &dyn TaskErrorFn,
           create_task_info_msg: &dyn TaskInfoFn,
@@ -65,23 +123,34 @@ pub fn file_restore_task(
                 .view::<Restore>()
                 .get_node_for_src(&src_rel_file_path.clone().into())
             {
-                // Create absolut path to the src file.
-                let src_abs_file_path: NPath<Abs, File> = fs_conn
-                    .src_mnt
-                    .abs_dir_path
-                    .add_rel_file(&src_rel_file_path);
+                // Create absolut path to the src file, which is the blob if deduplicated.
+                let src_abs_file_path: NPath<Abs, File> =
+                    fs_conn.src_mnt.abs_dir_path.add_rel_file(
+                        transferred_node
+                            .dedup_blob_rel_path
+                            .as_ref()
+                            .unwrap_or(&src_rel_file_path),
+                    );
 
-                // Set dest rel file path.
-                if let Some(UNPath::<Rel>::File(dest_rel_file_path)) = transferred_nodes_read
+                // The restored rel path.
+                let restored_rel_path = transferred_nodes_read
                     .view::<Restore>()
-                    .get_dest_rel_path(transferred_node)
-                {
-                    // Create absolut path to the dest file.
-                    let dest_abs_file_path: NPath<Abs, File> = fs_conn
-                        .dest_mnt
-                        .abs_dir_path
-                        .add_rel_file(&dest_rel_file_path);
+                    .get_dest_rel_path(transferred_node);
+
+                // Create absolut path to the dest file.
+                let dest_abs_file_path: Option<NPath<Abs, File>> =
+                    match (&restore_to, &restored_rel_path) {
+                        (Some(restore_to), _) => Some(restore_to.clone()),
+                        (None, Some(UNPath::<Rel>::File(dest_rel_file_path))) => Some(
+                            fs_conn
+                                .dest_mnt
+                                .abs_dir_path
+                                .add_rel_file(dest_rel_file_path),
+                        ),
+                        (None, _) => None,
+                    };
 
+                if let Some(dest_abs_file_path) = dest_abs_file_path {
                     // Init dest file signature.
                     let mut dest_file_signature: Option<[u8; 32]> = None;
 
@@ -98,13 +167,19 @@ pub fn file_restore_task(
                         dest_file_signature = task_read_signature(
                             &fs_conn.dest_mnt,
                             &dest_abs_file_path.clone(),
+                            transferred_node.signature_algo,
                             &create_task_error_msg,
                             &sender,
                         );
                     }
 
                     // Check if signatures are equal.
-                    if sig_valid_and_match(transferred_node.src_signature, dest_file_signature) {
+                    if sig_valid_and_match(
+                        transferred_node.src_signature,
+                        transferred_node.signature_algo,
+                        dest_file_signature,
+                        transferred_node.signature_algo,
+                    ) {
                         // No transfer needed.
                         sender
                             .send(create_task_info_msg(Arc::new(TaskInfo::UpToDate)))
@@ -120,8 +195,33 @@ pub fn file_restore_task(
                     }
                 }
 
-                // Set dest rel file path.
-                let mut dest_rel_file_path = src_rel_file_path.clone();
+                // Set connection and dest rel file path, which is popped by the data procs.
+                let target = match (&restore_to, &restored_rel_path) {
+                    (Some(restore_to), Some(restored_rel_path)) => restore_to_target(
+                        &fs_conn,
+                        restore_to,
+                        &src_rel_file_path,
+                        restored_rel_path,
+                    ),
+                    // The backup file may have a hashed name, so the restored path is taken.
+                    (None, Some(restored_rel_path)) => NPath::<Rel, File>::try_from(format!(
+                        "{}{}",
+                        restored_rel_path.to_unicode(),
+                        backup_extensions(&src_rel_file_path, restored_rel_path)
+                    ))
+                    .map(|dest_rel_file_path| (fs_conn.clone(), dest_rel_file_path)),
+                    _ => Ok((fs_conn.clone(), src_rel_file_path.clone())),
+                };
+
+                let (fs_conn, mut dest_rel_file_path) = match target {
+                    Ok(target) => target,
+                    Err(err) => {
+                        sender.send(create_task_error_msg(Arc::new(err))).unwrap();
+
+                        // Exit task and continue.
+                        return exit_task_and_continue(&create_task_info_msg, &sender);
+                    }
+                };
 
                 // Start transferring.
                 sender
@@ -168,12 +268,66 @@ pub fn file_restore_task(
                     data_procs.push(gz_decode_proc());
                 }
 
+                // Is delta compressed?
+                if transferred_node.flags.contains(Flags::DELTA_COMPRESSED) {
+                    // Get delta base.
+                    match transferred_node.delta_base() {
+                        Some(delta_base) => {
+                            // Read the base with the data procs of the base.
+                            match task_read_procs(
+                                delta_base.flags,
+                                &delta_base.password_id,
+                                &password_cache,
+                                &create_task_error_msg,
+                                &sender,
+                            )
+                            .and_then(|base_data_procs| {
+                                task_read_data(
+                                    &fs_conn.src_mnt,
+                                    &fs_conn
+                                        .src_mnt
+                                        .abs_dir_path
+                                        .add_rel_file(&delta_base.rel_path),
+                                    &base_data_procs,
+                                    &create_task_error_msg,
+                                    &sender,
+                                )
+                            }) {
+                                Some(base) => {
+                                    // Add delta applier.
+                                    data_procs.push(rdiff_apply_proc(Arc::new(base)));
+                                }
+                                None => {
+                                    // Exit task and continue.
+                                    return exit_task_and_continue(&create_task_info_msg, &sender);
+                                }
+                            }
+                        }
+                        None => {
+                            // No delta base.
+                            sender
+                                .send(create_task_error_msg(Arc::new(TaskError::NoDeltaBase)))
+                                .unwrap();
+
+                            // Exit task and continue.
+                            return exit_task_and_continue(&create_task_info_msg, &sender);
+                        }
+                    }
+                }
+
+                // Should be throttled? The throttle is the first, so the downloaded bytes are limited.
+                if let Some(throttle) = &throttle {
+                    data_procs.insert(0, throttle_proc(throttle.clone()));
+                }
+
                 // Transfer file.
                 let task_transfer_result = task_transfer_file(
                     &fs_conn,
                     &src_abs_file_path,
+                    transferred_node.dest_size,
                     &mut dest_rel_file_path,
                     &data_procs,
+                    None,
                     Some(&create_task_info_msg),
                     &create_task_error_msg,
                     &sender,
@@ -187,6 +341,15 @@ pub fn file_restore_task(
                     &create_task_error_msg,
                     &sender,
                 ) {
+                    // Transferred bytes.
+                    if let Some(bytes) = task_transfer_result {
+                        sender
+                            .send(create_task_info_msg(Arc::new(TransferredBytes {
+                                bytes: bytes as u64,
+                            })))
+                            .unwrap();
+                    }
+
                     // Transfer was successful.
                     sender
                         .send(create_task_info_msg(Arc::new(TaskInfo::Transferred)))
@@ -201,19 +364,28 @@ pub fn file_restore_task(
                     return exit_task_and_continue(&create_task_info_msg, &sender);
                 }
 
+                // The restored file.
+                let dest_abs_file_path = fs_conn
+                    .dest_mnt
+                    .abs_dir_path
+                    .add_rel_file(&dest_rel_file_path);
+
                 // Read dest file signature.
                 let dest_file_signature = task_read_signature(
                     &fs_conn.dest_mnt,
-                    &fs_conn
-                        .dest_mnt
-                        .abs_dir_path
-                        .add_rel_file(&dest_rel_file_path),
+                    &dest_abs_file_path,
+                    transferred_node.signature_algo,
                     &create_task_error_msg,
                     &sender,
                 );
 
                 // Check if signatures are equal.
-                if sig_valid_and_match(transferred_node.src_signature, dest_file_signature) {
+                if sig_valid_and_match(
+                    transferred_node.src_signature,
+                    transferred_node.signature_algo,
+                    dest_file_signature,
+                    transferred_node.signature_algo,
+                ) {
                     sender
                         .send(create_task_info_msg(Arc::new(TaskInfo::Verified)))
                         .unwrap();
@@ -222,6 +394,28 @@ pub fn file_restore_task(
                         .send(create_task_error_msg(Arc::new(TaskError::VerifiedFailed)))
                         .unwrap();
                 }
+
+                // Restore modified time and mode, if the fs supports it.
+                let metadata = FSMetaData::new(
+                    transferred_node.src_created,
+                    transferred_node.src_modified,
+                    None,
+                    None,
+                )
+                .with_mode(transferred_node.src_mode);
+
+                match fs_conn
+                    .dest_mnt
+                    .fs
+                    .read()
+                    .unwrap()
+                    .set_meta(&dest_abs_file_path.into(), &metadata)
+                {
+                    Ok(_) | Err(FSError::NotSupported(_)) => {}
+                    Err(err) => {
+                        sender.send(create_task_error_msg(Arc::new(err))).unwrap();
+                    }
+                }
             } else {
                 // No transferred node found.
                 sender
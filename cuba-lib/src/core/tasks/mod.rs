@@ -3,6 +3,7 @@ pub mod directory_restore_task;
 pub mod file_backup_task;
 pub mod file_restore_task;
 pub mod node_verify_task;
+pub mod progress_tracker;
 pub mod symlink_backup_task;
 pub mod symlink_restore_task;
 pub mod task_worker;
@@ -4,6 +4,7 @@ use std::error::Error;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::RwLock;
+use std::time::SystemTime;
 
 use crate::shared::message::Info;
 use crate::shared::message::Message;
@@ -16,19 +17,23 @@ use crate::shared::task_message::TaskError;
 use crate::shared::task_message::TaskInfo;
 use crate::shared::task_message::TaskMessage;
 
-use super::super::fs::fs_base::FSConnection;
+use super::super::fs::fs_base::{FSConnection, FSMount};
 use super::super::password_cache::PasswordCache;
 use super::super::process_data::age_procs::age_decrypt_proc;
 use super::super::process_data::data_processor::DataProcessor;
 use super::super::process_data::gz_procs::gz_decode_proc;
-use super::super::process_data::signature_proc::signature_proc;
+use super::super::process_data::rdiff_proc::rdiff_apply_proc;
+use super::super::process_data::signature_proc::SignatureProcessor;
 use super::super::transferred_node::Flags;
-use super::super::transferred_node::MaskedFlags;
 use super::super::transferred_node::Restore;
 use super::super::transferred_node::TransferredNodes;
 use super::super::transferred_node::sig_valid_and_match;
+use super::super::verify::VerifyMode;
 
 use super::task_helpers::exit_task_and_continue;
+use super::task_helpers::task_read_data;
+use super::task_helpers::task_read_procs;
+use super::task_helpers::task_read_signature;
 use super::task_helpers::task_transfer_file;
 use super::task_worker::Task;
 use super::task_worker::TaskErrorFn;
@@ -62,20 +67,29 @@ fn set_verified_ok(
             .unwrap();
     }
 
-    // Set flags.
-    transferred_nodes
-        .write()
-        .unwrap()
-        .view_mut::<Restore>()
-        .set_flags(src_rel_path, flags);
+    // Set flags and the time of a successful verify.
+    let mut transferred_nodes = transferred_nodes.write().unwrap();
+    let mut view = transferred_nodes.view_mut::<Restore>();
+
+    view.set_flags(src_rel_path, flags);
+
+    if ok {
+        view.set_last_verified(src_rel_path, SystemTime::now());
+    }
 }
 
 /// Task for verify the nodes.
+///
+/// If `backup_src_mnt` is given, files without a stored source signature are compared
+/// against a freshly computed signature of the source file. With `full`, this is done
+/// for all files.
 pub fn node_verify_task(
     src_rel_nodes: Arc<Mutex<VecDeque<UNPath<Rel>>>>,
     transferred_nodes: Arc<RwLock<TransferredNodes>>,
-    verify_flags: MaskedFlags,
+    verify_mode: VerifyMode,
     password_cache: Arc<Mutex<PasswordCache>>,
+    backup_src_mnt: Option<FSMount>,
+    full: bool,
 ) -> impl Task {
     move |create_task_error_msg: &dyn TaskErrorFn,
           create_task_info_msg: &dyn TaskInfoFn,
@@ -108,8 +122,8 @@ pub fn node_verify_task(
 
             // Check if a transferred node exists.
             if let Some(transferred_node) = transferred_node_opt {
-                // If verify flags match, verify ...
-                if verify_flags.matches(transferred_node.flags) {
+                // If selected by the verify mode, verify ...
+                if verify_mode.selects(&transferred_node) {
                     // Type?
                     match src_rel_path {
                         UNPath::Dir(ref src_rel_dir_path) => {
@@ -139,12 +153,18 @@ pub fn node_verify_task(
                             );
                         }
                         UNPath::File(ref src_rel_file_path) => {
-                            // Create absolut path to the src file.
+                            // Create absolut path to the src file, which is the blob if deduplicated.
                             let src_abs_file_path: NPath<Abs, File> =
-                                fs_conn.src_mnt.abs_dir_path.add_rel_file(src_rel_file_path);
+                                fs_conn.src_mnt.abs_dir_path.add_rel_file(
+                                    transferred_node
+                                        .dedup_blob_rel_path
+                                        .as_ref()
+                                        .unwrap_or(src_rel_file_path),
+                                );
 
-                            // Init transfer file signature.
-                            let transfer_file_signature = Arc::new(Mutex::new([0u8; 32]));
+                            // Init transfer file signature processor with the algorithm of the node.
+                            let mut signature_processor =
+                                SignatureProcessor::new(transferred_node.signature_algo);
 
                             // Make data procs vector.
                             let mut data_procs: Vec<DataProcessor> = Vec::new();
@@ -200,31 +220,118 @@ pub fn node_verify_task(
                                 data_procs.push(gz_decode_proc());
                             }
 
-                            // Add signature processor.
-                            data_procs.push(signature_proc(transfer_file_signature.clone()));
+                            // Is delta compressed?
+                            if transferred_node.flags.contains(Flags::DELTA_COMPRESSED) {
+                                // Get delta base.
+                                match transferred_node.delta_base() {
+                                    Some(delta_base) => {
+                                        // Read the base with the data procs of the base.
+                                        match task_read_procs(
+                                            delta_base.flags,
+                                            &delta_base.password_id,
+                                            &password_cache,
+                                            &create_task_error_msg,
+                                            &sender,
+                                        )
+                                        .and_then(
+                                            |base_data_procs| {
+                                                task_read_data(
+                                                    &fs_conn.src_mnt,
+                                                    &fs_conn
+                                                        .src_mnt
+                                                        .abs_dir_path
+                                                        .add_rel_file(&delta_base.rel_path),
+                                                    &base_data_procs,
+                                                    &create_task_error_msg,
+                                                    &sender,
+                                                )
+                                            },
+                                        ) {
+                                            Some(base) => {
+                                                // Add delta applier.
+                                                data_procs.push(rdiff_apply_proc(Arc::new(base)));
+                                            }
+                                            None => {
+                                                // Exit task and continue.
+                                                return exit_task_and_continue(
+                                                    &create_task_info_msg,
+                                                    &sender,
+                                                );
+                                            }
+                                        }
+                                    }
+                                    None => {
+                                        // No delta base.
+                                        sender
+                                            .send(create_task_error_msg(Arc::new(
+                                                TaskError::NoDeltaBase,
+                                            )))
+                                            .unwrap();
+
+                                        // Exit task and continue.
+                                        return exit_task_and_continue(
+                                            &create_task_info_msg,
+                                            &sender,
+                                        );
+                                    }
+                                }
+                            }
 
                             // Transfer file.
-                            task_transfer_file(
+                            let transferred = task_transfer_file(
                                 &fs_conn,
                                 &src_abs_file_path,
+                                transferred_node.dest_size,
                                 &mut NPath::<Rel, File>::default(),
                                 &data_procs,
+                                Some(&mut signature_processor),
                                 Some(&create_task_info_msg),
                                 &create_task_error_msg,
                                 &sender,
                             );
 
-                            // Note: signature_proc writes the signature when being dropped.
-                            // This is working here, because task_transfer_file gets ownership of
-                            // data_procs - which is dropped when leaving task_transfer_file.
-                            // If task_transfer_file borrows data_procs, signature_proc must be dropped
-                            // expicit before the call of sig_valid_and_match.
+                            // Compute the signature of the source file, if there is none
+                            // stored or a full verify is requested.
+                            let src_signature = match &backup_src_mnt {
+                                Some(backup_src_mnt)
+                                    if full || transferred_node.src_signature.is_none() =>
+                                {
+                                    task_read_signature(
+                                        backup_src_mnt,
+                                        &backup_src_mnt
+                                            .abs_dir_path
+                                            .add_rel_file(src_rel_file_path),
+                                        transferred_node.signature_algo,
+                                        &create_task_error_msg,
+                                        &sender,
+                                    )
+                                }
+                                _ => transferred_node.src_signature,
+                            };
+
+                            let ok = match src_signature {
+                                // Check if signatures are equal.
+                                Some(src_signature) => sig_valid_and_match(
+                                    Some(src_signature),
+                                    transferred_node.signature_algo,
+                                    Some(signature_processor.finalize()),
+                                    transferred_node.signature_algo,
+                                ),
+                                // Without a signature, check only if the file exists.
+                                None => transferred.is_some(),
+                            };
 
-                            // Check if signatures are equal.
-                            let ok = sig_valid_and_match(
-                                transferred_node.src_signature,
-                                Some(*transfer_file_signature.lock().unwrap()),
-                            );
+                            // Check if the size of the file is the size, which was written.
+                            let ok = ok
+                                && fs_conn
+                                    .src_mnt
+                                    .fs
+                                    .read()
+                                    .unwrap()
+                                    .meta(&src_abs_file_path.clone().into())
+                                    .is_ok_and(|metadata| {
+                                        transferred_node.dest_size_matches(&metadata)
+                                    });
 
                             set_verified_ok(
                                 ok,
@@ -0,0 +1,123 @@
+use crossbeam_channel::Sender;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::send_info;
+use crate::shared::message::Message;
+use crate::shared::progress_message::{ProgressInfo, ProgressMessage};
+
+/// The milestones of the progress in percent.
+const PROGRESS_MILESTONES: [usize; 4] = [25, 50, 75, 100];
+
+/// Defines a `GlobalProgressTracker`.
+///
+/// Tracks the processed work items of an operation and sends an info at each
+/// milestone, so that a monitor can follow the progress in the info log.
+pub struct GlobalProgressTracker {
+    operation: &'static str,
+    duration: usize,
+    processed: AtomicUsize,
+    sender: Sender<Arc<dyn Message>>,
+}
+
+/// Methods of `GlobalProgressTracker`.
+impl GlobalProgressTracker {
+    /// Creates a new `GlobalProgressTracker` for `duration` work items.
+    pub fn new(operation: &'static str, duration: usize, sender: Sender<Arc<dyn Message>>) -> Self {
+        Self {
+            operation,
+            duration,
+            processed: AtomicUsize::new(0),
+            sender,
+        }
+    }
+
+    /// Advances the progress by `items` work items.
+    pub fn advance(&self, items: usize) {
+        let previous = self.processed.fetch_add(items, Ordering::Relaxed);
+        self.send_milestones(previous, previous + items);
+    }
+
+    /// Completes the progress, the missing milestones are sent.
+    pub fn complete(&self) {
+        let previous = self.processed.fetch_max(self.duration, Ordering::Relaxed);
+        self.send_milestones(previous, self.duration);
+    }
+
+    /// Sends the milestones, which are reached from `previous` to `current` work items.
+    fn send_milestones(&self, previous: usize, current: usize) {
+        for milestone in PROGRESS_MILESTONES {
+            let threshold = (self.duration * milestone).div_ceil(100);
+
+            if previous < threshold && current >= threshold {
+                send_info!(
+                    self.sender,
+                    "{} progress {}% ({} of {} items)",
+                    self.operation,
+                    milestone,
+                    threshold,
+                    self.duration
+                );
+            }
+        }
+    }
+}
+
+/// Defines a `ProgressTicks`.
+///
+/// Sends the duration and the ticks of an operation. The sent ticks are counted, so the
+/// ticks never exceed the duration and the missing ticks complete the progress.
+pub struct ProgressTicks {
+    duration: usize,
+    sent: AtomicUsize,
+    sender: Sender<Arc<dyn Message>>,
+}
+
+/// Methods of `ProgressTicks`.
+impl ProgressTicks {
+    /// Creates a new `ProgressTicks` and sends the progress duration of `duration` ticks.
+    pub fn new(duration: usize, sender: Sender<Arc<dyn Message>>) -> Self {
+        sender
+            .send(Arc::new(ProgressMessage::new(
+                Arc::new(ProgressInfo::Duration),
+                duration as u64,
+            )))
+            .unwrap();
+
+        Self {
+            duration,
+            sent: AtomicUsize::new(0),
+            sender,
+        }
+    }
+
+    /// Sends up to `ticks` ticks, without exceeding the duration.
+    pub fn tick(&self, ticks: usize) {
+        let previous = self
+            .sent
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |sent| {
+                Some((sent + ticks).min(self.duration))
+            })
+            .unwrap();
+
+        self.send_ticks((previous + ticks).min(self.duration) - previous);
+    }
+
+    /// Completes the progress, the missing ticks are sent.
+    pub fn complete(&self) {
+        let previous = self.sent.swap(self.duration, Ordering::Relaxed);
+        self.send_ticks(self.duration.saturating_sub(previous));
+    }
+
+    /// Sends `ticks` ticks, if any.
+    fn send_ticks(&self, ticks: usize) {
+        if ticks > 0 {
+            self.sender
+                .send(Arc::new(ProgressMessage::new(
+                    Arc::new(ProgressInfo::Ticks),
+                    ticks as u64,
+                )))
+                .unwrap();
+        }
+    }
+}
@@ -14,6 +14,7 @@ use crate::shared::npath::Rel;
 use crate::shared::npath::Symlink;
 use crate::shared::task_message::TaskInfo;
 
+use super::super::filename_cipher::FilenameCipher;
 use super::super::fs::fs_base::FSConnection;
 use super::super::transferred_node::Backup;
 use super::super::transferred_node::Flags;
@@ -32,6 +33,7 @@ pub fn symlink_backup_task(
     src_rel_symlinks: Arc<Mutex<VecDeque<NPath<Rel, Symlink>>>>,
     transferred_nodes: Arc<RwLock<TransferredNodes>>,
     backup_flags: MaskedFlags,
+    filename_cipher: Option<Arc<FilenameCipher>>,
 ) -> impl Task {
     move |create_task_error_msg: &dyn TaskErrorFn,
           create_task_info_msg: &dyn TaskInfoFn,
@@ -121,6 +123,12 @@ pub fn symlink_backup_task(
                     .send(create_task_info_msg(Arc::new(TaskInfo::UpToDate)))
                     .unwrap();
             } else {
+                // Set dest rel symlink path, which is hashed if the names are encrypted.
+                let dest_rel_sym_path = match &filename_cipher {
+                    Some(filename_cipher) => filename_cipher.hash_rel_path(&src_rel_sym_path),
+                    None => src_rel_sym_path.clone(),
+                };
+
                 // Set symlink to transferred nodes.
                 transferred_nodes
                     .write()
@@ -129,7 +137,7 @@ pub fn symlink_backup_task(
                     .set_transferred_node(
                         &src_rel_sym_path.clone().into(),
                         &TransferredNode::from_symlink(
-                            &src_rel_sym_path,
+                            &dest_rel_sym_path,
                             transferred_node_flags,
                             &src_sym_metadata,
                         ),
@@ -4,7 +4,7 @@ use std::error::Error;
 use std::sync::Arc;
 use std::sync::Mutex;
 
-use crate::core::transferred_node::Restore;
+use crate::core::transferred_node::Backup;
 use crate::core::transferred_node::TransferredNodes;
 use crate::shared::message::Info;
 use crate::shared::message::Message;
@@ -50,9 +50,9 @@ pub fn symlink_restore_task(
                 .send(create_task_info_msg(Arc::new(TaskInfo::Start)))
                 .unwrap();
 
-            // Check if a transferred node exists.
+            // Check if a transferred node exists, the symlink is restored to its src path.
             if let Some(transferred_node) = transferred_nodes_read
-                .view::<Restore>()
+                .view::<Backup>()
                 .get_node_for_src(&src_rel_sym_path.clone().into())
             {
                 // Create absolut path to the dest symlink.
@@ -6,37 +6,35 @@ use std::io::Read;
 use std::io::Write;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::shared::config::SignatureAlgo;
 use crate::shared::message::Info;
 use crate::shared::message::Message;
 use crate::shared::npath::Abs;
 use crate::shared::npath::File;
 use crate::shared::npath::NPath;
 use crate::shared::npath::Rel;
-use crate::shared::progress_message::ProgressInfo;
-use crate::shared::progress_message::ProgressMessage;
+use crate::shared::task_message::SourceBytes;
+use crate::shared::task_message::TaskError;
 use crate::shared::task_message::TaskInfo;
 use crate::shared::task_message::TaskMessage;
 
 use super::super::fs::fs_base::FSBlockSize;
 use super::super::fs::fs_base::FSConnection;
 use super::super::fs::fs_base::FSMount;
-use super::super::process_data::data_processor::DataProcessor;
-use super::super::process_data::signature_proc::signature_proc;
+use super::super::password_cache::PasswordCache;
+use super::super::process_data::age_procs::age_decrypt_proc;
+use super::super::process_data::data_processor::{DataProcessor, traced_stage};
+use super::super::process_data::gz_procs::gz_decode_proc;
+use super::super::process_data::signature_proc::SignatureProcessor;
+use super::super::transferred_node::Flags;
 
 /// Exit task.
 pub fn exit_task_and_continue(
     create_task_info_msg: &dyn Fn(Arc<dyn Info + Send + Sync>) -> Arc<TaskMessage>,
     sender: &Sender<Arc<dyn Message>>,
 ) -> bool {
-    // Progress tick.
-    sender
-        .send(Arc::new(ProgressMessage::new(
-            Arc::new(ProgressInfo::Ticks),
-            1,
-        )))
-        .unwrap();
-
     // Task finished.
     sender
         .send(create_task_info_msg(Arc::new(TaskInfo::Finished)))
@@ -67,7 +65,17 @@ pub fn task_transfer_successful(
                 sender,
             ) {
                 Some(dest_file_meta) => match dest_file_meta.size {
-                    Some(size) => size == transferred_bytes as u64,
+                    Some(size) if size == transferred_bytes as u64 => true,
+                    Some(size) => {
+                        // The written bytes do not match the final size.
+                        sender
+                            .send(create_task_error_msg(Arc::new(TaskError::SizeMismatch {
+                                written: transferred_bytes as u64,
+                                size,
+                            })))
+                            .unwrap();
+                        false
+                    }
                     None => false,
                 },
                 None => false,
@@ -98,16 +106,91 @@ where
     }
 }
 
+/// Copies a file from fs_conn.src to fs_conn.dest on the server side.
+///
+/// Returns the size of the copy or `None`, if the file must be transferred.
+fn task_copy_file(
+    fs_conn: &FSConnection,
+    src_abs_file_path: &NPath<Abs, File>,
+    dest_rel_file_path: &NPath<Rel, File>,
+) -> Option<usize> {
+    let dest_abs_file_path = fs_conn
+        .dest_mnt
+        .abs_dir_path
+        .add_rel_file(dest_rel_file_path);
+    let dest_fs = fs_conn.dest_mnt.fs.read().unwrap();
+
+    // Any failure falls back to the transfer.
+    dest_fs
+        .copy_file(src_abs_file_path, &dest_abs_file_path)
+        .ok()?;
+
+    dest_fs
+        .meta(&dest_abs_file_path.into())
+        .ok()?
+        .size
+        .map(|size| size as usize)
+}
+
+/// Defines a `CountingReader`.
+///
+/// A reader that counts the bytes read from the source of a transfer.
+struct CountingReader {
+    reader: Box<dyn Read + Send>,
+    bytes: Arc<AtomicU64>,
+}
+
+/// Impl of `Read` for `CountingReader`.
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes_read = self.reader.read(buf)?;
+        self.bytes.fetch_add(bytes_read as u64, Ordering::Relaxed);
+
+        Ok(bytes_read)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 /// Transfers a file from fs_conn.src to fs_conn.dest.
+///
+/// With `src_size`, the progress of the file is sent instead of ticks.
 pub fn task_transfer_file(
     fs_conn: &FSConnection,
     src_abs_file_path: &NPath<Abs, File>,
+    src_size: Option<u64>,
     dest_rel_file_path: &mut NPath<Rel, File>,
     data_procs: &Vec<DataProcessor>,
+    signature_processor: Option<&mut SignatureProcessor>,
     create_task_info_msg: Option<&dyn Fn(Arc<dyn Info + Send + Sync>) -> Arc<TaskMessage>>,
     create_task_error_msg: &dyn Fn(Arc<dyn Error + Send + Sync>) -> Arc<TaskMessage>,
     sender: &Sender<Arc<dyn Message>>,
 ) -> Option<usize> {
+    let span = tracing::info_span!(
+        "transfer_file",
+        path = src_abs_file_path.to_unicode(),
+        bytes = tracing::field::Empty
+    );
+    let _entered = span.enter();
+
+    // Copy on the server, if the data is not processed.
+    if data_procs.is_empty()
+        && signature_processor.is_none()
+        && let Some(copied_bytes) = task_copy_file(fs_conn, src_abs_file_path, dest_rel_file_path)
+    {
+        span.record("bytes", copied_bytes);
+
+        // The copy read the whole source.
+        if let Some(create_task_info_msg) = create_task_info_msg {
+            sender
+                .send(create_task_info_msg(Arc::new(SourceBytes {
+                    bytes: copied_bytes as u64,
+                })))
+                .unwrap();
+        }
+
+        return Some(copied_bytes);
+    }
+
     // Open the src_file for reading.
     let src_reader: Box<dyn Read + Send> = task_handle_error(
         fs_conn
@@ -120,15 +203,34 @@ pub fn task_transfer_file(
         sender,
     )?;
 
+    // The bytes read from the source.
+    let src_bytes = Arc::new(AtomicU64::new(0));
+
     // Create buf reader.
-    let mut data: Box<dyn Read + Send> = Box::new(BufReader::new(src_reader));
+    let mut data: Box<dyn Read + Send> = Box::new(BufReader::new(CountingReader {
+        reader: src_reader,
+        bytes: src_bytes.clone(),
+    }));
 
     // Apply data processors.
-    for proc in data_procs.iter() {
-        data = proc(
-            sender.clone(),
-            Box::new(BufReader::new(data)),
-            Some(dest_rel_file_path),
+    for (stage, proc) in data_procs.iter().enumerate() {
+        data = traced_stage(
+            proc(
+                sender.clone(),
+                Box::new(BufReader::new(data)),
+                Some(dest_rel_file_path),
+            ),
+            stage,
+            src_abs_file_path.to_unicode(),
+        );
+    }
+
+    // Hash the processed data - if requested.
+    if let Some(signature_processor) = signature_processor.as_deref() {
+        data = traced_stage(
+            signature_processor.data_proc()(sender.clone(), Box::new(BufReader::new(data)), None),
+            data_procs.len(),
+            src_abs_file_path.to_unicode(),
         );
     }
 
@@ -138,9 +240,6 @@ pub fn task_transfer_file(
         &fs_conn.dest_mnt.fs.read().unwrap().block_size(),
     );
 
-    // Init bytes of the transfer.
-    let mut transferred_bytes = 0;
-
     // Write data.
     match fs_conn.dest_mnt.fs.read().unwrap().write_data(
         &fs_conn
@@ -161,8 +260,6 @@ pub fn task_transfer_file(
                             break; // EOR
                         }
 
-                        transferred_bytes += bytes_read;
-
                         task_handle_error(
                             write.write_all(&data_buffer[..bytes_read]),
                             &create_task_error_msg,
@@ -172,31 +269,139 @@ pub fn task_transfer_file(
                     None => return None,
                 };
 
-                // Send tick.
+                // Send progress, or a tick if the size is unknown.
                 if let Some(create_task_info_msg) = create_task_info_msg {
+                    let task_info = match src_size {
+                        Some(total) => TaskInfo::Progress {
+                            done: src_bytes.load(Ordering::Relaxed).min(total),
+                            total,
+                        },
+                        None => TaskInfo::Tick,
+                    };
+
                     sender
-                        .send(create_task_info_msg(Arc::new(TaskInfo::Tick)))
+                        .send(create_task_info_msg(Arc::new(task_info)))
                         .unwrap();
                 }
             }
 
+            // The bytes of the transfer, which reached the writer.
+            let transferred_bytes = write.bytes_written() as usize;
+
             // Finish write.
-            write.finish();
+            task_handle_error(write.finish(), &create_task_error_msg, sender)?;
+
+            span.record("bytes", transferred_bytes);
+
+            // The bytes read from the source.
+            if let Some(create_task_info_msg) = create_task_info_msg {
+                sender
+                    .send(create_task_info_msg(Arc::new(SourceBytes {
+                        bytes: src_bytes.load(Ordering::Relaxed),
+                    })))
+                    .unwrap();
+            }
+
+            // Take the signature, after all data is read.
+            if let Some(signature_processor) = signature_processor {
+                signature_processor.finalize();
+            }
+
+            Some(transferred_bytes)
         }
         Err(error) => {
             // Error
             sender.send(create_task_error_msg(Arc::new(error))).unwrap();
-            return None;
+            None
         }
     }
+}
+
+/// Reads the data of a file into memory, after applying the data processors.
+pub fn task_read_data(
+    fs_mnt: &FSMount,
+    abs_file_path: &NPath<Abs, File>,
+    data_procs: &[DataProcessor],
+    create_task_error_msg: &dyn Fn(Arc<dyn Error + Send + Sync>) -> Arc<TaskMessage>,
+    sender: &Sender<Arc<dyn Message>>,
+) -> Option<Vec<u8>> {
+    // Open the file for reading.
+    let reader: Box<dyn Read + Send> = task_handle_error(
+        fs_mnt.fs.read().unwrap().read_data(abs_file_path),
+        &create_task_error_msg,
+        sender,
+    )?;
+
+    // Create buf reader.
+    let mut data: Box<dyn Read + Send> = Box::new(BufReader::new(reader));
+
+    // Apply data processors.
+    for (stage, proc) in data_procs.iter().enumerate() {
+        data = traced_stage(
+            proc(sender.clone(), Box::new(BufReader::new(data)), None),
+            stage,
+            abs_file_path.to_unicode(),
+        );
+    }
+
+    // Read data.
+    let mut buffer = Vec::new();
+    task_handle_error(
+        data.read_to_end(&mut buffer),
+        &create_task_error_msg,
+        sender,
+    )?;
+
+    Some(buffer)
+}
+
+/// Returns the data processors to read a dest file with `flags`, encrypted with `password_id`.
+pub fn task_read_procs(
+    flags: Flags,
+    password_id: &Option<String>,
+    password_cache: &Mutex<PasswordCache>,
+    create_task_error_msg: &dyn Fn(Arc<dyn Error + Send + Sync>) -> Arc<TaskMessage>,
+    sender: &Sender<Arc<dyn Message>>,
+) -> Option<Vec<DataProcessor>> {
+    // Make data procs vector.
+    let mut data_procs: Vec<DataProcessor> = Vec::new();
+
+    // Is encypted?
+    if flags.contains(Flags::ENCRYPTED) {
+        // Get password id.
+        let Some(password_id) = password_id else {
+            sender
+                .send(create_task_error_msg(Arc::new(TaskError::NoPasswordId)))
+                .unwrap();
+            return None;
+        };
+
+        // Get password and add decryptor.
+        let password = task_handle_error(
+            password_cache
+                .lock()
+                .unwrap()
+                .get_password(password_id)
+                .cloned(),
+            &create_task_error_msg,
+            sender,
+        )?;
+        data_procs.push(age_decrypt_proc(password));
+    }
 
-    Some(transferred_bytes)
+    // Is compressed?
+    if flags.contains(Flags::COMPRESSED) {
+        data_procs.push(gz_decode_proc());
+    }
+
+    Some(data_procs)
 }
 
-/// Read the signature of a file.
+/// Read the signature of a file with `signature_algo`.
 pub fn task_read_signature(
     fs_mnt: &FSMount,
     abs_file_path: &NPath<Abs, File>,
+    signature_algo: SignatureAlgo,
     create_task_error_msg: &dyn Fn(Arc<dyn Error + Send + Sync>) -> Arc<TaskMessage>,
     sender: &Sender<Arc<dyn Message>>,
 ) -> Option<[u8; 32]> {
@@ -209,18 +414,17 @@ pub fn task_read_signature(
         return None;
     }
 
-    // Init signature.
-    let signature = Arc::new(Mutex::new([0u8; 32]));
-
-    // Init data_procs with signature proc.
-    let data_procs = vec![signature_proc(signature.clone())];
+    // Init signature processor.
+    let mut signature_processor = SignatureProcessor::new(signature_algo);
 
     // Transfer to destination.
-    task_transfer_file(
+    let transferred = task_transfer_file(
         &fs_conn,
         abs_file_path,
+        None,
         &mut NPath::default(),
-        &data_procs,
+        &Vec::new(),
+        Some(&mut signature_processor),
         None,
         &create_task_error_msg,
         sender,
@@ -232,5 +436,6 @@ pub fn task_read_signature(
         return None;
     }
 
-    Some(*signature.lock().unwrap())
+    // The signature is valid only for a complete read.
+    transferred.map(|_| signature_processor.finalize())
 }
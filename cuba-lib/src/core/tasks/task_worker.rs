@@ -1,6 +1,8 @@
 use crossbeam_channel::Sender;
+use std::cell::RefCell;
 use std::error::Error;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::thread;
 use trait_set::trait_set;
 
@@ -9,9 +11,12 @@ use crate::shared::message::Info;
 use crate::shared::message::Message;
 use crate::shared::npath::Rel;
 use crate::shared::npath::UNPath;
-use crate::shared::task_message::TaskMessage;
+use crate::shared::progress_message::ProgressMessage;
+use crate::shared::task_message::{SourceBytes, TaskMessage};
 
-use super::super::fs::fs_base::FSConnection;
+use super::super::fs::fs_base::{FSConnection, rich_error};
+use super::super::journal::Journal;
+use super::progress_tracker::{GlobalProgressTracker, ProgressTicks};
 
 trait_set! {
     pub trait TaskErrorFn = Fn(&UNPath<Rel>, Arc<dyn Error + Send + Sync>) -> Arc<TaskMessage>;
@@ -34,45 +39,154 @@ trait_set! {
 pub struct TaskWorker {
     fs_conn: FSConnection,
     sender: Sender<Arc<dyn Message>>,
+    progress_tracker: Option<Arc<GlobalProgressTracker>>,
+    progress_ticks: Option<Arc<ProgressTicks>>,
+    journal: Option<Arc<Journal>>,
+    total_bytes: Option<u64>,
+    read_bytes: Arc<AtomicU64>,
 }
 
 /// Methods of `TaskWorker`.
 impl TaskWorker {
     /// Creates a new `TaskWorker`.
     pub fn new(fs_conn: FSConnection, sender: Sender<Arc<dyn Message>>) -> Self {
-        Self { fs_conn, sender }
+        Self {
+            fs_conn,
+            sender,
+            progress_tracker: None,
+            progress_ticks: None,
+            journal: None,
+            total_bytes: None,
+            read_bytes: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Sets the progress tracker, which is advanced for each processed work item.
+    pub fn with_progress_tracker(mut self, progress_tracker: Arc<GlobalProgressTracker>) -> Self {
+        self.progress_tracker = Some(progress_tracker);
+        self
+    }
+
+    /// Sets the progress ticks, which are sent for each processed work item.
+    pub fn with_progress_ticks(mut self, progress_ticks: Arc<ProgressTicks>) -> Self {
+        self.progress_ticks = Some(progress_ticks);
+        self
+    }
+
+    /// Sets the journal, which records the task completion events.
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Sets the total bytes to read from the source files, the read bytes are sent as byte progress.
+    pub fn with_total_bytes(mut self, total_bytes: u64) -> Self {
+        self.total_bytes = Some(total_bytes);
+        self
     }
 
     /// Run function.
-    pub fn run(&self, run_state: Arc<RunState>, threads: usize, task: Arc<dyn Task>) {
+    ///
+    /// Each execution of the task runs in a `tracing` span named by `operation`.
+    /// Returns the number of processed work items.
+    pub fn run(
+        &self,
+        run_state: Arc<RunState>,
+        threads: usize,
+        operation: &'static str,
+        task: Arc<dyn Task>,
+    ) -> usize {
         let mut handles: Vec<thread::JoinHandle<()>> = vec![];
+        let processed = Arc::new(AtomicUsize::new(0));
 
         for thread_number in 0..threads {
             let fs = self.fs_conn.clone();
             let sender: Sender<Arc<dyn Message>> = self.sender.clone();
             let task: Arc<dyn Task> = Arc::clone(&task);
             let run_state = run_state.clone();
+            let processed = processed.clone();
+            let progress_tracker = self.progress_tracker.clone();
+            let progress_ticks = self.progress_ticks.clone();
+            let journal = self.journal.clone();
+            let total_bytes = self.total_bytes;
+            let read_bytes = self.read_bytes.clone();
 
             let handle: thread::JoinHandle<()> = thread::spawn(move || {
                 let mut processing: bool = true;
 
+                // The span of the current task execution.
+                let span = RefCell::new(tracing::Span::none());
+
                 let create_task_error_message =
-                    move |rel_path: &UNPath<Rel>, error: Arc<dyn Error + Send + Sync>| {
-                        Arc::new(TaskMessage::new(thread_number, rel_path, Some(error), None))
+                    |rel_path: &UNPath<Rel>, error: Arc<dyn Error + Send + Sync>| {
+                        span.borrow().record("path", rel_path.to_unicode());
+
+                        if let Some(journal) = &journal {
+                            journal.record_error(rel_path, error.as_ref());
+                        }
+
+                        Arc::new(TaskMessage::with_rich_error(
+                            thread_number,
+                            rel_path,
+                            Some(rich_error(error)),
+                            None,
+                        ))
                     };
 
                 let create_task_info_message =
-                    move |rel_path: &UNPath<Rel>, info: Arc<dyn Info + Send + Sync>| {
+                    |rel_path: &UNPath<Rel>, info: Arc<dyn Info + Send + Sync>| {
+                        span.borrow().record("path", rel_path.to_unicode());
+
+                        if let Some(journal) = &journal {
+                            journal.record_info(rel_path, info.as_ref());
+                        }
+
+                        // Send the byte progress, a file changed since the walk may exceed the total.
+                        if let Some(total_bytes) = total_bytes
+                            && let Some(bytes) = info.as_any().downcast_ref::<SourceBytes>()
+                        {
+                            let read =
+                                read_bytes.fetch_add(bytes.bytes, Ordering::Relaxed) + bytes.bytes;
+
+                            sender
+                                .send(Arc::new(ProgressMessage::bytes(
+                                    read.min(total_bytes),
+                                    total_bytes,
+                                )))
+                                .unwrap();
+                        }
+
                         Arc::new(TaskMessage::new(thread_number, rel_path, None, Some(info)))
                     };
 
                 while processing && !run_state.is_canceled() {
+                    *span.borrow_mut() = tracing::info_span!(
+                        "task",
+                        operation,
+                        thread = thread_number,
+                        path = tracing::field::Empty
+                    );
+                    let _entered = span.borrow().clone().entered();
+
                     processing = task(
                         &create_task_error_message,
                         &create_task_info_message,
                         fs.clone(),
                         sender.clone(),
                     );
+
+                    // A task continues after each work item.
+                    if processing {
+                        processed.fetch_add(1, Ordering::Relaxed);
+
+                        if let Some(progress_tracker) = &progress_tracker {
+                            progress_tracker.advance(1);
+                        }
+
+                        if let Some(progress_ticks) = &progress_ticks {
+                            progress_ticks.tick(1);
+                        }
+                    }
                 }
             });
 
@@ -82,5 +196,7 @@ impl TaskWorker {
         for handle in handles {
             handle.join().unwrap();
         }
+
+        processed.load(Ordering::Relaxed)
     }
 }
@@ -1,17 +1,22 @@
 #![allow(unused)]
 
 use bitflags::bitflags;
-use serde::{Deserialize, Serialize};
-use serde_with::DisplayFromStr;
-use serde_with::serde_as;
-use std::collections::HashMap;
-use std::io::{self, Read, Write};
+use flate2::{Compression, bufread::GzDecoder, write::GzEncoder};
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, BufReader, Read, Write};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 use std::time::SystemTime;
+use thiserror::Error;
 
 use crate::core::fs::fs_metadata::FSMetaData;
 use crate::core::fs::fs_symlink_meta::FSSymlinkMeta;
+use crate::shared::config::SignatureAlgo;
 use crate::shared::npath::Symlink;
 use crate::shared::npath::{Dir, File, NPath, Rel, UNPath};
 
@@ -19,11 +24,12 @@ use crate::shared::npath::{Dir, File, NPath, Rel, UNPath};
 bitflags! {
     #[derive(PartialEq, Hash, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
     pub struct Flags: u8 {
-        const COMPRESSED    = 0b00000001;
-        const ENCRYPTED     = 0b00000010;
-        const VERIFIED      = 0b00000100;
-        const VERIFY_ERROR  = 0b00001000;
-        const ORPHAN        = 0b00010000;
+        const COMPRESSED       = 0b00000001;
+        const ENCRYPTED        = 0b00000010;
+        const VERIFIED         = 0b00000100;
+        const VERIFY_ERROR     = 0b00001000;
+        const ORPHAN           = 0b00010000;
+        const DELTA_COMPRESSED = 0b00100000;
     }
 }
 
@@ -128,14 +134,38 @@ impl Default for MaskedFlags {
 }
 
 /// Compares two optional signatures.
-pub fn sig_valid_and_match(sig_a: Option<[u8; 32]>, sig_b: Option<[u8; 32]>) -> bool {
+///
+/// Signatures of different algorithms never match.
+pub fn sig_valid_and_match(
+    sig_a: Option<[u8; 32]>,
+    algo_a: SignatureAlgo,
+    sig_b: Option<[u8; 32]>,
+    algo_b: SignatureAlgo,
+) -> bool {
     match (sig_a, sig_b) {
         (None, None) => false,
-        (Some(sig_a), Some(sig_b)) => sig_a == sig_b,
+        (Some(sig_a), Some(sig_b)) => algo_a == algo_b && sig_a == sig_b,
         _ => false,
     }
 }
 
+/// Defines a `DeltaBase`.
+///
+/// The dest base file of a delta, with the flags and password id to read it.
+#[derive(Clone, Debug)]
+pub struct DeltaBase {
+    pub rel_path: NPath<Rel, File>,
+    pub flags: Flags,
+    pub password_id: Option<String>,
+}
+
+/// Defines a `MergeError`.
+#[derive(Debug, Error)]
+pub enum MergeError {
+    #[error("The encrypted names of the cuba jsons use different salts")]
+    NameSaltMismatch,
+}
+
 /// Defines a `TransferredNode`.
 ///
 /// Structure that holds information about a transferred node.
@@ -153,24 +183,70 @@ pub struct TransferredNode {
     // The signature of the src node.
     pub src_signature: Option<[u8; 32]>,
 
+    // The algorithm of the src signature.
+    #[serde(default)]
+    pub signature_algo: SignatureAlgo,
+
     // Source created.
     pub src_created: Option<SystemTime>,
 
     // Source modified.
     pub src_modified: Option<SystemTime>,
 
+    // Source size.
+    #[serde(default)]
+    pub src_size: Option<u64>,
+
     // Source symlink meta.
     pub src_symlink_meta: Option<FSSymlinkMeta>,
+
+    // Source permission bits (Unix only).
+    #[serde(default)]
+    pub src_mode: Option<u32>,
+
+    // The rel path of the dest base file, if the dest node is a delta.
+    #[serde(default)]
+    pub delta_base_rel_path: Option<NPath<Rel, File>>,
+
+    // The flags of the dest base file, if the dest node is a delta.
+    #[serde(default)]
+    pub delta_base_flags: Option<Flags>,
+
+    // The password id of the dest base file, if the dest node is a delta.
+    #[serde(default)]
+    pub delta_base_password_id: Option<String>,
+
+    // The rel path of the blob in the dedup store, if the dest node is deduplicated.
+    #[serde(default)]
+    pub dedup_blob_rel_path: Option<NPath<Rel, File>>,
+
+    // The time of the backup, which transferred the node.
+    #[serde(default)]
+    pub backup_timestamp: Option<SystemTime>,
+
+    // Dest size, the bytes written after compression and encryption.
+    #[serde(default)]
+    pub dest_size: Option<u64>,
+
+    // The encrypted src path, if the names of the backup are encrypted.
+    #[serde(default)]
+    pub encrypted_src_path: Option<String>,
+
+    // The time of the last successful verify.
+    #[serde(default)]
+    pub last_verified: Option<SystemTime>,
 }
 
 /// Methods of `TransferredNode`.
 impl TransferredNode {
     /// Creates a new `TransferredNode` instance from a file.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn from_file(
         path: &NPath<Rel, File>,
         flags: Flags,
         password_id: Option<String>,
         src_signature: &[u8; 32],
+        signature_algo: SignatureAlgo,
         metadata: &FSMetaData,
     ) -> Self {
         Self {
@@ -178,47 +254,263 @@ impl TransferredNode {
             flags,
             password_id,
             src_signature: Some(*src_signature),
+            signature_algo,
             src_created: metadata.created,
             src_modified: metadata.modified,
+            src_size: metadata.size,
+            src_mode: metadata.mode,
             src_symlink_meta: None,
+            delta_base_rel_path: None,
+            delta_base_flags: None,
+            delta_base_password_id: None,
+            dedup_blob_rel_path: None,
+            backup_timestamp: Some(SystemTime::now()),
+            dest_size: None,
+            encrypted_src_path: None,
+            last_verified: None,
         }
     }
 
     /// Creates a new `TransferredNode` instance from a dir.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn from_dir(path: &NPath<Rel, Dir>, flags: Flags, metadata: &FSMetaData) -> Self {
         Self {
             dest_rel_path: path.into(),
             flags,
             password_id: None,
             src_signature: None,
+            signature_algo: SignatureAlgo::default(),
             src_created: metadata.created,
             src_modified: metadata.modified,
+            src_size: metadata.size,
+            src_mode: metadata.mode,
             src_symlink_meta: None,
+            delta_base_rel_path: None,
+            delta_base_flags: None,
+            delta_base_password_id: None,
+            dedup_blob_rel_path: None,
+            backup_timestamp: Some(SystemTime::now()),
+            dest_size: None,
+            encrypted_src_path: None,
+            last_verified: None,
         }
     }
 
     /// Creates a new `TransferredNode` instance from a symlink.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn from_symlink(path: &NPath<Rel, Symlink>, flags: Flags, metadata: &FSMetaData) -> Self {
         Self {
             dest_rel_path: path.into(),
             flags,
             password_id: None,
             src_signature: None,
+            signature_algo: SignatureAlgo::default(),
             src_created: metadata.created,
             src_modified: metadata.modified,
+            src_size: metadata.size,
+            src_mode: metadata.mode,
             src_symlink_meta: metadata.symlink_meta.clone(),
+            delta_base_rel_path: None,
+            delta_base_flags: None,
+            delta_base_password_id: None,
+            dedup_blob_rel_path: None,
+            backup_timestamp: Some(SystemTime::now()),
+            dest_size: None,
+            encrypted_src_path: None,
+            last_verified: None,
         }
     }
+
+    /// Sets the dest base file, if the dest node is a delta.
+    pub fn with_delta_base(mut self, delta_base: Option<DeltaBase>) -> Self {
+        match delta_base {
+            Some(delta_base) => {
+                self.delta_base_rel_path = Some(delta_base.rel_path);
+                self.delta_base_flags = Some(delta_base.flags);
+                self.delta_base_password_id = delta_base.password_id;
+            }
+            None => {
+                self.delta_base_rel_path = None;
+                self.delta_base_flags = None;
+                self.delta_base_password_id = None;
+            }
+        }
+        self
+    }
+
+    /// Returns the dest base file, if the dest node is a delta.
+    ///
+    /// Nodes written by an older version have no flags of the base, they
+    /// take the flags and password id of the node.
+    pub fn delta_base(&self) -> Option<DeltaBase> {
+        self.delta_base_rel_path
+            .as_ref()
+            .map(|rel_path| match self.delta_base_flags {
+                Some(flags) => DeltaBase {
+                    rel_path: rel_path.clone(),
+                    flags,
+                    password_id: self.delta_base_password_id.clone(),
+                },
+                None => DeltaBase {
+                    rel_path: rel_path.clone(),
+                    flags: self.flags - Flags::DELTA_COMPRESSED,
+                    password_id: self.password_id.clone(),
+                },
+            })
+    }
+
+    /// Sets the blob in the dedup store, if the dest node is deduplicated.
+    pub fn with_dedup_blob(mut self, dedup_blob_rel_path: Option<NPath<Rel, File>>) -> Self {
+        self.dedup_blob_rel_path = dedup_blob_rel_path;
+        self
+    }
+
+    /// Sets the dest size, the bytes written to the dest file.
+    pub fn with_dest_size(mut self, dest_size: Option<u64>) -> Self {
+        self.dest_size = dest_size;
+        self
+    }
+
+    /// Returns true if the size of the dest file matches the dest size.
+    ///
+    /// Returns true if the node or the dest file has no size, e.g. nodes
+    /// written by an older version.
+    pub fn dest_size_matches(&self, metadata: &FSMetaData) -> bool {
+        match (self.dest_size, metadata.size) {
+            (Some(node_size), Some(size)) => node_size == size,
+            _ => true,
+        }
+    }
+
+    /// Returns true if size and modified time of the src are unchanged.
+    ///
+    /// Returns false if the node has no size or modified time, e.g. nodes
+    /// written by an older version.
+    pub fn src_unchanged(&self, metadata: &FSMetaData) -> bool {
+        match (
+            self.src_size,
+            metadata.size,
+            self.src_modified,
+            metadata.modified,
+        ) {
+            (Some(node_size), Some(size), Some(node_modified), Some(modified)) => {
+                node_size == size && node_modified == modified
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns the sorted src paths of the transferred nodes.
+pub fn list_src_paths(transferred_nodes: &TransferredNodes) -> Vec<UNPath<Rel>> {
+    let mut src_rel_paths: Vec<UNPath<Rel>> = transferred_nodes
+        .view::<Backup>()
+        .iter_src_nodes()
+        .cloned()
+        .collect();
+
+    src_rel_paths.sort();
+    src_rel_paths
+}
+
+/// Defines a `TransferNodesStats`.
+///
+/// The aggregate metrics of the transferred nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferNodesStats {
+    /// The number of files.
+    pub file_count: usize,
+
+    /// The number of directories.
+    pub dir_count: usize,
+
+    /// The sum of the src sizes of the files.
+    pub total_src_bytes: u64,
+
+    /// The number of compressed nodes.
+    pub compressed_count: usize,
+
+    /// The number of encrypted nodes.
+    pub encrypted_count: usize,
+
+    /// The number of orphan nodes.
+    pub orphan_count: usize,
+
+    /// The number of verified nodes.
+    pub verified_count: usize,
+
+    /// The number of nodes with a verify error.
+    pub error_count: usize,
 }
 
+/// The key of the salt of the encrypted names in the cuba json.
+///
+/// The keys of the nodes are prefixed by the node type, so they never collide with it.
+const NAME_SALT_KEY: &str = "name_salt";
+
 /// Defines the `TransferredNodes`.
 ///
-/// A map that holds all transferred nodes.
-#[serde_as]
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct TransferredNodes(
-    #[serde_as(as = "HashMap<DisplayFromStr, _>")] pub HashMap<UNPath<Rel>, TransferredNode>,
-);
+/// A map that holds all transferred nodes, with the salt of the encrypted names - if any.
+#[derive(Clone, Debug)]
+pub struct TransferredNodes {
+    nodes: HashMap<UNPath<Rel>, TransferredNode>,
+    name_salt: Option<String>,
+}
+
+/// Impl of `Serialize` for `TransferredNodes`.
+///
+/// The nodes are a map of the src paths, the salt is an additional entry.
+impl Serialize for TransferredNodes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(
+            self.nodes.len() + usize::from(self.name_salt.is_some()),
+        ))?;
+
+        if let Some(name_salt) = &self.name_salt {
+            map.serialize_entry(NAME_SALT_KEY, name_salt)?;
+        }
+
+        for (src_rel_path, node) in &self.nodes {
+            map.serialize_entry(&src_rel_path.to_string(), node)?;
+        }
+
+        map.end()
+    }
+}
+
+/// Impl of `Deserialize` for `TransferredNodes`.
+impl<'de> Deserialize<'de> for TransferredNodes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(TransferredNodesVisitor)
+    }
+}
+
+/// Defines a `TransferredNodesVisitor`.
+struct TransferredNodesVisitor;
+
+/// Impl of `Visitor` for `TransferredNodesVisitor`.
+impl<'de> Visitor<'de> for TransferredNodesVisitor {
+    type Value = TransferredNodes;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of transferred nodes")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut transferred_nodes = TransferredNodes::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            if key == NAME_SALT_KEY {
+                transferred_nodes.name_salt = Some(map.next_value()?);
+            } else {
+                let src_rel_path = UNPath::<Rel>::from_str(&key).map_err(de::Error::custom)?;
+                transferred_nodes.insert(src_rel_path, map.next_value()?);
+            }
+        }
+
+        Ok(transferred_nodes)
+    }
+}
 
 /// Impl of `Default` for `TransferredNodes`.
 impl Default for TransferredNodes {
@@ -231,7 +523,20 @@ impl Default for TransferredNodes {
 impl TransferredNodes {
     /// Create new `TransferredNodes`
     pub fn new() -> Self {
-        TransferredNodes(HashMap::new())
+        TransferredNodes {
+            nodes: HashMap::new(),
+            name_salt: None,
+        }
+    }
+
+    /// Returns the hex encoded salt of the encrypted names.
+    pub fn name_salt(&self) -> Option<&str> {
+        self.name_salt.as_deref()
+    }
+
+    /// Sets the hex encoded salt of the encrypted names.
+    pub fn set_name_salt(&mut self, name_salt: Option<String>) {
+        self.name_salt = name_salt;
     }
 
     /// Insert `flags` for all transferred nodes.
@@ -264,6 +569,79 @@ impl TransferredNodes {
         }
     }
 
+    /// Parses the transferred nodes from a gz compressed cuba json.
+    pub fn from_cuba_json(reader: impl Read) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(GzDecoder::new(BufReader::new(reader)))
+    }
+
+    /// Writes the transferred nodes as gz compressed cuba json.
+    pub fn to_cuba_json(&self, writer: impl Write) -> Result<(), io::Error> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+
+        // Finish encoder and flush writer.
+        encoder.finish()?.flush()
+    }
+
+    /// Merges the nodes of `other`, e.g. of a backup of another subdirectory.
+    ///
+    /// Nodes of `other`, which are not in `self`, are inserted. For nodes in
+    /// both, the node of the newer backup wins. Fails, if the encrypted names
+    /// of both use different salts.
+    pub fn merge(&mut self, other: &TransferredNodes) -> Result<(), MergeError> {
+        match (&self.name_salt, &other.name_salt) {
+            (Some(name_salt), Some(other_name_salt)) if name_salt != other_name_salt => {
+                return Err(MergeError::NameSaltMismatch);
+            }
+            (None, Some(_)) => self.name_salt = other.name_salt.clone(),
+            _ => {}
+        }
+
+        for (src_rel_path, other_node) in other.iter() {
+            match self.get_mut(src_rel_path) {
+                Some(node) => {
+                    if other_node.backup_timestamp > node.backup_timestamp {
+                        *node = other_node.clone();
+                    }
+                }
+                None => {
+                    self.insert(src_rel_path.clone(), other_node.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the aggregate metrics of the nodes.
+    pub fn statistics(&self) -> TransferNodesStats {
+        let mut stats = TransferNodesStats::default();
+
+        for (src_rel_path, node) in self.iter() {
+            if src_rel_path.is_file() {
+                stats.file_count += 1;
+                stats.total_src_bytes += node.src_size.unwrap_or(0);
+            } else if src_rel_path.is_dir() {
+                stats.dir_count += 1;
+            }
+
+            // Count the flags.
+            for (flag, count) in [
+                (Flags::COMPRESSED, &mut stats.compressed_count),
+                (Flags::ENCRYPTED, &mut stats.encrypted_count),
+                (Flags::ORPHAN, &mut stats.orphan_count),
+                (Flags::VERIFIED, &mut stats.verified_count),
+                (Flags::VERIFY_ERROR, &mut stats.error_count),
+            ] {
+                if node.flags.contains(flag) {
+                    *count += 1;
+                }
+            }
+        }
+
+        stats
+    }
+
     /// Returns the count of nodes.
     pub fn node_count(&self) -> usize {
         self.len()
@@ -288,14 +666,14 @@ impl Deref for TransferredNodes {
     type Target = HashMap<UNPath<Rel>, TransferredNode>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.nodes
     }
 }
 
 /// Impl of `DerefMut` for `TransferredNodes`.
 impl DerefMut for TransferredNodes {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.nodes
     }
 }
 
@@ -366,6 +744,43 @@ impl<'a> View<'a, Restore> {
         self.nodes.values().map(|node| &node.dest_rel_path)
     }
 
+    /// Returns the total dest size of the given rel src files, which a restore reads.
+    ///
+    /// The src size is taken, if the dest size is unknown.
+    pub fn total_dest_size<'b>(
+        &self,
+        src_rel_file_paths: impl IntoIterator<Item = &'b NPath<Rel, File>>,
+    ) -> u64 {
+        let src_rel_file_paths: HashSet<&NPath<Rel, File>> =
+            src_rel_file_paths.into_iter().collect();
+
+        self.nodes
+            .values()
+            .filter(|node| match &node.dest_rel_path {
+                UNPath::File(rel_file_path) => src_rel_file_paths.contains(rel_file_path),
+                _ => false,
+            })
+            .filter_map(|node| node.dest_size.or(node.src_size))
+            .sum()
+    }
+
+    /// Returns the rel src files, which the transferred nodes matching `filter` refer to
+    /// as delta base or dedup blob.
+    pub fn referred_rel_file_paths(
+        &self,
+        filter: impl Fn(&TransferredNode) -> bool,
+    ) -> HashSet<&'a NPath<Rel, File>> {
+        self.nodes
+            .values()
+            .filter(|node| filter(node))
+            .flat_map(|node| {
+                node.delta_base_rel_path
+                    .iter()
+                    .chain(node.dedup_blob_rel_path.iter())
+            })
+            .collect()
+    }
+
     /// Returns the dest rel path.
     pub fn get_dest_rel_path(&self, node: &TransferredNode) -> Option<UNPath<Rel>> {
         self.nodes
@@ -397,4 +812,15 @@ impl<'a> ViewMut<'a, Restore> {
             transferred_node.flags = flags;
         }
     }
+
+    /// Set the time of the last successful verify.
+    pub fn set_last_verified(&mut self, src_rel_path: &UNPath<Rel>, last_verified: SystemTime) {
+        if let Some(transferred_node) = self
+            .nodes
+            .values_mut()
+            .find(|node| node.dest_rel_path == *src_rel_path)
+        {
+            transferred_node.last_verified = Some(last_verified);
+        }
+    }
 }
@@ -11,7 +11,7 @@ pub fn move_rel_npaths<T>(
     let mut index = 0;
 
     while index < all_paths.len() {
-        if all_paths[index].components().count() == depth {
+        if all_paths[index].components_count() == depth {
             depth_paths.push_back(all_paths.remove(index).unwrap());
         } else {
             index += 1;
@@ -3,14 +3,14 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
 
-use crate::core::run_state::RunState;
+use crate::core::run_state::{RunError, RunState};
 use crate::send_error;
-use crate::shared::message::Message;
+use crate::send_warn;
+use crate::shared::message::{Message, StringError};
 use crate::shared::npath::Rel;
 use crate::shared::npath::UNPath;
-use crate::shared::progress_message::ProgressInfo;
-use crate::shared::progress_message::ProgressMessage;
 
 use super::cuba_json::read_cuba_json;
 use super::cuba_json::write_cuba_json;
@@ -18,20 +18,66 @@ use super::fs::fs_base::FSConnection;
 use super::fs::fs_base::FSMount;
 use super::password_cache::PasswordCache;
 use super::tasks::node_verify_task::node_verify_task;
+use super::tasks::progress_tracker::ProgressTicks;
 use super::tasks::task_worker::TaskWorker;
 use super::transferred_node::Flags;
 use super::transferred_node::MaskedFlags;
 use super::transferred_node::MatchMode;
 use super::transferred_node::Restore;
+use super::transferred_node::TransferredNode;
+
+/// Defines a `VerifyMode`.
+///
+/// Selects the nodes, which are verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyMode {
+    /// All nodes.
+    All,
+
+    /// The nodes, which are not verified yet or failed the last verify.
+    New,
+
+    /// The nodes, which are not successfully verified within the duration.
+    Stale(Duration),
+}
+
+/// Methods of `VerifyMode`.
+impl VerifyMode {
+    /// Returns true, if `node` is verified in this mode.
+    pub(crate) fn selects(&self, node: &TransferredNode) -> bool {
+        match self {
+            VerifyMode::All => true,
+            VerifyMode::New => MaskedFlags::new()
+                .with_mode(MatchMode::Uq)
+                .with_flags(Flags::VERIFIED)
+                .with_mask(Flags::VERIFIED | Flags::VERIFY_ERROR)
+                .matches(node.flags),
+            VerifyMode::Stale(max_age) => {
+                node.flags.contains(Flags::VERIFY_ERROR)
+                    || node.last_verified.is_none_or(|last_verified| {
+                        SystemTime::now()
+                            .duration_since(last_verified)
+                            .is_ok_and(|age| age > *max_age)
+                    })
+            }
+        }
+    }
+}
 
 /// Runs the verify process.
+///
+/// The `backup_src_mnt` is the source of the backup. It is used to compute the signatures of
+/// files without a stored signature, or of all files if `full` is set. The `verify_mode`
+/// selects the nodes, which are verified.
 pub fn run_verify(
     run_state: Arc<RunState>,
     threads: usize,
     fs_mnt: FSMount,
-    verify_all: bool,
+    backup_src_mnt: Option<FSMount>,
+    verify_mode: VerifyMode,
+    full: bool,
     sender: Sender<Arc<dyn Message>>,
-) {
+) -> Result<(), RunError> {
     // Set running to true.
     run_state.start();
 
@@ -42,15 +88,57 @@ pub fn run_verify(
     };
 
     // Open connection.
-    if let Err(err) = fs_conn.open() {
-        send_error!(sender, err);
-        return;
+    if let Err(err) = fs_conn.open(&run_state) {
+        run_state.stop();
+        return Err(RunError::fatal(err, &sender));
     }
 
-    // Read cuba json.
-    let transferred_nodes = match read_cuba_json(&fs_conn.src_mnt, &sender) {
-        Some(nodes) => nodes,
-        None => return,
+    // Create password cache.
+    let mut password_cache = PasswordCache::new();
+
+    // Read cuba json, the cipher keeps an encrypted cuba json encrypted.
+    let (transferred_nodes, cuba_json_cipher) =
+        match read_cuba_json(&fs_conn.src_mnt, &mut password_cache, &sender) {
+            Some(cuba_json) => cuba_json,
+            None => {
+                if let Err(err) = fs_conn.close() {
+                    send_error!(sender, err);
+                }
+
+                run_state.stop();
+                return Err(RunError::fatal(
+                    StringError::new("The cuba json can't be read".to_string()),
+                    &sender,
+                ));
+            }
+        };
+
+    // Connect the source of the backup.
+    let backup_src_mnt = match backup_src_mnt {
+        Some(backup_src_mnt) => {
+            let connected = backup_src_mnt.fs.write().unwrap().connect();
+
+            match connected {
+                Ok(()) => Some(backup_src_mnt),
+                Err(err) if full => {
+                    if let Err(err) = fs_conn.close() {
+                        send_error!(sender, err);
+                    }
+
+                    run_state.stop();
+                    return Err(RunError::fatal(err, &sender));
+                }
+                Err(err) => {
+                    send_warn!(
+                        sender,
+                        "Source not available, files without signature are only checked for existence: {}",
+                        err
+                    );
+                    None
+                }
+            }
+        }
+        None => None,
     };
 
     // Collect source directories and files.
@@ -60,65 +148,71 @@ pub fn run_verify(
         src_rel_nodes.push_back(src_rel_path.clone());
     }
 
-    // Create password cache.
-    let password_cache = PasswordCache::new();
+    // Sort, so that the verify order is deterministic.
+    src_rel_nodes.make_contiguous().sort();
 
     // Create arcs for tasks.
     let arc_mutex_src_rel_nodes = Arc::new(Mutex::new(src_rel_nodes));
     let arc_rwlock_transferred_nodes = Arc::new(RwLock::new(transferred_nodes));
     let arc_mutex_password_cache = Arc::new(Mutex::new(password_cache));
 
-    // Init task worker.
-    let task_worker = TaskWorker::new(fs_conn.clone(), sender.clone());
-
     // Progress duration.
     let items = arc_mutex_src_rel_nodes.lock().unwrap().len();
-    sender
-        .send(Arc::new(ProgressMessage::new(
-            Arc::new(ProgressInfo::Duration),
-            items as u64,
-        )))
-        .unwrap();
-
-    // Init verify flags.
-    let mut verify_flags: MaskedFlags = MaskedFlags::new();
-
-    if !verify_all {
-        verify_flags = verify_flags
-            .with_mode(MatchMode::Uq)
-            .with_flags(Flags::VERIFIED)
-            .with_mask(Flags::VERIFIED | Flags::VERIFY_ERROR);
-    }
+    let progress_ticks = Arc::new(ProgressTicks::new(items, sender.clone()));
+
+    // Init task worker.
+    let task_worker = TaskWorker::new(fs_conn.clone(), sender.clone())
+        .with_progress_ticks(progress_ticks.clone());
 
     // Run file verfiy.
     task_worker.run(
         run_state.clone(),
         threads,
+        "node_verify",
         Arc::new(node_verify_task(
             arc_mutex_src_rel_nodes,
             arc_rwlock_transferred_nodes.clone(),
-            verify_flags,
+            verify_mode,
             arc_mutex_password_cache.clone(),
+            backup_src_mnt.clone(),
+            full,
         )),
     );
 
     // Drop task worker.
     drop(task_worker);
 
+    // Complete the progress.
     if !run_state.is_canceled() {
-        // Write cuba json.
-        write_cuba_json(
-            &fs_conn.src_mnt,
-            &arc_rwlock_transferred_nodes.read().unwrap(),
-            &sender,
-        );
+        progress_ticks.complete();
     }
 
+    // Write cuba json, also of a canceled verify to keep the verified nodes.
+    write_cuba_json(
+        &fs_conn.src_mnt,
+        &arc_rwlock_transferred_nodes.read().unwrap(),
+        cuba_json_cipher.as_ref(),
+        &sender,
+    );
+
     // Close connection.
     if let Err(err) = fs_conn.close() {
         send_error!(sender, err);
     }
 
+    // Disconnect the source of the backup.
+    if let Some(backup_src_mnt) = backup_src_mnt
+        && let Err(err) = backup_src_mnt.fs.write().unwrap().disconnect()
+    {
+        send_error!(sender, err);
+    }
+
     // Set running to false.
     run_state.stop();
+
+    if run_state.is_canceled() {
+        return Err(RunError::Canceled);
+    }
+
+    Ok(())
 }
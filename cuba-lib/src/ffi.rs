@@ -0,0 +1,245 @@
+use crossbeam_channel::{Receiver, unbounded};
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::{CStr, CString, c_char},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::core::cuba::{Cuba, RunError, RunHandle};
+use crate::shared::{config::load_config_from_file, message::Message, msg_receiver::trace_error};
+
+thread_local! {
+    // The last error of the calling thread.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Sets the last error of the calling thread.
+fn set_last_error(error: &str) {
+    // A C string can not contain nul bytes.
+    let error = CString::new(error.replace('\0', " ")).unwrap_or_default();
+
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(error));
+}
+
+/// Clears the last error of the calling thread.
+fn clear_last_error() {
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = None);
+}
+
+/// Returns the message of a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Unknown panic")
+}
+
+/// Runs `body` and catches a panic, which must not unwind into C.
+///
+/// On a panic, the panic message is set as last error and `on_panic` is returned.
+fn catch_panic<T>(on_panic: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        set_last_error(&format!(
+            "Cuba panicked: {}",
+            panic_message(payload.as_ref())
+        ));
+        on_panic
+    })
+}
+
+/// Returns the first error in the received messages.
+fn first_error(receiver: &Receiver<Arc<dyn Message>>) -> Option<String> {
+    receiver
+        .try_iter()
+        .find_map(|msg| msg.err().map(|err| trace_error(err)))
+}
+
+/// Converts a C string argument.
+///
+/// # Safety
+///
+/// `arg` must be null or a valid nul terminated string.
+unsafe fn str_arg(arg: *const c_char, name: &str) -> Option<String> {
+    if arg.is_null() {
+        set_last_error(&format!("The argument {} is null", name));
+        return None;
+    }
+
+    match unsafe { CStr::from_ptr(arg) }.to_str() {
+        Ok(arg) => Some(arg.to_string()),
+        Err(_) => {
+            set_last_error(&format!("The argument {} is not valid UTF-8", name));
+            None
+        }
+    }
+}
+
+/// Defines a `CubaHandle`.
+///
+/// The cuba api with its loaded config, opaque to C.
+pub struct CubaHandle {
+    cuba: Cuba,
+    receiver: Receiver<Arc<dyn Message>>,
+}
+
+/// Defines a `CubaResult`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubaResult {
+    Ok = 0,
+    Error = 1,
+    InvalidArgument = 2,
+}
+
+/// Loads the config from the file at `path`.
+///
+/// Returns null on failure or panic, the reason is returned by `cuba_last_error`.
+/// The handle must be freed with `cuba_free`.
+///
+/// # Safety
+///
+/// `path` must be null or a valid nul terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cuba_load_config(path: *const c_char) -> *mut CubaHandle {
+    clear_last_error();
+
+    catch_panic(ptr::null_mut(), || {
+        let Some(path) = (unsafe { str_arg(path, "path") }) else {
+            return ptr::null_mut();
+        };
+
+        let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+
+        match load_config_from_file(sender.clone(), &path) {
+            Some(config) => {
+                let mut cuba = Cuba::new(sender);
+                cuba.set_config(config);
+
+                Box::into_raw(Box::new(CubaHandle { cuba, receiver }))
+            }
+            None => {
+                let error = first_error(&receiver);
+                set_last_error(error.as_deref().unwrap_or("Failed to load the config"));
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Runs the backup of the backup profile `profile`.
+///
+/// Blocks until the backup is finished. If the backup failed or any error
+/// occurred during the backup, the fatal or else the first error is returned
+/// by `cuba_last_error`. A panic is returned as error.
+///
+/// # Safety
+///
+/// `handle` must be null or a handle of `cuba_load_config`, which is not freed.
+/// `profile` must be null or a valid nul terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cuba_run_backup(
+    handle: *mut CubaHandle,
+    profile: *const c_char,
+) -> CubaResult {
+    clear_last_error();
+
+    catch_panic(CubaResult::Error, || {
+        let Some(handle) = (unsafe { handle.as_ref() }) else {
+            set_last_error("The argument handle is null");
+            return CubaResult::InvalidArgument;
+        };
+
+        let Some(profile) = (unsafe { str_arg(profile, "profile") }) else {
+            return CubaResult::InvalidArgument;
+        };
+
+        // Discard messages of a previous call.
+        handle.receiver.try_iter().for_each(drop);
+
+        let receiver = &handle.receiver;
+        let finished = AtomicBool::new(false);
+
+        // Consume the messages during the backup, so they do not pile up.
+        let error = thread::scope(|scope| {
+            let collector = scope.spawn(|| {
+                let mut error = None;
+
+                while !finished.load(Ordering::Acquire) {
+                    if let Ok(msg) = receiver.recv_timeout(Duration::from_millis(100))
+                        && error.is_none()
+                    {
+                        error = msg.err().map(|err| trace_error(err));
+                    }
+                }
+
+                error.or_else(|| first_error(receiver))
+            });
+
+            // The collector is stopped before a panic of the backup is passed on.
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                handle.cuba.run_backup(RunHandle::new(), &profile)
+            }));
+            finished.store(true, Ordering::Release);
+
+            let error = collector
+                .join()
+                .unwrap_or_else(|payload| panic::resume_unwind(payload));
+            let result = result.unwrap_or_else(|payload| panic::resume_unwind(payload));
+
+            // A fatal error takes precedence over the errors of single files.
+            match result {
+                Err(RunError::Fatal(err)) => Some(trace_error(err.as_ref())),
+                Err(err) => error.or_else(|| Some(err.to_string())),
+                Ok(_) => error,
+            }
+        });
+
+        match error {
+            Some(error) => {
+                set_last_error(&error);
+                CubaResult::Error
+            }
+            None => CubaResult::Ok,
+        }
+    })
+}
+
+/// Returns the last error of the calling thread or null.
+///
+/// The string is owned by cuba and valid until the next call of a cuba function
+/// on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn cuba_last_error() -> *const c_char {
+    panic::catch_unwind(|| {
+        LAST_ERROR.with(|last_error| {
+            last_error
+                .borrow()
+                .as_ref()
+                .map_or(ptr::null(), |error| error.as_ptr())
+        })
+    })
+    .unwrap_or(ptr::null())
+}
+
+/// Frees a handle of `cuba_load_config`.
+///
+/// # Safety
+///
+/// `handle` must be null or a handle of `cuba_load_config`, which is not freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cuba_free(handle: *mut CubaHandle) {
+    catch_panic((), || {
+        if !handle.is_null() {
+            drop(unsafe { Box::from_raw(handle) });
+        }
+    });
+}
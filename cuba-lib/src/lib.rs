@@ -1,2 +1,8 @@
 pub mod core;
 pub mod shared;
+
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ffi;
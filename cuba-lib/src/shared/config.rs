@@ -3,10 +3,13 @@ use std::{collections::HashMap, fmt, sync::Arc};
 use crossbeam_channel::Sender;
 use serde::{Deserialize, Serialize};
 use strum_macros::Display;
+use thiserror::Error;
 
-use crate::{send_error, shared::message::Message};
+use crate::core::glob_matcher::validate_pattern;
+use crate::{send_error, send_warn, shared::message::Message};
 
-use super::npath::{Abs, Dir, NPath, Rel};
+use super::config_env::interpolate_env;
+use super::npath::{Abs, Dir, File, NPath, Rel, max_path_len};
 
 /// Load config from file.
 pub fn load_config_from_file(sender: Sender<Arc<dyn Message>>, path: &str) -> Option<Config> {
@@ -19,10 +22,36 @@ pub fn load_config_from_file(sender: Sender<Arc<dyn Message>>, path: &str) -> Op
     }
 }
 
+/// The default compression level of gzip.
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+/// The maximum compression level of gzip.
+pub const MAX_COMPRESSION_LEVEL: u32 = 9;
+
 /// Load config from &str.
+///
+/// The environment variables `${VAR}` in the string values are substituted.
 pub fn load_config_from_str(sender: Sender<Arc<dyn Message>>, config: &str) -> Option<Config> {
-    match toml::from_str::<Config>(config) {
-        Ok(config) => Some(config),
+    let config = match interpolate_env(config) {
+        Ok(config) => config,
+        Err(err) => {
+            send_error!(sender, err);
+            return None;
+        }
+    };
+
+    match toml::from_str::<Config>(&config) {
+        Ok(mut config) => {
+            // A config with validation errors is still usable, but likely wrong.
+            for err in config.validate() {
+                send_warn!(sender, "{}", err);
+            }
+
+            // Out of range compression levels are clamped.
+            config.clamp_compression_levels();
+
+            Some(config)
+        }
         Err(err) => {
             send_error!(sender, err);
             None
@@ -30,6 +59,143 @@ pub fn load_config_from_str(sender: Sender<Arc<dyn Message>>, config: &str) -> O
     }
 }
 
+/// Defines a `ConfigError`.
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("No config entry {0} found")]
+    EntryNotFound(ConfigEntryKey),
+
+    #[error("A config entry {0} already exists")]
+    EntryExists(ConfigEntryKey),
+}
+
+/// Defines a `ConfigValidationError`.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ConfigValidationError {
+    #[error("The {1} of config entry {0} is empty")]
+    EmptyPath(ConfigEntryKey, &'static str),
+
+    #[error("The {1} of config entry {0} is too long ({2} > {3} bytes)")]
+    PathTooLong(ConfigEntryKey, &'static str, usize, usize),
+
+    #[error("The encrypt_filename of config entry {0} requires encrypt and a password_id")]
+    EncryptFilenameWithoutEncrypt(ConfigEntryKey),
+
+    #[error("The compression_level {1} of config entry {0} is out of range (0-{2}), {2} is used")]
+    CompressionLevelOutOfRange(ConfigEntryKey, u32, u32),
+
+    #[error("The {1} {2:?} of config entry {0} is not a defined filesystem")]
+    UnknownFilesystem(ConfigEntryKey, &'static str, String),
+
+    #[error("The encrypt of config entry {0} requires a password_id")]
+    EncryptWithoutPasswordId(ConfigEntryKey),
+
+    #[error("The password_id of config entry {0} is empty")]
+    EmptyPasswordId(ConfigEntryKey),
+
+    #[error("The {1} pattern {2:?} of config entry {0} is invalid: {3}")]
+    InvalidPattern(ConfigEntryKey, &'static str, String, String),
+
+    #[error("The transfer_threads must be at least 1")]
+    NoTransferThreads,
+}
+
+/// Checks, if `path` of the config entry `key` does not exceed the maximum path length.
+fn validate_path_len(
+    errors: &mut Vec<ConfigValidationError>,
+    key: ConfigEntryKey,
+    field: &'static str,
+    path: &str,
+) {
+    let max_len = max_path_len();
+
+    if path.len() > max_len {
+        errors.push(ConfigValidationError::PathTooLong(
+            key,
+            field,
+            path.len(),
+            max_len,
+        ));
+    }
+}
+
+/// Checks, if the filesystem `fs` of the config entry `key` is defined.
+fn validate_fs(
+    errors: &mut Vec<ConfigValidationError>,
+    filesystem: &FilesystemConfig,
+    key: ConfigEntryKey,
+    field: &'static str,
+    fs: &str,
+) {
+    if !filesystem.has_fs(fs) {
+        errors.push(ConfigValidationError::UnknownFilesystem(
+            key,
+            field,
+            fs.to_string(),
+        ));
+    }
+}
+
+/// Checks, if the glob `patterns` of the config entry `key` compile.
+fn validate_patterns(
+    errors: &mut Vec<ConfigValidationError>,
+    key: &ConfigEntryKey,
+    field: &'static str,
+    patterns: &Option<Vec<String>>,
+) {
+    for pattern in patterns.iter().flatten() {
+        if let Err(err) = validate_pattern(pattern) {
+            errors.push(ConfigValidationError::InvalidPattern(
+                key.clone(),
+                field,
+                pattern.clone(),
+                err.kind().to_string(),
+            ));
+        }
+    }
+}
+
+/// Checks, if the password id of the config entry `key` is not empty.
+fn validate_password_id(
+    errors: &mut Vec<ConfigValidationError>,
+    key: ConfigEntryKey,
+    password_id: &str,
+) {
+    if password_id.is_empty() {
+        errors.push(ConfigValidationError::EmptyPasswordId(key));
+    }
+}
+
+/// Returns the sorted names of `entries`.
+fn sorted_names<T>(entries: &HashMap<String, T>) -> Vec<&String> {
+    let mut names: Vec<&String> = entries.keys().collect();
+    names.sort();
+    names
+}
+
+/// Clones the entry of `key` in `entries` and inserts it as `new_name`.
+fn duplicate_in<T: Clone>(
+    entries: &mut HashMap<String, T>,
+    key: &ConfigEntryKey,
+    new_name: &str,
+) -> Result<(), ConfigError> {
+    if entries.contains_key(new_name) {
+        return Err(ConfigError::EntryExists(ConfigEntryKey::new(
+            key.entry_type.clone(),
+            new_name.to_string(),
+        )));
+    }
+
+    let entry = entries
+        .get(&key.name)
+        .cloned()
+        .ok_or_else(|| ConfigError::EntryNotFound(key.clone()))?;
+
+    entries.insert(new_name.to_string(), entry);
+
+    Ok(())
+}
+
 // Defines a `ConfigEntryType`.
 #[derive(Display, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ConfigEntryType {
@@ -39,6 +205,21 @@ pub enum ConfigEntryType {
     #[strum(to_string = "filesystem.webdav")]
     WebDAVFS,
 
+    #[strum(to_string = "filesystem.ftp")]
+    FtpFS,
+
+    #[strum(to_string = "filesystem.azure")]
+    AzureBlobFS,
+
+    #[strum(to_string = "filesystem.rclone")]
+    RcloneFS,
+
+    #[strum(to_string = "filesystem.s3")]
+    S3FS,
+
+    #[strum(to_string = "filesystem.mem")]
+    MemFS,
+
     #[strum(to_string = "backup")]
     Backup,
 
@@ -49,7 +230,17 @@ pub enum ConfigEntryType {
 /// Defines Methods for `ConfigEntryType`.
 impl ConfigEntryType {
     /// Returns all `ConfigEntryType`s.
-    pub const ALL: [Self; 4] = [Self::LocalFS, Self::WebDAVFS, Self::Backup, Self::Restore];
+    pub const ALL: [Self; 9] = [
+        Self::LocalFS,
+        Self::WebDAVFS,
+        Self::FtpFS,
+        Self::AzureBlobFS,
+        Self::RcloneFS,
+        Self::S3FS,
+        Self::MemFS,
+        Self::Backup,
+        Self::Restore,
+    ];
 }
 
 // Defines a `ConfigEntryKey`.
@@ -78,16 +269,25 @@ impl fmt::Display for ConfigEntryKey {
 pub enum ConfigEntryMut<'a> {
     LocalFS(&'a mut LocalFS),
     WebDAVFS(&'a mut WebDAVFS),
+    FtpFS(&'a mut FtpFS),
+    AzureBlobFS(&'a mut AzureBlobFS),
+    RcloneFS(&'a mut RcloneFS),
+    S3FS(&'a mut S3FS),
+    MemFS(&'a mut MemFS),
     Backup(&'a mut BackupConfig),
     Restore(&'a mut RestoreConfig),
 }
 
 /// Defines a `Config`.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     /// Number of transfer threads.
     pub transfer_threads: usize,
 
+    /// Optional limit of the bandwidth of all transfer threads in bytes per second.
+    #[serde(default)]
+    pub max_bandwidth_bytes_per_sec: Option<u64>,
+
     /// The filesystem profiles.
     pub filesystem: FilesystemConfig,
 
@@ -133,6 +333,41 @@ impl Config {
             });
         }
 
+        for name in self.filesystem.ftp.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::FtpFS,
+                name: name.clone(),
+            });
+        }
+
+        for name in self.filesystem.azure.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::AzureBlobFS,
+                name: name.clone(),
+            });
+        }
+
+        for name in self.filesystem.rclone.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::RcloneFS,
+                name: name.clone(),
+            });
+        }
+
+        for name in self.filesystem.s3.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::S3FS,
+                name: name.clone(),
+            });
+        }
+
+        for name in self.filesystem.mem.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::MemFS,
+                name: name.clone(),
+            });
+        }
+
         for name in self.backup.keys() {
             keys.push(ConfigEntryKey {
                 entry_type: ConfigEntryType::Backup,
@@ -168,6 +403,41 @@ impl Config {
             });
         }
 
+        for name in self.filesystem.ftp.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::FtpFS,
+                name: name.clone(),
+            });
+        }
+
+        for name in self.filesystem.azure.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::AzureBlobFS,
+                name: name.clone(),
+            });
+        }
+
+        for name in self.filesystem.rclone.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::RcloneFS,
+                name: name.clone(),
+            });
+        }
+
+        for name in self.filesystem.s3.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::S3FS,
+                name: name.clone(),
+            });
+        }
+
+        for name in self.filesystem.mem.keys() {
+            keys.push(ConfigEntryKey {
+                entry_type: ConfigEntryType::MemFS,
+                name: name.clone(),
+            });
+        }
+
         keys
     }
 
@@ -186,6 +456,36 @@ impl Config {
                 .get_mut(&key.name)
                 .map(ConfigEntryMut::WebDAVFS),
 
+            ConfigEntryType::FtpFS => self
+                .filesystem
+                .ftp
+                .get_mut(&key.name)
+                .map(ConfigEntryMut::FtpFS),
+
+            ConfigEntryType::AzureBlobFS => self
+                .filesystem
+                .azure
+                .get_mut(&key.name)
+                .map(ConfigEntryMut::AzureBlobFS),
+
+            ConfigEntryType::RcloneFS => self
+                .filesystem
+                .rclone
+                .get_mut(&key.name)
+                .map(ConfigEntryMut::RcloneFS),
+
+            ConfigEntryType::S3FS => self
+                .filesystem
+                .s3
+                .get_mut(&key.name)
+                .map(ConfigEntryMut::S3FS),
+
+            ConfigEntryType::MemFS => self
+                .filesystem
+                .mem
+                .get_mut(&key.name)
+                .map(ConfigEntryMut::MemFS),
+
             ConfigEntryType::Backup => self.backup.get_mut(&key.name).map(ConfigEntryMut::Backup),
 
             ConfigEntryType::Restore => {
@@ -207,6 +507,29 @@ impl Config {
                     .webdav
                     .insert(name.to_string(), WebDAVFS::default());
             }
+            ConfigEntryType::FtpFS => {
+                self.filesystem
+                    .ftp
+                    .insert(name.to_string(), FtpFS::default());
+            }
+            ConfigEntryType::AzureBlobFS => {
+                self.filesystem
+                    .azure
+                    .insert(name.to_string(), AzureBlobFS::default());
+            }
+            ConfigEntryType::RcloneFS => {
+                self.filesystem
+                    .rclone
+                    .insert(name.to_string(), RcloneFS::default());
+            }
+            ConfigEntryType::S3FS => {
+                self.filesystem.s3.insert(name.to_string(), S3FS::default());
+            }
+            ConfigEntryType::MemFS => {
+                self.filesystem
+                    .mem
+                    .insert(name.to_string(), MemFS::default());
+            }
             ConfigEntryType::Backup => {
                 self.backup
                     .insert(name.to_string(), BackupConfig::default());
@@ -227,6 +550,21 @@ impl Config {
             ConfigEntryType::WebDAVFS => {
                 self.filesystem.webdav.remove(&key.name);
             }
+            ConfigEntryType::FtpFS => {
+                self.filesystem.ftp.remove(&key.name);
+            }
+            ConfigEntryType::AzureBlobFS => {
+                self.filesystem.azure.remove(&key.name);
+            }
+            ConfigEntryType::RcloneFS => {
+                self.filesystem.rclone.remove(&key.name);
+            }
+            ConfigEntryType::S3FS => {
+                self.filesystem.s3.remove(&key.name);
+            }
+            ConfigEntryType::MemFS => {
+                self.filesystem.mem.remove(&key.name);
+            }
             ConfigEntryType::Backup => {
                 self.backup.remove(&key.name);
             }
@@ -236,6 +574,192 @@ impl Config {
         }
     }
 
+    /// Validates the config, returns an error for each violation.
+    ///
+    /// Empty `src_dir` or `dest_dir` of a backup refer to the root of the filesystem.
+    /// Paths must not exceed the maximum path length.
+    /// The filesystems of the backups and restores must be defined, the password ids
+    /// must not be empty and the include/exclude patterns must compile.
+    pub fn validate(&self) -> Vec<ConfigValidationError> {
+        let mut errors = Vec::new();
+
+        if self.transfer_threads < 1 {
+            errors.push(ConfigValidationError::NoTransferThreads);
+        }
+
+        // The base paths of the filesystems.
+        for name in sorted_names(&self.filesystem.local) {
+            validate_path_len(
+                &mut errors,
+                ConfigEntryKey::new(ConfigEntryType::LocalFS, name.clone()),
+                "dir",
+                self.filesystem.local[name].dir.to_unicode(),
+            );
+        }
+
+        for name in sorted_names(&self.filesystem.webdav) {
+            validate_path_len(
+                &mut errors,
+                ConfigEntryKey::new(ConfigEntryType::WebDAVFS, name.clone()),
+                "url",
+                self.filesystem.webdav[name].url.to_unicode(),
+            );
+
+            validate_password_id(
+                &mut errors,
+                ConfigEntryKey::new(ConfigEntryType::WebDAVFS, name.clone()),
+                &self.filesystem.webdav[name].password_id,
+            );
+        }
+
+        for name in sorted_names(&self.filesystem.ftp) {
+            validate_path_len(
+                &mut errors,
+                ConfigEntryKey::new(ConfigEntryType::FtpFS, name.clone()),
+                "dir",
+                self.filesystem.ftp[name].dir.to_unicode(),
+            );
+
+            validate_password_id(
+                &mut errors,
+                ConfigEntryKey::new(ConfigEntryType::FtpFS, name.clone()),
+                &self.filesystem.ftp[name].password_id,
+            );
+        }
+
+        for name in sorted_names(&self.filesystem.azure) {
+            validate_password_id(
+                &mut errors,
+                ConfigEntryKey::new(ConfigEntryType::AzureBlobFS, name.clone()),
+                &self.filesystem.azure[name].password_id,
+            );
+        }
+
+        for name in sorted_names(&self.filesystem.s3) {
+            validate_password_id(
+                &mut errors,
+                ConfigEntryKey::new(ConfigEntryType::S3FS, name.clone()),
+                &self.filesystem.s3[name].password_id,
+            );
+        }
+
+        // The dirs of the restores.
+        for name in sorted_names(&self.restore) {
+            let restore = &self.restore[name];
+
+            for (field, dir) in [
+                ("src_dir", &restore.src_dir),
+                ("dest_dir", &restore.dest_dir),
+            ] {
+                validate_path_len(
+                    &mut errors,
+                    ConfigEntryKey::new(ConfigEntryType::Restore, name.clone()),
+                    field,
+                    dir.to_unicode(),
+                );
+            }
+
+            let key = ConfigEntryKey::new(ConfigEntryType::Restore, name.clone());
+
+            for (field, fs) in [("src_fs", &restore.src_fs), ("dest_fs", &restore.dest_fs)] {
+                validate_fs(&mut errors, &self.filesystem, key.clone(), field, fs);
+            }
+
+            validate_patterns(&mut errors, &key, "include", &restore.include);
+            validate_patterns(&mut errors, &key, "exclude", &restore.exclude);
+        }
+
+        for name in sorted_names(&self.backup) {
+            let backup = &self.backup[name];
+
+            for (field, dir) in [("src_dir", &backup.src_dir), ("dest_dir", &backup.dest_dir)] {
+                // An automatic dest dir replaces the configured one.
+                if field == "dest_dir" && backup.dest_dir_auto {
+                    continue;
+                }
+
+                if dir.is_empty() {
+                    errors.push(ConfigValidationError::EmptyPath(
+                        ConfigEntryKey::new(ConfigEntryType::Backup, name.clone()),
+                        field,
+                    ));
+                }
+
+                validate_path_len(
+                    &mut errors,
+                    ConfigEntryKey::new(ConfigEntryType::Backup, name.clone()),
+                    field,
+                    dir.to_unicode(),
+                );
+            }
+
+            let key = ConfigEntryKey::new(ConfigEntryType::Backup, name.clone());
+
+            for (field, fs) in [("src_fs", &backup.src_fs), ("dest_fs", &backup.dest_fs)] {
+                validate_fs(&mut errors, &self.filesystem, key.clone(), field, fs);
+            }
+
+            validate_patterns(&mut errors, &key, "include", &backup.include);
+            validate_patterns(&mut errors, &key, "exclude", &backup.exclude);
+
+            match &backup.password_id {
+                Some(password_id) => validate_password_id(&mut errors, key.clone(), password_id),
+                None if backup.encrypt => {
+                    errors.push(ConfigValidationError::EncryptWithoutPasswordId(key.clone()));
+                }
+                None => {}
+            }
+
+            if backup.encrypt_filename && !(backup.encrypt && backup.password_id.is_some()) {
+                errors.push(ConfigValidationError::EncryptFilenameWithoutEncrypt(
+                    ConfigEntryKey::new(ConfigEntryType::Backup, name.clone()),
+                ));
+            }
+
+            if let Some(compression_level) = backup.compression_level
+                && compression_level > MAX_COMPRESSION_LEVEL
+            {
+                errors.push(ConfigValidationError::CompressionLevelOutOfRange(
+                    ConfigEntryKey::new(ConfigEntryType::Backup, name.clone()),
+                    compression_level,
+                    MAX_COMPRESSION_LEVEL,
+                ));
+            }
+        }
+
+        errors
+    }
+
+    /// Clamps the compression levels of the backups to the valid range.
+    pub fn clamp_compression_levels(&mut self) {
+        for backup in self.backup.values_mut() {
+            if let Some(compression_level) = &mut backup.compression_level {
+                *compression_level = (*compression_level).min(MAX_COMPRESSION_LEVEL);
+            }
+        }
+    }
+
+    /// Duplicates the entry with the given key as `new_name`.
+    ///
+    /// Fails, if an entry of the same type with `new_name` already exists.
+    pub fn duplicate_entry(
+        &mut self,
+        key: &ConfigEntryKey,
+        new_name: &str,
+    ) -> Result<(), ConfigError> {
+        match key.entry_type {
+            ConfigEntryType::LocalFS => duplicate_in(&mut self.filesystem.local, key, new_name),
+            ConfigEntryType::WebDAVFS => duplicate_in(&mut self.filesystem.webdav, key, new_name),
+            ConfigEntryType::FtpFS => duplicate_in(&mut self.filesystem.ftp, key, new_name),
+            ConfigEntryType::AzureBlobFS => duplicate_in(&mut self.filesystem.azure, key, new_name),
+            ConfigEntryType::RcloneFS => duplicate_in(&mut self.filesystem.rclone, key, new_name),
+            ConfigEntryType::S3FS => duplicate_in(&mut self.filesystem.s3, key, new_name),
+            ConfigEntryType::MemFS => duplicate_in(&mut self.filesystem.mem, key, new_name),
+            ConfigEntryType::Backup => duplicate_in(&mut self.backup, key, new_name),
+            ConfigEntryType::Restore => duplicate_in(&mut self.restore, key, new_name),
+        }
+    }
+
     /// Renames the entry with the given key.
     pub fn rename_entry(&mut self, key: &ConfigEntryKey, new_name: &str) {
         // Nothing to do.
@@ -254,6 +778,31 @@ impl Config {
                     self.filesystem.webdav.insert(new_name.to_string(), entry);
                 }
             }
+            ConfigEntryType::FtpFS => {
+                if let Some(entry) = self.filesystem.ftp.remove(&key.name) {
+                    self.filesystem.ftp.insert(new_name.to_string(), entry);
+                }
+            }
+            ConfigEntryType::AzureBlobFS => {
+                if let Some(entry) = self.filesystem.azure.remove(&key.name) {
+                    self.filesystem.azure.insert(new_name.to_string(), entry);
+                }
+            }
+            ConfigEntryType::RcloneFS => {
+                if let Some(entry) = self.filesystem.rclone.remove(&key.name) {
+                    self.filesystem.rclone.insert(new_name.to_string(), entry);
+                }
+            }
+            ConfigEntryType::S3FS => {
+                if let Some(entry) = self.filesystem.s3.remove(&key.name) {
+                    self.filesystem.s3.insert(new_name.to_string(), entry);
+                }
+            }
+            ConfigEntryType::MemFS => {
+                if let Some(entry) = self.filesystem.mem.remove(&key.name) {
+                    self.filesystem.mem.insert(new_name.to_string(), entry);
+                }
+            }
             ConfigEntryType::Backup => {
                 if let Some(entry) = self.backup.remove(&key.name) {
                     self.backup.insert(new_name.to_string(), entry);
@@ -269,10 +818,25 @@ impl Config {
 }
 
 /// Defines a `FilesystemConfig`.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FilesystemConfig {
     pub local: HashMap<String, LocalFS>,
     pub webdav: HashMap<String, WebDAVFS>,
+
+    #[serde(default)]
+    pub ftp: HashMap<String, FtpFS>,
+
+    #[serde(default)]
+    pub azure: HashMap<String, AzureBlobFS>,
+
+    #[serde(default)]
+    pub rclone: HashMap<String, RcloneFS>,
+
+    #[serde(default)]
+    pub s3: HashMap<String, S3FS>,
+
+    #[serde(default)]
+    pub mem: HashMap<String, MemFS>,
 }
 
 /// Methods of `FilesystemConfig`.
@@ -285,19 +849,104 @@ impl FilesystemConfig {
             }
         }
 
+        for ftp in self.ftp.values() {
+            if ftp.password_id == password_id {
+                return true;
+            }
+        }
+
+        for azure in self.azure.values() {
+            if azure.password_id == password_id {
+                return true;
+            }
+        }
+
+        for s3 in self.s3.values() {
+            if s3.password_id == password_id {
+                return true;
+            }
+        }
+
         false
     }
+
+    /// Checks if a filesystem with the name `fs` is defined.
+    pub fn has_fs(&self, fs: &str) -> bool {
+        self.local.contains_key(fs)
+            || self.webdav.contains_key(fs)
+            || self.ftp.contains_key(fs)
+            || self.azure.contains_key(fs)
+            || self.rclone.contains_key(fs)
+            || self.s3.contains_key(fs)
+            || self.mem.contains_key(fs)
+    }
+
+    /// Returns the password id of the filesystem with the name `fs` (if any).
+    pub fn password_id(&self, fs: &str) -> Option<&str> {
+        if let Some(webdav) = self.webdav.get(fs) {
+            Some(&webdav.password_id)
+        } else if let Some(ftp) = self.ftp.get(fs) {
+            Some(&ftp.password_id)
+        } else if let Some(azure) = self.azure.get(fs) {
+            Some(&azure.password_id)
+        } else {
+            self.s3.get(fs).map(|s3| s3.password_id.as_str())
+        }
+    }
 }
 
 // Defines a `LocalFS`.
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LocalFS {
     /// Directory.
     pub dir: NPath<Abs, Dir>,
+
+    /// Read files without updating their access time (Linux only).
+    ///
+    /// Files of other owners are read with a standard open, which updates the access time.
+    #[serde(default = "default_noatime")]
+    pub noatime: bool,
+}
+
+/// Returns the default for `noatime`.
+fn default_noatime() -> bool {
+    true
+}
+
+/// Impl of `Default` for `LocalFS`.
+impl Default for LocalFS {
+    fn default() -> Self {
+        Self {
+            dir: NPath::default(),
+            noatime: default_noatime(),
+        }
+    }
+}
+
+/// Defines an `AuthType`.
+///
+/// The authentication of http requests.
+#[derive(Display, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthType {
+    /// User and password.
+    #[default]
+    #[strum(to_string = "Basic")]
+    Basic,
+
+    /// A token, the user is not used.
+    #[strum(to_string = "Bearer")]
+    Bearer,
+}
+
+/// Methods of `AuthType`.
+impl AuthType {
+    /// Returns all `AuthType`s.
+    pub const ALL: [Self; 2] = [Self::Basic, Self::Bearer];
 }
 
 /// Defines a `WebDAVFS`.
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WebDAVFS {
     /// Url.
     pub url: NPath<Abs, Dir>,
@@ -308,12 +957,256 @@ pub struct WebDAVFS {
     /// Password id.
     pub password_id: String,
 
+    /// Optional authentication type, defaults to basic.
+    #[serde(default)]
+    pub auth_type: AuthType,
+
     /// Connection timeout in seconds.
     pub timeout_secs: u64,
+
+    /// Optional timeout for establishing a connection in seconds, defaults to `timeout_secs`.
+    #[serde(default)]
+    pub connect_timeout_secs: Option<u64>,
+
+    /// Optional timeout for reading requests (e.g. PROPFIND, GET) in seconds, defaults to `timeout_secs`.
+    #[serde(default)]
+    pub read_timeout_secs: Option<u64>,
+
+    /// Optional timeout for writing requests (e.g. PUT, MKCOL) in seconds, defaults to `timeout_secs`.
+    #[serde(default)]
+    pub write_timeout_secs: Option<u64>,
+
+    /// Follow redirects?
+    #[serde(default = "default_follow_redirects")]
+    pub follow_redirects: bool,
+
+    /// Maximum number of redirects to follow.
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: u32,
+
+    /// Number of retries of requests, which failed with a transient error (5xx, 429, connection).
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+
+    /// The backoff before the first retry in milliseconds, it doubles with each retry.
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+
+    /// Upload files in chunks, which are resumed after a failure?
+    #[serde(default)]
+    pub chunked_upload: bool,
+}
+
+/// Methods of `WebDAVFS`.
+impl WebDAVFS {
+    /// Returns the connect timeout in seconds.
+    pub fn connect_timeout_secs(&self) -> u64 {
+        self.connect_timeout_secs.unwrap_or(self.timeout_secs)
+    }
+
+    /// Returns the read timeout in seconds.
+    pub fn read_timeout_secs(&self) -> u64 {
+        self.read_timeout_secs.unwrap_or(self.timeout_secs)
+    }
+
+    /// Returns the write timeout in seconds.
+    pub fn write_timeout_secs(&self) -> u64 {
+        self.write_timeout_secs.unwrap_or(self.timeout_secs)
+    }
+}
+
+/// Returns the default for `follow_redirects`.
+fn default_follow_redirects() -> bool {
+    true
+}
+
+/// Returns the default for `max_redirects`.
+fn default_max_redirects() -> u32 {
+    10
+}
+
+/// Returns the default for `retries`.
+fn default_retries() -> u32 {
+    3
+}
+
+/// Returns the default for `retry_backoff_ms`.
+fn default_retry_backoff_ms() -> u64 {
+    1000
+}
+
+/// Impl of `Default` for `WebDAVFS`.
+impl Default for WebDAVFS {
+    fn default() -> Self {
+        Self {
+            url: NPath::default(),
+            user: String::new(),
+            password_id: String::new(),
+            auth_type: AuthType::default(),
+            timeout_secs: 0,
+            connect_timeout_secs: None,
+            read_timeout_secs: None,
+            write_timeout_secs: None,
+            follow_redirects: default_follow_redirects(),
+            max_redirects: default_max_redirects(),
+            retries: default_retries(),
+            retry_backoff_ms: default_retry_backoff_ms(),
+            chunked_upload: false,
+        }
+    }
+}
+
+/// Defines a `FtpFS`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FtpFS {
+    /// Host.
+    pub host: String,
+
+    /// Port.
+    #[serde(default = "default_ftp_port")]
+    pub port: u16,
+
+    /// Directory on the server.
+    pub dir: NPath<Abs, Dir>,
+
+    /// Use passive mode?
+    #[serde(default = "default_passive_mode")]
+    pub passive_mode: bool,
+
+    /// Use explicit TLS (FTPS)?
+    #[serde(default)]
+    pub use_tls: bool,
+
+    /// Username.
+    pub username: String,
+
+    /// Password id.
+    pub password_id: String,
+}
+
+/// Returns the default for `port`.
+fn default_ftp_port() -> u16 {
+    21
+}
+
+/// Returns the default for `passive_mode`.
+fn default_passive_mode() -> bool {
+    true
+}
+
+/// Impl of `Default` for `FtpFS`.
+impl Default for FtpFS {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: default_ftp_port(),
+            dir: NPath::default(),
+            passive_mode: default_passive_mode(),
+            use_tls: false,
+            username: String::new(),
+            password_id: String::new(),
+        }
+    }
+}
+
+/// Defines a `AzureBlobFS`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct AzureBlobFS {
+    /// Storage account.
+    pub account: String,
+
+    /// Container.
+    pub container: String,
+
+    /// Password id of the account key or the SAS token.
+    pub password_id: String,
+
+    /// Is the password a SAS token?
+    #[serde(default)]
+    pub sas_token: bool,
+}
+
+/// Defines a `RcloneFS`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct RcloneFS {
+    /// Rclone remote with optional path, e.g. `gdrive:Backups`.
+    pub remote: String,
+
+    /// Extra flags passed to rclone.
+    #[serde(default)]
+    pub rclone_flags: Vec<String>,
+}
+
+/// Defines a `S3FS`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct S3FS {
+    /// Endpoint of the S3-compatible storage, e.g. `https://s3.eu-central-003.backblazeb2.com`.
+    pub endpoint: String,
+
+    /// Region, which is part of the signature.
+    pub region: String,
+
+    /// Bucket.
+    pub bucket: String,
+
+    /// Key prefix of all objects, e.g. `backups`.
+    #[serde(default)]
+    pub prefix: String,
+
+    /// Access key id.
+    pub access_key_id: String,
+
+    /// Password id of the secret access key.
+    pub password_id: String,
+}
+
+/// Defines a `MemFS`.
+///
+/// The content is kept in memory and is shared by all mem filesystems with the
+/// same name, until the process exits. Useful for tests and trial runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct MemFS {}
+
+/// Defines a `RetentionConfig`.
+///
+/// The retention policy of the prune for the orphans (not in the source anymore) of a backup.
+/// An orphan is kept, if one of the rules keeps it. A backup holds a single version of each
+/// file, so there are no older versions to retain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct RetentionConfig {
+    /// Optional number of the most recently backed up orphans to keep.
+    #[serde(default)]
+    pub keep_last: Option<usize>,
+
+    /// Optional days to keep the orphans, counted from their backup.
+    #[serde(default)]
+    pub keep_within_days: Option<u64>,
+}
+
+/// Defines a `SignatureAlgo`.
+///
+/// The hash algorithm of the signatures of the files.
+#[derive(Display, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureAlgo {
+    /// BLAKE3, fast on large files.
+    #[default]
+    #[strum(to_string = "BLAKE3")]
+    Blake3,
+
+    /// SHA-256, for the tools of the users.
+    #[strum(to_string = "SHA-256")]
+    Sha256,
+}
+
+/// Methods of `SignatureAlgo`.
+impl SignatureAlgo {
+    /// Returns all `SignatureAlgo`s.
+    pub const ALL: [Self; 2] = [Self::Blake3, Self::Sha256];
 }
 
 /// Defines a `BackupConfig`.
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BackupConfig {
     /// The source filesystem.
     pub src_fs: String,
@@ -327,18 +1220,120 @@ pub struct BackupConfig {
     /// The destination directory.  
     pub dest_dir: NPath<Rel, Dir>,
 
+    /// Use `<hostname>/<backup name>` as destination directory, instead of `dest_dir`?
+    #[serde(default)]
+    pub dest_dir_auto: bool,
+
     /// Optional inclusion patterns (glob).
     pub include: Option<Vec<String>>,
 
     /// Optional exclusion patterns (glob).
     pub exclude: Option<Vec<String>>,
 
+    /// Optional file with exclusion patterns (`.gitignore`-style).
+    #[serde(default)]
+    pub exclude_patterns_file: Option<NPath<Abs, File>>,
+
+    /// Optional case sensitivity of the patterns, defaults to the platform.
+    #[serde(default)]
+    pub glob_case_sensitive: Option<bool>,
+
     /// Encrypt?
     pub encrypt: bool,
     pub password_id: Option<String>,
 
+    /// Store the files under hashed names, the names are encrypted in the cuba json?
+    #[serde(default)]
+    pub encrypt_filename: bool,
+
     /// Compress?
     pub compression: bool,
+
+    /// Optional compression level from 0 (none) to 9 (best), defaults to 6.
+    #[serde(default)]
+    pub compression_level: Option<u32>,
+
+    /// Skip the compression of already compressed files (e.g. ZIP, JPEG, MP4)?
+    #[serde(default)]
+    pub skip_recompression: bool,
+
+    /// Optional file extensions (e.g. `jpg`, `zip`), which are not compressed.
+    #[serde(default)]
+    pub compression_exclude_extensions: Option<Vec<String>>,
+
+    /// Upload only the delta to the previous version of changed files up to 64 MiB?
+    #[serde(default)]
+    pub delta_compression: bool,
+
+    /// Store files with the same content only once?
+    #[serde(default)]
+    pub dedup_enabled: bool,
+
+    /// The hash algorithm of the signatures, defaults to BLAKE3.
+    #[serde(default)]
+    pub signature_algo: SignatureAlgo,
+
+    /// Verify the backup after a successful backup?
+    #[serde(default)]
+    pub verify_after_backup: bool,
+
+    /// Optional maximum file size in bytes, larger files are skipped with a warning.
+    #[serde(default)]
+    pub max_file_size_bytes: Option<u64>,
+
+    /// Back up files again, which changed while they were backed up?
+    #[serde(default = "default_retry_changed_files")]
+    pub retry_changed_files: bool,
+
+    /// The days to keep the journals of the runs, 0 keeps them forever.
+    #[serde(default = "default_journal_retention_days")]
+    pub journal_retention_days: u32,
+
+    /// The retention policy of the prune.
+    #[serde(default)]
+    pub retention: RetentionConfig,
+}
+
+/// Returns the default for `retry_changed_files`.
+fn default_retry_changed_files() -> bool {
+    true
+}
+
+/// Returns the default for `journal_retention_days`.
+fn default_journal_retention_days() -> u32 {
+    30
+}
+
+/// Impl of `Default` for `BackupConfig`.
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            src_fs: String::new(),
+            dest_fs: String::new(),
+            src_dir: NPath::default(),
+            dest_dir: NPath::default(),
+            dest_dir_auto: false,
+            include: None,
+            exclude: None,
+            exclude_patterns_file: None,
+            glob_case_sensitive: None,
+            encrypt: false,
+            password_id: None,
+            encrypt_filename: false,
+            compression: false,
+            compression_level: None,
+            skip_recompression: false,
+            compression_exclude_extensions: None,
+            delta_compression: false,
+            dedup_enabled: false,
+            signature_algo: SignatureAlgo::default(),
+            verify_after_backup: false,
+            max_file_size_bytes: None,
+            retry_changed_files: default_retry_changed_files(),
+            journal_retention_days: default_journal_retention_days(),
+            retention: RetentionConfig::default(),
+        }
+    }
 }
 
 /// Methods of `BackupConfig`.
@@ -353,7 +1348,7 @@ impl BackupConfig {
 }
 
 /// Defines a `RestoreConfig`.
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct RestoreConfig {
     /// The source filesystem.
     pub src_fs: String,
@@ -376,27 +1371,115 @@ pub struct RestoreConfig {
 
 /// Example configuration file.
 pub const EXAMPLE_CONFIG: &str = r#"
+# String values may refer to environment variables like ${HOME}, which are
+# substituted on load. An unset variable is an error. Write $${HOME} for a
+# literal ${HOME}.
+
 # Number of parallel threads to use for transfers
 transfer_threads = 10
+# Limit the bandwidth of the backup and restore transfers in bytes per second
+# (optional). The limit is shared by all transfer threads.
+# max_bandwidth_bytes_per_sec = 1048576
 
 [filesystem.local."local_linux"]
 # A local filesystem with base user
 dir = "/home/user"
+# Read files without updating their access time (optional, default: true).
+# Only supported on Linux. Files of other owners are read with a standard open.
+noatime = true
 
 [filesystem.local."local_windows"]
 # A local filesystem with base C
 dir = "C:/"
 
 [filesystem.webdav."remote_storage"]
-# WebDAV server URL
+# WebDAV server URL, e.g. "https://example.com/remote.php/dav/${CUBA_WEBDAV_USER}"
 url = "https://example.com/remote.php/dav/user"
-# Username for authentication
+# Username for authentication, e.g. "${CUBA_WEBDAV_USER}"
 user = "user"
 # Identifier for password retrieval. Example: cuba password set webdav-pass
 password_id = "webdav-pass"
+# Authentication type (optional, default: "basic"). With "bearer", the password
+# is sent as bearer token and the user is ignored, e.g. for Nextcloud app tokens.
+auth_type = "basic"
 # Connection timeout in seconds. Increase this, if the upload of large files
 # failed due to timeout.
 timeout_secs = 3600
+# Optional timeouts per operation type in seconds. They default to timeout_secs.
+# Connect: establishing the connection
+# Read: reading requests like PROPFIND and GET
+# Write: writing requests like PUT, MKCOL and DELETE
+connect_timeout_secs = 30
+read_timeout_secs = 120
+write_timeout_secs = 3600
+# Follow redirects of the server (optional, default: true). Only redirects to
+# the same host, which keep the method (307, 308), are followed.
+follow_redirects = true
+# Maximum number of redirects to follow (optional, default: 10)
+max_redirects = 10
+# Retries of requests, which failed with a transient error like 502, 503, 429 or a
+# connection error (optional, default: 3). 0 disables the retries.
+retries = 3
+# Backoff before the first retry in milliseconds, doubled with each retry
+# (optional, default: 1000)
+retry_backoff_ms = 1000
+# Upload files in chunks with ranged PUT requests (optional, default: false). A
+# failed upload resumes at the length of the file on the server instead of
+# restarting. The server must support PUT with a Content-Range header.
+chunked_upload = false
+
+[filesystem.ftp."nas"]
+# FTP server host
+host = "nas.example.com"
+# FTP server port (optional, default: 21)
+port = 21
+# Directory on the server
+dir = "/backups"
+# Use passive mode (optional, default: true)
+passive_mode = true
+# Use explicit TLS (FTPS) (optional, default: false)
+use_tls = true
+# Username for authentication
+username = "user"
+# Identifier for password retrieval. Example: cuba password set ftp-pass
+password_id = "ftp-pass"
+
+[filesystem.azure."azure_storage"]
+# Azure storage account
+account = "myaccount"
+# Blob container
+container = "backups"
+# Identifier for retrieval of the account key or SAS token.
+# Example: cuba password set azure-key
+password_id = "azure-key"
+# Is the password a SAS token instead of an account key (optional, default: false)
+sas_token = false
+
+[filesystem.rclone."gdrive"]
+# Rclone remote with optional path. The remote must be configured with
+# rclone config. This gives access to all rclone storage providers.
+remote = "gdrive:Backups"
+# Extra flags passed to rclone (optional)
+rclone_flags = ["--fast-list"]
+
+[filesystem.s3."b2"]
+# Endpoint of the S3-compatible storage, e.g. Amazon S3, Backblaze B2 or MinIO.
+# The bucket is addressed path-style (endpoint/bucket/key).
+endpoint = "https://s3.eu-central-003.backblazeb2.com"
+# Region of the bucket, e.g. "us-east-1" for MinIO
+region = "eu-central-003"
+# Bucket
+bucket = "backups"
+# Key prefix of all objects (optional, default: none)
+prefix = "cuba"
+# Access key id (the key id for Backblaze B2)
+access_key_id = "0031234567890ab0000000001"
+# Identifier for retrieval of the secret access key.
+# Example: cuba password set b2-key
+password_id = "b2-key"
+
+[filesystem.mem."scratch"]
+# In-memory filesystem without settings. The content is lost, when cuba exits.
 
 [backup."backup_windows_documents"]
 # Source and destination filesystems (must match keys from [filesystem])
@@ -404,16 +1487,75 @@ src_fs = "local_windows"
 dest_fs = "remote_storage"
 src_dir = "user/Documents"
 dest_dir = "backups/cuba"
-# Optional inclusion patterns (glob)
+# Use <hostname>/<backup name> as destination directory instead of dest_dir
+# (optional, default: false). Useful if several machines back up to the same server.
+# dest_dir_auto = true
+# Optional inclusion patterns (glob). Patterns follow the .gitignore semantics:
+# "*.txt" matches at any level, "/notes.txt" only in src_dir, "src/**/*.rs" at
+# any depth below src and "logs/" only directories.
 include = ["**/*.txt"]
 # Optional exclusion patterns (glob)
 exclude = ["**/*.tmp"]
+# Optional file with exclusion patterns, one per line (.gitignore-style).
+# Blank lines and # comments are skipped, the patterns are added to exclude.
+# exclude_patterns_file = "C:/user/Documents/.cubaignore"
+# Optional case sensitivity of the patterns. Defaults to false on macOS and
+# Windows and to true on other platforms.
+glob_case_sensitive = false
 # Enable encryption
 encrypt = true
 # Optional password identifier for encryption
 password_id = "backup-pass"
+# Store the files under hashed names, so that the names are not visible at the
+# destination (optional, default: false). The original names are stored encrypted
+# in cuba.json.gz, requires encrypt and password_id. Unchanged files keep their names
+# until they are transferred again.
+# encrypt_filename = false
 # Enable compression
 compression = true
+# The compression level from 0 (none) to 9 (best) (optional, default: 6). Higher
+# levels take more CPU for a better ratio.
+# compression_level = 6
+# Skip the compression of already compressed files like ZIP, GZIP, JPEG, MP4 and PDF
+# (optional, default: false). The format is detected by the first bytes.
+skip_recompression = false
+# Don't compress files with the given extensions (optional). Faster than skip_recompression
+# for well known formats, the extensions are compared case-insensitive.
+# compression_exclude_extensions = ["jpg", "mp4", "zip", "gz", "7z"]
+# Upload only the delta to the previous version of changed files (optional,
+# default: false). Useful for files with small changes, e.g. databases. Only
+# files up to 64 MiB are delta compressed, they are held in memory while the
+# delta is computed.
+delta_compression = false
+# Store files with the same content only once in the cuba-dedup directory of
+# the destination (optional, default: false). Takes precedence over
+# delta_compression.
+dedup_enabled = false
+# The hash algorithm of the signatures, "blake3" or "sha256" (optional, default:
+# "blake3"). Files are transferred again on the next backup after a change.
+signature_algo = "blake3"
+# Verify the backup after the backup (optional, default: false). Failures of the
+# verify are reported as warnings.
+verify_after_backup = false
+# Skip files larger than the given size in bytes (optional). A warning is reported
+# for each skipped file.
+# max_file_size_bytes = 10737418240
+# Back up files again, which changed while they were backed up (optional, default: true).
+# Changed files are reported as warnings.
+retry_changed_files = true
+# Days to keep the journals of the runs in cuba-journal/ (optional, default: 30).
+# A journal lists the transferred and failed files of a run, 0 keeps them forever.
+# journal_retention_days = 30
+
+# Retention policy of the prune for the orphans, the files not in the source anymore
+# (optional). An orphan is kept, if one of the rules keeps it. Without a rule the
+# prune keeps all orphans. A backup holds a single version of each file, so the
+# rules count orphans and not versions.
+# [backup."backup_windows_documents".retention]
+# Keep the given number of the most recently backed up orphans
+# keep_last = 10
+# Keep the orphans backed up within the given days
+# keep_within_days = 30
 
 [restore."restore_windows_documents"]
 # Source and destination filesystems (must match keys from [filesystem])
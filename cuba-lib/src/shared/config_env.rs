@@ -0,0 +1,101 @@
+use thiserror::Error;
+use toml_edit::visit_mut::VisitMut;
+use toml_edit::{DocumentMut, Formatted, TomlError};
+
+/// Defines a `ConfigEnvError`.
+#[derive(Error, Debug)]
+pub enum ConfigEnvError {
+    #[error("The environment variable {0:?} of the config is not set")]
+    NotSet(String),
+
+    #[error("The environment variable in {0:?} is not closed by '}}'")]
+    Unclosed(String),
+
+    #[error(transparent)]
+    Parse(#[from] TomlError),
+}
+
+/// Substitutes the `${VAR}` of `value` by the environment variables.
+///
+/// `$${VAR}` is the escaped literal `${VAR}`.
+fn substitute(value: &str) -> Result<String, ConfigEnvError> {
+    let mut substituted = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(index) = rest.find('$') {
+        substituted.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some(tail) = rest.strip_prefix("$${") {
+            // Escaped.
+            substituted.push_str("${");
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("${") {
+            let end = tail
+                .find('}')
+                .ok_or_else(|| ConfigEnvError::Unclosed(value.to_string()))?;
+
+            let name = &tail[..end];
+            let var = std::env::var(name).map_err(|_| ConfigEnvError::NotSet(name.to_string()))?;
+
+            substituted.push_str(&var);
+            rest = &tail[end + 1..];
+        } else {
+            substituted.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    substituted.push_str(rest);
+
+    Ok(substituted)
+}
+
+/// Defines an `EnvSubstitution`.
+///
+/// Visits the string values of a document, the first error stops the substitution.
+#[derive(Default)]
+struct EnvSubstitution {
+    error: Option<ConfigEnvError>,
+}
+
+/// Impl of `VisitMut` for `EnvSubstitution`.
+impl VisitMut for EnvSubstitution {
+    fn visit_string_mut(&mut self, node: &mut Formatted<String>) {
+        // Strings without a variable are left untouched.
+        if self.error.is_some() || !node.value().contains("${") {
+            return;
+        }
+
+        match substitute(node.value()) {
+            Ok(value) => {
+                // Keep the whitespace and comments around the value.
+                let decor = node.decor().clone();
+                *node = Formatted::new(value);
+                *node.decor_mut() = decor;
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+}
+
+/// Substitutes the environment variables `${VAR}` in the string values of the config.
+///
+/// The layout of the config is kept, so that the errors of the parser point to the
+/// right lines. `$${VAR}` is the escaped literal `${VAR}`.
+pub fn interpolate_env(config: &str) -> Result<String, ConfigEnvError> {
+    // Nothing to substitute.
+    if !config.contains("${") {
+        return Ok(config.to_string());
+    }
+
+    let mut doc = config.parse::<DocumentMut>()?;
+    let mut env_substitution = EnvSubstitution::default();
+
+    env_substitution.visit_document_mut(&mut doc);
+
+    match env_substitution.error {
+        Some(err) => Err(err),
+        None => Ok(doc.to_string()),
+    }
+}
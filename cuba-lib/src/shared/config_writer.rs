@@ -53,9 +53,24 @@ impl ConfigWriter {
         // transfer_threads
         doc["transfer_threads"] = toml_edit::value(config.transfer_threads as i64);
 
+        // max_bandwidth_bytes_per_sec
+        match config.max_bandwidth_bytes_per_sec {
+            Some(max_bandwidth) => {
+                doc["max_bandwidth_bytes_per_sec"] = toml_edit::value(max_bandwidth as i64);
+            }
+            None => {
+                doc.remove("max_bandwidth_bytes_per_sec");
+            }
+        }
+
         // filesystem
         Self::patch_table(doc, "filesystem.local", &config.filesystem.local);
         Self::patch_table(doc, "filesystem.webdav", &config.filesystem.webdav);
+        Self::patch_table(doc, "filesystem.ftp", &config.filesystem.ftp);
+        Self::patch_table(doc, "filesystem.azure", &config.filesystem.azure);
+        Self::patch_table(doc, "filesystem.rclone", &config.filesystem.rclone);
+        Self::patch_table(doc, "filesystem.s3", &config.filesystem.s3);
+        Self::patch_table(doc, "filesystem.mem", &config.filesystem.mem);
 
         // backup
         Self::patch_table(doc, "backup", &config.backup);
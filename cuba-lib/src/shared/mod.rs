@@ -1,9 +1,15 @@
 pub mod clean_message;
 pub mod config;
+pub mod config_env;
 pub mod config_writer;
 pub mod message;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod msg_dispatcher;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod msg_receiver;
 pub mod npath;
 pub mod progress_message;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod progress_rate;
+pub mod rich_error;
 pub mod task_message;
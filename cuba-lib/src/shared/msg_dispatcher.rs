@@ -1,17 +1,71 @@
+use serde::{Deserialize, Serialize};
 use std::{
-    sync::{Arc, Mutex},
+    any::Any,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
     thread::{self, JoinHandle},
 };
 
 use crossbeam_channel::{Receiver, Sender, unbounded};
 
+use super::message::{ErrorMessage, InfoMessage, Message, WarnMessage};
+
+/// Defines a `DispatcherMetrics`.
+///
+/// A snapshot of the message counts of a `MsgDispatcher`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DispatcherMetrics {
+    /// The number of dispatched info messages.
+    pub info_count: u64,
+
+    /// The number of dispatched warn messages.
+    pub warn_count: u64,
+
+    /// The number of dispatched error messages.
+    pub error_count: u64,
+}
+
+/// Defines a `MsgCounters`.
+///
+/// The message counters shared with the dispatch thread.
+#[derive(Default, Clone)]
+struct MsgCounters {
+    info_count: Arc<AtomicU64>,
+    warn_count: Arc<AtomicU64>,
+    error_count: Arc<AtomicU64>,
+}
+
+/// Methods of `MsgCounters`.
+impl MsgCounters {
+    /// Counts `value`, if it is an info, warn or error message.
+    fn count<T: 'static>(&self, value: &T) {
+        let Some(message) = (value as &dyn Any).downcast_ref::<Arc<dyn Message>>() else {
+            return;
+        };
+
+        let message = message.as_any();
+
+        if message.is::<InfoMessage>() {
+            self.info_count.fetch_add(1, Ordering::Relaxed);
+        } else if message.is::<WarnMessage>() {
+            self.warn_count.fetch_add(1, Ordering::Relaxed);
+        } else if message.is::<ErrorMessage>() {
+            self.error_count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
 /// Defines a `MsgDispatcher`.
 ///
 /// Sends messages from a source to all subscribers.
 pub struct MsgDispatcher<T: Send + Sync + Clone + 'static> {
     source: Receiver<T>,
     receivers: Arc<Mutex<Vec<Sender<T>>>>,
+    counters: MsgCounters,
     shutdown_sender: Option<Sender<()>>,
+    flush_sender: Option<Sender<Sender<()>>>,
     thread_handle: Option<JoinHandle<()>>,
 }
 
@@ -24,7 +78,9 @@ impl<T: Send + Sync + Clone + 'static> MsgDispatcher<T> {
         Self {
             source,
             receivers: Arc::new(Mutex::new(Vec::new())),
+            counters: MsgCounters::default(),
             shutdown_sender: None,
+            flush_sender: None,
             thread_handle: None,
         }
     }
@@ -36,34 +92,78 @@ impl<T: Send + Sync + Clone + 'static> MsgDispatcher<T> {
         receiver
     }
 
+    /// Returns a snapshot of the message counts.
+    pub fn metrics(&self) -> DispatcherMetrics {
+        DispatcherMetrics {
+            info_count: self.counters.info_count.load(Ordering::Relaxed),
+            warn_count: self.counters.warn_count.load(Ordering::Relaxed),
+            error_count: self.counters.error_count.load(Ordering::Relaxed),
+        }
+    }
+
     /// Starts the `MsgDispatcher`.
     pub fn start(&mut self) {
         let source = self.source.clone();
 
         let receivers = Arc::clone(&self.receivers);
+        let counters = self.counters.clone();
         let (shutdown_sender, shutdown_receiver) = unbounded();
         self.shutdown_sender = Some(shutdown_sender);
+        let (flush_sender, flush_receiver) = unbounded::<Sender<()>>();
+        self.flush_sender = Some(flush_sender);
 
         self.thread_handle = Some(thread::spawn(move || {
+            let dispatch = |value: T| {
+                counters.count(&value);
+
+                let mut lock = receivers.lock().unwrap();
+                lock.retain(|sender| sender.send(value.clone()).is_ok());
+            };
+
             loop {
                 crossbeam_channel::select! {
                     recv(source) -> msg => {
                         match msg {
-                            Ok(value) => {
-                                let mut lock = receivers.lock().unwrap();
-                                lock.retain(|sender| sender.send(value.clone()).is_ok());
-                            }
+                            Ok(value) => dispatch(value),
                             Err(_) => break, // Source closed.
                         }
                     }
-                    recv(shutdown_receiver) -> _ => break,
+                    recv(flush_receiver) -> ack_sender => {
+                        // Dispatch the pending messages, then acknowledge.
+                        source.try_iter().for_each(dispatch);
+
+                        if let Ok(ack_sender) = ack_sender {
+                            let _ = ack_sender.send(());
+                        }
+                    }
+                    recv(shutdown_receiver) -> _ => {
+                        // Dispatch the pending messages.
+                        source.try_iter().for_each(dispatch);
+                        break;
+                    }
                 }
             }
         }));
     }
 
+    /// Dispatches the messages, which were sent to the source before.
+    ///
+    /// Blocks until the messages are sent to the subscribers, so that a subscriber can be
+    /// stopped without losing them.
+    pub fn flush(&self) {
+        if let Some(flush_sender) = &self.flush_sender {
+            let (ack_sender, ack_receiver) = unbounded();
+
+            if flush_sender.send(ack_sender).is_ok() {
+                let _ = ack_receiver.recv();
+            }
+        }
+    }
+
     /// Stops the `MsgDispatcher`.
     pub fn stop(&mut self) {
+        self.flush_sender = None;
+
         if let Some(sender) = self.shutdown_sender.take() {
             // Signal shutdown.
             let _ = sender.send(());
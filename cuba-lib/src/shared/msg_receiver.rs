@@ -3,7 +3,6 @@ use std::error::Error;
 use std::sync::Arc;
 use std::thread;
 use std::thread::JoinHandle;
-use std::time::Duration;
 
 use crate::shared::clean_message::{CleanInfo, CleanMessage};
 use crate::shared::message::Message;
@@ -11,7 +10,8 @@ use crate::shared::message::{ErrorMessage, WarnMessage};
 use crate::shared::message::{Info, InfoMessage};
 use crate::shared::npath::{Rel, UNPath};
 use crate::shared::progress_message::{ProgressInfo, ProgressMessage};
-use crate::shared::task_message::{TaskInfo, TaskMessage};
+use crate::shared::rich_error::RichError;
+use crate::shared::task_message::{TaskInfo, TaskMessage, TransferredBytes};
 
 /// Trace error.
 pub fn trace_error(err: &dyn std::error::Error) -> String {
@@ -70,6 +70,9 @@ pub trait MsgHandler {
     ) {
     }
 
+    /// Handles a `TransferredBytes` message.
+    fn task_transferred_bytes(&self, _thread_number: usize, _rel_path: &UNPath<Rel>, _bytes: u64) {}
+
     /// Handles a `TaskInfo::Tick` message.
     fn task_tick(
         &self,
@@ -79,6 +82,16 @@ pub trait MsgHandler {
     ) {
     }
 
+    /// Handles a `TaskInfo::Progress` message.
+    fn task_progress(
+        &self,
+        _thread_number: usize,
+        _rel_path: &UNPath<Rel>,
+        _done: u64,
+        _total: u64,
+    ) {
+    }
+
     /// Handles a `TaskInfo::UpToDate` message.
     fn task_up_to_date(
         &self,
@@ -98,13 +111,7 @@ pub trait MsgHandler {
     }
 
     /// Handles a `TaskMessage` with error.
-    fn task_error(
-        &self,
-        _thread_number: usize,
-        _rel_path: &UNPath<Rel>,
-        _error: &(dyn Error + Send + Sync),
-    ) {
-    }
+    fn task_error(&self, _thread_number: usize, _rel_path: &UNPath<Rel>, _error: &RichError) {}
 
     /// Handles a `ProgressInfo::Ticks` message.
     fn progress_ticks(&self, _ticks: u64, _info: &(dyn Info + Send + Sync)) {}
@@ -112,6 +119,9 @@ pub trait MsgHandler {
     /// Handles a `ProgressInfo::Duration` message.
     fn progress_duration(&self, _ticks: u64, _info: &(dyn Info + Send + Sync)) {}
 
+    /// Handles a `ProgressInfo::ByteProgress` message.
+    fn progress_bytes(&self, _transferred: u64, _total: u64, _info: &(dyn Info + Send + Sync)) {}
+
     /// Handles a `CleanInfo::Ok` message.
     fn clean_ok(&self, _rel_path: &UNPath<Rel>, _info: &(dyn Info + Send + Sync)) {}
 
@@ -131,9 +141,109 @@ pub trait MsgHandler {
     fn error(&self, _error: &(dyn Error + Send + Sync)) {}
 }
 
+/// Dispatches a message to the `MsgHandler`.
+fn handle_message(msg_handler: &(dyn MsgHandler + Sync + Send), message: &dyn Message) {
+    if let Some(task_message) = message.as_any().downcast_ref::<TaskMessage>() {
+        if let Some(info) = task_message.info() {
+            if let Some(task_info) = info.as_any().downcast_ref::<TaskInfo>() {
+                match task_info {
+                    TaskInfo::Start => msg_handler.task_start(
+                        task_message.thread_number,
+                        &task_message.rel_path,
+                        info,
+                    ),
+                    TaskInfo::Transferring => msg_handler.task_transferring(
+                        task_message.thread_number,
+                        &task_message.rel_path,
+                        info,
+                    ),
+                    TaskInfo::Finished => msg_handler.task_finished(
+                        task_message.thread_number,
+                        &task_message.rel_path,
+                        info,
+                    ),
+                    TaskInfo::Transferred => msg_handler.task_transferred(
+                        task_message.thread_number,
+                        &task_message.rel_path,
+                        info,
+                    ),
+                    TaskInfo::Tick => msg_handler.task_tick(
+                        task_message.thread_number,
+                        &task_message.rel_path,
+                        info,
+                    ),
+                    TaskInfo::Progress { done, total } => msg_handler.task_progress(
+                        task_message.thread_number,
+                        &task_message.rel_path,
+                        *done,
+                        *total,
+                    ),
+                    TaskInfo::UpToDate => msg_handler.task_up_to_date(
+                        task_message.thread_number,
+                        &task_message.rel_path,
+                        info,
+                    ),
+                    TaskInfo::Verified => msg_handler.task_verified(
+                        task_message.thread_number,
+                        &task_message.rel_path,
+                        info,
+                    ),
+                }
+            } else if let Some(transferred_bytes) = info.as_any().downcast_ref::<TransferredBytes>()
+            {
+                msg_handler.task_transferred_bytes(
+                    task_message.thread_number,
+                    &task_message.rel_path,
+                    transferred_bytes.bytes,
+                );
+            }
+        } else if let Some(err) = task_message.rich_error() {
+            msg_handler.task_error(task_message.thread_number, &task_message.rel_path, err);
+        }
+    } else if let Some(progress_message) = message.as_any().downcast_ref::<ProgressMessage>()
+        && let Some(info) = progress_message.info()
+    {
+        if let Some(progress_info) = info.as_any().downcast_ref::<ProgressInfo>() {
+            match progress_info {
+                ProgressInfo::Ticks => msg_handler.progress_ticks(progress_message.ticks, info),
+                ProgressInfo::Duration => {
+                    msg_handler.progress_duration(progress_message.ticks, info)
+                }
+                ProgressInfo::ByteProgress { transferred, total } => {
+                    msg_handler.progress_bytes(*transferred, *total, info)
+                }
+            }
+        }
+    } else if let Some(clean_message) = message.as_any().downcast_ref::<CleanMessage>() {
+        if let Some(info) = clean_message.info() {
+            if let Some(clean_info) = info.as_any().downcast_ref::<CleanInfo>() {
+                match clean_info {
+                    CleanInfo::Ok => msg_handler.clean_ok(&clean_message.rel_path, info),
+                    CleanInfo::Removed => msg_handler.clean_removed(&clean_message.rel_path, info),
+                }
+            }
+        } else if let Some(err) = clean_message.err() {
+            msg_handler.clean_error(&clean_message.rel_path, err);
+        }
+    } else if let Some(info_message) = message.as_any().downcast_ref::<InfoMessage>()
+        && let Some(info) = info_message.info()
+    {
+        msg_handler.info(info);
+    } else if let Some(warn_message) = message.as_any().downcast_ref::<WarnMessage>()
+        && let Some(info) = warn_message.info()
+    {
+        msg_handler.warn(info);
+    } else if let Some(error_message) = message.as_any().downcast_ref::<ErrorMessage>()
+        && let Some(err) = error_message.err()
+    {
+        msg_handler.error(err);
+    }
+}
+
 /// Defines a `MsgReceiver`.
 ///
-/// The `MsgReceiver` can be used to handle messages.
+/// The `MsgReceiver` can be used to handle messages. The messages of a task thread are
+/// handled in the order they were sent, the channels keep the order of each sender.
 pub struct MsgReceiver {
     receiver: Receiver<Arc<dyn Message>>,
     shutdown_sender: Option<Sender<()>>,
@@ -172,61 +282,15 @@ impl MsgReceiver {
                 select! {
                     recv(receiver) -> message => {
                         if let Ok(message) = message {
-                            if let Some(task_message) = message.as_ref().as_any().downcast_ref::<TaskMessage>() {
-                                if let Some(info) = task_message.info() {
-                                    if let Some(task_info) = info.as_any().downcast_ref::<TaskInfo>() {
-                                        match task_info {
-                                            TaskInfo::Start => msg_handler.task_start(task_message.thread_number, &task_message.rel_path, info),
-                                            TaskInfo::Transferring => msg_handler.task_transferring(task_message.thread_number, &task_message.rel_path, info),
-                                            TaskInfo::Finished => msg_handler.task_finished(task_message.thread_number, &task_message.rel_path, info),
-                                            TaskInfo::Transferred => msg_handler.task_transferred(task_message.thread_number, &task_message.rel_path, info),
-                                            TaskInfo::Tick => msg_handler.task_tick(task_message.thread_number, &task_message.rel_path, info),
-                                            TaskInfo::UpToDate => msg_handler.task_up_to_date(task_message.thread_number, &task_message.rel_path, info),
-                                            TaskInfo::Verified => msg_handler.task_verified(task_message.thread_number, &task_message.rel_path, info)
-                                        }
-                                    }
-                                }
-                                else if let Some(err) = task_message.err() {
-                                    msg_handler.task_error(task_message.thread_number, &task_message.rel_path, err);
-                                }
-                            }
-                            else if let Some(progress_message) = message.as_ref().as_any().downcast_ref::<ProgressMessage>()
-                                && let Some(info) = progress_message.info() {
-                                    if let Some(progress_info) = info.as_any().downcast_ref::<ProgressInfo>() {
-                                        match progress_info {
-                                            ProgressInfo::Ticks => msg_handler.progress_ticks(progress_message.ticks, info),
-                                            ProgressInfo::Duration => msg_handler.progress_duration(progress_message.ticks, info)
-                                        }
-                                    }
-                                }
-                            else if let Some(clean_message) = message.as_ref().as_any().downcast_ref::<CleanMessage>() {
-                                if let Some(info) = clean_message.info() {
-                                    if let Some(clean_info) = info.as_any().downcast_ref::<CleanInfo>() {
-                                        match clean_info {
-                                            CleanInfo::Ok => msg_handler.clean_ok(&clean_message.rel_path, info),
-                                            CleanInfo::Removed => msg_handler.clean_removed(&clean_message.rel_path, info)
-                                        }
-                                    }
-                                }
-                                else if let Some(err) = clean_message.err() {
-                                    msg_handler.clean_error(&clean_message.rel_path, err);
-                                }
-                            }
-                            else if let Some(info_message) = message.as_ref().as_any().downcast_ref::<InfoMessage>()
-                                && let Some(info) = info_message.info() {
-                                    msg_handler.info(info);
-                                }
-                            else if let Some(warn_message) = message.as_ref().as_any().downcast_ref::<WarnMessage>()
-                                && let Some(info) = warn_message.info() {
-                                    msg_handler.warn(info);
-                                }
-                            else if let Some(error_message) = message.as_ref().as_any().downcast_ref::<ErrorMessage>()
-                                && let Some(err) = error_message.err() {
-                                    msg_handler.error(err);
-                                }
+                            handle_message(msg_handler.as_ref(), message.as_ref());
                         }
                     },
                     recv(shutdown_receiver) -> _ => {
+                        // Handle the pending messages, before the handler is stopped.
+                        for message in receiver.try_iter() {
+                            handle_message(msg_handler.as_ref(), message.as_ref());
+                        }
+
                         break;
                     },
                 }
@@ -235,12 +299,12 @@ impl MsgReceiver {
     }
 
     /// Stops the `MsgReceiver`.
+    ///
+    /// The received messages are handled, the messages in a `MsgDispatcher` are flushed
+    /// with `MsgDispatcher::flush` before.
     pub fn stop(&mut self) {
         if let Some(sender) = self.shutdown_sender.take() {
-            // Lets wait a little bit to receiver pending messages.
-            thread::sleep(Duration::from_millis(100));
-
-            // Signal shutdown.
+            // Signal shutdown, the pending messages are handled before.
             let _ = sender.send(());
         }
 
@@ -1,14 +1,19 @@
 #![allow(dead_code)]
 
+use globset::GlobBuilder;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
 use std::ffi::OsStr;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use thiserror::Error;
 use unicode_normalization::UnicodeNormalization;
 use unicode_segmentation::UnicodeSegmentation;
@@ -17,6 +22,114 @@ static UNIX_ROOT: Lazy<Regex> = Lazy::new(|| Regex::new(r"^/").unwrap());
 static WINDOWS_DRIVE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z]:").unwrap());
 static URL_SCHEME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z][a-zA-Z0-9+\-.]*:/").unwrap());
 
+/// Returns the default case sensitivity of the platform.
+///
+/// The default filesystems of macOS and Windows are case-insensitive.
+pub fn default_case_sensitive() -> bool {
+    !cfg!(any(target_os = "macos", target_os = "windows"))
+}
+
+/// The capacity of the glob cache.
+const GLOB_CACHE_CAPACITY: usize = 64;
+
+/// The default maximum length of a path in bytes.
+pub const DEFAULT_MAX_PATH_LEN: usize = 4096;
+
+/// The maximum length of a path in bytes of the platform, Windows without long paths
+/// allows 260.
+pub const PLATFORM_MAX_PATH_LEN: usize = if cfg!(windows) {
+    260
+} else {
+    DEFAULT_MAX_PATH_LEN
+};
+
+/// The maximum length of a path in bytes.
+static MAX_PATH_LEN: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_PATH_LEN);
+
+/// Sets the maximum length of a path in bytes, e.g. 260 on Windows without long paths.
+pub fn set_max_path_len(max_len: usize) {
+    MAX_PATH_LEN.store(max_len, AtomicOrdering::Relaxed);
+}
+
+/// Returns the maximum length of a path in bytes.
+pub fn max_path_len() -> usize {
+    MAX_PATH_LEN.load(AtomicOrdering::Relaxed)
+}
+
+/// Checks, if a path does not exceed the maximum length.
+fn check_path_len(normalize_path: &str) -> Result<(), NPathError> {
+    let max_len = max_path_len();
+
+    if normalize_path.len() > max_len {
+        Err(NPathError::TooLong(normalize_path.len(), max_len))
+    } else {
+        Ok(())
+    }
+}
+
+thread_local! {
+    /// The glob cache of the thread.
+    static GLOB_CACHE: RefCell<GlobCache> = RefCell::new(GlobCache::new());
+}
+
+/// Defines a `GlobCache`.
+///
+/// A least recently used cache of compiled glob patterns. Invalid patterns are cached as `None`.
+struct GlobCache {
+    matchers: HashMap<String, Option<globset::GlobMatcher>>,
+    order: VecDeque<String>,
+}
+
+/// Methods of `GlobCache`.
+impl GlobCache {
+    /// Creates a new `GlobCache`.
+    fn new() -> Self {
+        Self {
+            matchers: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns true if the nfc normalized `pattern` matches `nfc_path`.
+    fn is_match(&mut self, pattern: &str, nfc_path: &str) -> bool {
+        if let Some(position) = self.order.iter().position(|cached| cached == pattern) {
+            // Mark as recently used.
+            if let Some(cached) = self.order.remove(position) {
+                self.order.push_back(cached);
+            }
+        } else {
+            // Evict the least recently used pattern.
+            if self.order.len() >= GLOB_CACHE_CAPACITY
+                && let Some(evicted) = self.order.pop_front()
+            {
+                self.matchers.remove(&evicted);
+            }
+
+            let matcher = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .case_insensitive(!default_case_sensitive())
+                .build()
+                .ok()
+                .map(|glob| glob.compile_matcher());
+
+            self.matchers.insert(pattern.to_string(), matcher);
+            self.order.push_back(pattern.to_string());
+        }
+
+        self.matchers
+            .get(pattern)
+            .and_then(|matcher| matcher.as_ref())
+            .is_some_and(|matcher| matcher.is_match(nfc_path))
+    }
+}
+
+/// Returns true if the glob `pattern` matches `nfc_path`.
+fn glob_matches_nfc(pattern: &str, nfc_path: &str) -> bool {
+    let nfc_pattern: String = pattern.nfc().collect();
+
+    GLOB_CACHE.with(|glob_cache| glob_cache.borrow_mut().is_match(&nfc_pattern, nfc_path))
+}
+
 /// Defines a `NPathRoot`.
 #[derive(Error, Debug)]
 pub enum NPathRoot {
@@ -114,6 +227,9 @@ pub enum NPathError {
 
     #[error("Invalid operation")]
     InvalidOperation,
+
+    #[error("Path is too long ({0} > {1} bytes)")]
+    TooLong(usize, usize),
 }
 
 /// Defines a `UNPath<K>`
@@ -290,6 +406,15 @@ impl<K> UNPath<K> {
         }
     }
 
+    /// Returns true if the `UNPath` contains an escaped byte of a lossy conversion.
+    pub fn is_lossy(&self) -> bool {
+        match self {
+            UNPath::File(file_path) => file_path.is_lossy(),
+            UNPath::Dir(dir_path) => dir_path.is_lossy(),
+            UNPath::Symlink(sym_path) => sym_path.is_lossy(),
+        }
+    }
+
     /// Returns true if the `UNPath` is a file path.
     pub fn is_file(&self) -> bool {
         match self {
@@ -317,6 +442,15 @@ impl<K> UNPath<K> {
         }
     }
 
+    /// Returns the index of the variant.
+    fn variant_index(&self) -> u8 {
+        match self {
+            UNPath::File(_file_path) => 0,
+            UNPath::Dir(_dir_path) => 1,
+            UNPath::Symlink(_sym_path) => 2,
+        }
+    }
+
     /// Returns the `UNPath` as raw str.
     pub fn to_unicode(&self) -> &str {
         match self {
@@ -334,6 +468,16 @@ impl<K> UNPath<K> {
             UNPath::Symlink(sym_path) => sym_path.to_nfc(),
         }
     }
+
+    /// Returns true if the glob `pattern` matches the nfc path.
+    pub fn glob_matches(&self, pattern: &str) -> bool {
+        glob_matches_nfc(pattern, self.to_nfc())
+    }
+
+    /// Returns the `UNPath` truncated in the middle to `max_len` graphemes.
+    pub fn compact_display(&self, max_len: usize) -> String {
+        compact_middle(self.to_unicode(), max_len)
+    }
 }
 
 /// Methods of an absolute `UNPath`.
@@ -348,24 +492,26 @@ impl UNPath<Abs> {
     }
 
     /// `UNPath<Rel> = UNPath<Abs> - NPath<Abs, Dir>`
+    #[deprecated(note = "use `strip_prefix` instead")]
     pub fn sub_abs_dir(&self, abs_dir_path: &NPath<Abs, Dir>) -> Result<UNPath<Rel>, NPathError> {
+        self.strip_prefix(abs_dir_path)
+    }
+
+    /// Returns the rel path of the absolute `UNPath` below the `prefix` dir.
+    pub fn strip_prefix(&self, prefix: &NPath<Abs, Dir>) -> Result<UNPath<Rel>, NPathError> {
         match self {
-            UNPath::File(self_abs_file_path) => {
-                match self_abs_file_path.sub_abs_dir(abs_dir_path) {
-                    Ok(rel_path) => Ok(rel_path.into()),
-                    Err(err) => Err(err),
-                }
-            }
-            UNPath::Dir(self_abs_dir_path) => match self_abs_dir_path.sub_abs_dir(abs_dir_path) {
+            UNPath::File(self_abs_file_path) => match self_abs_file_path.sub_abs_dir(prefix) {
+                Ok(rel_path) => Ok(rel_path.into()),
+                Err(err) => Err(err),
+            },
+            UNPath::Dir(self_abs_dir_path) => match self_abs_dir_path.sub_abs_dir(prefix) {
+                Ok(rel_path) => Ok(rel_path.into()),
+                Err(err) => Err(err),
+            },
+            UNPath::Symlink(self_abs_sym_path) => match self_abs_sym_path.sub_abs_dir(prefix) {
                 Ok(rel_path) => Ok(rel_path.into()),
                 Err(err) => Err(err),
             },
-            UNPath::Symlink(self_abs_sym_path) => {
-                match self_abs_sym_path.sub_abs_dir(abs_dir_path) {
-                    Ok(rel_path) => Ok(rel_path.into()),
-                    Err(err) => Err(err),
-                }
-            }
         }
     }
 
@@ -506,6 +652,23 @@ impl<K> Hash for UNPath<K> {
     }
 }
 
+/// Impl of `PartialOrd` for `UNPath`.
+impl<K> PartialOrd for UNPath<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Impl of `Ord` for `UNPath`.
+///
+/// Orders depth-first lexicographic by the nfc path, so a directory is ordered before its children.
+impl<K> Ord for UNPath<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_nfc_components(self.to_nfc(), other.to_nfc())
+            .then_with(|| self.variant_index().cmp(&other.variant_index()))
+    }
+}
+
 /// Defines a `NPath<K, T>`.
 ///
 /// `NPath` ensures a normalized pattern for paths.
@@ -525,21 +688,42 @@ impl<K> Hash for UNPath<K> {
 pub struct NPath<K, T> {
     unicode: String,
     nfc: String,
+    component_count: usize,
     _marker: PhantomData<(K, T)>,
 }
 
+/// Methods of an absolute `NPath` to construct it.
+impl<T> NPath<Abs, T> {
+    /// Creates an absolute `NPath` from a normalized path, without a length check.
+    fn from_normalized(norm_path: &str) -> Result<Self, NPathError> {
+        if has_root(norm_path) || norm_path.is_empty() {
+            Ok(NPath::from_unicode(norm_path))
+        } else {
+            Err(NPathError::NoAbsPath)
+        }
+    }
+}
+
+/// Methods of a relative `NPath` to construct it.
+impl<T> NPath<Rel, T> {
+    /// Creates a relative `NPath` from a normalized path, without a length check.
+    fn from_normalized(norm_path: &str) -> Result<Self, NPathError> {
+        if !has_root(norm_path) || norm_path.is_empty() {
+            Ok(NPath::from_unicode(norm_path))
+        } else {
+            Err(NPathError::NoRelPath)
+        }
+    }
+}
+
 /// Impl of `TryFrom` for an absolute `NPath`.
 impl<T> TryFrom<&str> for NPath<Abs, T> {
     type Error = NPathError;
 
     fn try_from(path: &str) -> Result<Self, Self::Error> {
         let norm_path = normalize_path(path);
-
-        if has_root(&norm_path) || norm_path.is_empty() {
-            Ok(NPath::from_unicode(&norm_path))
-        } else {
-            Err(NPathError::NoAbsPath)
-        }
+        check_path_len(&norm_path)?;
+        Self::from_normalized(&norm_path)
     }
 }
 
@@ -549,12 +733,8 @@ impl<T> TryFrom<String> for NPath<Abs, T> {
 
     fn try_from(path: String) -> Result<Self, Self::Error> {
         let norm_path = normalize_path(&path);
-
-        if has_root(&norm_path) || norm_path.is_empty() {
-            Ok(NPath::from_unicode(&norm_path))
-        } else {
-            Err(NPathError::NoAbsPath)
-        }
+        check_path_len(&norm_path)?;
+        Self::from_normalized(&norm_path)
     }
 }
 
@@ -564,12 +744,8 @@ impl<T> TryFrom<&str> for NPath<Rel, T> {
 
     fn try_from(path: &str) -> Result<Self, Self::Error> {
         let norm_path = normalize_path(path);
-
-        if !has_root(&norm_path) || norm_path.is_empty() {
-            Ok(NPath::from_unicode(&norm_path))
-        } else {
-            Err(NPathError::NoRelPath)
-        }
+        check_path_len(&norm_path)?;
+        Self::from_normalized(&norm_path)
     }
 }
 
@@ -579,13 +755,120 @@ impl<T> TryFrom<String> for NPath<Rel, T> {
 
     fn try_from(path: String) -> Result<Self, Self::Error> {
         let norm_path = normalize_path(&path);
+        check_path_len(&norm_path)?;
+        Self::from_normalized(&norm_path)
+    }
+}
+
+/// Lossy conversion of an `NPath`.
+impl<K, T> NPath<K, T>
+where
+    for<'a> NPath<K, T>: TryFrom<&'a str, Error = NPathError>,
+{
+    /// Creates a `NPath` from an `OsStr`, invalid unicode is escaped.
+    ///
+    /// Each invalid byte becomes U+FFFD and two hex digits, a U+FFFD of the path is doubled.
+    /// So different paths get different names and `unescape_os_path` restores the original.
+    /// Use `is_lossy` to check if the path was not valid unicode.
+    pub fn from_os_str_lossy(path: &OsStr) -> Result<Self, NPathError> {
+        let mut escaped = String::new();
+
+        for chunk in path.as_encoded_bytes().utf8_chunks() {
+            for char in chunk.valid().chars() {
+                if char == char::REPLACEMENT_CHARACTER {
+                    escaped.push(char);
+                }
+                escaped.push(char);
+            }
+
+            for byte in chunk.invalid() {
+                escaped.push(char::REPLACEMENT_CHARACTER);
+                escaped.push_str(&format!("{:02X}", byte));
+            }
+        }
+
+        Self::try_from(escaped.as_str())
+    }
+}
 
-        if !has_root(&norm_path) || norm_path.is_empty() {
-            Ok(NPath::from_unicode(&norm_path))
+/// Returns the os path of a path, which was escaped by `NPath::from_os_str_lossy`.
+pub fn unescape_os_path(path: PathBuf) -> PathBuf {
+    let Some(path_str) = path
+        .to_str()
+        .filter(|path_str| path_str.contains(char::REPLACEMENT_CHARACTER))
+    else {
+        return path;
+    };
+
+    let mut bytes = Vec::with_capacity(path_str.len());
+    let mut chars = path_str.chars();
+
+    while let Some(char) = chars.next() {
+        if char != char::REPLACEMENT_CHARACTER {
+            bytes.extend_from_slice(char.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+
+        let rest = chars.as_str();
+
+        // A doubled U+FFFD or two hex digits, anything else is kept as is.
+        if rest.starts_with(char::REPLACEMENT_CHARACTER) {
+            chars.next();
+            bytes.extend_from_slice(char.encode_utf8(&mut [0; 4]).as_bytes());
+        } else if let Some(byte) = rest
+            .get(..2)
+            .filter(|hex| {
+                hex.bytes()
+                    .all(|digit| matches!(digit, b'0'..=b'9' | b'A'..=b'F'))
+            })
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            chars.nth(1);
+            bytes.push(byte);
         } else {
-            Err(NPathError::NoRelPath)
+            bytes.extend_from_slice(char.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+    }
+
+    os_path_from_bytes(bytes)
+}
+
+/// Returns the os path of the raw bytes.
+#[cfg(unix)]
+fn os_path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+/// Returns the os path of the WTF-8 bytes, unpaired surrogates are decoded to UTF-16.
+#[cfg(windows)]
+fn os_path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::windows::ffi::OsStringExt;
+
+    let mut wide = Vec::with_capacity(bytes.len());
+
+    for chunk in bytes.utf8_chunks() {
+        wide.extend(chunk.valid().encode_utf16());
+
+        for surrogate in chunk.invalid().chunks(3) {
+            if let [first, second, third] = surrogate {
+                wide.push(
+                    (u16::from(*first & 0x0F) << 12)
+                        | (u16::from(*second & 0x3F) << 6)
+                        | u16::from(*third & 0x3F),
+                );
+            }
         }
     }
+
+    PathBuf::from(std::ffi::OsString::from_wide(&wide))
+}
+
+/// Returns the os path of the bytes, invalid unicode can't be restored on this platform.
+#[cfg(not(any(unix, windows)))]
+fn os_path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
 }
 
 /// Impl of `Debug` for `NPath`.
@@ -608,13 +891,22 @@ impl<K, T> NPath<K, T> {
     fn from_unicode(path_str: &str) -> Self {
         let unicode = path_str.to_string();
         let nfc = path_str.nfc().collect();
+        let component_count = count_components(path_str);
         Self {
             unicode,
             nfc,
+            component_count,
             _marker: PhantomData,
         }
     }
 
+    /// Returns the number of path segments, counted at construction time.
+    ///
+    /// Equals `components().count()`, without iterating the path.
+    pub fn components_count(&self) -> usize {
+        self.component_count
+    }
+
     /// Returns true, if the `NPath` ends with `rel_path`.
     pub fn ends_with(&self, rel_path: &NPath<Rel, T>) -> bool {
         self.nfc.ends_with(&rel_path.nfc)
@@ -624,6 +916,7 @@ impl<K, T> NPath<K, T> {
     pub fn clear(&mut self) {
         self.unicode.clear();
         self.nfc.clear();
+        self.component_count = count_components("");
     }
 
     /// Returns true if the `NPath` is empty.
@@ -631,6 +924,23 @@ impl<K, T> NPath<K, T> {
         self.unicode.is_empty()
     }
 
+    /// Returns true if the `NPath` contains an escaped byte of `from_os_str_lossy`.
+    ///
+    /// A doubled U+FFFD is an escaped U+FFFD of a valid path.
+    pub fn is_lossy(&self) -> bool {
+        let mut chars = self.unicode.chars();
+
+        while let Some(char) = chars.next() {
+            if char == char::REPLACEMENT_CHARACTER
+                && chars.next() != Some(char::REPLACEMENT_CHARACTER)
+            {
+                return true;
+            }
+        }
+
+        false
+    }
+
     /// Returns the `NPath` as path.
     pub fn to_path(&self) -> &Path {
         Path::new(&self.unicode)
@@ -645,6 +955,20 @@ impl<K, T> NPath<K, T> {
     pub fn to_nfc(&self) -> &str {
         &self.nfc
     }
+
+    /// Returns true if the glob `pattern` matches the nfc path.
+    ///
+    /// Compiled patterns are cached per thread.
+    pub fn glob_matches(&self, pattern: &str) -> bool {
+        glob_matches_nfc(pattern, &self.nfc)
+    }
+
+    /// Returns the `NPath` truncated in the middle to `max_len` graphemes.
+    ///
+    /// The middle is replaced with `…`, so the root and the file name stay visible.
+    pub fn compact_display(&self, max_len: usize) -> String {
+        compact_middle(&self.unicode, max_len)
+    }
 }
 
 /// Impl of `Clone` for `NPath`.
@@ -653,6 +977,7 @@ impl<K, T> Clone for NPath<K, T> {
         NPath {
             unicode: self.unicode.clone(),
             nfc: self.nfc.clone(),
+            component_count: self.component_count,
             _marker: PhantomData,
         }
     }
@@ -668,6 +993,22 @@ impl<K1, T1, K2, T2> PartialEq<NPath<K2, T2>> for NPath<K1, T1> {
     }
 }
 
+/// Impl of `PartialOrd` for `NPath`.
+impl<K, T> PartialOrd for NPath<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Impl of `Ord` for `NPath`.
+///
+/// Orders depth-first lexicographic by the nfc path, so a directory is ordered before its children.
+impl<K, T> Ord for NPath<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_nfc_components(&self.nfc, &other.nfc)
+    }
+}
+
 /// Impl of `Hash` for `NPath`.
 impl<K, T> Hash for NPath<K, T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -686,18 +1027,32 @@ impl<K, T> Serialize for NPath<K, T> {
     }
 }
 
-/// Impl of `Deserialize` for `NPath`.
-impl<'de, K, T> Deserialize<'de> for NPath<K, T>
-where
-    NPath<K, T>: TryFrom<String>,
-    <NPath<K, T> as TryFrom<String>>::Error: std::fmt::Display,
-{
+/// Impl of `Deserialize` for an absolute `NPath`.
+///
+/// The length is not checked, a stored path stays readable. The config validation
+/// reports too long paths.
+impl<'de, T> Deserialize<'de> for NPath<Abs, T> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         let path_raw = String::deserialize(deserializer)?;
-        NPath::<K, T>::try_from(path_raw).map_err(serde::de::Error::custom)
+        NPath::<Abs, T>::from_normalized(&normalize_path(&path_raw))
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Impl of `Deserialize` for a relative `NPath`.
+///
+/// The length is not checked, a stored path stays readable.
+impl<'de, T> Deserialize<'de> for NPath<Rel, T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let path_raw = String::deserialize(deserializer)?;
+        NPath::<Rel, T>::from_normalized(&normalize_path(&path_raw))
+            .map_err(serde::de::Error::custom)
     }
 }
 
@@ -975,6 +1330,56 @@ impl NPath<Abs, Symlink> {
     }
 }
 
+/// Returns the number of path segments of a normalized path.
+///
+/// A root (e.g. `/`, `C:/` or `https://`) is counted as one segment, like in `components()`.
+fn count_components(path: &str) -> usize {
+    let root_match = URL_SCHEME
+        .find(path)
+        .or_else(|| WINDOWS_DRIVE.find(path))
+        .or_else(|| UNIX_ROOT.find(path));
+
+    match root_match {
+        Some(expr_match) => {
+            1 + path[expr_match.end()..]
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .count()
+        }
+        // A relative path has no root, each separator starts a new segment.
+        None => path.matches('/').count() + 1,
+    }
+}
+
+/// Helper for comparing nfc paths component by component.
+fn cmp_nfc_components(nfc_a: &str, nfc_b: &str) -> Ordering {
+    nfc_a.split('/').cmp(nfc_b.split('/'))
+}
+
+/// Helper for truncating a path in the middle.
+fn compact_middle(path: &str, max_len: usize) -> String {
+    let graphemes: Vec<&str> = path.graphemes(true).collect();
+
+    if graphemes.len() <= max_len {
+        return path.to_string();
+    }
+
+    if max_len == 0 {
+        return String::new();
+    }
+
+    // Keep one third of the head and two thirds of the tail.
+    let keep_len = max_len - 1;
+    let head_len = keep_len / 3;
+    let tail_len = keep_len - head_len;
+
+    format!(
+        "{}…{}",
+        graphemes[..head_len].concat(),
+        graphemes[graphemes.len() - tail_len..].concat()
+    )
+}
+
 /// Helper for subtraction
 fn sub_from_start(
     left_unicode: &str,
@@ -19,6 +19,10 @@ pub enum ProgressInfo {
     /// Can be used by cli or gui to show that the progress total duration is n ticks.
     #[strum(to_string = "Duration")]
     Duration,
+
+    /// Can be used by cli or gui to show the transferred bytes of the total bytes.
+    #[strum(to_string = "ByteProgress")]
+    ByteProgress { transferred: u64, total: u64 },
 }
 
 /// Impl of `Info` for `ProgressInfo`.
@@ -43,13 +47,34 @@ pub struct ProgressMessage {
 
     /// The ticks.
     pub ticks: u64,
+
+    /// The bytes transferred so far.
+    pub bytes_transferred: u64,
+
+    /// The total bytes to transfer.
+    pub total_bytes: u64,
 }
 
 /// Methods of `ProgressMessage`.
 impl ProgressMessage {
     /// Creates a new `ProgressMessage`.
     pub fn new(info: Arc<dyn Info + Send + Sync>, ticks: u64) -> Self {
-        ProgressMessage { info, ticks }
+        ProgressMessage {
+            info,
+            ticks,
+            bytes_transferred: 0,
+            total_bytes: 0,
+        }
+    }
+
+    /// Creates a new `ProgressMessage` with a `ProgressInfo::ByteProgress`.
+    pub fn bytes(transferred: u64, total: u64) -> Self {
+        ProgressMessage {
+            info: Arc::new(ProgressInfo::ByteProgress { transferred, total }),
+            ticks: 0,
+            bytes_transferred: transferred,
+            total_bytes: total,
+        }
     }
 }
 
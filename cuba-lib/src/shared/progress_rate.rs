@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The window of the current throughput.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(5);
+
+/// Defines a `ProgressRate`.
+///
+/// Measures the elapsed time, the estimated time remaining and the current throughput of a run,
+/// fed by the transferred bytes of the `ProgressInfo::ByteProgress` messages.
+#[derive(Clone, Debug)]
+pub struct ProgressRate {
+    /// The start of the run.
+    start: Instant,
+
+    /// The samples of the transferred bytes, the first one starts the window.
+    samples: VecDeque<(Instant, u64)>,
+}
+
+/// Methods of `ProgressRate`.
+impl ProgressRate {
+    /// Creates a new `ProgressRate`, which starts now.
+    pub fn new() -> Self {
+        let start = Instant::now();
+
+        Self {
+            start,
+            samples: VecDeque::from([(start, 0)]),
+        }
+    }
+
+    /// Restarts the `ProgressRate`, e.g. for the next run.
+    pub fn restart(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Updates the bytes transferred so far.
+    pub fn update(&mut self, transferred: u64) {
+        let now = Instant::now();
+        self.samples.push_back((now, transferred));
+
+        // Keep the last sample before the window, as start of the window.
+        while self.samples.len() > 2 && now.duration_since(self.samples[1].0) >= THROUGHPUT_WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Returns the elapsed time of the run.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Returns the estimated time remaining by the normalized `progress` of the run.
+    pub fn eta(&self, progress: f32) -> Option<Duration> {
+        if progress <= 0.0 || !progress.is_finite() {
+            return None;
+        }
+
+        let progress = progress.min(1.0);
+
+        Some(self.elapsed().mul_f32((1.0 - progress) / progress))
+    }
+
+    /// Returns true, if bytes were transferred, e.g. not for a clean.
+    pub fn has_bytes(&self) -> bool {
+        self.samples.len() > 1
+    }
+
+    /// Returns the current throughput in bytes per second.
+    pub fn bytes_per_sec(&self) -> f64 {
+        let (first, last) = match (self.samples.front(), self.samples.back()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 0.0,
+        };
+
+        // The window lasts until now, so that the throughput drops, if no bytes are transferred.
+        let window = first.0.elapsed().as_secs_f64();
+
+        if window > 0.0 {
+            last.1.saturating_sub(first.1) as f64 / window
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the current throughput in MiB per second.
+    pub fn mib_per_sec(&self) -> f64 {
+        self.bytes_per_sec() / (1024.0 * 1024.0)
+    }
+}
+
+/// Impl of `Default` for `ProgressRate`.
+impl Default for ProgressRate {
+    fn default() -> Self {
+        ProgressRate::new()
+    }
+}
@@ -0,0 +1,115 @@
+use serde_json::{Value, json};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+};
+
+/// Defines a `RichError`.
+///
+/// Preserves the original error together with structured context (operation name, path),
+/// so that a `MsgHandler` can output all fields.
+///
+/// `Display` and `source` are those of the original error, so a `RichError` is transparent
+/// when printed.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use cuba_lib::shared::rich_error::RichError;
+/// use cuba_lib::shared::task_message::TaskError;
+///
+/// let rich_error = RichError::new(Arc::new(TaskError::TransferFailed))
+///     .with_operation("transfer")
+///     .with_path("dir/file.txt");
+///
+/// assert_eq!(rich_error.operation(), Some("transfer"));
+/// assert_eq!(rich_error.to_string(), "Transfer failed");
+/// ```
+#[derive(Debug, Clone)]
+pub struct RichError {
+    /// The name of the failed operation (if any).
+    operation: Option<String>,
+
+    /// The path of the failed operation (if any).
+    path: Option<String>,
+
+    /// The original error.
+    error: Arc<dyn Error + Send + Sync>,
+}
+
+/// Methods of `RichError`.
+impl RichError {
+    /// Creates a new `RichError`.
+    pub fn new(error: Arc<dyn Error + Send + Sync>) -> Self {
+        RichError {
+            operation: None,
+            path: None,
+            error,
+        }
+    }
+
+    /// Sets the operation name.
+    pub fn with_operation(mut self, operation: &str) -> Self {
+        self.operation = Some(operation.to_string());
+        self
+    }
+
+    /// Sets the path.
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Returns the operation name (if any).
+    pub fn operation(&self) -> Option<&str> {
+        self.operation.as_deref()
+    }
+
+    /// Returns the path (if any).
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Returns the original error.
+    pub fn error(&self) -> &(dyn Error + Send + Sync) {
+        self.error.as_ref()
+    }
+
+    /// Returns the messages of all causes of the original error.
+    pub fn causes(&self) -> Vec<String> {
+        let mut causes = Vec::new();
+        let mut source = self.error.source();
+
+        while let Some(err) = source {
+            causes.push(err.to_string());
+            source = err.source();
+        }
+
+        causes
+    }
+
+    /// Returns all fields as json.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "operation": self.operation,
+            "path": self.path,
+            "error": self.error.to_string(),
+            "causes": self.causes(),
+        })
+    }
+}
+
+/// Impl of `Display` for `RichError`.
+impl Display for RichError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.error)
+    }
+}
+
+/// Impl of `Error` for `RichError`.
+impl Error for RichError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
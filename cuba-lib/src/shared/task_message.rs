@@ -9,6 +9,7 @@ use thiserror::Error;
 
 use super::message::{Info, Message};
 use super::npath::{Rel, UNPath};
+use super::rich_error::RichError;
 
 /// Defines a `TaskError`.
 #[derive(Error, Debug)]
@@ -28,6 +29,14 @@ pub enum TaskError {
     /// Can be used by cli or gui to show that password id is missing.
     #[error("No password id available")]
     NoPasswordId,
+
+    /// Can be used by cli or gui to show that the base of a delta is missing.
+    #[error("No delta base available")]
+    NoDeltaBase,
+
+    /// Can be used by cli or gui to show that the size of a transferred file does not match the written bytes.
+    #[error("Size mismatch, {written} bytes written, but the destination has {size} bytes")]
+    SizeMismatch { written: u64, size: u64 },
 }
 
 /// Defines a `TaskInfo`.
@@ -59,6 +68,10 @@ pub enum TaskInfo {
     #[strum(to_string = "Tick")]
     Tick,
 
+    /// Can be used by cli or gui to show the bytes done of the total bytes of the file, which is transferred.
+    #[strum(to_string = "Progress {done}/{total}")]
+    Progress { done: u64, total: u64 },
+
     /// Can be used by cli or gui to show that a file or directory is up to date and no transfer is needed.
     #[strum(to_string = "Up to date")]
     UpToDate,
@@ -74,6 +87,51 @@ impl Info for TaskInfo {
     }
 }
 
+/// Defines a `TransferredBytes`.
+///
+/// Can be used by cli or gui to show the number of bytes, a task has written
+/// for a file. Sent before `TaskInfo::Transferred`.
+#[derive(Debug, PartialEq)]
+pub struct TransferredBytes {
+    pub bytes: u64,
+}
+
+/// Impl of `Info` for `TransferredBytes`.
+impl Info for TransferredBytes {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Impl of `Display` for `TransferredBytes`.
+impl Display for TransferredBytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes transferred", self.bytes)
+    }
+}
+
+/// Defines a `SourceBytes`.
+///
+/// The number of bytes, a task has read from the source file. Advances the byte progress.
+#[derive(Debug, PartialEq)]
+pub struct SourceBytes {
+    pub bytes: u64,
+}
+
+/// Impl of `Info` for `SourceBytes`.
+impl Info for SourceBytes {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Impl of `Display` for `SourceBytes`.
+impl Display for SourceBytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes read", self.bytes)
+    }
+}
+
 /// Defines a `TaskMessage`.
 ///
 /// # Example
@@ -96,7 +154,7 @@ pub struct TaskMessage {
     pub rel_path: UNPath<Rel>,
 
     /// Error (if any).
-    error: Option<Arc<dyn Error + Send + Sync>>,
+    error: Option<RichError>,
 
     /// Info (if any).
     info: Option<Arc<dyn Info + Send + Sync>>,
@@ -105,25 +163,52 @@ pub struct TaskMessage {
 /// Methods of `TaskMessage`.
 impl TaskMessage {
     /// Creates a new `TaskMessage`.
+    ///
+    /// The error is wrapped in a `RichError`, which falls back to `rel_path` as path.
     pub fn new(
         thread_number: usize,
         rel_path: &UNPath<Rel>,
         error: Option<Arc<dyn Error + Send + Sync>>,
         info: Option<Arc<dyn Info + Send + Sync>>,
+    ) -> Self {
+        Self::with_rich_error(thread_number, rel_path, error.map(RichError::new), info)
+    }
+
+    /// Creates a new `TaskMessage` with an error, which already carries its context.
+    ///
+    /// The `RichError` falls back to `rel_path` as path.
+    pub fn with_rich_error(
+        thread_number: usize,
+        rel_path: &UNPath<Rel>,
+        error: Option<RichError>,
+        info: Option<Arc<dyn Info + Send + Sync>>,
     ) -> Self {
         TaskMessage {
             thread_number,
             rel_path: rel_path.clone(),
-            error,
+            error: error.map(|rich_error| {
+                if rich_error.path().is_none() {
+                    rich_error.with_path(rel_path.to_unicode())
+                } else {
+                    rich_error
+                }
+            }),
             info,
         }
     }
+
+    /// Returns the error with its context (if any).
+    pub fn rich_error(&self) -> Option<&RichError> {
+        self.error.as_ref()
+    }
 }
 
 /// Impl of `Message` for `TaskMessage`.
 impl Message for TaskMessage {
     fn err(&self) -> Option<&(dyn Error + Send + Sync)> {
-        self.error.as_deref()
+        self.error
+            .as_ref()
+            .map(|error| error as &(dyn Error + Send + Sync))
     }
 
     fn info(&self) -> Option<&(dyn Info + Send + Sync)> {
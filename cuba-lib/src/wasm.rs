@@ -0,0 +1,17 @@
+use wasm_bindgen::prelude::*;
+
+use crate::core::transferred_node::{TransferredNodes, list_src_paths};
+
+/// Lists the src paths of a backup.
+///
+/// Takes the content of the `cuba.json.gz` in the destination directory of
+/// the backup, since a browser has no access to the filesystems.
+#[wasm_bindgen(js_name = listBackupContents)]
+pub fn list_backup_contents(cuba_json_gz: &[u8]) -> Result<Vec<String>, JsError> {
+    let transferred_nodes = TransferredNodes::from_cuba_json(cuba_json_gz)?;
+
+    Ok(list_src_paths(&transferred_nodes)
+        .iter()
+        .map(|src_rel_path| src_rel_path.to_unicode().to_string())
+        .collect())
+}
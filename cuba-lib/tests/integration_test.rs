@@ -0,0 +1,859 @@
+//! Runs the full backup, verify, restore and clean cycle against mem filesystems.
+
+use crossbeam_channel::{Receiver, unbounded};
+use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+use secrecy::SecretString;
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, Once};
+
+use cuba_lib::core::cuba::{Cuba, RunHandle, VerifyMode};
+use cuba_lib::core::fs::fs_base::FS;
+use cuba_lib::core::fs::mem_fs::MemFS;
+use cuba_lib::shared::config::load_config_from_str;
+use cuba_lib::shared::message::Message;
+use cuba_lib::shared::npath::{Abs, Dir, File, NPath, Rel, UNPath};
+
+/// The number of files in the source.
+const FILE_COUNT: usize = 100;
+
+/// The number of files, which are removed from the source before the clean.
+const REMOVED_COUNT: usize = 10;
+
+/// The password id of the encryption.
+const PASSWORD_ID: &str = "cuba-integration-test";
+
+/// The secrets of the mem keyring by user.
+type MemSecrets = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// Defines a `MemCredential`.
+///
+/// An entry of the mem keyring.
+#[derive(Debug)]
+struct MemCredential {
+    user: String,
+    secrets: MemSecrets,
+}
+
+/// Impl of `CredentialApi` for `MemCredential`.
+impl CredentialApi for MemCredential {
+    fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert(self.user.clone(), secret.to_vec());
+        Ok(())
+    }
+
+    fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+        self.secrets
+            .lock()
+            .unwrap()
+            .get(&self.user)
+            .cloned()
+            .ok_or(keyring::Error::NoEntry)
+    }
+
+    fn delete_credential(&self) -> keyring::Result<()> {
+        self.secrets
+            .lock()
+            .unwrap()
+            .remove(&self.user)
+            .map(|_| ())
+            .ok_or(keyring::Error::NoEntry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Defines a `MemCredentialBuilder`.
+///
+/// Builds the entries of the mem keyring, which share their secrets.
+#[derive(Debug, Default)]
+struct MemCredentialBuilder {
+    secrets: MemSecrets,
+}
+
+/// Impl of `CredentialBuilderApi` for `MemCredentialBuilder`.
+impl CredentialBuilderApi for MemCredentialBuilder {
+    fn build(
+        &self,
+        _target: Option<&str>,
+        _service: &str,
+        user: &str,
+    ) -> keyring::Result<Box<Credential>> {
+        Ok(Box::new(MemCredential {
+            user: user.to_string(),
+            secrets: self.secrets.clone(),
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Replaces the OS keyring by the mem keyring, so the passwords of the tests are always
+/// available and never stored in the OS keyring.
+fn use_mem_keyring() {
+    static MEM_KEYRING: Once = Once::new();
+
+    MEM_KEYRING.call_once(|| {
+        keyring::set_default_credential_builder(Box::new(MemCredentialBuilder::default()));
+    });
+}
+
+/// Defines a `MemDir`.
+///
+/// A dir of a named `MemFS`, which the tests fill and read.
+struct MemDir {
+    fs: MemFS,
+    abs_dir_path: NPath<Abs, Dir>,
+}
+
+/// Methods of `MemDir`.
+impl MemDir {
+    /// Creates the `MemDir` of `dir` in the `MemFS` with `fs_name`, an empty `dir` is the root.
+    fn new(fs_name: &str, dir: &str) -> Self {
+        let mut fs = MemFS::named(fs_name);
+        fs.connect().unwrap();
+
+        let abs_dir_path = match dir {
+            "" => NPath::<Abs, Dir>::default(),
+            dir => {
+                NPath::<Abs, Dir>::default().add_rel_dir(&NPath::<Rel, Dir>::try_from(dir).unwrap())
+            }
+        };
+
+        MemDir { fs, abs_dir_path }
+    }
+
+    /// Returns the abs path of the file `rel_path`.
+    fn abs_file_path(&self, rel_path: &str) -> NPath<Abs, File> {
+        self.abs_dir_path
+            .add_rel_file(&NPath::<Rel, File>::try_from(rel_path).unwrap())
+    }
+
+    /// Writes `data` to the file `rel_path`.
+    fn write(&self, rel_path: &str, data: &[u8]) {
+        let mut writer = self.fs.write_data(&self.abs_file_path(rel_path)).unwrap();
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap();
+    }
+
+    /// Returns the data of the file `rel_path`.
+    fn read(&self, rel_path: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.fs
+            .read_data(&self.abs_file_path(rel_path))
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        data
+    }
+
+    /// Returns true, if the file `rel_path` exists.
+    fn is_file(&self, rel_path: &str) -> bool {
+        self.fs.meta(&self.abs_file_path(rel_path).into()).is_ok()
+    }
+
+    /// Returns true, if the file or dir `rel_path` exists.
+    fn exists(&self, rel_path: &str) -> bool {
+        self.is_file(rel_path)
+            || self
+                .fs
+                .meta(&UNPath::Dir(
+                    self.abs_dir_path
+                        .add_rel_dir(&NPath::<Rel, Dir>::try_from(rel_path).unwrap()),
+                ))
+                .is_ok()
+    }
+
+    /// Removes the file `rel_path`.
+    fn remove(&self, rel_path: &str) {
+        self.fs.remove_file(&self.abs_file_path(rel_path)).unwrap();
+    }
+
+    /// Returns the abs paths of the entries of the dir `rel_path`, an empty `rel_path` is
+    /// the dir itself.
+    fn list_dir(&self, rel_path: &str) -> Vec<String> {
+        let abs_dir_path = match rel_path {
+            "" => self.abs_dir_path.clone(),
+            rel_path => self
+                .abs_dir_path
+                .add_rel_dir(&NPath::<Rel, Dir>::try_from(rel_path).unwrap()),
+        };
+
+        self.fs
+            .list_dir(&abs_dir_path)
+            .unwrap()
+            .iter()
+            .map(|abs_path| abs_path.to_unicode().to_string())
+            .collect()
+    }
+}
+
+/// Returns the config of the test with the source, dest and restore filesystems of `name`.
+fn make_config(name: &str, encrypt: bool) -> String {
+    format!(
+        r#"
+transfer_threads = 4
+
+[filesystem.local]
+
+[filesystem.webdav]
+
+[filesystem.mem."{name}_src"]
+
+[filesystem.mem."{name}_dest"]
+
+[filesystem.mem."{name}_restored"]
+
+[backup."backup"]
+src_fs = "{name}_src"
+dest_fs = "{name}_dest"
+src_dir = "src"
+dest_dir = "backup"
+compression = true
+encrypt = {encrypt}
+password_id = "{PASSWORD_ID}"
+
+[restore."restore"]
+src_fs = "{name}_dest"
+dest_fs = "{name}_restored"
+src_dir = "backup"
+dest_dir = "restored"
+"#
+    )
+}
+
+/// Returns the config of the pattern test, the backup selects the files by patterns.
+fn make_pattern_config(root: &Path) -> String {
+    format!(
+        r#"
+transfer_threads = 4
+
+[filesystem.local."local"]
+dir = '{root}'
+
+[filesystem.webdav]
+
+[filesystem.mem."mem_patterns"]
+
+[backup."backup"]
+src_fs = "local"
+dest_fs = "mem_patterns"
+src_dir = "src"
+dest_dir = "backup"
+include = ["**/*.txt", "**/*.log", "**/*.tmp"]
+exclude = ["**/*.tmp", "cache/", "skip.log"]
+encrypt = false
+compression = false
+
+[restore."restore"]
+src_fs = "mem_patterns"
+dest_fs = "local"
+src_dir = "backup"
+dest_dir = "restored"
+"#,
+        root = root.to_string_lossy().replace('\\', "/"),
+    )
+}
+
+/// Returns the content of the file with `index`, the files differ in content and size.
+fn file_content(index: usize) -> Vec<u8> {
+    let mut content = format!("File {index}\n").repeat(index + 1).into_bytes();
+    content.extend((0..index * 37).map(|byte| (byte * 31 + index) as u8));
+    content
+}
+
+/// Returns the rel path of the file with `index`, spread over some dirs.
+fn file_rel_path(index: usize) -> String {
+    format!("dir_{}/sub_{}/file_{index}.txt", index % 7, index % 3)
+}
+
+/// Returns the error messages received so far.
+fn take_errors(receiver: &Receiver<Arc<dyn Message>>) -> Vec<String> {
+    receiver
+        .try_iter()
+        .filter(|message| message.err().is_some())
+        .map(|message| message.to_string())
+        .collect()
+}
+
+#[test]
+fn backup_verify_restore_clean() {
+    let src_dir = MemDir::new("cycle_src", "src");
+    let restored_dir = MemDir::new("cycle_restored", "restored");
+
+    // Create the source files.
+    for index in 0..FILE_COUNT {
+        src_dir.write(&file_rel_path(index), &file_content(index));
+    }
+
+    let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+    let mut cuba = Cuba::new(sender.clone());
+
+    // The password of the encryption is in the mem keyring.
+    use_mem_keyring();
+    cuba.set_password(
+        PASSWORD_ID,
+        &SecretString::from("integration-test-password"),
+    );
+    assert!(cuba.get_password(PASSWORD_ID).is_some());
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    cuba.set_config(load_config_from_str(sender, &make_config("cycle", true)).unwrap());
+
+    // Backup.
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(stats.files, FILE_COUNT);
+    assert_eq!(stats.nodes.file_count, FILE_COUNT);
+    assert_eq!(stats.nodes.compressed_count, FILE_COUNT);
+    assert_eq!(stats.nodes.encrypted_count, FILE_COUNT);
+
+    // The first backup has no cuba json to read.
+    let errors = take_errors(&receiver);
+    assert!(
+        errors.iter().all(|error| error.contains("cuba.json")),
+        "{errors:?}"
+    );
+
+    // Verify.
+    cuba.run_verify(RunHandle::default(), "backup", &VerifyMode::All, &false)
+        .unwrap();
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    // Restore and compare with the source.
+    cuba.run_restore(RunHandle::default(), "restore", None, None)
+        .unwrap();
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    for index in 0..FILE_COUNT {
+        let rel_path = file_rel_path(index);
+        assert_eq!(
+            restored_dir.read(&rel_path),
+            file_content(index),
+            "Restored file {rel_path} differs"
+        );
+    }
+
+    // Remove some source files, the next backup marks them as orphans.
+    for index in 0..REMOVED_COUNT {
+        src_dir.remove(&file_rel_path(index));
+    }
+
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(stats.nodes.orphan_count, REMOVED_COUNT);
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    // Clean, no orphans remain.
+    cuba.run_clean(RunHandle::default(), "backup").unwrap();
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(stats.nodes.orphan_count, 0);
+    assert_eq!(stats.nodes.file_count, FILE_COUNT - REMOVED_COUNT);
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+}
+
+#[test]
+fn backup_include_exclude() {
+    // The source is a local dir, only there an unreadable dir proves that excluded dirs are not
+    // descended.
+    let root = std::env::temp_dir().join(format!("cuba-patterns-{}", std::process::id()));
+    let src_dir = root.join("src");
+    let restored_dir = root.join("restored");
+
+    // Create the source files.
+    for rel_path in [
+        "keep.txt",
+        "remove.tmp",
+        "sub/keep.log",
+        "sub/skip.log",
+        "sub/remove.tmp",
+        "cache/keep.txt",
+        "sub/cache/keep.txt",
+    ] {
+        let path = src_dir.join(rel_path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, rel_path).unwrap();
+    }
+
+    std::fs::create_dir_all(&restored_dir).unwrap();
+
+    // Root ignores the permissions, the created files are owned by the current user.
+    #[cfg(unix)]
+    let restrict_cache = {
+        use std::os::unix::fs::MetadataExt;
+
+        std::fs::metadata(&src_dir).unwrap().uid() != 0
+    };
+
+    // An excluded dir is not descended, so it needs no permissions.
+    #[cfg(unix)]
+    if restrict_cache {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::set_permissions(
+            src_dir.join("cache"),
+            std::fs::Permissions::from_mode(0o000),
+        )
+        .unwrap();
+    }
+
+    let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+    let mut cuba = Cuba::new(sender.clone());
+
+    cuba.set_config(load_config_from_str(sender, &make_pattern_config(&root)).unwrap());
+
+    // Backup, only the included files, which are not excluded, are found.
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(stats.files, 2);
+    assert_eq!(stats.directories, 1);
+
+    // The first backup has no cuba json to read.
+    let errors = take_errors(&receiver);
+    assert!(
+        errors.iter().all(|error| error.contains("cuba.json")),
+        "{errors:?}"
+    );
+
+    // Restore and check the selected files.
+    cuba.run_restore(RunHandle::default(), "restore", None, None)
+        .unwrap();
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    for rel_path in ["keep.txt", "sub/keep.log"] {
+        assert!(restored_dir.join(rel_path).exists(), "{rel_path} missing");
+    }
+
+    // The exclude wins, if include and exclude match.
+    for rel_path in [
+        "remove.tmp",
+        "sub/skip.log",
+        "sub/remove.tmp",
+        "cache",
+        "sub/cache",
+    ] {
+        assert!(!restored_dir.join(rel_path).exists(), "{rel_path} restored");
+    }
+
+    // Cleanup.
+    #[cfg(unix)]
+    if restrict_cache {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::set_permissions(
+            src_dir.join("cache"),
+            std::fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+    }
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn backup_glob_patterns() {
+    let src_dir = MemDir::new("globs_src", "src");
+    let restored_dir = MemDir::new("globs_restored", "restored");
+
+    // Create the source files.
+    for rel_path in [
+        "logs/a.log",
+        "logs/x/y/b.log",
+        "logs/x/c.txt",
+        "logs/tmp/d.log",
+        "other.log",
+        "build/top.o",
+        "build/out/bin.o",
+        "build/out/tmp/e.o",
+        "build/tmp",
+    ] {
+        src_dir.write(rel_path, rel_path.as_bytes());
+    }
+
+    // `**` in the middle and trailing, `tmp/` matches only directories.
+    let config = r#"
+transfer_threads = 2
+
+[filesystem.local]
+
+[filesystem.webdav]
+
+[filesystem.mem."globs_src"]
+
+[filesystem.mem."globs_dest"]
+
+[filesystem.mem."globs_restored"]
+
+[backup."backup"]
+src_fs = "globs_src"
+dest_fs = "globs_dest"
+src_dir = "src"
+dest_dir = "backup"
+include = ["logs/**/*.log", "build/**"]
+exclude = ["tmp/"]
+encrypt = false
+compression = false
+
+[restore."restore"]
+src_fs = "globs_dest"
+dest_fs = "globs_restored"
+src_dir = "backup"
+dest_dir = "restored"
+"#;
+
+    let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+    let mut cuba = Cuba::new(sender.clone());
+
+    cuba.set_config(load_config_from_str(sender, config).unwrap());
+
+    // Backup and restore the selected files.
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(stats.files, 5);
+
+    cuba.run_restore(RunHandle::default(), "restore", None, None)
+        .unwrap();
+
+    // The first backup has no cuba json to read.
+    let errors = take_errors(&receiver);
+    assert!(
+        errors.iter().all(|error| error.contains("cuba.json")),
+        "{errors:?}"
+    );
+
+    for rel_path in [
+        "logs/a.log",
+        "logs/x/y/b.log",
+        "build/top.o",
+        "build/out/bin.o",
+        "build/tmp",
+    ] {
+        assert!(restored_dir.is_file(rel_path), "{rel_path} missing");
+    }
+
+    for rel_path in ["logs/x/c.txt", "logs/tmp", "other.log", "build/out/tmp"] {
+        assert!(!restored_dir.exists(rel_path), "{rel_path} restored");
+    }
+}
+
+#[test]
+fn backup_statistics() {
+    let src_dir = MemDir::new("statistics_src", "src");
+    let backup_dir = MemDir::new("statistics_dest", "backup");
+
+    // Create the source files.
+    for index in 0..REMOVED_COUNT {
+        src_dir.write(&file_rel_path(index), &file_content(index));
+    }
+
+    let config = r#"
+transfer_threads = 2
+
+[filesystem.local]
+
+[filesystem.webdav]
+
+[filesystem.mem."statistics_src"]
+
+[filesystem.mem."statistics_dest"]
+
+[backup."backup"]
+src_fs = "statistics_src"
+dest_fs = "statistics_dest"
+src_dir = "src"
+dest_dir = "backup"
+encrypt = false
+compression = true
+
+[restore]
+"#;
+
+    let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+    let mut cuba = Cuba::new(sender.clone());
+
+    cuba.set_config(load_config_from_str(sender, config).unwrap());
+
+    // Backup, all files are compressed and not verified yet.
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    let _ = take_errors(&receiver);
+
+    let total_src_bytes: u64 = (0..REMOVED_COUNT)
+        .map(|index| file_content(index).len() as u64)
+        .sum();
+
+    assert_eq!(stats.nodes.file_count, REMOVED_COUNT);
+    assert_eq!(stats.nodes.dir_count, stats.directories);
+    assert_eq!(stats.nodes.total_src_bytes, total_src_bytes);
+    assert_eq!(stats.nodes.compressed_count, REMOVED_COUNT);
+    assert_eq!(stats.nodes.encrypted_count, 0);
+    assert_eq!(stats.nodes.orphan_count, 0);
+    assert_eq!(stats.nodes.verified_count, 0);
+    assert_eq!(stats.nodes.error_count, 0);
+
+    // Corrupt a backup file, the verify marks it with an error.
+    let corrupted_name = backup_dir
+        .list_dir("dir_0/sub_0")
+        .into_iter()
+        .filter_map(|path| path.rsplit('/').next().map(str::to_string))
+        .find(|name| name.contains("file_0.txt"))
+        .unwrap();
+    backup_dir.write(&format!("dir_0/sub_0/{corrupted_name}"), b"corrupted");
+
+    cuba.run_verify(RunHandle::default(), "backup", &VerifyMode::All, &false)
+        .unwrap();
+    let errors = take_errors(&receiver);
+    assert!(
+        !errors.is_empty() && errors.iter().all(|error| error.contains("file_0.txt")),
+        "{errors:?}"
+    );
+
+    let nodes = cuba.get_backup_statistics("backup").unwrap();
+    assert_eq!(nodes.verified_count, REMOVED_COUNT + stats.directories);
+    assert_eq!(nodes.error_count, 1);
+
+    // Remove a source file, the backup marks it as orphan and transfers the corrupted file.
+    src_dir.remove(&file_rel_path(1));
+
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    assert_eq!(stats.nodes.file_count, REMOVED_COUNT);
+    assert_eq!(stats.nodes.orphan_count, 1);
+    assert_eq!(stats.nodes.error_count, 0);
+}
+
+#[test]
+fn backup_cuba_json_fallback() {
+    let src_dir = MemDir::new("fallback_src", "src");
+    let backup_dir = MemDir::new("fallback_dest", "backup");
+
+    // Create the source files.
+    for index in 0..REMOVED_COUNT {
+        src_dir.write(&file_rel_path(index), &file_content(index));
+    }
+
+    let config = r#"
+transfer_threads = 2
+
+[filesystem.local]
+
+[filesystem.webdav]
+
+[filesystem.mem."fallback_src"]
+
+[filesystem.mem."fallback_dest"]
+
+[backup."backup"]
+src_fs = "fallback_src"
+dest_fs = "fallback_dest"
+src_dir = "src"
+dest_dir = "backup"
+encrypt = false
+compression = false
+
+[restore]
+"#;
+
+    let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+    let mut cuba = Cuba::new(sender.clone());
+
+    cuba.set_config(load_config_from_str(sender, config).unwrap());
+
+    // The second backup keeps the cuba json of the first one as backup.
+    cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    let _ = take_errors(&receiver);
+
+    assert!(backup_dir.is_file("cuba.json.gz.bak"));
+    assert!(!backup_dir.is_file("cuba.json.gz.tmp"));
+
+    // Corrupt the cuba json, the backup falls back to the backup of the cuba json.
+    backup_dir.write("cuba.json.gz", b"corrupted");
+
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(stats.nodes.file_count, REMOVED_COUNT);
+
+    let messages: Vec<String> = receiver
+        .try_iter()
+        .map(|message| message.to_string())
+        .collect();
+    assert!(
+        messages
+            .iter()
+            .any(|message| message.contains("is not readable")),
+        "{messages:?}"
+    );
+
+    // The cuba json is readable again.
+    assert!(backup_dir.read("cuba.json.gz") != b"corrupted");
+
+    // Corrupt the cuba json and its backup, the backup is aborted and keeps them.
+    backup_dir.write("cuba.json.gz", b"corrupted");
+    backup_dir.write("cuba.json.gz.bak", b"corrupted");
+
+    assert!(cuba.run_backup(RunHandle::default(), "backup").is_err());
+    assert_eq!(backup_dir.read("cuba.json.gz"), b"corrupted");
+}
+
+#[test]
+fn backup_dest_dir_auto() {
+    let src_dir = MemDir::new("dest_auto_src", "src");
+    let dest_dir = MemDir::new("dest_auto_dest", "");
+
+    // Create the source files, the dest has no backup dirs yet.
+    for index in 0..REMOVED_COUNT {
+        src_dir.write(&file_rel_path(index), &file_content(index));
+    }
+
+    let config = r#"
+transfer_threads = 2
+
+[filesystem.local]
+
+[filesystem.webdav]
+
+[filesystem.mem."dest_auto_src"]
+
+[filesystem.mem."dest_auto_dest"]
+
+[backup."backup"]
+src_fs = "dest_auto_src"
+dest_fs = "dest_auto_dest"
+src_dir = "src"
+dest_dir = ""
+dest_dir_auto = true
+encrypt = false
+compression = false
+
+[restore]
+"#;
+
+    let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+    let mut cuba = Cuba::new(sender.clone());
+
+    cuba.set_config(load_config_from_str(sender, config).unwrap());
+
+    // The backup creates the <hostname>/<backup_name> dirs.
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(stats.files, REMOVED_COUNT);
+    let _ = take_errors(&receiver);
+
+    let host_dirs = dest_dir.list_dir("");
+    assert_eq!(host_dirs.len(), 1, "{host_dirs:?}");
+
+    let host_dir = host_dirs[0].trim_matches('/');
+    assert!(dest_dir.is_file(&format!("{host_dir}/backup/cuba.json.gz")));
+
+    // The existing dirs are kept on the next backup.
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(stats.nodes.file_count, REMOVED_COUNT);
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+}
+
+#[test]
+fn backup_dedup_clean_restore() {
+    let src_dir = MemDir::new("dedup_src", "src");
+    let restored_dir = MemDir::new("dedup_restored", "restored");
+
+    // Create the source files, every content exists twice.
+    for index in 0..REMOVED_COUNT {
+        src_dir.write(&file_rel_path(index), &file_content(index / 2));
+    }
+
+    let config = make_config("dedup", false).replace(
+        "compression = true\n",
+        "compression = true\ndedup_enabled = true\n",
+    );
+
+    let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+    let mut cuba = Cuba::new(sender.clone());
+
+    cuba.set_config(load_config_from_str(sender, &config).unwrap());
+
+    // Backup, remove a file and backup again, which marks it as orphan.
+    cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    let _ = take_errors(&receiver);
+
+    src_dir.remove(&file_rel_path(0));
+
+    let stats = cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    assert_eq!(stats.nodes.orphan_count, 1);
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    // Clean, the dedup files are kept.
+    cuba.run_clean(RunHandle::default(), "backup").unwrap();
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    let stats = cuba.get_backup_statistics("backup").unwrap();
+    assert_eq!(stats.file_count, REMOVED_COUNT - 1);
+    assert_eq!(stats.orphan_count, 0);
+
+    // Restore and compare with the source.
+    cuba.run_restore(RunHandle::default(), "restore", None, None)
+        .unwrap();
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+
+    for index in 1..REMOVED_COUNT {
+        let rel_path = file_rel_path(index);
+        assert_eq!(
+            restored_dir.read(&rel_path),
+            file_content(index / 2),
+            "Restored file {rel_path} differs"
+        );
+    }
+
+    assert!(!restored_dir.is_file(&file_rel_path(0)));
+}
+
+#[test]
+fn backup_dedup_prune() {
+    let src_dir = MemDir::new("dedup_prune_src", "src");
+    let backup_dir = MemDir::new("dedup_prune_dest", "backup");
+
+    // Create the source files, the first two share their content.
+    src_dir.write(&file_rel_path(0), &file_content(0));
+    src_dir.write(&file_rel_path(1), &file_content(0));
+    src_dir.write(&file_rel_path(2), &file_content(2));
+
+    // Prune all orphans.
+    let config = make_config("dedup_prune", false).replace(
+        "compression = true\n",
+        "compression = true\ndedup_enabled = true\n",
+    ) + "\n[backup.\"backup\".retention]\nkeep_last = 0\n";
+
+    let (sender, receiver) = unbounded::<Arc<dyn Message>>();
+    let mut cuba = Cuba::new(sender.clone());
+
+    cuba.set_config(load_config_from_str(sender, &config).unwrap());
+
+    cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    let _ = take_errors(&receiver);
+    assert_eq!(backup_dir.list_dir("cuba-dedup").len(), 2);
+
+    // Prune an orphan, its blob is kept for the other file with the same content.
+    src_dir.remove(&file_rel_path(0));
+
+    cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    cuba.run_prune(RunHandle::default(), "backup").unwrap();
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+    assert_eq!(backup_dir.list_dir("cuba-dedup").len(), 2);
+
+    // Prune an orphan, its blob is removed.
+    src_dir.remove(&file_rel_path(2));
+
+    cuba.run_backup(RunHandle::default(), "backup").unwrap();
+    cuba.run_prune(RunHandle::default(), "backup").unwrap();
+    assert_eq!(take_errors(&receiver), Vec::<String>::new());
+    assert_eq!(backup_dir.list_dir("cuba-dedup").len(), 1);
+
+    let stats = cuba.get_backup_statistics("backup").unwrap();
+    assert_eq!(stats.file_count, 1);
+    assert_eq!(stats.orphan_count, 0);
+}